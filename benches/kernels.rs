@@ -0,0 +1,140 @@
+//! Escape time kernels on tiles of the kind the app computes, and on a full frame across cores.
+//! `cargo bench --bench kernels -- boundary` runs a single group.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use glam::{DVec2, UVec2};
+
+use fractal::kernel::{
+    compute_frame, FractalKernel, KernelOptions, NeverCancelled, PixelGrid, ScalarKernel,
+};
+use fractal::mandelbrot_simd::{KernelPixel, Pixel, Precision, SimdKernel, MANDELBROT_POWER};
+use fractal::math::DRect;
+use fractal::raw_export;
+
+const TILE_SIZE: u32 = 128;
+// a 1920x1080 window at the default zoom
+const FRAME_SIZE: UVec2 = UVec2::new(1920, 1080);
+const DEFAULT_CENTER: DVec2 = DVec2::new(-0.74, 0.0);
+const DEFAULT_FRAME_HEIGHT: f64 = 2.5;
+
+struct Scene {
+    name: &'static str,
+    tile: DRect,
+    max_iter: u32,
+}
+
+// tile sizes as a 1080 pixel high window shows them, iteration limits as the app picks them
+fn scenes() -> [Scene; 3] {
+    let tile_height = |frame_height: f64| frame_height * TILE_SIZE as f64 / FRAME_SIZE.y as f64;
+    let deep_height = DEFAULT_FRAME_HEIGHT / 1e10;
+
+    [
+        // left of the main cardioid, mostly escaping within a few iterations
+        Scene {
+            name: "default_view",
+            tile: DRect::from_center_size(
+                DEFAULT_CENTER + DVec2::new(-0.6, 0.3),
+                DVec2::splat(tile_height(DEFAULT_FRAME_HEIGHT)),
+            ),
+            max_iter: 1000,
+        },
+        // inside the main cardioid, every point runs to the limit
+        Scene {
+            name: "interior",
+            tile: DRect::from_center_size(
+                DVec2::new(-0.2, 0.0),
+                DVec2::splat(tile_height(DEFAULT_FRAME_HEIGHT)),
+            ),
+            max_iter: 1000,
+        },
+        // seahorse valley at 1e10 zoom, escaping and interior points mixed
+        Scene {
+            name: "boundary_1e10",
+            tile: DRect::from_center_size(
+                DVec2::new(-0.743_643_887_037_151, 0.131_825_904_205_330),
+                DVec2::splat(tile_height(deep_height)),
+            ),
+            max_iter: 4087,
+        },
+    ]
+}
+
+fn bench_tile<K: FractalKernel, P: KernelPixel>(
+    criterion: &mut Criterion,
+    kernel_name: &str,
+    kernel: &K,
+) {
+    let size = UVec2::splat(TILE_SIZE);
+    let mut group = criterion.benchmark_group(format!("tile_{}", kernel_name));
+    group.sample_size(10);
+
+    for scene in scenes() {
+        let grid = PixelGrid::new(scene.tile, size);
+        let options = KernelOptions {
+            max_iter: scene.max_iter,
+            power: MANDELBROT_POWER,
+        };
+        let mut buffer = vec![P::default(); size.element_product() as usize];
+        group.bench_function(BenchmarkId::from_parameter(scene.name), |bencher| {
+            bencher.iter(|| {
+                compute_frame(
+                    kernel,
+                    grid,
+                    grid.all(),
+                    options,
+                    NeverCancelled,
+                    &mut buffer,
+                )
+                .unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn tiles(criterion: &mut Criterion) {
+    bench_tile::<_, Pixel>(criterion, "simd_banded", &SimdKernel::default());
+    bench_tile::<_, f32>(criterion, "simd_smooth", &SimdKernel::default());
+    let f32_kernel = SimdKernel {
+        precision: Precision::F32,
+        ..SimdKernel::default()
+    };
+    bench_tile::<_, Pixel>(criterion, "simd_f32_banded", &f32_kernel);
+    bench_tile::<_, f32>(criterion, "simd_f32_smooth", &f32_kernel);
+    bench_tile::<_, Pixel>(criterion, "scalar_banded", &ScalarKernel);
+    bench_tile::<_, f32>(criterion, "scalar_smooth", &ScalarKernel);
+}
+
+// the export path, bands of the frame on every physical core
+fn full_frame(criterion: &mut Criterion) {
+    let frame = DRect::from_center_size(
+        DEFAULT_CENTER,
+        DVec2::new(
+            DEFAULT_FRAME_HEIGHT * FRAME_SIZE.x as f64 / FRAME_SIZE.y as f64,
+            DEFAULT_FRAME_HEIGHT,
+        ),
+    );
+    let grid = PixelGrid::new(frame, FRAME_SIZE);
+    let options = KernelOptions {
+        max_iter: 1000,
+        power: MANDELBROT_POWER,
+    };
+    let mut group = criterion.benchmark_group("full_frame");
+    group.sample_size(10);
+
+    group.bench_function("simd_banded", |bencher| {
+        bencher.iter(|| {
+            raw_export::compute::<_, Pixel>(&SimdKernel::default(), grid, options, None).unwrap()
+        })
+    });
+    group.bench_function("simd_smooth", |bencher| {
+        bencher.iter(|| {
+            raw_export::compute::<_, f32>(&SimdKernel::default(), grid, options, None).unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, tiles, full_frame);
+criterion_main!(benches);
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer};
+use winit::keyboard::ModifiersState;
+
+use crate::event::MouseButtons;
+
+/// What a mouse button does while it is held, the names are the keys of the `[mouse]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Gesture {
+    /// Drags the view along.
+    Pan,
+    /// Zooms into the box spanned by the drag once the button is released.
+    BoxZoom,
+    /// Flies to the clicked point.
+    ContextAction,
+    /// Zooms around the pressed point, dragging down zooms out.
+    ZoomAxis,
+    /// Logs the escape count of the clicked pixel.
+    Inspect,
+}
+
+const GESTURES: [(Gesture, &str); 5] = [
+    (Gesture::Pan, "pan"),
+    (Gesture::BoxZoom, "box_zoom"),
+    (Gesture::ContextAction, "context_action"),
+    (Gesture::ZoomAxis, "zoom_axis"),
+    (Gesture::Inspect, "inspect"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Binding {
+    button: MouseButtons,
+    // all of them have to be held, others may be too
+    modifiers: ModifiersState,
+}
+
+const fn binding(button: MouseButtons, modifiers: ModifiersState) -> Binding {
+    Binding { button, modifiers }
+}
+
+// left drags as it always has, the other buttons were unused
+const DEFAULT_BINDINGS: [(Gesture, Option<Binding>); 5] = [
+    (
+        Gesture::Pan,
+        Some(binding(MouseButtons::Left, ModifiersState::empty())),
+    ),
+    (
+        Gesture::BoxZoom,
+        Some(binding(MouseButtons::Left, ModifiersState::SHIFT)),
+    ),
+    (
+        Gesture::ContextAction,
+        Some(binding(MouseButtons::Middle, ModifiersState::empty())),
+    ),
+    (
+        Gesture::ZoomAxis,
+        Some(binding(MouseButtons::Right, ModifiersState::empty())),
+    ),
+    (
+        Gesture::Inspect,
+        Some(binding(MouseButtons::Left, ModifiersState::CONTROL)),
+    ),
+];
+
+/// The mouse button and modifiers of every gesture, the file only lists the ones that differ
+/// from the defaults. An invalid table is reported and leaves all of them at their defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings {
+    bindings: Vec<(Gesture, Binding)>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self::with_overrides(HashMap::new()).unwrap()
+    }
+}
+
+impl Bindings {
+    /// The gesture of the binding with the most modifiers among those of `button`
+    /// whose modifiers are all held, ties go to the gesture listed first.
+    pub fn gesture(&self, button: MouseButtons, modifiers: ModifiersState) -> Option<Gesture> {
+        self.bindings
+            .iter()
+            .filter(|(_, binding)| {
+                binding.button == button && modifiers.contains(binding.modifiers)
+            })
+            .rev()
+            .max_by_key(|(_, binding)| binding.modifiers.bits().count_ones())
+            .map(|&(gesture, _)| gesture)
+    }
+
+    fn with_overrides(overrides: HashMap<Gesture, Option<Binding>>) -> Result<Self, String> {
+        let bindings: Vec<(Gesture, Binding)> = DEFAULT_BINDINGS
+            .into_iter()
+            .filter_map(|(gesture, binding)| {
+                Some((
+                    gesture,
+                    overrides.get(&gesture).copied().unwrap_or(binding)?,
+                ))
+            })
+            .collect();
+
+        for (index, (gesture, binding)) in bindings.iter().enumerate() {
+            if let Some((other, _)) = bindings[index + 1..]
+                .iter()
+                .find(|(_, other)| other == binding)
+            {
+                return Err(format!(
+                    "{} is bound to both {} and {}",
+                    binding_name(binding),
+                    gesture_name(*gesture),
+                    gesture_name(*other)
+                ));
+            }
+        }
+
+        Ok(Self { bindings })
+    }
+
+    fn from_table(table: HashMap<String, toml::Value>) -> Result<Self, String> {
+        let mut overrides = HashMap::new();
+        for (name, value) in table {
+            let gesture = GESTURES
+                .iter()
+                .find(|&&(_, gesture_name)| gesture_name == name)
+                .map(|&(gesture, _)| gesture)
+                .ok_or_else(|| format!("unknown gesture {}", name))?;
+            let value = value
+                .as_str()
+                .ok_or_else(|| format!("{} should be a string like \"Shift+Left\"", name))?;
+            overrides.insert(gesture, parse_binding(value)?);
+        }
+
+        Self::with_overrides(overrides)
+    }
+}
+
+impl<'de> Deserialize<'de> for Bindings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let table = HashMap::<String, toml::Value>::deserialize(deserializer)?;
+
+        // a mistake here shouldn't cost the rest of the settings, the defaults are usable
+        Ok(Self::from_table(table).unwrap_or_else(|error| {
+            tracing::warn!("Invalid mouse bindings, using the defaults: {}", error);
+            Self::default()
+        }))
+    }
+}
+
+// "Ctrl+Shift+Left" or "none"
+fn parse_binding(text: &str) -> Result<Option<Binding>, String> {
+    if text.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let button = match parts.pop().unwrap_or_default() {
+        "Left" => MouseButtons::Left,
+        "Right" => MouseButtons::Right,
+        "Middle" => MouseButtons::Middle,
+        "Back" => MouseButtons::Back,
+        "Forward" => MouseButtons::Forward,
+        other => return Err(format!("unknown mouse button {:?}", other)),
+    };
+    let mut modifiers = ModifiersState::empty();
+    for part in parts {
+        modifiers |= match part {
+            "Shift" => ModifiersState::SHIFT,
+            "Ctrl" => ModifiersState::CONTROL,
+            "Alt" => ModifiersState::ALT,
+            "Super" => ModifiersState::SUPER,
+            other => return Err(format!("unknown modifier {:?}", other)),
+        };
+    }
+
+    Ok(Some(binding(button, modifiers)))
+}
+
+fn binding_name(binding: &Binding) -> String {
+    let mut name = String::new();
+    for (modifier, modifier_name) in [
+        (ModifiersState::SHIFT, "Shift"),
+        (ModifiersState::CONTROL, "Ctrl"),
+        (ModifiersState::ALT, "Alt"),
+        (ModifiersState::SUPER, "Super"),
+    ] {
+        if binding.modifiers.contains(modifier) {
+            name += modifier_name;
+            name += "+";
+        }
+    }
+
+    name + &format!("{:?}", binding.button)
+}
+
+fn gesture_name(gesture: Gesture) -> &'static str {
+    GESTURES
+        .iter()
+        .find(|&&(other, _)| other == gesture)
+        .map(|&(_, name)| name)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Bindings {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn modifiers_pick_the_most_specific_binding() {
+        let bindings = Bindings::default();
+        let gesture = |button, modifiers| bindings.gesture(button, modifiers);
+
+        assert_eq!(
+            gesture(MouseButtons::Left, ModifiersState::empty()),
+            Some(Gesture::Pan)
+        );
+        assert_eq!(
+            gesture(MouseButtons::Left, ModifiersState::SHIFT),
+            Some(Gesture::BoxZoom)
+        );
+        assert_eq!(
+            gesture(
+                MouseButtons::Left,
+                ModifiersState::SHIFT | ModifiersState::ALT
+            ),
+            Some(Gesture::BoxZoom)
+        );
+        // both need a single modifier
+        assert_eq!(
+            gesture(
+                MouseButtons::Left,
+                ModifiersState::SHIFT | ModifiersState::CONTROL
+            ),
+            Some(Gesture::BoxZoom)
+        );
+        // extra modifiers don't get in the way of a binding without them
+        assert_eq!(
+            gesture(MouseButtons::Left, ModifiersState::ALT),
+            Some(Gesture::Pan)
+        );
+        assert_eq!(
+            gesture(MouseButtons::Left, ModifiersState::CONTROL),
+            Some(Gesture::Inspect)
+        );
+        assert_eq!(
+            gesture(MouseButtons::Right, ModifiersState::SHIFT),
+            Some(Gesture::ZoomAxis)
+        );
+        assert_eq!(
+            gesture(MouseButtons::Middle, ModifiersState::empty()),
+            Some(Gesture::ContextAction)
+        );
+        assert_eq!(gesture(MouseButtons::Back, ModifiersState::empty()), None);
+    }
+
+    #[test]
+    fn overrides_replace_the_defaults() {
+        // left-handed, buttons swapped
+        let bindings =
+            parse("pan = \"Right\"\nzoom_axis = \"Left\"\nbox_zoom = \"Ctrl+Alt+Right\"");
+        assert_eq!(
+            bindings.gesture(MouseButtons::Right, ModifiersState::empty()),
+            Some(Gesture::Pan)
+        );
+        assert_eq!(
+            bindings.gesture(MouseButtons::Left, ModifiersState::SHIFT),
+            Some(Gesture::ZoomAxis)
+        );
+        assert_eq!(
+            bindings.gesture(
+                MouseButtons::Right,
+                ModifiersState::CONTROL | ModifiersState::ALT
+            ),
+            Some(Gesture::BoxZoom)
+        );
+        assert_eq!(
+            bindings.gesture(MouseButtons::Right, ModifiersState::CONTROL),
+            Some(Gesture::Pan)
+        );
+
+        let bindings = parse("context_action = \"none\"");
+        assert_eq!(
+            bindings.gesture(MouseButtons::Middle, ModifiersState::empty()),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_tables_fall_back_to_the_defaults() {
+        for text in [
+            // taken from pan, which keeps it
+            "zoom_axis = \"Left\"",
+            "pan = \"Shift+Left\"",
+            "pan = \"Hyper+Left\"",
+            "pan = \"Wheel\"",
+            "pan = 1",
+            "fly = \"Left\"",
+        ] {
+            assert_eq!(parse(text), Bindings::default(), "{}", text);
+        }
+
+        let error =
+            Bindings::from_table(toml::from_str("pan = \"Shift+Left\"").unwrap()).unwrap_err();
+        assert_eq!(error, "Shift+Left is bound to both pan and box_zoom");
+    }
+}
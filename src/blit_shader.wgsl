@@ -7,6 +7,18 @@ struct VertexOutput {
 struct PushConstant {
     proj_mat: mat4x4<f32>,
     texture_size: vec2<f32>,
+    dither: u32,
+    coloring_mode: u32,
+    no_data: u32,
+    palette_mapping: u32,
+    edge_detection: u32,
+    exposure: f32,
+    gamma: f32,
+    time: f32,
+    fade_duration: f32,
+    tile_size: u32,
+    newton: u32,
+    interior_color: vec4<f32>,
 };
 var<push_constant> pc: PushConstant;
 
@@ -34,5 +46,9 @@ var color: texture_2d<u32>;
 @fragment
 fn fs_main(vertex: VertexOutput) -> @location(0) u32 {
     let r = textureLoad(color, vec2<u32>(vertex.tex_coord), 0).r;
+    // pages are drawn over the previous preview, only where they hold data
+    if (r == pc.no_data) {
+        discard;
+    }
     return r;
 }
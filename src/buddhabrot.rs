@@ -0,0 +1,211 @@
+//! The Buddhabrot: how often the escaping orbits of random points pass through each pixel of a
+//! frame. Unlike the escape time kernels a pixel depends on points anywhere in the plane, so the
+//! whole frame accumulates into one histogram that any number of workers add to.
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use glam::{DVec2, UVec2};
+
+use crate::kernel::CancelSignal;
+use crate::math::DRect;
+
+/// The points are sampled here, every orbit that escapes starts within it.
+pub const SAMPLE_RECT: DRect = DRect {
+    pos: DVec2::new(-2.0, -2.0),
+    size: DVec2::new(4.0, 4.0),
+};
+// samples between checks of the cancel signal
+const CANCEL_CHECK_SAMPLES: u64 = 4096;
+
+/// Visits per pixel of a frame, the first row is the top one.
+#[derive(Debug)]
+pub struct Histogram {
+    size: UVec2,
+    counts: Vec<AtomicU32>,
+}
+
+impl Histogram {
+    pub fn new(size: UVec2) -> Self {
+        Self {
+            size,
+            counts: (0..size.element_product())
+                .map(|_| AtomicU32::new(0))
+                .collect(),
+        }
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// The counts so far, workers may still be adding to them.
+    pub fn counts(&self) -> Vec<u32> {
+        self.counts
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    // the pixel of `frame` that `point` falls into, if any
+    fn add(&self, frame: DRect, point: DVec2) {
+        let offset = (point - frame.pos) / frame.size;
+        if !(0.0..1.0).contains(&offset.x) || !(0.0..1.0).contains(&offset.y) {
+            return;
+        }
+
+        let x = (offset.x * self.size.x as f64) as u32;
+        let y = ((1.0 - offset.y) * self.size.y as f64) as u32;
+        let index = y.min(self.size.y - 1) * self.size.x + x;
+        self.counts[index as usize].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A small generator for the sample points, the same seed gives the same points.
+#[derive(Debug, Clone)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Samples `samples` points of `SAMPLE_RECT` and adds the orbit of every one that escapes within
+/// `max_iter` iterations to `histogram`, which covers `frame`. Returns the number of samples
+/// taken, fewer if cancelled.
+pub fn accumulate<C: CancelSignal>(
+    histogram: &Histogram,
+    frame: DRect,
+    max_iter: u32,
+    rng: &mut SplitMix64,
+    samples: u64,
+    cancel_signal: &C,
+) -> u64 {
+    let mut orbit = Vec::with_capacity(max_iter as usize);
+
+    for sample in 0..samples {
+        if sample % CANCEL_CHECK_SAMPLES == 0 && cancel_signal.is_cancelled() {
+            return sample;
+        }
+
+        let c = SAMPLE_RECT.pos + DVec2::new(rng.next_f64(), rng.next_f64()) * SAMPLE_RECT.size;
+        if escaping_orbit(c, max_iter, &mut orbit) {
+            for &z in &orbit {
+                histogram.add(frame, z);
+            }
+        }
+    }
+
+    samples
+}
+
+// fills `orbit` with the points of z -> z^2 + c before it escapes, false if it doesn't
+fn escaping_orbit(c: DVec2, max_iter: u32, orbit: &mut Vec<DVec2>) -> bool {
+    orbit.clear();
+    if in_main_bulbs(c) {
+        return false;
+    }
+
+    let mut z = DVec2::ZERO;
+    for _ in 0..max_iter {
+        z = DVec2::new(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+        if z.length_squared() > 4.0 {
+            return true;
+        }
+        orbit.push(z);
+    }
+
+    false
+}
+
+// the main cardioid and the period-2 bulb, most of the area of the set; their orbits never
+// escape, there's no need to iterate them up to the limit
+fn in_main_bulbs(c: DVec2) -> bool {
+    let q = (c.x - 0.25).powi(2) + c.y * c.y;
+    let in_cardioid = q * (q + (c.x - 0.25)) <= 0.25 * c.y * c.y;
+    let in_bulb = (c.x + 1.0).powi(2) + c.y * c.y <= 1.0 / 16.0;
+
+    in_cardioid || in_bulb
+}
+
+/// Brightness from 0 to 1 of every count, the square root of its share of the largest one,
+/// which brings out the faint outer orbits next to the dense core.
+pub fn tone_map(counts: &[u32]) -> Vec<f32> {
+    let max = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+
+    counts
+        .iter()
+        .map(|&count| (count as f32 / max).sqrt())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::NeverCancelled;
+
+    #[test]
+    fn orbits_land_on_their_pixels() {
+        let mut orbit = Vec::new();
+        // 0.5, 0.75, 1.0625, 1.62890625, then 3.153... escapes
+        assert!(escaping_orbit(DVec2::new(0.5, 0.0), 100, &mut orbit));
+        assert_eq!(orbit.len(), 4);
+        assert_eq!(orbit[1], DVec2::new(0.75, 0.0));
+
+        // inside the cardioid and the bulb, and a bounded orbit outside of both
+        for c in [DVec2::ZERO, DVec2::new(-1.0, 0.0), DVec2::new(-1.3, 0.0)] {
+            assert!(!escaping_orbit(c, 1000, &mut orbit), "{}", c);
+        }
+
+        // a pixel per unit from -2 to 2, the top row first
+        let frame = DRect::from_pos_size(DVec2::splat(-2.0), DVec2::splat(4.0));
+        let histogram = Histogram::new(UVec2::splat(4));
+        histogram.add(frame, DVec2::new(0.5, 0.25));
+        histogram.add(frame, DVec2::new(-1.5, -1.5));
+        histogram.add(frame, DVec2::new(2.5, 0.0));
+        let counts = histogram.counts();
+        assert_eq!(counts[4 + 2], 1);
+        assert_eq!(counts[12], 1);
+        assert_eq!(counts.iter().sum::<u32>(), 2);
+    }
+
+    #[test]
+    fn accumulation_follows_the_seed() {
+        let frame = DRect::from_center_size(DVec2::new(-0.5, 0.0), DVec2::new(3.0, 3.0));
+        let run = |seed| {
+            let histogram = Histogram::new(UVec2::splat(32));
+            let samples = accumulate(
+                &histogram,
+                frame,
+                200,
+                &mut SplitMix64::new(seed),
+                20_000,
+                &NeverCancelled,
+            );
+            assert_eq!(samples, 20_000);
+            histogram.counts()
+        };
+
+        let counts = run(1);
+        assert!(counts.iter().any(|&count| count > 0));
+        assert_eq!(counts, run(1));
+        assert_ne!(counts, run(2));
+
+        let brightness = tone_map(&counts);
+        assert_eq!(brightness.iter().copied().fold(0.0, f32::max), 1.0);
+        assert!(brightness.iter().all(|value| (0.0..=1.0).contains(value)));
+        assert_eq!(tone_map(&[0, 0]), [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,284 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use glam::UVec2;
+
+use crate::buddhabrot::{accumulate, tone_map, Histogram, SplitMix64};
+use crate::kernel::CancelToken;
+use crate::math::DRect;
+use crate::runtime::Runtime;
+use crate::RenderContext;
+
+/// Samples of a frame unless the settings ask for another count.
+pub const DEFAULT_BUDDHABROT_SAMPLES: u64 = 100_000_000;
+// a worker takes this many samples at a time
+const BATCH_SAMPLES: u64 = 1 << 16;
+// the picture is updated whenever this many more samples are done
+const PROGRESS_SAMPLES: u64 = 4_000_000;
+
+/// Draws the Buddhabrot of the frame in place of the escape time tiles. The whole frame
+/// accumulates into one histogram on the workers, every `PROGRESS_SAMPLES` samples the
+/// picture is tone-mapped and drawn again.
+#[derive(Debug)]
+pub struct BuddhabrotRenderer {
+    pub enabled: bool,
+    /// Random points sampled for a frame.
+    pub samples: u64,
+
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    target: Option<Target>,
+    cancel_token: CancelToken,
+    // of the frame accumulating, with the generation of its workers
+    histogram: Option<(Arc<Histogram>, u64)>,
+    // the histogram changed since the texture was last written
+    dirty: bool,
+}
+
+// the tone-mapped histogram, sized like it
+#[derive(Debug)]
+struct Target {
+    size: UVec2,
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+impl BuddhabrotRenderer {
+    /// `format` is the view format of the surface.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, samples: u64) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+            label: None,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+            label: None,
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(include_str!("buddhabrot_shader.wgsl").into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            enabled: false,
+            samples,
+            pipeline,
+            bind_group_layout,
+            target: None,
+            cancel_token: CancelToken::new(),
+            histogram: None,
+            dirty: false,
+        }
+    }
+
+    /// Starts accumulating `frame` at `size` pixels over, the previous frame is dropped.
+    /// `on_progress` gets the generation of the frame and the samples done so far, from the
+    /// workers, every `PROGRESS_SAMPLES` samples and once they are all done.
+    pub fn start<F>(
+        &mut self,
+        runtime: &Runtime,
+        frame: DRect,
+        size: UVec2,
+        max_iter: u32,
+        worker_count: usize,
+        on_progress: F,
+    ) where
+        F: Fn(u64, u64) + Send + Sync + 'static,
+    {
+        let cancel_token = self.cancel_token.next_generation();
+        let generation = cancel_token.generation();
+        let histogram = Arc::new(Histogram::new(size.max(UVec2::ONE)));
+        self.histogram = Some((histogram.clone(), generation));
+        self.dirty = true;
+
+        let samples = self.samples;
+        tracing::debug!(
+            generation,
+            samples,
+            width = size.x,
+            height = size.y,
+            workers = worker_count,
+            "Buddhabrot started"
+        );
+        let claimed = Arc::new(AtomicU64::new(0));
+        let done = Arc::new(AtomicU64::new(0));
+        let on_progress = Arc::new(on_progress);
+        for worker in 0..worker_count as u64 {
+            let cancel_token = cancel_token.clone();
+            let histogram = histogram.clone();
+            let claimed = claimed.clone();
+            let done = done.clone();
+            let on_progress = on_progress.clone();
+            // every frame and worker samples its own points
+            let mut rng = SplitMix64::new(generation * worker_count as u64 + worker);
+
+            // a batch at a time
+            runtime.spawn_steps(move || {
+                let start = claimed.fetch_add(BATCH_SAMPLES, Ordering::Relaxed);
+                if start >= samples {
+                    return false;
+                }
+                let batch = BATCH_SAMPLES.min(samples - start);
+                let taken = accumulate(&histogram, frame, max_iter, &mut rng, batch, &cancel_token);
+                if taken < batch {
+                    return false;
+                }
+
+                let total = done.fetch_add(batch, Ordering::Relaxed) + batch;
+                if total / PROGRESS_SAMPLES != (total - batch) / PROGRESS_SAMPLES
+                    || total == samples
+                {
+                    tracing::trace!(generation, samples = total, "Buddhabrot progress");
+                    on_progress(generation, total);
+                }
+
+                true
+            });
+        }
+    }
+
+    /// Stops the workers and frees the histogram and its texture.
+    pub fn stop(&mut self) {
+        self.cancel_token.next_generation();
+        self.histogram = None;
+        self.target = None;
+    }
+
+    /// Called with the generation `on_progress` got, false for a frame that was replaced since.
+    pub fn progress(&mut self, generation: u64) -> bool {
+        let current = self
+            .histogram
+            .as_ref()
+            .is_some_and(|&(_, current)| current == generation);
+        self.dirty |= current;
+
+        current
+    }
+
+    /// Draws the histogram so far onto `render_info.view`, black until there is one.
+    pub fn render(&mut self, render_info: &RenderContext) {
+        if let Some((histogram, _)) = self.histogram.as_ref().filter(|_| self.dirty) {
+            self.dirty = false;
+            let size = histogram.size();
+            if self
+                .target
+                .as_ref()
+                .is_none_or(|target| target.size != size)
+            {
+                self.target = Some(self.create_target(render_info.device, size));
+            }
+
+            let brightness = tone_map(&histogram.counts());
+            render_info.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &self.target.as_ref().unwrap().texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                bytemuck::cast_slice(&brightness),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size.x * size_of::<f32>() as u32),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let mut command_encoder = render_info
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: render_info.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            if let Some(target) = self.target.as_ref() {
+                render_pass.set_pipeline(&self.pipeline);
+                render_info.apply_viewport(&mut render_pass);
+                render_pass.set_bind_group(0, &target.bind_group, &[]);
+                render_pass.draw(0..3, 0..1);
+            }
+        }
+
+        render_info.queue.submit(Some(command_encoder.finish()));
+    }
+
+    fn create_target(&self, device: &wgpu::Device, size: UVec2) -> Target {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Float,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+            label: None,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+            label: None,
+        });
+
+        Target {
+            size,
+            texture,
+            bind_group,
+        }
+    }
+}
@@ -0,0 +1,30 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+
+// a single triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var result: VertexOutput;
+    result.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+
+    return result;
+}
+
+
+@group(0)
+@binding(0)
+var brightness: texture_2d<f32>;
+
+// the histogram has a texel per pixel of the window, the first row at the top
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let last = vec2<i32>(textureDimensions(brightness)) - 1;
+    let texel = min(vec2<i32>(vertex.position.xy), last);
+    let value = textureLoad(brightness, texel, 0).r;
+
+    return vec4<f32>(value, value, value, 1.0);
+}
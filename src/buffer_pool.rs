@@ -23,10 +23,15 @@ impl BufferPool {
 
     pub fn take(&mut self) -> Arc<Mutex<Vec<u8>>> {
         if let Some(buf) = self.buffers.iter().find(|buf| Arc::strong_count(buf) == 1) {
+            let mut data = buf.lock();
+            if data.len() < self.buf_size {
+                data.resize(self.buf_size, 0);
+            }
+            drop(data);
             buf.clone()
         } else {
             self.total_allocated += 1;
-            println!("Total allocated buffers: {}", self.total_allocated);
+            tracing::debug!(total = self.total_allocated, "Allocated a tile buffer");
 
             self.buffers
                 .push(Arc::new(Mutex::new(vec![0u8; self.buf_size])));
@@ -34,6 +39,23 @@ impl BufferPool {
         }
     }
 
+    /// Buffers taken from now on hold at least `buf_size` bytes, pooled ones grow when reused.
+    /// Never shrinks, tasks of the old size may still be waiting for theirs.
+    pub fn grow(&mut self, buf_size: usize) {
+        self.buf_size = self.buf_size.max(buf_size);
+    }
+
+    /// Frees the buffers not taken right now, returns how many.
+    /// Buffers still in use stay in the pool and are reused once returned.
+    pub fn release_free(&mut self) -> usize {
+        let count = self.buffers.len();
+        self.buffers.retain(|buf| Arc::strong_count(buf) > 1);
+        let released = count - self.buffers.len();
+        self.total_allocated -= released;
+
+        released
+    }
+
     pub(crate) fn taken_buffer_count(&self) -> u32 {
         self.buffers
             .iter()
@@ -41,3 +63,31 @@ impl BufferPool {
             .count() as u32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_keeps_taken_buffers() {
+        let mut pool = BufferPool::new(16, 4);
+        let taken = pool.take();
+
+        assert_eq!(pool.release_free(), 3);
+        assert_eq!(pool.taken_buffer_count(), 1);
+
+        // returned buffers are reused, the pool grows again on demand
+        drop(taken);
+        let first = pool.take();
+        let _second = pool.take();
+        assert_eq!(pool.taken_buffer_count(), 2);
+        assert_eq!(pool.total_allocated, 2);
+
+        // a reused buffer grows to the new size
+        drop(first);
+        pool.grow(64);
+        pool.grow(32);
+        assert_eq!(pool.take().lock().len(), 64);
+        assert_eq!(pool.total_allocated, 2);
+    }
+}
@@ -0,0 +1,136 @@
+use std::path::Path;
+
+use anyhow::bail;
+
+use crate::palette::Palette;
+use crate::view_state::ViewState;
+
+/// A file dropped on the window, read off the event loop thread.
+#[derive(Debug)]
+pub enum DroppedFile {
+    /// A saved view with the palette it names, if that one could be read. Boxed, the view
+    /// outgrows the other events the file travels among.
+    View(Box<ViewState>, Option<Palette>),
+    Palette(Palette),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    View,
+    Palette,
+    // JSON is the format of palettes and of hand-written views alike
+    ViewOrPalette,
+}
+
+impl Kind {
+    fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "toml" => Some(Kind::View),
+            "png" => Some(Kind::Palette),
+            "json" => Some(Kind::ViewOrPalette),
+            _ => None,
+        }
+    }
+}
+
+impl DroppedFile {
+    /// Whether `load` knows the extension, the others are ignored without reading them.
+    pub fn is_supported(path: &Path) -> bool {
+        Kind::of(path).is_some()
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        match Kind::of(path) {
+            Some(Kind::View) => Ok(Self::view(ViewState::from_file(path)?)),
+            Some(Kind::Palette) => Ok(DroppedFile::Palette(Palette::from_file(path)?)),
+            Some(Kind::ViewOrPalette) => match ViewState::from_file(path) {
+                Ok(view) => Ok(Self::view(view)),
+                Err(view_error) => match Palette::from_file(path) {
+                    Ok(palette) => Ok(DroppedFile::Palette(palette)),
+                    Err(palette_error) => bail!(
+                        "{} is neither a view ({:#}) nor a palette ({:#})",
+                        path.display(),
+                        view_error,
+                        palette_error
+                    ),
+                },
+            },
+            None => bail!("{} is neither a view nor a palette", path.display()),
+        }
+    }
+
+    // a broken palette keeps the current one, the view is still worth going to
+    fn view(view: ViewState) -> Self {
+        let palette = view
+            .palette
+            .as_deref()
+            .and_then(|path| match Palette::from_file(path) {
+                Ok(palette) => Some(palette),
+                Err(error) => {
+                    tracing::warn!("{:#}, keeping the current palette", error);
+                    None
+                }
+            });
+
+        DroppedFile::View(Box::new(view), palette)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn files_load_by_extension() {
+        assert!(matches!(
+            DroppedFile::load(Path::new("palettes/sunset.json")).unwrap(),
+            DroppedFile::Palette(_)
+        ));
+        assert!(matches!(
+            DroppedFile::load(Path::new("palette.png")).unwrap(),
+            DroppedFile::Palette(_)
+        ));
+
+        let dir = std::env::temp_dir().join(format!("fractal_dropped_file_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let view = ViewState {
+            center_x: -0.75,
+            center_y: 0.1,
+            height: 0.01,
+            iteration_factor: 1.0,
+            power: 2.0,
+            escape_metric: Default::default(),
+            coloring_mode: Default::default(),
+            palette_mapping: Default::default(),
+            kind: Default::default(),
+            julia_c: None,
+            rotation: 0.0,
+            palette: Some(PathBuf::from("palettes/sunset.json")),
+        };
+        let toml_path = dir.join("view.toml");
+        view.save(&toml_path).unwrap();
+        let json_path = dir.join("view.json");
+        std::fs::write(&json_path, serde_json::to_string(&view).unwrap()).unwrap();
+        for path in [toml_path, json_path] {
+            match DroppedFile::load(&path).unwrap() {
+                DroppedFile::View(loaded, palette) => {
+                    assert_eq!(*loaded, view);
+                    assert!(palette.is_some());
+                }
+                DroppedFile::Palette(_) => panic!("{} loaded as a palette", path.display()),
+            }
+        }
+
+        let broken_path = dir.join("broken.json");
+        std::fs::write(&broken_path, "{}").unwrap();
+        assert!(DroppedFile::load(&broken_path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!DroppedFile::is_supported(Path::new("notes.txt")));
+        assert!(!DroppedFile::is_supported(Path::new("no_extension")));
+        assert!(DroppedFile::is_supported(Path::new("VIEW.TOML")));
+    }
+}
@@ -1,3 +1,53 @@
+use glam::Vec3;
+#[cfg(not(target_arch = "wasm32"))]
+use tracing_subscriber::EnvFilter;
+
+// wgpu logs every adapter and resource at info, and its backends report failing to probe
+// drivers that aren't used in the end
+const DEFAULT_LOG_FILTER: &str = "info,wgpu_core=warn,wgpu_hal=off,naga=warn";
+
+/// Logs to stderr, filtered by `FRACTAL_LOG` or else `RUST_LOG` in `EnvFilter` syntax,
+/// e.g. `FRACTAL_LOG=fractal=debug` for every tile and `fractal=trace` for every decision of
+/// the tile scheduler. Info and above by default.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init_tracing() {
+    let directives = ["FRACTAL_LOG", "RUST_LOG"]
+        .into_iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()));
+    let mut invalid = None;
+    let filter = match directives.as_deref().map(EnvFilter::try_new) {
+        Some(Ok(filter)) => filter,
+        Some(Err(error)) => {
+            invalid = Some(error);
+            EnvFilter::new(DEFAULT_LOG_FILTER)
+        }
+        None => EnvFilter::new(DEFAULT_LOG_FILTER),
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+    if let Some(error) = invalid {
+        tracing::warn!(
+            "Ignoring the log filter {:?}: {}",
+            directives.unwrap(),
+            error
+        );
+    }
+}
+
+/// Logs info and above to the browser console, panics included.
+#[cfg(target_arch = "wasm32")]
+pub fn init_tracing() {
+    console_error_panic_hook::set_once();
+    tracing_wasm::set_as_global_default_with_config(
+        tracing_wasm::WASMLayerConfigBuilder::new()
+            .set_max_level(tracing::Level::INFO)
+            .build(),
+    );
+}
+
 pub fn is_test_build() -> bool {
     cfg!(test)
 }
@@ -5,3 +55,201 @@ pub fn is_test_build() -> bool {
 pub fn is_debug_build() -> bool {
     cfg!(debug_assertions)
 }
+
+// smaller tiles drown in per-tile overhead, larger ones make the first frame wait
+pub(crate) const TILE_SIZE_RANGE: std::ops::RangeInclusive<u32> = 16..=512;
+
+/// Tile size override from `FRACTAL_TILE_SIZE`, to compare latency against per-tile overhead.
+pub fn tile_size() -> Option<u32> {
+    let value = std::env::var("FRACTAL_TILE_SIZE").ok()?;
+    let tile_size = parse_tile_size(&value);
+    if tile_size.is_none() {
+        tracing::warn!(
+            "Ignoring FRACTAL_TILE_SIZE={}, expected a power of two from {} to {}",
+            value,
+            TILE_SIZE_RANGE.start(),
+            TILE_SIZE_RANGE.end()
+        );
+    }
+
+    tile_size
+}
+
+pub(crate) fn parse_tile_size(value: &str) -> Option<u32> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|tile_size| tile_size.is_power_of_two() && TILE_SIZE_RANGE.contains(tile_size))
+}
+
+/// Interior color override from `FRACTAL_INTERIOR_COLOR` as `rrggbb` hex sRGB, returned as linear rgb.
+pub fn interior_color() -> Option<Vec3> {
+    let value = std::env::var("FRACTAL_INTERIOR_COLOR").ok()?;
+    let color = parse_color(&value);
+    if color.is_none() {
+        tracing::warn!(
+            "Ignoring FRACTAL_INTERIOR_COLOR={}, expected an rrggbb hex color",
+            value
+        );
+    }
+
+    color
+}
+
+pub(crate) fn parse_color(value: &str) -> Option<Vec3> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    let srgb = Vec3::new(channel(0)? as f32, channel(2)? as f32, channel(4)? as f32) / 255.0;
+
+    Some(srgb_to_linear(srgb))
+}
+
+pub(crate) fn srgb_to_linear(srgb: Vec3) -> Vec3 {
+    Vec3::from_array(srgb.to_array().map(|value| {
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    }))
+}
+
+/// Present mode override from `FRACTAL_PRESENT_MODE`: fifo, fifo-relaxed, mailbox or immediate.
+pub fn present_mode() -> Option<wgpu::PresentMode> {
+    let value = std::env::var("FRACTAL_PRESENT_MODE").ok()?;
+    let present_mode = parse_present_mode(&value);
+    if present_mode.is_none() {
+        tracing::warn!(
+            "Ignoring FRACTAL_PRESENT_MODE={}, expected fifo, fifo-relaxed, mailbox or immediate",
+            value
+        );
+    }
+
+    present_mode
+}
+
+pub(crate) fn parse_present_mode(value: &str) -> Option<wgpu::PresentMode> {
+    match value.to_ascii_lowercase().as_str() {
+        "fifo" => Some(wgpu::PresentMode::Fifo),
+        "fifo-relaxed" => Some(wgpu::PresentMode::FifoRelaxed),
+        "mailbox" => Some(wgpu::PresentMode::Mailbox),
+        "immediate" => Some(wgpu::PresentMode::Immediate),
+        _ => None,
+    }
+}
+
+/// Worker thread count override from `FRACTAL_WORKER_THREADS`, see `Settings::worker_count`.
+pub fn worker_threads() -> Option<usize> {
+    let value = std::env::var("FRACTAL_WORKER_THREADS").ok()?;
+    let worker_threads = parse_worker_threads(&value);
+    if worker_threads.is_none() {
+        tracing::warn!(
+            "Ignoring FRACTAL_WORKER_THREADS={}, expected a positive count",
+            value
+        );
+    }
+
+    worker_threads
+}
+
+pub(crate) fn parse_worker_threads(value: &str) -> Option<usize> {
+    value.parse::<usize>().ok().filter(|&count| count > 0)
+}
+
+/// Backend from `FRACTAL_BACKEND`: vulkan, dx12, metal or gl.
+pub fn backends() -> Option<wgpu::Backends> {
+    let value = std::env::var("FRACTAL_BACKEND").ok()?;
+    let backends = parse_backends(&value);
+    if backends.is_none() {
+        tracing::warn!(
+            "Ignoring FRACTAL_BACKEND={}, expected vulkan, dx12, metal or gl",
+            value
+        );
+    }
+
+    backends
+}
+
+fn parse_backends(value: &str) -> Option<wgpu::Backends> {
+    match value.to_ascii_lowercase().as_str() {
+        "vulkan" => Some(wgpu::Backends::VULKAN),
+        "dx12" => Some(wgpu::Backends::DX12),
+        "metal" => Some(wgpu::Backends::METAL),
+        "gl" => Some(wgpu::Backends::GL),
+        _ => None,
+    }
+}
+
+/// Part of the adapter name from `FRACTAL_ADAPTER`, matched case-insensitively.
+pub fn adapter_name() -> Option<String> {
+    std::env::var("FRACTAL_ADAPTER")
+        .ok()
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_size_is_a_bounded_power_of_two() {
+        assert_eq!(parse_tile_size("16"), Some(16));
+        assert_eq!(parse_tile_size("512"), Some(512));
+        assert_eq!(parse_tile_size("8"), None);
+        assert_eq!(parse_tile_size("1024"), None);
+        assert_eq!(parse_tile_size("96"), None);
+        assert_eq!(parse_tile_size("0"), None);
+        assert_eq!(parse_tile_size("large"), None);
+    }
+
+    #[test]
+    fn worker_threads_are_positive() {
+        assert_eq!(parse_worker_threads("1"), Some(1));
+        assert_eq!(parse_worker_threads("32"), Some(32));
+        assert_eq!(parse_worker_threads("0"), None);
+        assert_eq!(parse_worker_threads("-2"), None);
+        assert_eq!(parse_worker_threads("all"), None);
+    }
+
+    #[test]
+    fn backend_names() {
+        assert_eq!(parse_backends("vulkan"), Some(wgpu::Backends::VULKAN));
+        assert_eq!(parse_backends("DX12"), Some(wgpu::Backends::DX12));
+        assert_eq!(parse_backends("metal"), Some(wgpu::Backends::METAL));
+        assert_eq!(parse_backends("gl"), Some(wgpu::Backends::GL));
+        assert_eq!(parse_backends("opengl"), None);
+    }
+
+    #[test]
+    fn present_mode_names() {
+        assert_eq!(parse_present_mode("fifo"), Some(wgpu::PresentMode::Fifo));
+        assert_eq!(
+            parse_present_mode("Fifo-Relaxed"),
+            Some(wgpu::PresentMode::FifoRelaxed)
+        );
+        assert_eq!(
+            parse_present_mode("MAILBOX"),
+            Some(wgpu::PresentMode::Mailbox)
+        );
+        assert_eq!(
+            parse_present_mode("immediate"),
+            Some(wgpu::PresentMode::Immediate)
+        );
+        assert_eq!(parse_present_mode("vsync"), None);
+    }
+
+    #[test]
+    fn interior_color_is_hex_srgb() {
+        assert_eq!(parse_color("000000"), Some(Vec3::ZERO));
+        assert_eq!(parse_color("#ffffff"), Some(Vec3::ONE));
+        let gray = parse_color("808080").unwrap();
+        assert!((gray - Vec3::splat(0.2158605)).abs().max_element() < 1e-6);
+        assert_eq!(parse_color("fff"), None);
+        assert_eq!(parse_color("00ff0g"), None);
+        assert_eq!(parse_color("ééé"), None);
+    }
+}
@@ -1,6 +1,8 @@
+use std::path::PathBuf;
+
 use glam::{IVec2, UVec2};
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum MouseButtons {
     Left,
     Right,
@@ -8,6 +10,8 @@ pub enum MouseButtons {
     Back,
     Forward,
     Other(u8),
+    // a finger on a touch screen, it pans whatever the mouse bindings are
+    Touch,
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -18,23 +22,62 @@ pub enum ElementState {
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Event<UserEvent> {
+    // zero while the window is minimized
     Resized(UVec2),
+    // physical pixels per logical pixel, changes when the window moves to another monitor
+    ScaleFactorChanged(f64),
     WindowClose,
     RedrawFinished,
     MouseWheel(UVec2, f32),
-    MouseMove { position: UVec2, delta: IVec2 },
+    MouseMove {
+        position: UVec2,
+        delta: IVec2,
+    },
     MouseButton(MouseButtons, ElementState, UVec2),
     Custom(UserEvent),
-    TouchpadMagnify(UVec2, f32),
+    // multiplies the visible size of the picture by `scale_factor` around `position`
+    // and moves the point at `position` by `delta` pixels
+    PinchZoom {
+        position: UVec2,
+        delta: IVec2,
+        scale_factor: f64,
+    },
+    // horizontal pan in pixels and zoom in wheel lines of one touchpad scroll
+    TouchpadScroll(UVec2, IVec2, f32),
     KeyboardInput(winit::event::KeyEvent),
+    ModifiersChanged(winit::keyboard::ModifiersState),
+    // keys and buttons released while another window has focus are never reported
+    FocusLost,
+    FileDropped(PathBuf),
     Unknown,
 }
 
-#[derive(PartialEq, Debug, Copy, Clone)]
+/// Mouse cursors of the app, the window owner maps them to the platform ones.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum CursorKind {
+    #[default]
+    Default,
+    Grabbing,
+    Crosshair,
+    ResizeVertical,
+    Progress,
+}
+
+#[derive(PartialEq, Debug, Clone)]
 pub enum EventResult {
     Continue,
     Redraw,
     Exit,
+    SetTitle(String),
+    SetCursor(CursorKind),
+    // the window is owned by the event loop, the app only asks for the switch
+    ToggleFullscreen,
+    // switches the surface to the next supported present mode
+    CyclePresentMode,
+    // handled like a memory warning from the system, for trying it on desktop
+    SimulateMemoryWarning,
+    // opens another window exploring on its own, at the view of this one
+    NewWindow,
 }
 
 impl From<winit::event::ElementState> for ElementState {
@@ -0,0 +1,235 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{UVec2, Vec2};
+
+use crate::RenderContext;
+
+/// Relative contrast from which FXAA smooths an edge, lower values also soften filaments.
+pub const DEFAULT_EDGE_THRESHOLD: f32 = 0.125;
+// dark areas below this contrast are left alone, whatever their relative contrast
+const EDGE_THRESHOLD_MIN: f32 = 1.0 / 32.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Params {
+    texel_size: Vec2,
+    edge_threshold: f32,
+    edge_threshold_min: f32,
+}
+
+/// Post-process anti-aliasing of the finished frame: the screen pass renders into `target_view`
+/// and `apply` draws it onto the surface with band edges smoothed.
+#[derive(Debug)]
+pub struct Fxaa {
+    pub enabled: bool,
+    pub edge_threshold: f32,
+
+    format: wgpu::TextureFormat,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    params_buf: wgpu::Buffer,
+    target: Option<Target>,
+}
+
+// the frame as the screen pass left it, sized like the surface
+#[derive(Debug)]
+struct Target {
+    size: UVec2,
+    view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Fxaa {
+    /// `format` is the view format of the surface, the screen pass renders in it.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, edge_threshold: f32) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: None,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+            label: None,
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(include_str!("fxaa_shader.wgsl").into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let params_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            size: size_of::<Params>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+            label: None,
+        });
+
+        Self {
+            enabled: false,
+            edge_threshold,
+            format,
+            pipeline,
+            bind_group_layout,
+            sampler,
+            params_buf,
+            target: None,
+        }
+    }
+
+    /// Where the frame of `size` pixels goes before `apply`, reallocated when the size changes.
+    pub fn target_view(&mut self, device: &wgpu::Device, size: UVec2) -> &wgpu::TextureView {
+        let size = size.max(UVec2::ONE);
+        if self
+            .target
+            .as_ref()
+            .is_none_or(|target| target.size != size)
+        {
+            self.target = Some(self.create_target(device, size));
+        }
+
+        &self.target.as_ref().unwrap().view
+    }
+
+    /// Frees the intermediate texture, the next frame allocates it again if enabled.
+    pub fn release_target(&mut self) {
+        self.target = None;
+    }
+
+    /// Draws the frame from `target_view` onto `render_info.view`, into its viewport if it has one.
+    pub fn apply(&self, render_info: &RenderContext) {
+        let Some(target) = self.target.as_ref() else {
+            return;
+        };
+
+        let params = Params {
+            texel_size: Vec2::ONE / target.size.as_vec2(),
+            edge_threshold: self.edge_threshold,
+            edge_threshold_min: EDGE_THRESHOLD_MIN,
+        };
+        render_info
+            .queue
+            .write_buffer(&self.params_buf, 0, bytemuck::bytes_of(&params));
+
+        let mut command_encoder = render_info
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: render_info.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&self.pipeline);
+            render_info.apply_viewport(&mut render_pass);
+            render_pass.set_bind_group(0, &target.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        render_info.queue.submit(Some(command_encoder.finish()));
+    }
+
+    fn create_target(&self, device: &wgpu::Device, size: UVec2) -> Target {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+            label: None,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.params_buf.as_entire_binding(),
+                },
+            ],
+            label: None,
+        });
+
+        Target {
+            size,
+            view,
+            bind_group,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+
+struct Params {
+    texel_size: vec2<f32>,
+    // local contrast relative to the brightest neighbor below which a pixel is left alone
+    edge_threshold: f32,
+    // absolute contrast below which dark areas are left alone
+    edge_threshold_min: f32,
+};
+
+
+// how far along an edge the filter reaches, in pixels
+const SPAN_MAX: f32 = 8.0;
+const REDUCE_MUL: f32 = 1.0 / 8.0;
+const REDUCE_MIN: f32 = 1.0 / 128.0;
+
+
+// a single triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var result: VertexOutput;
+    result.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    result.uv = uv;
+
+    return result;
+}
+
+
+@group(0)
+@binding(0)
+var the_sampler: sampler;
+@group(0)
+@binding(1)
+var frame: texture_2d<f32>;
+@group(0)
+@binding(2)
+var<uniform> params: Params;
+
+fn sample(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(frame, the_sampler, uv, 0.0).rgb;
+}
+
+// the frame holds linear values, the square root is close to perceived brightness
+fn luma(color: vec3<f32>) -> f32 {
+    return sqrt(dot(color, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = vertex.uv;
+    let texel = params.texel_size;
+
+    let center = sample(uv);
+    let luma_m = luma(center);
+    let luma_nw = luma(sample(uv + vec2<f32>(-1.0, -1.0) * texel));
+    let luma_ne = luma(sample(uv + vec2<f32>(1.0, -1.0) * texel));
+    let luma_sw = luma(sample(uv + vec2<f32>(-1.0, 1.0) * texel));
+    let luma_se = luma(sample(uv + vec2<f32>(1.0, 1.0) * texel));
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // filaments are thin but contrast with their surroundings less than band edges,
+    // the threshold keeps them sharp
+    if (luma_max - luma_min < max(params.edge_threshold_min, luma_max * params.edge_threshold)) {
+        return vec4<f32>(center, 1.0);
+    }
+
+    // perpendicular to the luma gradient, along the edge
+    var dir = vec2<f32>(
+        (luma_sw + luma_se) - (luma_nw + luma_ne),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let dir_scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * dir_scale, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX)) * texel;
+
+    let near = 0.5 * (sample(uv + dir * (1.0 / 3.0 - 0.5)) + sample(uv + dir * (2.0 / 3.0 - 0.5)));
+    let far = near * 0.5 + 0.25 * (sample(uv - dir * 0.5) + sample(uv + dir * 0.5));
+
+    // the wide blend crossed another edge, the narrow one stays on this one
+    let luma_far = luma(far);
+    if (luma_far < luma_min || luma_far > luma_max) {
+        return vec4<f32>(near, 1.0);
+    }
+
+    return vec4<f32>(far, 1.0);
+}
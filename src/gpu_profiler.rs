@@ -0,0 +1,262 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use web_time::Instant;
+
+/// Parts of a frame timed on the GPU, each by a pair of timestamp queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuScope {
+    /// Texture writes of the tiles finished since the last frame. The data is staged on the CPU
+    /// between the two markers, if the GPU idles meanwhile that counts too.
+    Upload,
+    /// The pass drawing the pages onto the frame.
+    Screen,
+}
+
+impl GpuScope {
+    pub const ALL: [GpuScope; 2] = [GpuScope::Upload, GpuScope::Screen];
+
+    fn begin_index(self) -> u32 {
+        self as u32 * 2
+    }
+
+    fn end_index(self) -> u32 {
+        self as u32 * 2 + 1
+    }
+}
+
+const QUERY_COUNT: u32 = GpuScope::ALL.len() as u32 * 2;
+const QUERY_BUFFER_SIZE: u64 = QUERY_COUNT as u64 * size_of::<u64>() as u64;
+// frames whose timestamps may be on their way back at once, a frame finding no free slot
+// is left out of the averages
+const RING_SIZE: usize = 3;
+const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Times the tile uploads and the screen pass with timestamp queries. Created only on devices
+/// with `Features::TIMESTAMP_QUERY`; the timestamps of a frame are resolved into a ring of
+/// buffers and read back once mapped, usually a frame later.
+#[derive(Debug)]
+pub struct GpuProfiler {
+    query_set: wgpu::QuerySet,
+    resolve_buf: wgpu::Buffer,
+    readback: [Readback; RING_SIZE],
+    // nanoseconds per timestamp tick
+    timestamp_period: f64,
+    averages: Averages,
+    last_log: Instant,
+}
+
+#[derive(Debug)]
+struct Readback {
+    buffer: wgpu::Buffer,
+    in_flight: bool,
+    // set by the map callback, `Some(false)` if mapping failed
+    mapped: Arc<Mutex<Option<bool>>>,
+}
+
+// mean durations of the frames read back since the last log
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Averages {
+    totals: [Duration; GpuScope::ALL.len()],
+    frames: u32,
+}
+
+impl GpuProfiler {
+    /// `None` unless the device was created with `Features::TIMESTAMP_QUERY`.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: None,
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        let resolve_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            size: QUERY_BUFFER_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+            label: None,
+        });
+        let readback = std::array::from_fn(|_| Readback {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                size: QUERY_BUFFER_SIZE,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+                label: None,
+            }),
+            in_flight: false,
+            mapped: Arc::default(),
+        });
+
+        Some(Self {
+            query_set,
+            resolve_buf,
+            readback,
+            timestamp_period: queue.get_timestamp_period() as f64,
+            averages: Averages::default(),
+            last_log: Instant::now(),
+        })
+    }
+
+    /// Timestamps around a render pass timed as `scope`.
+    pub fn render_pass_writes(&self, scope: GpuScope) -> wgpu::RenderPassTimestampWrites<'_> {
+        wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(scope.begin_index()),
+            end_of_pass_write_index: Some(scope.end_index()),
+        }
+    }
+
+    /// Submits an empty pass writing the begin or end timestamp of `scope`, for work that has
+    /// no pass of its own. Queue writes run at the start of the next submission, so a marker
+    /// submitted after them is ordered behind them.
+    pub fn write_marker(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scope: GpuScope,
+        end: bool,
+    ) {
+        let index = if end {
+            scope.end_index()
+        } else {
+            scope.begin_index()
+        };
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: None,
+            timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: Some(index),
+                end_of_pass_write_index: None,
+            }),
+        });
+        queue.submit(Some(command_encoder.finish()));
+    }
+
+    /// Resolves the timestamps of the frame just rendered, reads back the ones that arrived
+    /// and logs the averages now and then.
+    pub fn end_frame(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.collect(device);
+        self.resolve(device, queue);
+
+        if self.last_log.elapsed() >= LOG_INTERVAL {
+            self.last_log = Instant::now();
+            let averages = std::mem::take(&mut self.averages);
+            if let Some([upload, screen]) = averages.means() {
+                tracing::info!(
+                    frames = averages.frames,
+                    upload_ms = upload.as_secs_f64() * 1000.0,
+                    screen_ms = screen.as_secs_f64() * 1000.0,
+                    "GPU frame time"
+                );
+            }
+        }
+    }
+
+    fn resolve(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let Some(readback) = self
+            .readback
+            .iter_mut()
+            .find(|readback| !readback.in_flight)
+        else {
+            return;
+        };
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        command_encoder.resolve_query_set(&self.query_set, 0..QUERY_COUNT, &self.resolve_buf, 0);
+        command_encoder.copy_buffer_to_buffer(
+            &self.resolve_buf,
+            0,
+            &readback.buffer,
+            0,
+            QUERY_BUFFER_SIZE,
+        );
+        queue.submit(Some(command_encoder.finish()));
+
+        readback.in_flight = true;
+        let mapped = readback.mapped.clone();
+        readback
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *mapped.lock() = Some(result.is_ok());
+            });
+    }
+
+    fn collect(&mut self, device: &wgpu::Device) {
+        device.poll(wgpu::Maintain::Poll);
+
+        for readback in self
+            .readback
+            .iter_mut()
+            .filter(|readback| readback.in_flight)
+        {
+            let Some(mapped) = readback.mapped.lock().take() else {
+                continue;
+            };
+            if mapped {
+                let timestamps: Vec<u64> =
+                    bytemuck::pod_collect_to_vec(&readback.buffer.slice(..).get_mapped_range());
+                self.averages.record(&timestamps, self.timestamp_period);
+                readback.buffer.unmap();
+            }
+            readback.in_flight = false;
+        }
+    }
+}
+
+impl Averages {
+    // `timestamps` holds the begin and end tick of every scope
+    fn record(&mut self, timestamps: &[u64], timestamp_period: f64) {
+        let mut durations = [Duration::ZERO; GpuScope::ALL.len()];
+        for (duration, scope) in durations.iter_mut().zip(GpuScope::ALL) {
+            let begin = timestamps[scope.begin_index() as usize];
+            let end = timestamps[scope.end_index() as usize];
+            // unwritten queries read as zero, and some drivers reset the counter on idle
+            if begin == 0 || end < begin {
+                return;
+            }
+            *duration = Duration::from_nanos(((end - begin) as f64 * timestamp_period) as u64);
+        }
+
+        for (total, duration) in self.totals.iter_mut().zip(durations) {
+            *total += duration;
+        }
+        self.frames += 1;
+    }
+
+    fn means(&self) -> Option<[Duration; GpuScope::ALL.len()]> {
+        (self.frames > 0).then(|| self.totals.map(|total| total / self.frames))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_skip_invalid_frames() {
+        let mut averages = Averages::default();
+        assert_eq!(averages.means(), None);
+
+        // 1.5ns per tick
+        averages.record(&[1000, 3000, 4000, 8000], 1.5);
+        averages.record(&[10000, 11000, 12000, 12000], 1.5);
+        // a scope that was never written, and a counter that went backwards
+        averages.record(&[0, 0, 4000, 8000], 1.5);
+        averages.record(&[1000, 3000, 8000, 4000], 1.5);
+
+        assert_eq!(averages.frames, 2);
+        assert_eq!(
+            averages.means(),
+            Some([Duration::from_nanos(2250), Duration::from_nanos(3000)])
+        );
+    }
+}
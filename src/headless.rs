@@ -0,0 +1,433 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, ensure, Context};
+use glam::{DVec2, UVec2};
+use pollster::FutureExt;
+
+use crate::env;
+use crate::mandel_texture::MandelTexture;
+use crate::math::DRect;
+use crate::palette::Palette;
+use crate::presets::Preset;
+use crate::settings::Settings;
+use crate::software;
+use crate::tiled_fractal_app::{DEFAULT_CENTER, DEFAULT_FRAME_HEIGHT};
+use crate::RenderContext;
+
+pub const USAGE: &str = "Usage: fractal [--palette palette.json] [--preset name] [--fresh] [--present-mode fifo|fifo-relaxed|mailbox|immediate] [--stats stats.json] [--threads N] [--size WxH [--center-x X] [--center-y Y] [--zoom Z] [--rotation DEGREES] [--out out.png] [--software]]";
+
+const TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Args {
+    /// Color stops replacing the built-in palette, see `Palette::from_file`.
+    pub palette: Option<PathBuf>,
+    /// Where the interactive app flies to after starting, headless renders start from it.
+    pub preset: Option<&'static Preset>,
+    /// Starts the interactive app at the default view instead of the one it was closed at.
+    pub fresh: bool,
+    /// Overrides the `present_mode` setting of the interactive app.
+    pub present_mode: Option<wgpu::PresentMode>,
+    /// Where the interactive app writes the `SessionStats` of the session on exit.
+    pub stats: Option<PathBuf>,
+    /// Overrides the `worker_threads` setting.
+    pub worker_threads: Option<usize>,
+    /// `None` without `--size`, the app then runs interactively.
+    pub headless: Option<HeadlessArgs>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadlessArgs {
+    pub size: UVec2,
+    pub center: DVec2,
+    /// Magnification relative to the initial view of the interactive app.
+    pub zoom: f64,
+    /// Radians the image is turned by counterclockwise about its center, like the rotate key
+    /// turns the view.
+    pub rotation: f64,
+    pub out: PathBuf,
+    /// Renders on the CPU only, for machines without a GPU.
+    pub software: bool,
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Args> {
+    let mut palette = None;
+    let mut preset = None;
+    let mut fresh = false;
+    let mut present_mode = None;
+    let mut stats = None;
+    let mut worker_threads = None;
+    let mut size = None;
+    let mut center = DEFAULT_CENTER;
+    let mut zoom = 1.0f64;
+    let mut rotation = 0.0f64;
+    let mut out = PathBuf::from("fractal.png");
+    let mut software = false;
+    let mut has_headless_args = false;
+
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        has_headless_args |= !matches!(
+            flag.as_str(),
+            "--palette" | "--preset" | "--fresh" | "--present-mode" | "--stats" | "--threads"
+        );
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("Missing value for {}", flag))
+        };
+
+        match flag.as_str() {
+            "--palette" => palette = Some(PathBuf::from(value()?)),
+            // later --center-x, --center-y and --zoom adjust the preset
+            "--preset" => {
+                let found = Preset::find(&value()?)?;
+                center = found.center;
+                zoom = found.zoom();
+                preset = Some(found);
+            }
+            "--fresh" => fresh = true,
+            "--present-mode" => {
+                let value = value()?;
+                present_mode = Some(env::parse_present_mode(&value).ok_or_else(|| {
+                    anyhow!(
+                        "Invalid --present-mode {}, expected fifo, fifo-relaxed, mailbox or immediate",
+                        value
+                    )
+                })?);
+            }
+            "--stats" => stats = Some(PathBuf::from(value()?)),
+            "--threads" => {
+                let value = value()?;
+                worker_threads = Some(env::parse_worker_threads(&value).ok_or_else(|| {
+                    anyhow!("Invalid --threads {}, expected a positive count", value)
+                })?);
+            }
+            "--size" => size = Some(parse_size(&value()?)?),
+            "--center-x" => center.x = value()?.parse().context("Invalid --center-x")?,
+            "--center-y" => center.y = value()?.parse().context("Invalid --center-y")?,
+            "--zoom" => zoom = value()?.parse().context("Invalid --zoom")?,
+            "--rotation" => {
+                let degrees: f64 = value()?.parse().context("Invalid --rotation")?;
+                rotation = degrees.to_radians();
+            }
+            "--out" => out = PathBuf::from(value()?),
+            "--software" => software = true,
+            _ => bail!("Unknown argument {}", flag),
+        }
+    }
+
+    let Some(size) = size else {
+        if has_headless_args {
+            bail!("--size is required");
+        }
+        return Ok(Args {
+            palette,
+            preset,
+            fresh,
+            present_mode,
+            stats,
+            worker_threads,
+            headless: None,
+        });
+    };
+    if zoom.is_nan() || zoom <= 0.0 {
+        bail!("--zoom must be positive");
+    }
+    if !rotation.is_finite() {
+        bail!("--rotation must be finite");
+    }
+    // the CPU renderer fills an upright grid
+    if software && rotation != 0.0 {
+        bail!("--rotation is not supported with --software");
+    }
+
+    Ok(Args {
+        palette,
+        preset,
+        fresh,
+        present_mode,
+        stats,
+        worker_threads,
+        headless: Some(HeadlessArgs {
+            size,
+            center,
+            zoom,
+            rotation,
+            out,
+            software,
+        }),
+    })
+}
+
+fn parse_size(value: &str) -> anyhow::Result<UVec2> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| anyhow!("Invalid --size {}, expected WxH", value))?;
+    let size = UVec2::new(
+        width.parse().context("Invalid --size width")?,
+        height.parse().context("Invalid --size height")?,
+    );
+    if size.min_element() == 0 {
+        bail!("--size must not be empty");
+    }
+
+    Ok(size)
+}
+
+/// Renders one image with the same pipeline as the window and writes it to `args.out`.
+pub fn render(args: &HeadlessArgs, settings: &Settings, palette: &Palette) -> anyhow::Result<()> {
+    if args.software {
+        let image = software::render(
+            frame_rect(args),
+            args.size,
+            palette,
+            settings.palette_mapping,
+            settings.interior_color,
+        )?;
+        image.save(&args.out)?;
+        return Ok(());
+    }
+
+    // WGPU_BACKEND restricts the backends, by default GL is tried on machines without
+    // a Vulkan or Metal driver
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::from_env().unwrap_or_else(crate::instance_backends),
+        flags: Default::default(),
+        backend_options: Default::default(),
+    });
+    let adapter = crate::request_adapter(&instance, None, wgpu::PowerPreference::HighPerformance)
+        .ok_or_else(|| anyhow!(crate::NO_ADAPTER_HELP))?;
+    let (device, queue) = crate::request_device(&adapter, false).block_on()?;
+
+    let max_size = device.limits().max_texture_dimension_2d;
+    if args.size.max_element() > max_size {
+        bail!(
+            "--size {}x{} exceeds the largest image the GPU can render, {}x{}",
+            args.size.x,
+            args.size.y,
+            max_size,
+            max_size
+        );
+    }
+
+    let surface_config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: TARGET_FORMAT,
+        width: args.size.x,
+        height: args.size.y,
+        present_mode: wgpu::PresentMode::Fifo,
+        desired_maximum_frame_latency: 2,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats: vec![TARGET_FORMAT],
+    };
+    let target = device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: args.size.x,
+            height: args.size.y,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: TARGET_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+        label: None,
+    });
+    let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut mandel_texture = MandelTexture::new(
+        &device,
+        &queue,
+        &surface_config,
+        args.size,
+        // there is a single frame at time zero, fading would leave the tiles transparent;
+        // it is only taken once all tiles are there, which the overview and the pyramid would
+        // delay
+        &Settings {
+            tile_fade: false,
+            overview_fill: false,
+            tile_pyramid: false,
+            ..settings.clone()
+        },
+        palette,
+    )?;
+
+    // computed in one go rather than streamed, the image is the same on every run
+    let frame_rect = frame_rect(args);
+    mandel_texture.set_rotation(args.rotation);
+    let max_iter = mandel_texture.max_iters_for(frame_rect);
+    mandel_texture.compute_frame_blocking(frame_rect, max_iter)?;
+
+    let render_context = RenderContext {
+        device: &device,
+        queue: &queue,
+        view: &view,
+        time: 0.0,
+        profiler: None,
+        viewport: None,
+    };
+    mandel_texture.render(&render_context);
+    ensure!(
+        mandel_texture.is_frame_complete(frame_rect),
+        "The image was taken before all of its tiles were there"
+    );
+
+    let image = read_texture(&device, &queue, &target, args.size)?;
+    image.save(&args.out)?;
+
+    Ok(())
+}
+
+fn frame_rect(args: &HeadlessArgs) -> DRect {
+    let aspect = DVec2::new(args.size.x as f64 / args.size.y as f64, 1.0);
+    DRect::from_center_size(args.center, aspect * DEFAULT_FRAME_HEIGHT / args.zoom)
+}
+
+fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    size: UVec2,
+) -> anyhow::Result<image::RgbaImage> {
+    let row_size = size.x * 4;
+    let padded_row_size = row_size.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        size: (padded_row_size * size.y) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+        label: None,
+    });
+
+    let mut command_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    command_encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_size),
+                rows_per_image: Some(size.y),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(Some(command_encoder.finish()));
+
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |_| {});
+    device.poll(wgpu::Maintain::Wait).panic_on_timeout();
+
+    let data = slice.get_mapped_range();
+    let pixels = data
+        .chunks(padded_row_size as usize)
+        .flat_map(|row| &row[..row_size as usize])
+        .copied()
+        .collect();
+
+    image::RgbaImage::from_raw(size.x, size.y, pixels)
+        .ok_or_else(|| anyhow!("Texture readback has the wrong size"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Option<HeadlessArgs>> {
+        parse_args(args.iter().map(|arg| arg.to_string())).map(|args| args.headless)
+    }
+
+    #[test]
+    fn parse_headless_args() {
+        assert_eq!(parse(&[]).unwrap(), None);
+        // a palette alone keeps the app interactive
+        let args = parse_args(["--palette".to_string(), "a.json".to_string()]).unwrap();
+        assert_eq!(args.palette, Some(PathBuf::from("a.json")));
+        assert_eq!(args.headless, None);
+        assert!(parse(&["--palette"]).is_err());
+
+        let args = parse(&[
+            "--size",
+            "1920x1080",
+            "--center-x",
+            "-0.5",
+            "--zoom",
+            "4",
+            "--out",
+            "a.png",
+            "--software",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(args.size, UVec2::new(1920, 1080));
+        assert_eq!(args.center, DVec2::new(-0.5, DEFAULT_CENTER.y));
+        assert_eq!(args.zoom, 4.0);
+        assert_eq!(args.out, PathBuf::from("a.png"));
+        assert!(args.software);
+        assert_eq!(args.rotation, 0.0);
+
+        let args = parse(&["--size", "10x10", "--rotation", "90"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(args.rotation, std::f64::consts::FRAC_PI_2);
+        assert!(parse(&["--size", "10x10", "--rotation", "inf"]).is_err());
+        assert!(parse(&["--size", "10x10", "--rotation", "15", "--software"]).is_err());
+
+        assert!(parse(&["--zoom", "4"]).is_err());
+        assert!(parse(&["--size", "1920"]).is_err());
+        assert!(parse(&["--size", "0x10"]).is_err());
+        assert!(parse(&["--size", "10x10", "--zoom", "0"]).is_err());
+        assert!(parse(&["--size", "10x10", "--center-x"]).is_err());
+        assert!(parse(&["--size", "10x10", "--bogus"]).is_err());
+
+        // a preset alone keeps the app interactive, with a size it is where the render starts
+        let args = parse_args(["--preset".to_string(), "seahorse".to_string()]).unwrap();
+        assert_eq!(args.preset.unwrap().name, "seahorse");
+        assert_eq!(args.headless, None);
+        let args = parse(&[
+            "--preset",
+            "minibrot",
+            "--size",
+            "10x10",
+            "--center-y",
+            "0.5",
+        ])
+        .unwrap()
+        .unwrap();
+        let minibrot = Preset::find("minibrot").unwrap();
+        assert_eq!(args.center, DVec2::new(minibrot.center.x, 0.5));
+        assert_eq!(args.zoom, minibrot.zoom());
+        assert!(parse(&["--preset", "nowhere"]).is_err());
+
+        // --fresh only concerns the interactive app
+        let args = parse_args(["--fresh".to_string()]).unwrap();
+        assert!(args.fresh && args.headless.is_none());
+        assert!(!parse_args([]).unwrap().fresh);
+
+        let args = parse_args(["--present-mode".to_string(), "Immediate".to_string()]).unwrap();
+        assert_eq!(args.present_mode, Some(wgpu::PresentMode::Immediate));
+        assert_eq!(args.headless, None);
+        assert!(parse_args(["--present-mode".to_string(), "vsync".to_string()]).is_err());
+
+        let args = parse_args(["--stats".to_string(), "stats.json".to_string()]).unwrap();
+        assert_eq!(args.stats, Some(PathBuf::from("stats.json")));
+        assert_eq!(args.headless, None);
+        assert!(parse_args([]).unwrap().stats.is_none());
+
+        // the thread count applies to headless renders as well
+        let args = parse_args(["--threads".to_string(), "3".to_string()]).unwrap();
+        assert_eq!(args.worker_threads, Some(3));
+        assert_eq!(args.headless, None);
+        let args = parse_args(
+            ["--threads", "2", "--size", "10x10"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        )
+        .unwrap();
+        assert_eq!(args.worker_threads, Some(2));
+        assert!(args.headless.is_some());
+        assert!(parse_args(["--threads".to_string(), "0".to_string()]).is_err());
+    }
+}
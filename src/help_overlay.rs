@@ -0,0 +1,346 @@
+use std::convert::Infallible;
+
+use embedded_graphics::mono_font::ascii::FONT_7X13;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
+use glam::UVec2;
+use winit::keyboard::KeyCode;
+
+use crate::RenderContext;
+
+const TITLE: &str = "Keys, any key hides this list";
+// logical pixels around the text, multiplied by the scale factor of the monitor like the font
+const PADDING: u32 = 8;
+// characters between the keys and what they do
+const COLUMN_GAP: usize = 2;
+
+/// The list of keys and what they do, drawn over the top left corner of the window while
+/// `visible`.
+#[derive(Debug)]
+pub struct HelpOverlay {
+    pub visible: bool,
+
+    shortcuts: Vec<(String, &'static str)>,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    // the text rasterized at the scale it was last drawn at
+    target: Option<Target>,
+}
+
+#[derive(Debug)]
+struct Target {
+    scale: u32,
+    size: UVec2,
+    bind_group: wgpu::BindGroup,
+}
+
+impl HelpOverlay {
+    /// `format` is the view format of the surface, `shortcuts` are the keys and what they do.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        shortcuts: Vec<(String, &'static str)>,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+            label: None,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+            label: None,
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(include_str!("help_overlay_shader.wgsl").into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            visible: false,
+            shortcuts,
+            pipeline,
+            bind_group_layout,
+            target: None,
+        }
+    }
+
+    /// Draws the list over `render_info.view`, which is `window_size` pixels, when visible.
+    pub fn render(&mut self, render_info: &RenderContext, window_size: UVec2, scale_factor: f64) {
+        if !self.visible || window_size.min_element() == 0 {
+            return;
+        }
+
+        let scale = (scale_factor.round() as u32).max(1);
+        if self
+            .target
+            .as_ref()
+            .is_none_or(|target| target.scale != scale)
+        {
+            self.target = Some(self.create_target(render_info, scale));
+        }
+        let target = self.target.as_ref().unwrap();
+        // cut off by a window too small for all of it
+        let size = target.size.min(window_size);
+
+        let mut command_encoder = render_info
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: render_info.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_scissor_rect(0, 0, size.x, size.y);
+            render_pass.set_bind_group(0, &target.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        render_info.queue.submit(Some(command_encoder.finish()));
+    }
+
+    fn create_target(&self, render_info: &RenderContext, scale: u32) -> Target {
+        let (size, mask) = rasterize(&self.shortcuts, scale);
+        let extent = wgpu::Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        };
+        let texture = render_info.device.create_texture(&wgpu::TextureDescriptor {
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+            label: None,
+        });
+        render_info.queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &mask,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size.x),
+                rows_per_image: None,
+            },
+            extent,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = render_info
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                }],
+                label: None,
+            });
+
+        Target {
+            scale,
+            size,
+            bind_group,
+        }
+    }
+}
+
+/// How the list shows `key`, the character it types where that is shorter than its name.
+pub fn key_label(key: KeyCode) -> String {
+    let label = match key {
+        KeyCode::Backquote => "`",
+        KeyCode::Backslash => "\\",
+        KeyCode::BracketLeft => "[",
+        KeyCode::BracketRight => "]",
+        KeyCode::Comma => ",",
+        KeyCode::Equal => "=",
+        KeyCode::Minus => "-",
+        KeyCode::Period => ".",
+        KeyCode::Quote => "'",
+        KeyCode::Semicolon => ";",
+        KeyCode::Slash => "/",
+        key => {
+            let name = format!("{:?}", key);
+            return match name.strip_prefix("Key").or(name.strip_prefix("Digit")) {
+                Some(char) if char.len() == 1 => char.to_string(),
+                _ => name,
+            };
+        }
+    };
+
+    label.to_string()
+}
+
+// the list as coverage, a byte per pixel with the first row at the top, and its size with the
+// font and padding `scale` times their size
+fn rasterize(shortcuts: &[(String, &'static str)], scale: u32) -> (UVec2, Vec<u8>) {
+    let key_width = shortcuts
+        .iter()
+        .map(|(keys, _)| keys.len())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<String> = std::iter::once(TITLE.to_string())
+        .chain(std::iter::once(String::new()))
+        .chain(shortcuts.iter().map(|(keys, description)| {
+            format!(
+                "{:<width$}{}",
+                keys,
+                description,
+                width = key_width + COLUMN_GAP
+            )
+        }))
+        .collect();
+
+    let char_size = FONT_7X13.character_size;
+    let columns = lines.iter().map(|line| line.len()).max().unwrap_or(0) as u32;
+    let text_size = UVec2::new(
+        columns * (char_size.width + FONT_7X13.character_spacing),
+        lines.len() as u32 * char_size.height,
+    );
+    let mut mask = Mask {
+        size: text_size + 2 * PADDING,
+        pixels: vec![0; ((text_size.x + 2 * PADDING) * (text_size.y + 2 * PADDING)) as usize],
+    };
+    let style = MonoTextStyle::new(&FONT_7X13, BinaryColor::On);
+    for (row, line) in lines.iter().enumerate() {
+        let position = Point::new(
+            PADDING as i32,
+            (PADDING + row as u32 * char_size.height) as i32,
+        );
+        Text::with_baseline(line, position, style, Baseline::Top)
+            .draw(&mut mask)
+            .unwrap();
+    }
+
+    let size = mask.size * scale;
+    let pixels = (0..size.y)
+        .flat_map(|y| (0..size.x).map(move |x| (x, y)))
+        .map(|(x, y)| mask.pixels[((y / scale) * mask.size.x + x / scale) as usize])
+        .collect();
+
+    (size, pixels)
+}
+
+// what the font is drawn into
+struct Mask {
+    size: UVec2,
+    pixels: Vec<u8>,
+}
+
+impl OriginDimensions for Mask {
+    fn size(&self) -> Size {
+        Size::new(self.size.x, self.size.y)
+    }
+}
+
+impl DrawTarget for Mask {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            let (Ok(x), Ok(y)) = (u32::try_from(point.x), u32::try_from(point.y)) else {
+                continue;
+            };
+            if x < self.size.x && y < self.size.y {
+                self.pixels[(y * self.size.x + x) as usize] = if color.is_on() { 255 } else { 0 };
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_list_scales_with_the_monitor() {
+        assert_eq!(key_label(KeyCode::KeyQ), "Q");
+        assert_eq!(key_label(KeyCode::Digit7), "7");
+        assert_eq!(key_label(KeyCode::Semicolon), ";");
+        assert_eq!(key_label(KeyCode::PageUp), "PageUp");
+        assert_eq!(key_label(KeyCode::F1), "F1");
+
+        let shortcuts = vec![
+            ("Q".to_string(), "Antialiasing on or off"),
+            ("PageUp".to_string(), "More iterations"),
+        ];
+        let (size, mask) = rasterize(&shortcuts, 1);
+        assert_eq!(mask.len(), size.element_product() as usize);
+        assert_eq!(size.y, 4 * 13 + 2 * PADDING);
+        // the descriptions line up after the longest key
+        let columns = "PageUp".len() + COLUMN_GAP + "Antialiasing on or off".len();
+        assert_eq!(size.x, columns.max(TITLE.len()) as u32 * 7 + 2 * PADDING);
+        assert!(mask.contains(&255));
+        assert!(mask[..(PADDING * size.x) as usize]
+            .iter()
+            .all(|&coverage| coverage == 0));
+
+        let (double_size, double_mask) = rasterize(&shortcuts, 2);
+        assert_eq!(double_size, size * 2);
+        let texel = |mask: &[u8], width: u32, x: u32, y: u32| mask[(y * width + x) as usize];
+        for (x, y) in [(10, 10), (20, 30), (size.x - 1, size.y - 1)] {
+            assert_eq!(
+                texel(&double_mask, double_size.x, 2 * x + 1, 2 * y),
+                texel(&mask, size.x, x, y)
+            );
+        }
+    }
+}
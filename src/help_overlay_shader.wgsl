@@ -0,0 +1,34 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+
+// a single triangle covering the screen, the scissor rect cuts the list out of it
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var result: VertexOutput;
+    result.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+
+    return result;
+}
+
+
+@group(0)
+@binding(0)
+var coverage: texture_2d<f32>;
+
+// dark enough behind the text to read it over any part of the fractal
+const TEXT_COLOR: vec4<f32> = vec4<f32>(0.95, 0.95, 0.95, 1.0);
+const BACKGROUND_COLOR: vec4<f32> = vec4<f32>(0.0, 0.0, 0.0, 0.75);
+
+// the list has a texel per pixel from the top left corner of the window
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    let last = vec2<i32>(textureDimensions(coverage)) - 1;
+    let texel = min(vec2<i32>(vertex.position.xy), last);
+    let value = textureLoad(coverage, texel, 0).r;
+
+    return mix(BACKGROUND_COLOR, TEXT_COLOR, value);
+}
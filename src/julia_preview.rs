@@ -0,0 +1,403 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use glam::{DVec2, UVec2, Vec3};
+use parking_lot::Mutex;
+use web_time::Instant;
+
+use crate::kernel::{compute_frame, CancelToken, KernelOptions, PixelGrid};
+use crate::mandel_texture::calc_max_iters;
+use crate::mandelbrot_simd::{EscapeMetric, FractalKind, Pixel, Precision, SimdKernel};
+use crate::math::{DRect, URect};
+use crate::palette::{Palette, PaletteMapping};
+use crate::runtime::Runtime;
+use crate::software::colorize;
+use crate::RenderContext;
+
+/// Height of the plane the Julia half shows at first, every Julia set of the Mandelbrot set
+/// lies within a radius of 2 around the origin.
+pub const JULIA_FRAME_HEIGHT: f64 = 4.5;
+/// A new set starts at most this often while the parameter keeps changing.
+pub const JULIA_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+// the image has a texel for this many pixels of its half on each axis
+const RESOLUTION_DIVISOR: u32 = 2;
+
+/// What the image depends on besides the parameter and the frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JuliaStyle {
+    pub power: f64,
+    pub palette_mapping: PaletteMapping,
+    pub interior_color: Vec3,
+}
+
+/// The Julia set of the point under the cursor, in the right half of the split view. A single
+/// job on the runtime computes the whole image at a fraction of the resolution of the half and
+/// colors it like the screen shader would; a changed parameter cancels it and starts over, at
+/// most every `JULIA_UPDATE_INTERVAL`, so the set keeps up with the cursor.
+#[derive(Debug)]
+pub struct JuliaPreview {
+    pub enabled: bool,
+    /// The parameter of the set, the point of the Mandelbrot half last under the cursor.
+    pub c: DVec2,
+    /// The part of the plane the half shows.
+    pub frame_rect: DRect,
+
+    palette: Palette,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    target: Option<Target>,
+    cancel_token: CancelToken,
+    // the last job started, None once it has to start over
+    started: Option<(Job, Instant)>,
+    // the redraw that starts a job held back by the interval is already asked for
+    wakeup: bool,
+    // the image of the latest job, taken by the next render
+    finished: Arc<Mutex<Option<image::RgbaImage>>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Job {
+    c: DVec2,
+    frame_rect: DRect,
+    size: UVec2,
+    style: JuliaStyle,
+}
+
+// the last finished image, sized like it
+#[derive(Debug)]
+struct Target {
+    size: UVec2,
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+// the texels of an image for a half of `half_size` pixels
+fn image_size(half_size: UVec2) -> UVec2 {
+    (half_size / RESOLUTION_DIVISOR).max(UVec2::ONE)
+}
+
+impl JuliaPreview {
+    /// `format` is the view format of the surface.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, palette: &Palette) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+            label: None,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+            label: None,
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(include_str!("julia_shader.wgsl").into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            enabled: false,
+            c: DVec2::ZERO,
+            frame_rect: DRect::from_center_size(DVec2::ZERO, DVec2::splat(JULIA_FRAME_HEIGHT)),
+            palette: palette.clone(),
+            pipeline,
+            bind_group_layout,
+            sampler,
+            target: None,
+            cancel_token: CancelToken::new(),
+            started: None,
+            wakeup: false,
+            finished: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The image is computed again with `palette`.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        self.started = None;
+    }
+
+    /// Starts the set of `c` in `frame_rect` for a half of `half_size` pixels, unless the last
+    /// job was for the same or started less than `JULIA_UPDATE_INTERVAL` ago. `on_ready` is
+    /// called from the runtime once the image is there for `render`. Returns how long to wait
+    /// before asking again for a job held back, once per job.
+    pub fn update<F>(
+        &mut self,
+        runtime: &Runtime,
+        half_size: UVec2,
+        style: JuliaStyle,
+        on_ready: F,
+    ) -> Option<Duration>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let job = Job {
+            c: self.c,
+            frame_rect: self.frame_rect,
+            size: image_size(half_size),
+            style,
+        };
+        let now = Instant::now();
+        if let Some((started, start_time)) = self.started {
+            if started == job {
+                return None;
+            }
+            let next_start = start_time + JULIA_UPDATE_INTERVAL;
+            if now < next_start {
+                return (!std::mem::replace(&mut self.wakeup, true)).then(|| next_start - now);
+            }
+        }
+        self.started = Some((job, now));
+        self.wakeup = false;
+
+        let cancel_token = self.cancel_token.next_generation();
+        let palette = self.palette.clone();
+        let finished = self.finished.clone();
+        runtime.spawn_blocking(move || {
+            let Some(image) = compute(job, &palette, &cancel_token) else {
+                return;
+            };
+
+            // a job started meanwhile has the newer image
+            let mut finished = finished.lock();
+            if cancel_token.is_cancelled() {
+                return;
+            }
+            *finished = Some(image);
+            drop(finished);
+            on_ready();
+        });
+
+        None
+    }
+
+    /// Stops the job and frees the image, the next `update` starts over.
+    pub fn stop(&mut self) {
+        self.cancel_token.next_generation();
+        self.started = None;
+        self.target = None;
+        *self.finished.lock() = None;
+    }
+
+    /// Draws the last finished image over the viewport of `render_info`, nothing until the
+    /// first one is there.
+    pub fn render(&mut self, render_info: &RenderContext) {
+        if let Some(image) = self.finished.lock().take() {
+            let size = UVec2::new(image.width(), image.height());
+            if self
+                .target
+                .as_ref()
+                .is_none_or(|target| target.size != size)
+            {
+                self.target = Some(self.create_target(render_info.device, size));
+            }
+
+            render_info.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &self.target.as_ref().unwrap().texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                image.as_raw(),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size.x * 4),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        let Some(target) = self.target.as_ref() else {
+            return;
+        };
+
+        let mut command_encoder = render_info
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: render_info.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&self.pipeline);
+            render_info.apply_viewport(&mut render_pass);
+            render_pass.set_bind_group(0, &target.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        render_info.queue.submit(Some(command_encoder.finish()));
+    }
+
+    fn create_target(&self, device: &wgpu::Device, size: UVec2) -> Target {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // the colors are sRGB encoded like the ones of an export
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+            label: None,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+            ],
+            label: None,
+        });
+
+        Target {
+            size,
+            texture,
+            bind_group,
+        }
+    }
+}
+
+// the colored image of `job`, None if a newer job cancelled it
+fn compute(job: Job, palette: &Palette, cancel_token: &CancelToken) -> Option<image::RgbaImage> {
+    let grid = PixelGrid::new(job.frame_rect, job.size);
+    let kernel = SimdKernel {
+        kind: FractalKind::Julia(job.c),
+        escape_metric: EscapeMetric::default(),
+        precision: Precision::for_pixel_size(grid.pixel_size().max_element()),
+    };
+    let options = KernelOptions {
+        max_iter: calc_max_iters(job.frame_rect),
+        power: job.style.power,
+    };
+
+    let mut values = vec![Pixel::default(); job.size.element_product() as usize];
+    let pixels = URect::from_pos_size(UVec2::ZERO, job.size);
+    compute_frame(
+        &kernel,
+        grid,
+        pixels,
+        options,
+        cancel_token.clone(),
+        &mut values,
+    )
+    .ok()?;
+
+    // kernel rows go upwards, image rows downwards
+    let width = job.size.x as usize;
+    let values: Vec<Pixel> = values.chunks(width).rev().flatten().copied().collect();
+
+    Some(colorize(
+        &values,
+        job.size,
+        palette,
+        job.style.palette_mapping,
+        job.style.interior_color,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mandelbrot_simd::MANDELBROT_POWER;
+
+    #[test]
+    fn image_is_a_fraction_of_its_half() {
+        assert_eq!(image_size(UVec2::new(960, 1080)), UVec2::new(480, 540));
+        assert_eq!(image_size(UVec2::new(1, 0)), UVec2::ONE);
+    }
+
+    #[test]
+    fn cancelled_job_has_no_image() {
+        // the Julia set of 0 is the unit disk
+        let job = Job {
+            c: DVec2::ZERO,
+            frame_rect: DRect::from_center_size(DVec2::ZERO, DVec2::splat(JULIA_FRAME_HEIGHT)),
+            size: UVec2::new(48, 32),
+            style: JuliaStyle {
+                power: MANDELBROT_POWER,
+                palette_mapping: PaletteMapping::default(),
+                interior_color: Vec3::ONE,
+            },
+        };
+        let palette = Palette::builtin();
+        let cancel_token = CancelToken::new();
+
+        let image = compute(job, &palette, &cancel_token).unwrap();
+        assert_eq!(image.dimensions(), (48, 32));
+        assert_eq!(image.get_pixel(24, 16).0, [255, 255, 255, 255]);
+        assert_ne!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+
+        cancel_token.next_generation();
+        assert!(compute(job, &palette, &cancel_token).is_none());
+    }
+}
@@ -0,0 +1,31 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+
+// a single triangle covering the viewport
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var result: VertexOutput;
+    result.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    result.uv = uv;
+
+    return result;
+}
+
+
+@group(0)
+@binding(0)
+var the_sampler: sampler;
+@group(0)
+@binding(1)
+var image: texture_2d<f32>;
+
+// the image is smaller than the viewport, it is stretched over it with linear filtering
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(textureSampleLevel(image, the_sampler, vertex.uv, 0.0).rgb, 1.0);
+}
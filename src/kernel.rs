@@ -0,0 +1,549 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use glam::{DVec2, UVec2};
+use web_time::Instant;
+
+use crate::mandelbrot_simd::{KernelPixel, ESCAPE_RADIUS, MANDELBROT_POWER};
+use crate::math::{DRect, URect};
+
+/// Escape time evaluation of batches of points, the frame and boundary passes are generic over it.
+pub trait FractalKernel {
+    /// Writes the escape count of each of `points` for `z = z^power + c` to `out`.
+    /// Points are complex numbers as (re, im).
+    fn escape<P: KernelPixel>(&self, points: &[DVec2], max_iter: u32, power: f64, out: &mut [P]);
+
+    /// Continues `escape` for points still inside the set after `start_iter` iterations,
+    /// `orbits` holds their last `z` and is updated to the `z` after `max_iter` iterations.
+    /// Zero orbits at `start_iter` zero give the same values as `escape`.
+    fn resume<P: KernelPixel>(
+        &self,
+        points: &[DVec2],
+        orbits: &mut [DVec2],
+        start_iter: u32,
+        max_iter: u32,
+        power: f64,
+        out: &mut [P],
+    );
+}
+
+/// Pixels covering `frame`, pixel (x, y) samples it at the lower left corner of its cell and
+/// rows go from `frame.pos.y` upwards. Parts of the grid computed on their own, like the tiles
+/// of a page or the bands of an export, sample exactly the points of the whole grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelGrid {
+    pub frame: DRect,
+    pub size: UVec2,
+}
+
+impl PixelGrid {
+    pub fn new(frame: DRect, size: UVec2) -> Self {
+        Self { frame, size }
+    }
+
+    /// Every pixel of the grid.
+    pub fn all(&self) -> URect {
+        URect::from_pos_size(UVec2::ZERO, self.size)
+    }
+
+    pub fn point(&self, pixel: UVec2) -> DVec2 {
+        let pixel_width = self.frame.size.x / self.size.x as f64;
+        let cy = self.frame.pos.y + self.frame.size.y * (pixel.y as f64 / self.size.y as f64);
+
+        DVec2::new(pixel.x as f64 * pixel_width + self.frame.pos.x, cy)
+    }
+
+    pub fn pixel_size(&self) -> DVec2 {
+        self.frame.size / self.size.as_dvec2()
+    }
+}
+
+/// What is computed for every pixel. The escape radius is `ESCAPE_RADIUS` for all kernels,
+/// the escape metric is a setting of the kernel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KernelOptions {
+    pub max_iter: u32,
+    /// Exponent of `z = z^power + c`, fractional powers are supported from 2 up.
+    pub power: f64,
+}
+
+/// Last `z` of the pixels of a frame still inside the set after `iterations` iterations,
+/// raising the iteration limit continues them with `resume_frame` instead of starting over.
+#[derive(Debug, Clone, Default)]
+pub struct InteriorOrbits {
+    pub iterations: u32,
+    pixels: Vec<u32>,
+    orbits: Vec<DVec2>,
+}
+
+impl InteriorOrbits {
+    /// Every pixel of a `size` frame before the first iteration.
+    pub fn start(size: UVec2) -> Self {
+        let count = size.element_product();
+        Self {
+            iterations: 0,
+            pixels: (0..count).collect(),
+            orbits: vec![DVec2::ZERO; count as usize],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    /// No pixel is left inside, raising the limit further changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+}
+
+/// Polled between rows of work, a cancelled frame stops with an error.
+pub trait CancelSignal {
+    fn is_cancelled(&self) -> bool;
+}
+
+/// Stops work running on other threads. Tokens cloned from one another share a generation
+/// counter: `next_generation` hands out a token for new work and cancels every older one,
+/// `cancel` stops the work of its own generation only, the newer work carries on.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    current: Arc<AtomicU64>,
+    generation: u64,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token for new work, the ones handed out before are cancelled.
+    pub fn next_generation(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+            generation: self.current.fetch_add(1, Ordering::Relaxed) + 1,
+        }
+    }
+
+    pub fn cancel(&self) {
+        // a newer generation is cancelled by the next one, not by this stale token
+        let _ = self.current.compare_exchange(
+            self.generation,
+            self.generation + 1,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.current.load(Ordering::Relaxed) != self.generation
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+impl CancelSignal for CancelToken {
+    fn is_cancelled(&self) -> bool {
+        CancelToken::is_cancelled(self)
+    }
+}
+
+/// For work that always runs to the end, without the atomic load.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NeverCancelled;
+
+impl CancelSignal for NeverCancelled {
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// `z^power` in polar form, fractional powers take the principal branch.
+pub fn complex_pow(z: DVec2, power: f64) -> DVec2 {
+    let norm = z.length_squared();
+    if norm == 0.0 {
+        return DVec2::ZERO;
+    }
+
+    let radius = norm.powf(power / 2.0);
+    let (sin, cos) = (power * z.y.atan2(z.x)).sin_cos();
+
+    DVec2::new(radius * cos, radius * sin)
+}
+
+/// Computes the `pixels` of `grid` into `buffer`, row by row from the bottom one.
+pub fn compute_frame<K: FractalKernel, P: KernelPixel, C: CancelSignal>(
+    kernel: &K,
+    grid: PixelGrid,
+    pixels: URect,
+    options: KernelOptions,
+    cancel_token: C,
+    buffer: &mut [P],
+) -> anyhow::Result<()> {
+    assert_eq!(buffer.len(), pixels.size.element_product() as usize);
+
+    let now = Instant::now();
+
+    let mut points = vec![DVec2::ZERO; pixels.size.x as usize];
+
+    for (y, row) in buffer.chunks_exact_mut(pixels.size.x as usize).enumerate() {
+        if cancel_token.is_cancelled() {
+            return Err(anyhow!("Cancelled"));
+        }
+
+        for (x, point) in points.iter_mut().enumerate() {
+            *point = grid.point(pixels.pos + UVec2::new(x as u32, y as u32));
+        }
+
+        kernel.escape(&points, options.max_iter, options.power, row);
+    }
+
+    tracing::trace!(
+        elapsed_ms = now.elapsed().as_millis() as u64,
+        pixels = pixels.size.element_product(),
+        "Frame computed"
+    );
+
+    Ok(())
+}
+
+/// Continues the pixels of `interior` up to `options.max_iter`, writes their values to `buffer`
+/// and keeps the ones still inside. Pixels that escaped before keep their values in `buffer`.
+/// From `InteriorOrbits::start` the `pixels` are computed like with `compute_frame`.
+pub fn resume_frame<K: FractalKernel, P: KernelPixel, C: CancelSignal>(
+    kernel: &K,
+    grid: PixelGrid,
+    pixels: URect,
+    options: KernelOptions,
+    cancel_token: C,
+    interior: &mut InteriorOrbits,
+    buffer: &mut [P],
+) -> anyhow::Result<()> {
+    assert_eq!(buffer.len(), pixels.size.element_product() as usize);
+    assert!(
+        interior.iterations <= options.max_iter,
+        "Orbits can only continue to a higher iteration limit"
+    );
+
+    // a row worth of points per batch, like `compute_frame`
+    let width = pixels.size.x;
+    let batch_size = width as usize;
+    let mut points = Vec::with_capacity(batch_size);
+    let mut values = vec![P::default(); batch_size];
+
+    for (indices, orbits) in interior
+        .pixels
+        .chunks(batch_size)
+        .zip(interior.orbits.chunks_mut(batch_size))
+    {
+        if cancel_token.is_cancelled() {
+            return Err(anyhow!("Cancelled"));
+        }
+
+        points.clear();
+        points.extend(
+            indices
+                .iter()
+                .map(|&index| grid.point(pixels.pos + UVec2::new(index % width, index / width))),
+        );
+
+        let values = &mut values[..indices.len()];
+        kernel.resume(
+            &points,
+            orbits,
+            interior.iterations,
+            options.max_iter,
+            options.power,
+            values,
+        );
+        for (&index, &value) in indices.iter().zip(values.iter()) {
+            buffer[index as usize] = value;
+        }
+    }
+
+    let resumed = interior.pixels.len();
+    let mut kept = 0;
+    for i in 0..interior.pixels.len() {
+        if buffer[interior.pixels[i] as usize].value() == 0.0 {
+            interior.pixels[kept] = interior.pixels[i];
+            interior.orbits[kept] = interior.orbits[i];
+            kept += 1;
+        }
+    }
+    interior.pixels.truncate(kept);
+    interior.orbits.truncate(kept);
+    tracing::trace!(
+        from = interior.iterations,
+        to = options.max_iter,
+        resumed,
+        still_inside = kept,
+        "Frame resumed"
+    );
+    interior.iterations = options.max_iter;
+
+    Ok(())
+}
+
+// the 3x3 grid around a pixel without its center, which is the already computed sample
+const SUBPIXEL_OFFSETS: [DVec2; 8] = [
+    DVec2::new(-1.0 / 3.0, -1.0 / 3.0),
+    DVec2::new(0.0, -1.0 / 3.0),
+    DVec2::new(1.0 / 3.0, -1.0 / 3.0),
+    DVec2::new(-1.0 / 3.0, 0.0),
+    DVec2::new(1.0 / 3.0, 0.0),
+    DVec2::new(-1.0 / 3.0, 1.0 / 3.0),
+    DVec2::new(0.0, 1.0 / 3.0),
+    DVec2::new(1.0 / 3.0, 1.0 / 3.0),
+];
+
+/// Re-samples pixels on the set boundary with a 3x3 sub-pixel grid.
+/// A pixel is refined when its value differs from a neighbor by more than `threshold`
+/// or when it lies on the tile border, since its neighbor there belongs to another tile.
+/// Returns the number of refined pixels.
+pub fn refine_boundary<K: FractalKernel, P: KernelPixel, C: CancelSignal>(
+    kernel: &K,
+    grid: PixelGrid,
+    pixels: URect,
+    options: KernelOptions,
+    threshold: u16,
+    cancel_token: C,
+    buffer: &mut [P],
+) -> anyhow::Result<u32> {
+    assert_eq!(buffer.len(), pixels.size.element_product() as usize);
+
+    let pixel_size = grid.pixel_size();
+
+    let width = pixels.size.x;
+    let height = pixels.size.y;
+    let threshold = threshold as f64;
+    let differs = |a: P, b: P| {
+        let (a, b) = (a.value(), b.value());
+        (a == 0.0) != (b == 0.0) || (a - b).abs() > threshold
+    };
+
+    let mut boundary = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let idx = (y * width + x) as usize;
+            let value = buffer[idx];
+
+            let is_border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            let is_boundary = is_border
+                || differs(value, buffer[idx - 1])
+                || differs(value, buffer[idx + 1])
+                || differs(value, buffer[idx - width as usize])
+                || differs(value, buffer[idx + width as usize]);
+
+            if is_boundary {
+                boundary.push((x, y));
+            }
+        }
+    }
+
+    let mut samples = [P::default(); SUBPIXEL_OFFSETS.len()];
+    for (i, &(x, y)) in boundary.iter().enumerate() {
+        if i % width as usize == 0 && cancel_token.is_cancelled() {
+            return Err(anyhow!("Cancelled"));
+        }
+
+        let origin = grid.point(pixels.pos + UVec2::new(x, y));
+        let points = SUBPIXEL_OFFSETS.map(|offset| offset * pixel_size + origin);
+
+        let idx = (y * width + x) as usize;
+        kernel.escape(&points, options.max_iter, options.power, &mut samples);
+        buffer[idx] = average(buffer[idx], &samples);
+    }
+
+    tracing::trace!(pixels = boundary.len(), threshold, "Boundary refined");
+    Ok(boundary.len() as u32)
+}
+
+fn average<P: KernelPixel>(center: P, samples: &[P]) -> P {
+    let all = samples.iter().chain(std::iter::once(&center));
+
+    let (escaped_count, escaped_sum) = all
+        .map(|sample| sample.value())
+        .filter(|&value| value != 0.0)
+        .fold((0u32, 0.0f64), |(count, sum), value| {
+            (count + 1, sum + value)
+        });
+
+    // a pixel mostly inside the set stays inside
+    let sample_count = samples.len() as u32 + 1;
+    if 2 * escaped_count < sample_count {
+        return P::interior();
+    }
+
+    P::from_value(escaped_sum / escaped_count as f64)
+}
+
+/// Plain f64 reference for the optimized kernels, one point at a time with the `Euclidean`
+/// metric. The tests check the others against it, the benchmarks measure what they gain.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScalarKernel;
+
+impl FractalKernel for ScalarKernel {
+    fn escape<P: KernelPixel>(&self, points: &[DVec2], max_iter: u32, power: f64, out: &mut [P]) {
+        let mut orbits = vec![DVec2::ZERO; points.len()];
+        self.resume(points, &mut orbits, 0, max_iter, power, out);
+    }
+
+    fn resume<P: KernelPixel>(
+        &self,
+        points: &[DVec2],
+        orbits: &mut [DVec2],
+        start_iter: u32,
+        max_iter: u32,
+        power: f64,
+        out: &mut [P],
+    ) {
+        let escape_norm = ESCAPE_RADIUS * ESCAPE_RADIUS;
+
+        for ((&c, z), out) in points.iter().zip(orbits).zip(out) {
+            *out = P::interior();
+
+            for iteration in start_iter..max_iter {
+                *z = if power == MANDELBROT_POWER {
+                    DVec2::new(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c
+                } else {
+                    complex_pow(*z, power) + c
+                };
+                let norm = z.length_squared();
+                if norm >= escape_norm {
+                    *out = P::escaped(iteration, norm, power);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mandelbrot_simd::{SimdKernel, MANDELBROT_POWER};
+
+    #[test]
+    fn resumed_frame_matches_full_compute() {
+        let grid = PixelGrid::new(
+            DRect::from_pos_size(DVec2::new(-2.2, -1.2), DVec2::new(3.0, 2.4)),
+            UVec2::new(96, 64),
+        );
+        let size = grid.size;
+        let cancel_token = CancelToken::new();
+
+        for power in [MANDELBROT_POWER, 3.5] {
+            let mut expected = vec![0.0f32; size.element_product() as usize];
+            compute_frame(
+                &SimdKernel::default(),
+                grid,
+                grid.all(),
+                KernelOptions {
+                    max_iter: 1000,
+                    power,
+                },
+                cancel_token.clone(),
+                &mut expected,
+            )
+            .unwrap();
+
+            let mut actual = vec![0.0f32; size.element_product() as usize];
+            let mut interior = InteriorOrbits::start(size);
+            for max_iter in [20, 200, 1000] {
+                resume_frame(
+                    &SimdKernel::default(),
+                    grid,
+                    grid.all(),
+                    KernelOptions { max_iter, power },
+                    cancel_token.clone(),
+                    &mut interior,
+                    &mut actual,
+                )
+                .unwrap();
+            }
+
+            assert_eq!(actual, expected);
+            assert_eq!(interior.iterations, 1000);
+            assert_eq!(
+                interior.len(),
+                expected.iter().filter(|&&value| value == 0.0).count()
+            );
+        }
+    }
+
+    #[test]
+    fn newer_generations_cancel_older_work() {
+        let tile = CancelToken::new();
+        let first = tile.next_generation();
+        let running = first.clone();
+        assert!(!running.is_cancelled());
+
+        let second = tile.next_generation();
+        assert!(first.is_cancelled() && running.is_cancelled());
+        assert!(!second.is_cancelled());
+        assert!(second.generation() > first.generation());
+
+        // a stale token leaves the newer work running
+        first.cancel();
+        assert!(!second.is_cancelled());
+        second.cancel();
+        assert!(second.is_cancelled());
+        assert!(!tile.next_generation().is_cancelled());
+    }
+
+    #[test]
+    fn kernels_agree_on_sample_grid() {
+        for power in [MANDELBROT_POWER, 3.5] {
+            kernels_agree(power);
+        }
+    }
+
+    fn kernels_agree(power: f64) {
+        let grid = PixelGrid::new(
+            DRect::from_pos_size(DVec2::new(-2.2, -1.2), DVec2::new(3.0, 2.4)),
+            UVec2::new(96, 64),
+        );
+        let size = grid.size;
+        let options = KernelOptions {
+            max_iter: 1000,
+            power,
+        };
+        let cancel_token = CancelToken::new();
+
+        let mut expected = vec![0.0f32; size.element_product() as usize];
+        compute_frame(
+            &ScalarKernel,
+            grid,
+            grid.all(),
+            options,
+            cancel_token.clone(),
+            &mut expected,
+        )
+        .unwrap();
+        let mut actual = vec![0.0f32; size.element_product() as usize];
+        compute_frame(
+            &SimdKernel::default(),
+            grid,
+            grid.all(),
+            options,
+            cancel_token,
+            &mut actual,
+        )
+        .unwrap();
+
+        let interior_count = expected.iter().filter(|&&value| value == 0.0).count();
+        assert!(interior_count > 0);
+
+        for (index, (&value, &reference)) in actual.iter().zip(&expected).enumerate() {
+            assert!(
+                (value - reference).abs() <= 1e-4 * reference.max(1.0),
+                "pixel {}: {} != {}",
+                index,
+                value,
+                reference
+            );
+        }
+    }
+}
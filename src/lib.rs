@@ -0,0 +1,10 @@
+//! The escape time kernels and the frame passes around them, shared by the app and the
+//! benchmarks.
+#![feature(portable_simd)]
+
+pub mod buddhabrot;
+pub mod kernel;
+pub mod mandelbrot_simd;
+pub mod math;
+pub mod raw_export;
+pub mod tiling;
@@ -1,56 +1,158 @@
-#![feature(portable_simd)]
-#![feature(test)]
 #![allow(dead_code)]
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::{anyhow, Context};
 use bytemuck::Zeroable;
-use glam::{IVec2, UVec2};
+use glam::{DVec2, IVec2, UVec2};
+#[cfg(not(target_arch = "wasm32"))]
 use pollster::FutureExt;
-use tokio::time::Instant;
+use web_time::Instant;
 use wgpu::Limits;
 use winit::application::ApplicationHandler;
 use winit::event::{DeviceEvent, DeviceId};
 use winit::event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy};
-use winit::window::WindowId;
+use winit::window::{CursorIcon, WindowId};
 
-use crate::event::{ElementState, Event, EventResult, MouseButtons};
-use crate::tiled_fractal_app::UserEvent;
+use fractal::{buddhabrot, kernel, mandelbrot_simd, math, raw_export, tiling};
 
+use crate::event::{CursorKind, ElementState, Event, EventResult, MouseButtons};
+use crate::gpu_profiler::GpuProfiler;
+use crate::mandel_texture::TrimLevel;
+use crate::palette::Palette;
+use crate::presets::Preset;
+use crate::session_stats::SessionStats;
+use crate::settings::Settings;
+use crate::tiled_fractal_app::{AppEvent, AppEventProxy};
+use crate::touch::TouchTracker;
+use crate::view_state::{ViewSlots, ViewState};
+
+mod bindings;
+mod buddhabrot_renderer;
 mod buffer_pool;
+mod dropped_file;
 mod env;
 mod event;
+mod fxaa;
+mod gpu_profiler;
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+mod help_overlay;
+mod julia_preview;
 mod mandel_texture;
-mod mandelbrot_simd;
-mod math;
+mod mipmaps;
+mod palette;
+mod presets;
+mod progress_bar;
+mod recency;
 mod render_pods;
+mod runtime;
+mod session_stats;
+mod settings;
+mod software;
+mod tile_cache;
+mod tile_pyramid;
+mod tile_stats;
 mod tiled_fractal_app;
+mod touch;
+mod view_state;
+mod work_queue;
+
+type UserEventType = AppEvent;
+
+pub const WINDOW_TITLE: &str = "Mandelbrot explorer";
+
+// logical pixels of a touchpad or high resolution wheel scroll that count as one wheel line
+const PIXELS_PER_LINE: f64 = 120.0;
+
+pub const NO_ADAPTER_HELP: &str = "No GPU adapter found on the Vulkan, Metal, DX12 or GL \
+backends, nor a software one. Updating the graphics driver or picking a backend with \
+FRACTAL_BACKEND may help, without a GPU images can still be rendered on the CPU: \
+fractal --size 1920x1080 --software";
+
+pub const NO_WEBGPU_HELP: &str = "No GPU adapter found, the browser may not support WebGPU or \
+have it turned off";
 
-type UserEventType = UserEvent;
+// frames with validation errors in a row after which the app gives up
+const MAX_CONSECUTIVE_DEVICE_ERRORS: u32 = 5;
 
 struct WindowContext<'window> {
     window: Arc<winit::window::Window>,
-    surface: wgpu::Surface<'window>,
+    // dropped while the app is suspended, the platform may destroy the native window meanwhile
+    surface: Option<wgpu::Surface<'window>>,
     surface_config: wgpu::SurfaceConfiguration,
+    // supported by the surface, V cycles through them
+    present_modes: Vec<wgpu::PresentMode>,
 
+    // set up by the first window, the others share them
+    instance: wgpu::Instance,
     adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
+    // with the `gpu_timing` setting on a device that supports timestamp queries
+    profiler: Option<GpuProfiler>,
+
+    is_redraw_requested: bool,
+    // applied before the next frame, outside of its error scope
+    pending_present_mode: Option<wgpu::PresentMode>,
+    // the surface configuration changed, it is applied before acquiring the next frame
+    surface_dirty: bool,
+    // the last frame failed for lack of memory and the fractal textures were shrunk
+    surface_out_of_memory: bool,
+
+    // cursor positions, the surface and the window size are all in physical pixels
+    mouse_position: Option<UVec2>,
+    scale_factor: f64,
+    // sub-pixel part of horizontal touchpad scrolling not yet panned
+    pan_remainder: f64,
+    drag: Option<Drag>,
+    touches: TouchTracker,
+}
+
+// a mouse button held down since it was pressed in the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Drag {
+    button: MouseButtons,
+    // the cursor left the window, raw device motion moves the view until it comes back
+    outside: bool,
+    // came back in, the next cursor position only moves the cursor, the raw motion outside
+    // moved the view already
+    entered: bool,
+    // sub-pixel part of raw motion not yet passed on
+    remainder: DVec2,
 }
 
 struct AppState<'window> {
-    window: Option<WindowContext<'window>>,
-    fractal_app: Option<tiled_fractal_app::TiledFractalApp>,
+    // every window with the app exploring in it, the loop ends with the last one
+    windows: HashMap<WindowId, (WindowContext<'window>, tiled_fractal_app::TiledFractalApp)>,
+    // the window last focused, the next session starts at its view
+    focused: Option<WindowId>,
+    settings: Settings,
+    palette: Palette,
+    // flown to once the window is up
+    preset: Option<&'static Preset>,
+    // where the last session ended, the window starts there
+    saved_view: Option<ViewState>,
+    view_slots: ViewSlots,
 
     event_loop_proxy: EventLoopProxy<UserEventType>,
+    // instance, adapter, device and queue for the first window, requested before the loop
+    // starts in the browser where requests can't block
+    gpu: Option<(wgpu::Instance, wgpu::Adapter, wgpu::Device, wgpu::Queue)>,
 
     start: Instant,
 
-    is_redrawing: bool,
-    is_resizing: bool,
-    is_redraw_requested: bool,
-
-    mouse_position: Option<UVec2>,
+    // when the last frame started rendering, for the `max_frame_rate` setting
+    last_frame: Option<Instant>,
+    // of the device all windows share
+    device_errors: DeviceErrors,
+    // the window couldn't be set up, the process exits with an error once the loop ends
+    startup_failed: bool,
+    // with `--stats`, where to write them on exit
+    session_stats: Option<(PathBuf, SessionStats)>,
 }
 
 pub struct RenderContext<'a> {
@@ -58,254 +160,674 @@ pub struct RenderContext<'a> {
     pub queue: &'a wgpu::Queue,
     pub view: &'a wgpu::TextureView,
     pub time: f64,
+    pub profiler: Option<&'a GpuProfiler>,
+    /// The part of `view` to draw into, all of it if None.
+    pub viewport: Option<math::URect>,
 }
 
+impl RenderContext<'_> {
+    /// Limits the drawing of `render_pass` to the viewport; a clear still covers the whole view.
+    pub fn apply_viewport(&self, render_pass: &mut wgpu::RenderPass) {
+        if let Some(viewport) = self.viewport {
+            let pos = viewport.pos.as_vec2();
+            let size = viewport.size.as_vec2();
+            render_pass.set_viewport(pos.x, pos.y, size.x, size.y, 0.0, 1.0);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
+    env::init_tracing();
+    let args = match headless::parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{}\n{}", error, headless::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut settings = match Settings::load() {
+        Ok(settings) => settings,
+        Err(error) => {
+            tracing::error!("{:#}", error);
+            std::process::exit(2);
+        }
+    };
+    let saved_view = match ViewState::path() {
+        Some(path) if !args.fresh && args.headless.is_none() => ViewState::load(&path),
+        _ => None,
+    };
+    let view_slots = match ViewSlots::path() {
+        Some(path) if args.headless.is_none() => ViewSlots::load(&path),
+        _ => ViewSlots::default(),
+    };
+    // the command line has the last word, the last session's palette comes before the config
+    let saved_palette = saved_view.as_ref().and_then(|view| view.palette.clone());
+    settings.palette = args.palette.or(saved_palette).or(settings.palette);
+    settings.present_mode = args.present_mode.or(settings.present_mode);
+    settings.worker_threads = args.worker_threads.or(settings.worker_threads);
+    tracing::info!("Computing on {} worker threads", settings.worker_count());
+    let palette = Palette::load(settings.palette.as_deref());
+
+    if let Some(headless_args) = args.headless {
+        if let Err(error) = headless::render(&headless_args, &settings, &palette) {
+            tracing::error!("Headless render failed: {:#}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let event_loop: EventLoop<UserEventType> = EventLoop::<UserEventType>::with_user_event()
         .build()
         .unwrap();
     let mut app_state = AppState {
-        window: None,
-        fractal_app: None,
-        is_redrawing: false,
-        is_resizing: false,
-        is_redraw_requested: true,
+        windows: HashMap::new(),
+        focused: None,
+        settings,
+        palette,
+        preset: args.preset,
+        saved_view,
+        view_slots,
+        device_errors: DeviceErrors::default(),
+        startup_failed: false,
+        session_stats: args.stats.map(|path| (path, SessionStats::default())),
+        last_frame: None,
         start: Instant::now(),
-        mouse_position: None,
         event_loop_proxy: event_loop.create_proxy(),
+        gpu: None,
     };
     event_loop.run_app(&mut app_state).unwrap();
-}
-
-impl<'a> ApplicationHandler<UserEventType> for AppState<'_> {
-    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: winit::event::StartCause) {
-        let _ = (event_loop, cause);
+    if app_state.startup_failed {
+        std::process::exit(1);
     }
+}
 
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window_attr =
-            winit::window::Window::default_attributes().with_title("Mandelbrot explorer");
-        let window = event_loop.create_window(window_attr).unwrap();
-        let window = Arc::new(window);
-
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
-            flags: Default::default(),
-            backend_options: Default::default(),
-        });
-        let surface = instance.create_surface(window.clone()).unwrap();
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    env::init_tracing();
+    wasm_bindgen_futures::spawn_local(async {
+        if let Err(error) = run_in_browser().await {
+            tracing::error!("{:#}", error);
+        }
+    });
+}
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::LowPower,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .block_on()
-            .expect("No suitable GPU adapters found on the system.");
+// the explorer in a canvas appended to the page. There are no files for the settings, views
+// and statistics, the session starts at the defaults and isn't saved
+#[cfg(target_arch = "wasm32")]
+async fn run_in_browser() -> anyhow::Result<()> {
+    use winit::platform::web::EventLoopExtWebSys;
 
-        dbg!(adapter.get_info());
+    let settings = Settings::default();
+    let palette = Palette::load(None);
 
-        // Make sure we use the texture resolution limits from the adapter, so we can support images the size of the surface.
-        let limits = Limits {
-            max_push_constant_size: 256,
-            ..Default::default()
-        }
-        .using_resolution(adapter.limits());
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: instance_backends(),
+        flags: Default::default(),
+        backend_options: Default::default(),
+    });
+    // every adapter of the browser can present to its canvases
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::LowPower,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        })
+        .await
+        .ok_or_else(|| anyhow!(NO_WEBGPU_HELP))?;
+    let (device, queue) = request_device(&adapter, settings.gpu_timing)
+        .await
+        .with_context(|| adapter_description(&adapter.get_info()))?;
 
-        let features = wgpu::Features::PUSH_CONSTANTS | wgpu::Features::TEXTURE_FORMAT_16BIT_NORM;
+    let event_loop: EventLoop<UserEventType> = EventLoop::<UserEventType>::with_user_event()
+        .build()
+        .context("Failed to start the event loop")?;
+    let app_state = AppState {
+        windows: HashMap::new(),
+        focused: None,
+        settings,
+        palette,
+        preset: None,
+        saved_view: None,
+        view_slots: ViewSlots::default(),
+        device_errors: DeviceErrors::default(),
+        startup_failed: false,
+        session_stats: None,
+        last_frame: None,
+        start: Instant::now(),
+        event_loop_proxy: event_loop.create_proxy(),
+        gpu: Some((instance, adapter, device, queue)),
+    };
+    event_loop.spawn_app(app_state);
 
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: features,
-                    required_limits: limits,
-                    memory_hints: Default::default(),
-                },
-                None,
-            )
-            .block_on()
-            .expect("Unable to find a suitable GPU adapter.");
+    Ok(())
+}
 
-        let window_size = window.inner_size();
-        let mut surface_config = surface
-            .get_default_config(&adapter, window_size.width, window_size.height)
-            .expect("Surface isn't supported by the adapter.");
-        let surface_view_format = surface_config.format.add_srgb_suffix();
-        surface_config.view_formats.push(surface_view_format);
-        surface.configure(&device, &surface_config);
+impl ApplicationHandler<UserEventType> for AppState<'_> {
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: winit::event::StartCause) {
+        let _ = (event_loop, cause);
+    }
 
-        self.window = Some(WindowContext {
-            window: window.clone(),
-            surface,
-            surface_config,
-            adapter,
-            device,
-            queue,
-        });
-        let window_state = self.window.as_ref().unwrap();
+    // the first resume creates the window, later ones come back from the background
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if !self.windows.is_empty() {
+            for window_id in self.window_ids() {
+                self.resume_surface(event_loop, window_id);
+            }
+            return;
+        }
 
-        self.fractal_app = Some(tiled_fractal_app::TiledFractalApp::new(
-            window_state,
-            self.event_loop_proxy.clone(),
-        ));
+        let saved_view = self.saved_view.take();
+        let view_slots = std::mem::take(&mut self.view_slots);
+        match self.create_window(event_loop, saved_view.as_ref(), view_slots) {
+            Ok(window_id) => {
+                if let Some(preset) = self.preset.take() {
+                    let fractal_app = &mut self.windows.get_mut(&window_id).unwrap().1;
+                    let result = fractal_app.fly_to_preset(preset);
+                    self.process_event_result(event_loop, window_id, result);
+                }
+            }
+            Err(error) => {
+                tracing::error!("{:#}", error);
+                self.startup_failed = true;
+                event_loop.exit();
+            }
+        }
     }
 
     fn user_event(&mut self, event_loop: &ActiveEventLoop, event: UserEventType) {
-        if self.window.is_none() {
+        // the window may have been closed since
+        let Some((_, fractal_app)) = self.windows.get_mut(&event.window_id) else {
             return;
-        }
+        };
 
-        let result = self
-            .fractal_app
-            .as_mut()
-            .unwrap()
-            .update(Event::Custom(event));
-        self.process_event_result(event_loop, result);
+        let result = fractal_app.update(Event::Custom(event.event));
+        self.process_event_result(event_loop, event.window_id, result);
     }
 
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
+        window_id: WindowId,
         event: winit::event::WindowEvent,
     ) {
-        if self.window.is_none() {
+        let Some((window_state, fractal_app)) = self.windows.get_mut(&window_id) else {
             return;
-        }
+        };
 
-        if self.mouse_position.is_none() {
+        if window_state.mouse_position.is_none() {
             if let winit::event::WindowEvent::CursorMoved { position, .. } = event {
                 let position = UVec2::new(position.x as u32, position.y as u32);
-                self.mouse_position = Some(position);
+                window_state.mouse_position = Some(position);
             }
         }
+        if event == winit::event::WindowEvent::Focused(true) {
+            self.focused = Some(window_id);
+        }
 
         let event_result = match event {
-            winit::event::WindowEvent::Resized(_)
-            | winit::event::WindowEvent::ScaleFactorChanged { .. } => {
-                let window_state = self.window.as_mut().unwrap();
-                let window_size = window_state.window.inner_size();
-
-                let window_size = UVec2::new(window_size.width.max(1), window_size.height.max(1));
-                window_state.surface_config.width = window_size.x;
-                window_state.surface_config.height = window_size.y;
-                window_state
-                    .surface
-                    .configure(&window_state.device, &window_state.surface_config);
-
-                self.fractal_app
-                    .as_mut()
-                    .unwrap()
-                    .update(Event::Resized(window_size))
+            winit::event::WindowEvent::Resized(_) => window_state.resize_surface(fractal_app),
+
+            // the window usually changes its physical size with the scale factor, a resize
+            // keeps the vertical extent of the frame so the view doesn't jump
+            winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                if let Some(mouse_position) = window_state.mouse_position.as_mut() {
+                    *mouse_position =
+                        rescaled_position(*mouse_position, window_state.scale_factor, scale_factor);
+                }
+                window_state.scale_factor = scale_factor;
+                let result = fractal_app.update(Event::ScaleFactorChanged(scale_factor));
+                self.process_event_result(event_loop, window_id, result);
+
+                let Some((window_state, fractal_app)) = self.windows.get_mut(&window_id) else {
+                    return;
+                };
+                window_state.resize_surface(fractal_app)
             }
 
             winit::event::WindowEvent::RedrawRequested => {
-                self.is_redraw_requested = true;
+                window_state.is_redraw_requested = true;
 
                 EventResult::Continue
             }
 
             event => {
                 let mut empty_mouse_position = UVec2::zeroed();
-                let mouse_position = self
+                let mouse_position = window_state
                     .mouse_position
                     .as_mut()
                     .unwrap_or(&mut empty_mouse_position);
-                let event = process_window_event(event, mouse_position);
+                let event = process_window_event(
+                    event,
+                    mouse_position,
+                    &mut window_state.pan_remainder,
+                    &mut window_state.drag,
+                    &mut window_state.touches,
+                    window_state.scale_factor,
+                );
 
-                self.fractal_app.as_mut().unwrap().update(event)
+                fractal_app.update(event)
             }
         };
 
-        self.process_event_result(event_loop, event_result);
+        self.process_event_result(event_loop, window_id, event_result);
     }
 
+    // cursor events may stop once the cursor leaves the window, raw device motion keeps a drag
+    // going until it comes back. The release still reaches the window, which holds the
+    // pointer while a button is down.
     fn device_event(
         &mut self,
         event_loop: &ActiveEventLoop,
-        device_id: DeviceId,
+        _device_id: DeviceId,
         event: DeviceEvent,
     ) {
-        let _ = (event_loop, device_id, event);
+        let DeviceEvent::MouseMotion { delta } = event else {
+            return;
+        };
+        // device events have no window, the drag goes on in the one it started in
+        let Some((&window_id, (window_state, fractal_app))) = self
+            .windows
+            .iter_mut()
+            .find(|(_, (window_state, _))| window_state.drag.is_some_and(|drag| drag.outside))
+        else {
+            return;
+        };
+        let drag = window_state.drag.as_mut().unwrap();
+
+        let mouse_position = window_state.mouse_position.unwrap_or_default();
+        let event = drag_motion_event(
+            DVec2::new(delta.0, delta.1),
+            mouse_position,
+            &mut drag.remainder,
+        );
+        let event_result = fractal_app.update(event);
+        self.process_event_result(event_loop, window_id, event_result);
     }
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        if self.window.is_none() {
-            return;
-        }
+        let window_ids = self.window_ids();
+        for &window_id in &window_ids {
+            let Some((window_state, fractal_app)) = self.windows.get_mut(&window_id) else {
+                continue;
+            };
 
-        let result = self.finish_resizing();
-        self.process_event_result(event_loop, result);
+            // animations render frame after frame on their own until they end, every other
+            // frame is asked for by an event
+            if fractal_app.is_animating() {
+                window_state.is_redraw_requested = true;
+            }
+        }
 
-        self.redraw_if_needed();
+        if self
+            .windows
+            .values()
+            .any(|(window_state, _)| window_state.is_redraw_requested)
+        {
+            self.limit_frame_rate();
+        }
+        for window_id in window_ids {
+            self.redraw_if_needed(event_loop, window_id);
+        }
     }
 
+    // device, queue and the fractal textures stay, only the surfaces go with the native windows
     fn suspended(&mut self, event_loop: &ActiveEventLoop) {
         let _ = event_loop;
+        for (window_state, fractal_app) in self.windows.values_mut() {
+            window_state.surface = None;
+            window_state.is_redraw_requested = false;
+            fractal_app.set_suspended(true);
+        }
     }
 
     fn exiting(&mut self, event_loop: &ActiveEventLoop) {
         let _ = event_loop;
-        self.window = None;
-        self.fractal_app = None;
+        // the next session starts where the window last used left off
+        let last_used = self
+            .focused
+            .and_then(|window_id| self.windows.get(&window_id))
+            .or_else(|| self.windows.values().next())
+            .map(|(_, fractal_app)| fractal_app);
+        if let (Some(fractal_app), Some(path)) = (last_used, ViewState::path()) {
+            let view = fractal_app.view_state(self.settings.palette.clone());
+            if let Err(error) = view.save(&path) {
+                tracing::error!("{:#}", error);
+            }
+        }
+        if let (Some(fractal_app), Some(path)) = (last_used, ViewSlots::path()) {
+            // an empty file would only be noise next to the view
+            let view_slots = fractal_app.view_slots();
+            if view_slots != &ViewSlots::default() || path.exists() {
+                if let Err(error) = view_slots.save(&path) {
+                    tracing::error!("{:#}", error);
+                }
+            }
+        }
+        for (_, fractal_app) in self.windows.values() {
+            let tile_stats = fractal_app.tile_stats();
+            tracing::info!("Tile statistics:\n{}", tile_stats);
+            if let Some((_, stats)) = self.session_stats.as_mut() {
+                stats.add_tiles(&tile_stats);
+            }
+        }
+        if let Some((path, stats)) = self.session_stats.as_ref() {
+            match stats.save(path) {
+                Ok(()) => tracing::info!("Session statistics written to {}", path.display()),
+                Err(error) => tracing::error!("{:#}", error),
+            }
+        }
+        self.windows.clear();
     }
 
     fn memory_warning(&mut self, event_loop: &ActiveEventLoop) {
-        let _ = event_loop;
+        if self.windows.is_empty() {
+            return;
+        }
+
+        tracing::warn!("Memory warning, trimming caches and textures");
+        for window_id in self.window_ids() {
+            let Some((window_state, fractal_app)) = self.windows.get_mut(&window_id) else {
+                continue;
+            };
+            let result = fractal_app.trim_memory(&window_state.device, TrimLevel::Textures);
+            self.process_event_result(event_loop, window_id, result);
+        }
     }
 }
 
-impl<'a> AppState<'_> {
-    fn process_event_result(&mut self, event_loop: &ActiveEventLoop, event_result: EventResult) {
+impl AppState<'_> {
+    fn window_ids(&self) -> Vec<WindowId> {
+        self.windows.keys().copied().collect()
+    }
+
+    // a window with its app at `view`, failures name the adapter they happened on; the first
+    // window sets up the GPU device, the others share it
+    fn create_window(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        view: Option<&ViewState>,
+        view_slots: ViewSlots,
+    ) -> anyhow::Result<WindowId> {
+        let window_attr = winit::window::Window::default_attributes().with_title(WINDOW_TITLE);
+        // a canvas of its own at the end of the page, the page's style sizes it
+        #[cfg(target_arch = "wasm32")]
+        let window_attr = {
+            use winit::platform::web::WindowAttributesExtWebSys;
+            window_attr.with_append(true)
+        };
+        let window = event_loop
+            .create_window(window_attr)
+            .context("Failed to create the window")?;
+        let window = Arc::new(window);
+
+        let first_window = self.windows.is_empty();
+        let shared = self
+            .windows
+            .values()
+            .next()
+            .map(|(window_state, _)| {
+                (
+                    window_state.instance.clone(),
+                    window_state.adapter.clone(),
+                    window_state.device.clone(),
+                    window_state.queue.clone(),
+                )
+            })
+            .or_else(|| self.gpu.clone());
+        let instance = match &shared {
+            Some((instance, ..)) => instance.clone(),
+            None => wgpu::Instance::new(&wgpu::InstanceDescriptor {
+                backends: instance_backends(),
+                flags: Default::default(),
+                backend_options: Default::default(),
+            }),
+        };
+        let surface = instance
+            .create_surface(window.clone())
+            .context("Failed to create the window surface")?;
+
+        let (adapter, device, queue) = match shared {
+            Some((_, adapter, device, queue)) => (adapter, device, queue),
+            None => request_gpu(&instance, &surface, self.settings.gpu_timing)?,
+        };
+        let info = adapter.get_info();
+        let on_adapter = || adapter_description(&info);
+
+        let profiler = GpuProfiler::new(&device, &queue);
+        if self.settings.gpu_timing && profiler.is_none() && first_window {
+            tracing::warn!("The GPU doesn't support timestamp queries, GPU timing is off");
+        }
+
+        let window_size = window.inner_size();
+        let mut surface_config = surface
+            .get_default_config(&adapter, window_size.width, window_size.height)
+            .ok_or_else(|| anyhow!("The window surface isn't supported"))
+            .with_context(on_adapter)?;
+        let surface_view_format = surface_config.format.add_srgb_suffix();
+        surface_config.view_formats.push(surface_view_format);
+        let present_modes = surface.get_capabilities(&adapter).present_modes;
+        surface_config.present_mode =
+            choose_present_mode(&present_modes, self.settings.present_mode);
+        surface.configure(&device, &surface_config);
+
+        let window_state = WindowContext {
+            window: window.clone(),
+            surface: Some(surface),
+            surface_config,
+            present_modes,
+            instance,
+            adapter,
+            device,
+            queue,
+            profiler,
+            is_redraw_requested: true,
+            pending_present_mode: None,
+            surface_dirty: false,
+            surface_out_of_memory: false,
+            mouse_position: None,
+            scale_factor: window.scale_factor(),
+            pan_remainder: 0.0,
+            drag: None,
+            touches: TouchTracker::default(),
+        };
+
+        let fractal_app = tiled_fractal_app::TiledFractalApp::new(
+            &window_state,
+            AppEventProxy::new(self.event_loop_proxy.clone(), window.id()),
+            &self.settings,
+            &self.palette,
+            view,
+            view_slots,
+        )
+        .with_context(on_adapter)?;
+        self.windows
+            .insert(window.id(), (window_state, fractal_app));
+        self.focused = Some(window.id());
+
+        Ok(window.id())
+    }
+
+    // a new surface for the window the app had before it was suspended, the window may have
+    // changed its size meanwhile. Without one the app stays suspended until the next resume
+    // tries again.
+    fn resume_surface(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId) {
+        let Some((window_state, fractal_app)) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+        let surface = match window_state
+            .instance
+            .create_surface(window_state.window.clone())
+        {
+            Ok(surface) => surface,
+            Err(error) => {
+                tracing::error!(
+                    "Failed to recreate the window surface, retrying on the next resume: {}",
+                    error
+                );
+                window_state.surface = None;
+                return;
+            }
+        };
+        window_state.surface = Some(surface);
+        window_state.surface_dirty = true;
+
+        let result = window_state.resize_surface(fractal_app);
+        fractal_app.set_suspended(false);
+        window_state.is_redraw_requested = true;
+        window_state.window.request_redraw();
+        self.process_event_result(event_loop, window_id, result);
+    }
+
+    fn process_event_result(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event_result: EventResult,
+    ) {
+        let Some((window_state, fractal_app)) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+
         match event_result {
             EventResult::Continue => {}
 
             EventResult::Redraw => {
-                self.is_redraw_requested = true;
+                window_state.is_redraw_requested = true;
             }
             EventResult::Exit => {
-                self.is_redraw_requested = false;
-                event_loop.exit();
+                window_state.is_redraw_requested = false;
+                self.close_window(event_loop, window_id);
+            }
+            EventResult::SetTitle(title) => {
+                window_state.window.set_title(&title);
+            }
+            EventResult::SetCursor(cursor) => {
+                let icon = match cursor {
+                    CursorKind::Default => CursorIcon::Default,
+                    CursorKind::Grabbing => CursorIcon::Grabbing,
+                    CursorKind::Crosshair => CursorIcon::Crosshair,
+                    CursorKind::ResizeVertical => CursorIcon::NsResize,
+                    CursorKind::Progress => CursorIcon::Progress,
+                };
+                window_state.window.set_cursor(icon);
+            }
+            // the resize that follows goes through the usual path, leaving fullscreen
+            // restores the previous windowed size
+            EventResult::ToggleFullscreen => {
+                let window = &window_state.window;
+                let fullscreen = match window.fullscreen() {
+                    Some(_) => None,
+                    // on the monitor the window is on
+                    None => Some(winit::window::Fullscreen::Borderless(None)),
+                };
+                window.set_fullscreen(fullscreen);
+            }
+            EventResult::CyclePresentMode => {
+                let current = window_state
+                    .pending_present_mode
+                    .unwrap_or(window_state.surface_config.present_mode);
+                window_state.pending_present_mode =
+                    Some(next_present_mode(&window_state.present_modes, current));
+                window_state.is_redraw_requested = true;
+            }
+            EventResult::SimulateMemoryWarning => self.memory_warning(event_loop),
+            // the view slots come along, the last window closed saves its own
+            EventResult::NewWindow => {
+                let view = fractal_app.view_state(self.settings.palette.clone());
+                let view_slots = fractal_app.view_slots().clone();
+                if let Err(error) = self.create_window(event_loop, Some(&view), view_slots) {
+                    tracing::error!("{:#}", error);
+                }
             }
         }
     }
 
-    fn redraw_if_needed(&mut self) {
-        if self.is_redrawing {
-            let error = self
-                .window
-                .as_ref()
-                .unwrap()
-                .device
-                .pop_error_scope()
-                .block_on();
-            if let Some(error) = error {
-                panic!("Device error: {:?}", error);
+    // the last window ends the loop, `exiting` saves its view; the others just go
+    fn close_window(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId) {
+        if self.windows.len() == 1 {
+            event_loop.exit();
+            return;
+        }
+
+        if let Some((_, fractal_app)) = self.windows.remove(&window_id) {
+            let tile_stats = fractal_app.tile_stats();
+            tracing::info!("Tile statistics:\n{}", tile_stats);
+            if let Some((_, stats)) = self.session_stats.as_mut() {
+                stats.add_tiles(&tile_stats);
             }
         }
-        self.is_redrawing = false;
+        if self.focused == Some(window_id) {
+            self.focused = None;
+        }
+    }
+
+    // sleeps out the rest of the frame interval of `max_frame_rate`, events that come in
+    // meanwhile are handled along with the next frame
+    fn limit_frame_rate(&self) {
+        let (Some(max_frame_rate), Some(last_frame)) =
+            (self.settings.max_frame_rate, self.last_frame)
+        else {
+            return;
+        };
 
-        if !self.is_redraw_requested {
+        // the browser paces the frames of the page, which can't sleep
+        if cfg!(target_arch = "wasm32") {
             return;
         }
 
-        self.is_redraw_requested = false;
-        self.is_redrawing = true;
+        let next_frame = last_frame + Duration::from_secs_f64(1.0 / max_frame_rate);
+        let remaining = next_frame.saturating_duration_since(Instant::now());
+        if !remaining.is_zero() {
+            std::thread::sleep(remaining);
+        }
+    }
 
-        let window_state = self.window.as_mut().unwrap();
+    fn redraw_if_needed(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId) {
+        let Some((window_state, fractal_app)) = self.windows.get_mut(&window_id) else {
+            return;
+        };
 
-        let surface_texture = match window_state.surface.get_current_texture() {
-            Ok(frame) => frame,
-            Err(_) => {
-                window_state
-                    .surface
-                    .configure(&window_state.device, &window_state.surface_config);
-                window_state
-                    .surface
-                    .get_current_texture()
-                    .expect("Failed to acquire next surface texture.")
+        if let Some(present_mode) = window_state.pending_present_mode.take() {
+            window_state.surface_config.present_mode = present_mode;
+            window_state.surface_dirty = true;
+            fractal_app.set_present_mode(present_mode);
+        }
+
+        if !window_state.is_redraw_requested {
+            return;
+        }
+
+        window_state.is_redraw_requested = false;
+
+        // the resize that restores the window asks for a frame again
+        if fractal_app.is_minimized() {
+            return;
+        }
+
+        // suspended, the next resume asks for a frame again
+        let Some(surface) = window_state.surface.as_ref() else {
+            return;
+        };
+        if std::mem::take(&mut window_state.surface_dirty) {
+            let config = &window_state.surface_config;
+            tracing::debug!(
+                width = config.width,
+                height = config.height,
+                present_mode = ?config.present_mode,
+                "Surface reconfigured"
+            );
+            surface.configure(&window_state.device, config);
+        }
+
+        let surface_texture = match surface.get_current_texture() {
+            Ok(frame) => {
+                window_state.surface_out_of_memory = false;
+                frame
+            }
+            Err(error) => {
+                self.skip_frame(event_loop, window_id, error);
+                return;
             }
         };
         let surface_texture_view =
@@ -320,46 +842,505 @@ impl<'a> AppState<'_> {
             .device
             .push_error_scope(wgpu::ErrorFilter::Validation);
 
-        self.fractal_app.as_mut().unwrap().render(&RenderContext {
+        self.last_frame = Some(Instant::now());
+        fractal_app.render(&RenderContext {
             device: &window_state.device,
             queue: &window_state.queue,
             view: &surface_texture_view,
             time: self.start.elapsed().as_secs_f64(),
+            profiler: window_state.profiler.as_ref(),
+            viewport: None,
         });
+        if let Some(profiler) = window_state.profiler.as_mut() {
+            profiler.end_frame(&window_state.device, &window_state.queue);
+        }
+        if let Some((_, stats)) = self.session_stats.as_mut() {
+            stats.add_frame(fractal_app.zoom());
+        }
+
+        let error = pop_frame_error_scope(&window_state.device);
+        match self.device_errors.record(error.as_ref()) {
+            DeviceErrorAction::Continue => surface_texture.present(),
+            // the frame is dropped unpresented, the next one starts over from a fresh
+            // surface configuration and fresh textures
+            DeviceErrorAction::Recover => {
+                drop(surface_texture);
+                window_state.surface_dirty = true;
+                fractal_app.recover();
+                window_state.is_redraw_requested = true;
+            }
+            DeviceErrorAction::Abort => {
+                tracing::error!(
+                    "Giving up after {} frames with device errors",
+                    MAX_CONSECUTIVE_DEVICE_ERRORS
+                );
+                event_loop.exit();
+                return;
+            }
+        }
+
+        let result = fractal_app.update(Event::RedrawFinished);
+        self.process_event_result(event_loop, window_id, result);
+        let Some((window_state, fractal_app)) = self.windows.get(&window_id) else {
+            return;
+        };
+        if window_state.is_redraw_requested || fractal_app.is_animating() {
+            // the loop waits for events, wake it up for the next animation frame
+            window_state.window.request_redraw();
+        }
+    }
+
+    // the frame is tried again with the next redraw
+    fn skip_frame(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        error: wgpu::SurfaceError,
+    ) {
+        let Some((window_state, fractal_app)) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+
+        match error {
+            // the surface doesn't match the window anymore, usually in the middle of a resize
+            wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost => {
+                window_state.surface_dirty = true;
+            }
+            // the compositor stalled, the surface itself is fine
+            wgpu::SurfaceError::Timeout | wgpu::SurfaceError::Other => {
+                tracing::warn!(%error, "Skipping a frame");
+            }
+            // one retry with smaller fractal textures, memory is freed by then or never
+            wgpu::SurfaceError::OutOfMemory => {
+                let shrunk = !window_state.surface_out_of_memory
+                    && fractal_app.shrink_texture(&window_state.device);
+                if !shrunk {
+                    tracing::error!("Out of GPU memory for the window surface, exiting");
+                    event_loop.exit();
+                    return;
+                }
+
+                tracing::warn!("Out of GPU memory for the window surface, reducing the resolution");
+                window_state.surface_out_of_memory = true;
+                window_state.surface_dirty = true;
+            }
+        }
+
+        window_state.is_redraw_requested = true;
+        window_state.window.request_redraw();
+    }
+}
+
+impl WindowContext<'_> {
+    // the size may be stale while the scale factor changes, the following resize catches up;
+    // the surface is configured right before the next frame, not for every step of a drag.
+    // A minimized window keeps its surface, the app is told about the zero size and the
+    // restoring resize, which may well be to the size the surface still has
+    fn resize_surface(
+        &mut self,
+        fractal_app: &mut tiled_fractal_app::TiledFractalApp,
+    ) -> EventResult {
+        let window_size = inner_window_size(&self.window);
+        let surface_size = UVec2::new(self.surface_config.width, self.surface_config.height);
+        if window_size == surface_size && !fractal_app.is_minimized() {
+            return EventResult::Continue;
+        }
+
+        if window_size.min_element() > 0 {
+            self.surface_config.width = window_size.x;
+            self.surface_config.height = window_size.y;
+            self.surface_dirty = true;
+        }
+
+        fractal_app.update(Event::Resized(window_size))
+    }
+}
+
+// the requested mode if the surface supports it, otherwise the lowest latency one that doesn't tear
+fn choose_present_mode(
+    supported: &[wgpu::PresentMode],
+    requested: Option<wgpu::PresentMode>,
+) -> wgpu::PresentMode {
+    if let Some(requested) = requested {
+        if supported.contains(&requested) {
+            return requested;
+        }
+        tracing::warn!(
+            "Present mode {:?} isn't supported, the surface supports {:?}",
+            requested,
+            supported
+        );
+    }
+
+    [wgpu::PresentMode::Mailbox, wgpu::PresentMode::Fifo]
+        .into_iter()
+        .find(|mode| supported.contains(mode))
+        // every surface supports Fifo, an empty list only comes from a broken driver
+        .unwrap_or(wgpu::PresentMode::Fifo)
+}
+
+fn next_present_mode(
+    supported: &[wgpu::PresentMode],
+    current: wgpu::PresentMode,
+) -> wgpu::PresentMode {
+    let next = supported
+        .iter()
+        .position(|&mode| mode == current)
+        .map_or(0, |index| index + 1);
+
+    supported
+        .get(next)
+        .or(supported.first())
+        .copied()
+        .unwrap_or(current)
+}
+
+// keeps a cursor position at the same logical spot when the scale factor changes
+fn rescaled_position(position: UVec2, old_scale_factor: f64, new_scale_factor: f64) -> UVec2 {
+    (position.as_dvec2() * new_scale_factor / old_scale_factor)
+        .round()
+        .as_uvec2()
+}
+
+// minimized windows report a zero size, which surfaces can't be configured with
+fn inner_window_size(window: &winit::window::Window) -> UVec2 {
+    let window_size = window.inner_size();
+    UVec2::new(window_size.width, window_size.height)
+}
+
+// the requested backend comes on top of the default ones, which are the fallback
+fn instance_backends() -> wgpu::Backends {
+    let default = wgpu::Backends::PRIMARY | wgpu::Backends::GL;
+    env::backends().map_or(default, |requested| requested | default)
+}
+
+// the adapter and device for the first window, on the instance `surface` was created with
+#[cfg(not(target_arch = "wasm32"))]
+fn request_gpu(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface,
+    gpu_timing: bool,
+) -> anyhow::Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue)> {
+    let adapter = request_adapter(instance, Some(surface), wgpu::PowerPreference::LowPower)
+        .ok_or_else(|| anyhow!(NO_ADAPTER_HELP))?;
+    let (device, queue) = request_device(&adapter, gpu_timing)
+        .block_on()
+        .with_context(|| adapter_description(&adapter.get_info()))?;
+
+    Ok((adapter, device, queue))
+}
+
+// the requests can't block in the browser, the first window finds `AppState::gpu` there
+#[cfg(target_arch = "wasm32")]
+fn request_gpu(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface,
+    gpu_timing: bool,
+) -> anyhow::Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue)> {
+    let _ = (instance, surface, gpu_timing);
+
+    Err(anyhow!(
+        "The GPU device must be requested before the window in the browser"
+    ))
+}
+
+// the context of errors that depend on the adapter
+fn adapter_description(info: &wgpu::AdapterInfo) -> String {
+    format!(
+        "On {} ({:?}, {:?}, driver {} {})",
+        info.name, info.backend, info.device_type, info.driver, info.driver_info
+    )
+}
+
+// FRACTAL_BACKEND and FRACTAL_ADAPTER pick among the adapters that can render to `surface`,
+// without them or when nothing matches the default order applies
+#[cfg(not(target_arch = "wasm32"))]
+fn request_adapter(
+    instance: &wgpu::Instance,
+    surface: Option<&wgpu::Surface>,
+    power_preference: wgpu::PowerPreference,
+) -> Option<wgpu::Adapter> {
+    let requested_backends = env::backends();
+    let requested_name = env::adapter_name();
+
+    let mut candidates: Vec<wgpu::Adapter> = if requested_backends.is_some()
+        || requested_name.is_some()
+    {
+        instance
+            .enumerate_adapters(requested_backends.unwrap_or(wgpu::Backends::all()))
+            .into_iter()
+            .filter(|adapter| surface.is_none_or(|surface| adapter.is_surface_supported(surface)))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    if requested_backends.is_some() && candidates.is_empty() {
+        tracing::warn!(
+            "No adapter is available for FRACTAL_BACKEND, falling back to the default backends"
+        );
+    }
+
+    if let Some(requested_name) = requested_name.filter(|_| !candidates.is_empty()) {
+        let index = candidates
+            .iter()
+            .position(|adapter| adapter_matches(&adapter.get_info().name, &requested_name));
+        match index {
+            Some(index) => candidates = vec![candidates.swap_remove(index)],
+            None => {
+                let names: Vec<String> = candidates
+                    .iter()
+                    .map(|adapter| adapter.get_info().name)
+                    .collect();
+                tracing::warn!(
+                    "No adapter matches FRACTAL_ADAPTER={}, available are {:?}",
+                    requested_name,
+                    names
+                );
+                // without a backend request the default choice is as good as any
+                if requested_backends.is_none() {
+                    candidates.clear();
+                }
+            }
+        }
+    }
+
+    candidates
+        .sort_by_key(|adapter| device_type_rank(adapter.get_info().device_type, power_preference));
+    let adapter = match candidates.into_iter().next() {
+        Some(adapter) => adapter,
+        None => default_adapter(instance, surface, power_preference)?,
+    };
 
-        surface_texture.present();
+    let info = adapter.get_info();
+    let driver = format!("{} {}", info.driver, info.driver_info);
+    tracing::info!(
+        "Using {} on {:?}, driver {}",
+        info.name,
+        info.backend,
+        driver.trim()
+    );
+
+    Some(adapter)
+}
+
+// the primary backends first, GL only without any of them, then a software adapter
+// like WARP or llvmpipe, which VMs and CI machines often have as their only one
+#[cfg(not(target_arch = "wasm32"))]
+fn default_adapter(
+    instance: &wgpu::Instance,
+    surface: Option<&wgpu::Surface>,
+    power_preference: wgpu::PowerPreference,
+) -> Option<wgpu::Adapter> {
+    for backends in [wgpu::Backends::PRIMARY, wgpu::Backends::GL] {
+        let adapter = instance
+            .enumerate_adapters(backends)
+            .into_iter()
+            .filter(|adapter| surface.is_none_or(|surface| adapter.is_surface_supported(surface)))
+            .min_by_key(|adapter| {
+                device_type_rank(adapter.get_info().device_type, power_preference)
+            });
+        if adapter.is_some() {
+            return adapter;
+        }
     }
 
-    fn finish_resizing(&mut self) -> EventResult {
-        if self.is_resizing {
-            self.is_resizing = false;
+    instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference,
+            force_fallback_adapter: true,
+            compatible_surface: surface,
+        })
+        .block_on()
+}
+
+fn adapter_matches(adapter_name: &str, requested_name: &str) -> bool {
+    adapter_name
+        .to_lowercase()
+        .contains(&requested_name.to_lowercase())
+}
+
+// lower is preferred, like wgpu orders adapters for a power preference
+fn device_type_rank(
+    device_type: wgpu::DeviceType,
+    power_preference: wgpu::PowerPreference,
+) -> usize {
+    let order = match power_preference {
+        wgpu::PowerPreference::HighPerformance => [
+            wgpu::DeviceType::DiscreteGpu,
+            wgpu::DeviceType::IntegratedGpu,
+        ],
+        _ => [
+            wgpu::DeviceType::IntegratedGpu,
+            wgpu::DeviceType::DiscreteGpu,
+        ],
+    };
 
-            let window_size = self.window.as_ref().unwrap().window.inner_size();
+    order
+        .iter()
+        .position(|&preferred| preferred == device_type)
+        .unwrap_or(order.len())
+}
 
-            self.fractal_app
-                .as_mut()
-                .unwrap()
-                .update(Event::Resized(UVec2::new(
-                    window_size.width,
-                    window_size.height,
-                )))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeviceErrorAction {
+    Continue,
+    Recover,
+    Abort,
+}
+
+// counts frames with validation errors in a row, a clean frame starts over
+#[derive(Debug, Default)]
+struct DeviceErrors {
+    consecutive: u32,
+}
+
+impl DeviceErrors {
+    fn record(&mut self, error: Option<&wgpu::Error>) -> DeviceErrorAction {
+        let Some(error) = error else {
+            self.consecutive = 0;
+            return DeviceErrorAction::Continue;
+        };
+
+        self.consecutive += 1;
+        tracing::error!(
+            consecutive = self.consecutive,
+            %error,
+            "Device error, dropping the frame"
+        );
+        if self.consecutive >= MAX_CONSECUTIVE_DEVICE_ERRORS {
+            DeviceErrorAction::Abort
         } else {
-            EventResult::Continue
+            DeviceErrorAction::Recover
         }
     }
 }
 
+// the validation errors of the frame since its error scope was pushed
+#[cfg(not(target_arch = "wasm32"))]
+fn pop_frame_error_scope(device: &wgpu::Device) -> Option<wgpu::Error> {
+    device.pop_error_scope().block_on()
+}
+
+// the browser answers after the frame, its errors are only logged and the frame is presented
+// regardless
+#[cfg(target_arch = "wasm32")]
+fn pop_frame_error_scope(device: &wgpu::Device) -> Option<wgpu::Error> {
+    let error = device.pop_error_scope();
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Some(error) = error.await {
+            tracing::error!(%error, "Device error");
+        }
+    });
+
+    None
+}
+
+// timestamp queries are requested for `gpu_timing` only, they are skipped where unsupported
+async fn request_device(
+    adapter: &wgpu::Adapter,
+    gpu_timing: bool,
+) -> anyhow::Result<(wgpu::Device, wgpu::Queue)> {
+    // without push constants, e.g. on WebGPU, the shader parameters go through a uniform buffer
+    let mut features = adapter.features() & wgpu::Features::PUSH_CONSTANTS;
+    let push_constants = !features.is_empty();
+    if gpu_timing {
+        features |= adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
+    }
+    // the texture resolution limits of the adapter, so the textures can be as large as the surface
+    let limits = Limits {
+        max_push_constant_size: if push_constants { 256 } else { 0 },
+        ..Default::default()
+    }
+    .using_resolution(adapter.limits());
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: features,
+                required_limits: limits,
+                memory_hints: Default::default(),
+            },
+            None,
+        )
+        .await
+        .context("Unable to create a device on the GPU adapter")?;
+
+    // errors outside of a frame's error scope would abort the process by default
+    device.on_uncaptured_error(Box::new(|error| {
+        tracing::error!(%error, "Uncaptured device error");
+    }));
+
+    Ok((device, queue))
+}
+
+// vertical scrolling zooms by fractional lines, the zoom is continuous so slow scrolling isn't lost;
+// horizontal scrolling pans by whole pixels and carries the remainder over to the next event,
+// a diagonal scroll does both
+fn pixel_scroll_event<UserEvent>(
+    delta: DVec2,
+    scale_factor: f64,
+    position: UVec2,
+    pan_remainder: &mut f64,
+) -> Event<UserEvent> {
+    let lines = delta.y / (PIXELS_PER_LINE * scale_factor);
+
+    // a fraction left from an earlier pan must not jump the view once panning resumes
+    let pan = if delta.x == 0.0 {
+        0.0
+    } else {
+        *pan_remainder + delta.x
+    };
+    let pixels = pan.trunc();
+    *pan_remainder = pan - pixels;
+
+    Event::TouchpadScroll(position, IVec2::new(pixels as i32, 0), lines as f32)
+}
+
+// drags by the whole pixels of raw mouse motion and carries the remainder over to the next event
+fn drag_motion_event<UserEvent>(
+    delta: DVec2,
+    position: UVec2,
+    drag_remainder: &mut DVec2,
+) -> Event<UserEvent> {
+    let motion = *drag_remainder + delta;
+    let pixels = motion.trunc();
+    *drag_remainder = motion - pixels;
+
+    Event::MouseMove {
+        position,
+        delta: pixels.as_ivec2(),
+    }
+}
+
 fn process_window_event<UserEvent>(
     event: winit::event::WindowEvent,
     mouse_position: &mut UVec2,
+    pan_remainder: &mut f64,
+    drag: &mut Option<Drag>,
+    touches: &mut TouchTracker,
+    scale_factor: f64,
 ) -> Event<UserEvent> {
     match event {
         winit::event::WindowEvent::Resized(size) => {
-            Event::Resized(UVec2::new(size.width.max(1), size.height.max(1)))
+            Event::Resized(UVec2::new(size.width, size.height))
+        }
+        winit::event::WindowEvent::Focused(false) => Event::FocusLost,
+        winit::event::WindowEvent::Focused(true) => Event::Unknown,
+        winit::event::WindowEvent::CursorEntered { .. } => {
+            if let Some(drag) = drag.as_mut().filter(|drag| drag.outside) {
+                drag.outside = false;
+                drag.entered = true;
+            }
+            Event::Unknown
+        }
+        winit::event::WindowEvent::CursorLeft { .. } => {
+            if let Some(drag) = drag.as_mut() {
+                drag.outside = true;
+                drag.remainder = DVec2::ZERO;
+            }
+            Event::Unknown
         }
-        winit::event::WindowEvent::Focused(_is_focused) => Event::Unknown,
-        winit::event::WindowEvent::CursorEntered { .. } => Event::Unknown,
-        winit::event::WindowEvent::CursorLeft { .. } => Event::Unknown,
         winit::event::WindowEvent::CursorMoved {
             position: _position,
             ..
@@ -368,38 +1349,329 @@ fn process_window_event<UserEvent>(
             let new_pos = UVec2::new(_position.x as u32, _position.y as u32);
             *mouse_position = new_pos;
 
+            // outside the window a drag follows raw device motion instead, see `device_event`
+            let delta = match drag.as_mut() {
+                Some(drag) if drag.outside => return Event::Unknown,
+                Some(drag) if drag.entered => {
+                    drag.entered = false;
+                    IVec2::ZERO
+                }
+                _ => IVec2::try_from(new_pos).unwrap() - IVec2::try_from(prev_pos).unwrap(),
+            };
+
             Event::MouseMove {
                 position: new_pos,
-                delta: IVec2::try_from(new_pos).unwrap() - IVec2::try_from(prev_pos).unwrap(),
+                delta,
             }
         }
         winit::event::WindowEvent::Occluded(_is_occluded) => Event::Unknown,
-        winit::event::WindowEvent::MouseInput { state, button, .. } => Event::MouseButton(
-            MouseButtons::from(button),
-            ElementState::from(state),
-            mouse_position.clone(),
-        ),
+        winit::event::WindowEvent::MouseInput { state, button, .. } => {
+            // the drag belongs to the first button pressed, whatever gesture the app binds to it
+            let button = MouseButtons::from(button);
+            match state {
+                winit::event::ElementState::Pressed if drag.is_none() => {
+                    *drag = Some(Drag {
+                        button,
+                        outside: false,
+                        entered: false,
+                        remainder: DVec2::ZERO,
+                    });
+                }
+                winit::event::ElementState::Released
+                    if drag.is_some_and(|drag| drag.button == button) =>
+                {
+                    *drag = None;
+                }
+                _ => {}
+            }
+
+            Event::MouseButton(button, ElementState::from(state), *mouse_position)
+        }
         winit::event::WindowEvent::MouseWheel {
             delta,
             phase: _phase,
             ..
         } => match delta {
             winit::event::MouseScrollDelta::LineDelta(_l1, l2) => {
-                Event::MouseWheel(mouse_position.clone(), l2)
+                Event::MouseWheel(*mouse_position, l2)
             }
-            winit::event::MouseScrollDelta::PixelDelta(_pix) => Event::Unknown,
+            winit::event::MouseScrollDelta::PixelDelta(pix) => pixel_scroll_event(
+                DVec2::new(pix.x, pix.y),
+                scale_factor,
+                *mouse_position,
+                pan_remainder,
+            ),
         },
+        // winit doesn't report where the gesture is, the cursor sits at it on touchpads;
+        // only updates during the gesture zoom, the start and end phases carry no movement
         winit::event::WindowEvent::PinchGesture {
             device_id: _device_id,
             delta,
-            phase: _phase,
-        } => {
-            // Event::TouchpadMagnify(mouse_position.clone(), delta as f32)
-            Event::MouseWheel(mouse_position.clone(), -50.0 * delta as f32)
-        }
+            phase: winit::event::TouchPhase::Moved,
+        } => Event::PinchZoom {
+            position: *mouse_position,
+            delta: IVec2::ZERO,
+            scale_factor: 1.0 + delta,
+        },
+        winit::event::WindowEvent::Touch(touch) => touches.update(
+            touch.id,
+            touch.phase,
+            DVec2::new(touch.location.x, touch.location.y),
+        ),
         winit::event::WindowEvent::CloseRequested => Event::WindowClose,
+        winit::event::WindowEvent::DroppedFile(path) => Event::FileDropped(path),
         winit::event::WindowEvent::Moved(_position) => Event::Unknown,
         winit::event::WindowEvent::KeyboardInput { event, .. } => Event::KeyboardInput(event),
+        winit::event::WindowEvent::ModifiersChanged(modifiers) => {
+            Event::ModifiersChanged(modifiers.state())
+        }
         _ => Event::Unknown,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // submits an out of bounds copy, which fails validation
+    fn submit_invalid_copy(device: &wgpu::Device, queue: &wgpu::Queue) {
+        let buffer = |size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                size,
+                usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+                label: None,
+            })
+        };
+        let (source, destination) = (buffer(4), buffer(4));
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        command_encoder.copy_buffer_to_buffer(&source, 0, &destination, 0, 8);
+        queue.submit(Some(command_encoder.finish()));
+    }
+
+    fn frame_error(device: &wgpu::Device, queue: &wgpu::Queue, fail: bool) -> Option<wgpu::Error> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        if fail {
+            submit_invalid_copy(device, queue);
+        }
+        device.pop_error_scope().block_on()
+    }
+
+    #[test]
+    fn device_errors_are_survived() {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: instance_backends(),
+            flags: Default::default(),
+            backend_options: Default::default(),
+        });
+        let Some(adapter) = request_adapter(&instance, None, wgpu::PowerPreference::LowPower)
+        else {
+            eprintln!("No adapter, skipping the device error test");
+            return;
+        };
+        let (device, queue) = request_device(&adapter, false).block_on().unwrap();
+        let mut errors = DeviceErrors::default();
+
+        // a single error drops the frame and the next clean one starts the count over
+        let error = frame_error(&device, &queue, true);
+        assert!(error.is_some());
+        assert_eq!(errors.record(error.as_ref()), DeviceErrorAction::Recover);
+        let error = frame_error(&device, &queue, false);
+        assert_eq!(errors.record(error.as_ref()), DeviceErrorAction::Continue);
+
+        let actions: Vec<_> = (0..MAX_CONSECUTIVE_DEVICE_ERRORS)
+            .map(|_| errors.record(frame_error(&device, &queue, true).as_ref()))
+            .collect();
+        assert!(actions[..actions.len() - 1]
+            .iter()
+            .all(|&action| action == DeviceErrorAction::Recover));
+        assert_eq!(actions.last(), Some(&DeviceErrorAction::Abort));
+
+        // outside of an error scope the error is only logged
+        submit_invalid_copy(&device, &queue);
+        assert!(frame_error(&device, &queue, false).is_none());
+    }
+
+    #[test]
+    fn pixel_scroll_matches_line_scroll() {
+        let position = UVec2::new(10, 20);
+        let mut pan_remainder = 0.0;
+        let mut scroll = |delta: DVec2, scale_factor| {
+            pixel_scroll_event::<()>(delta, scale_factor, position, &mut pan_remainder)
+        };
+
+        assert_eq!(
+            scroll(DVec2::new(0.0, 240.0), 1.0),
+            Event::TouchpadScroll(position, IVec2::ZERO, 2.0)
+        );
+        assert_eq!(
+            scroll(DVec2::new(0.0, -240.0), 2.0),
+            Event::TouchpadScroll(position, IVec2::ZERO, -1.0)
+        );
+        // neither axis of a diagonal scroll is dropped
+        assert_eq!(
+            scroll(DVec2::new(3.0, -240.0), 2.0),
+            Event::TouchpadScroll(position, IVec2::new(3, 0), -1.0)
+        );
+
+        // slow horizontal scrolling eventually pans
+        let pans: Vec<_> = (0..4).map(|_| scroll(DVec2::new(0.4, 0.0), 1.0)).collect();
+        assert_eq!(pans[0], Event::TouchpadScroll(position, IVec2::ZERO, 0.0));
+        assert_eq!(
+            pans[2],
+            Event::TouchpadScroll(position, IVec2::new(1, 0), 0.0)
+        );
+        assert_eq!(pans[3], Event::TouchpadScroll(position, IVec2::ZERO, 0.0));
+
+        // zooming drops the fraction left from panning
+        scroll(DVec2::new(0.9, 0.0), 1.0);
+        scroll(DVec2::new(0.0, 120.0), 1.0);
+        assert_eq!(
+            scroll(DVec2::new(0.4, 0.0), 1.0),
+            Event::TouchpadScroll(position, IVec2::ZERO, 0.0)
+        );
+    }
+
+    #[test]
+    fn adapter_choice() {
+        assert!(adapter_matches("NVIDIA GeForce RTX 3060", "geforce"));
+        assert!(adapter_matches("AMD Radeon(TM) Graphics", "Radeon"));
+        assert!(!adapter_matches("Intel(R) UHD Graphics", "nvidia"));
+
+        let low_power = wgpu::PowerPreference::LowPower;
+        let high_performance = wgpu::PowerPreference::HighPerformance;
+        assert!(
+            device_type_rank(wgpu::DeviceType::IntegratedGpu, low_power)
+                < device_type_rank(wgpu::DeviceType::DiscreteGpu, low_power)
+        );
+        assert!(
+            device_type_rank(wgpu::DeviceType::DiscreteGpu, high_performance)
+                < device_type_rank(wgpu::DeviceType::IntegratedGpu, high_performance)
+        );
+        assert!(
+            device_type_rank(wgpu::DeviceType::IntegratedGpu, high_performance)
+                < device_type_rank(wgpu::DeviceType::Cpu, high_performance)
+        );
+    }
+
+    #[test]
+    fn present_mode_prefers_mailbox() {
+        use wgpu::PresentMode::{Fifo, Immediate, Mailbox};
+
+        assert_eq!(
+            choose_present_mode(&[Fifo, Immediate, Mailbox], None),
+            Mailbox
+        );
+        assert_eq!(choose_present_mode(&[Immediate, Fifo], None), Fifo);
+        assert_eq!(
+            choose_present_mode(&[Fifo, Immediate], Some(Immediate)),
+            Immediate
+        );
+        assert_eq!(choose_present_mode(&[Fifo], Some(Mailbox)), Fifo);
+
+        let supported = [Fifo, Immediate, Mailbox];
+        assert_eq!(next_present_mode(&supported, Fifo), Immediate);
+        assert_eq!(next_present_mode(&supported, Mailbox), Fifo);
+        assert_eq!(next_present_mode(&[Fifo], Fifo), Fifo);
+        assert_eq!(
+            next_present_mode(&supported, wgpu::PresentMode::AutoVsync),
+            Fifo
+        );
+    }
+
+    #[test]
+    fn rescaled_position_keeps_logical_spot() {
+        let position = UVec2::new(301, 200);
+
+        assert_eq!(rescaled_position(position, 1.0, 2.0), UVec2::new(602, 400));
+        assert_eq!(rescaled_position(position, 2.0, 1.0), UVec2::new(151, 100));
+        assert_eq!(rescaled_position(position, 1.5, 1.5), position);
+    }
+
+    #[test]
+    fn drags_follow_the_cursor_inside_the_window() {
+        use winit::dpi::PhysicalPosition;
+        use winit::event::{ElementState as WinitState, MouseButton, WindowEvent};
+
+        let device_id = DeviceId::dummy();
+        let moved = |x, y| WindowEvent::CursorMoved {
+            device_id,
+            position: PhysicalPosition::new(x, y),
+        };
+        let input = |state, button| WindowEvent::MouseInput {
+            device_id,
+            state,
+            button,
+        };
+        let mut mouse_position = UVec2::new(10, 10);
+        let mut drag = None;
+        let mut touches = TouchTracker::default();
+        let mut process = |event| {
+            process_window_event::<()>(
+                event,
+                &mut mouse_position,
+                &mut 0.0,
+                &mut drag,
+                &mut touches,
+                1.0,
+            )
+        };
+        let move_event = |x, y, delta: IVec2| Event::MouseMove {
+            position: UVec2::new(x, y),
+            delta,
+        };
+
+        process(input(WinitState::Pressed, MouseButton::Left));
+        assert_eq!(
+            process(moved(14.0, 8.0)),
+            move_event(14, 8, IVec2::new(4, -2))
+        );
+
+        // outside the raw motion moves the view, coming back only moves the cursor
+        process(WindowEvent::CursorLeft { device_id });
+        assert_eq!(process(moved(0.0, 8.0)), Event::Unknown);
+        process(WindowEvent::CursorEntered { device_id });
+        assert_eq!(process(moved(3.0, 5.0)), move_event(3, 5, IVec2::ZERO));
+        assert_eq!(process(moved(5.0, 5.0)), move_event(5, 5, IVec2::new(2, 0)));
+
+        // only the button that drags ends the drag
+        process(input(WinitState::Pressed, MouseButton::Right));
+        process(input(WinitState::Released, MouseButton::Right));
+        process(WindowEvent::CursorLeft { device_id });
+        assert_eq!(process(moved(0.0, 5.0)), Event::Unknown);
+        process(input(WinitState::Released, MouseButton::Left));
+        assert_eq!(process(moved(2.0, 5.0)), move_event(2, 5, IVec2::new(2, 0)));
+    }
+
+    #[test]
+    fn drag_motion_keeps_sub_pixel_deltas() {
+        let position = UVec2::new(10, 20);
+        let mut drag_remainder = DVec2::ZERO;
+        let mut drag =
+            |x, y| drag_motion_event::<()>(DVec2::new(x, y), position, &mut drag_remainder);
+
+        assert_eq!(
+            drag(2.0, -3.0),
+            Event::MouseMove {
+                position,
+                delta: IVec2::new(2, -3),
+            }
+        );
+        assert_eq!(
+            drag(0.6, -0.6),
+            Event::MouseMove {
+                position,
+                delta: IVec2::ZERO,
+            }
+        );
+        assert_eq!(
+            drag(0.6, -0.6),
+            Event::MouseMove {
+                position,
+                delta: IVec2::new(1, -1),
+            }
+        );
+    }
+}
@@ -1,47 +1,279 @@
 use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::mem::{size_of, swap};
-use std::sync::atomic::AtomicBool;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::{bail, Context};
 use bytemuck::Zeroable;
-use glam::{DVec2, Mat4, UVec2, Vec2, Vec3};
+use glam::{DVec2, I64Vec2, IVec2, Mat4, UVec2, Vec3, Vec4};
 use parking_lot::Mutex;
-use tokio::runtime::Runtime;
-use tokio::sync::Semaphore;
-use tokio::task::JoinHandle;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+use web_time::Instant;
 use wgpu::util::DeviceExt;
 
 use crate::buffer_pool::BufferPool;
-use crate::mandelbrot_simd::{mandelbrot_simd, Pixel, MAX_ITER};
+use crate::env;
+use crate::gpu_profiler::GpuScope;
+use crate::kernel::{
+    resume_frame, CancelSignal, CancelToken, FractalKernel, InteriorOrbits, KernelOptions,
+    NeverCancelled, PixelGrid,
+};
+use crate::mandelbrot_simd::{
+    EscapeMetric, FractalKind, KernelPixel, Pixel, SimdKernel, MANDELBROT_POWER, MAX_ITER,
+};
 use crate::math::{DRect, URect};
+use crate::mipmaps::{mip_level_count, MipFormat, MipGenerator, MipLevel};
+use crate::palette::{Palette, PaletteMapping, PALETTE_SIZE};
+use crate::raw_export;
 use crate::render_pods::{PushConst, ScreenRect};
+use crate::runtime::{self, Runtime};
+use crate::settings::Settings;
+use crate::tile_cache::TileCache;
+use crate::tile_pyramid::{covering_keys, fallback_levels, texel_size, AtlasSlots};
+use crate::tile_stats::{TileStats, TileStatsSummary};
+use crate::tiling::{
+    compute_tile, is_interior_rect, page_coord, page_rect, tile_kernel, tile_rects,
+};
+use crate::work_queue::WorkQueue;
 use crate::RenderContext;
 
-const TILE_SIZE: u32 = 128;
-const TEXTURE_SIZE: u32 = 4 * 1024;
+pub const DEFAULT_TILE_SIZE: u32 = 128;
+// below this the window is rendered at reduced resolution from the start
+const MIN_TEXTURE_SIZE: u32 = 2 * 1024;
+// caps memory: the nine pages and two preview textures of up to 4 bytes per texel
+// take about 160 MB at 2560x1440, larger windows are rendered at reduced resolution
+const MAX_TEXTURE_SIZE: u32 = 2560;
+// pages per side of the grid around the frame, the outer ring is computed ahead of panning
+const PAGE_GRID_SIZE: i32 = 3;
+/// The coarsest `MandelTexture::set_render_scale`, it divides the smallest tile size.
+pub const MAX_RENDER_SCALE: u32 = 4;
+/// sRGB gray level shown where nothing is computed yet, e.g. right after zooming out.
+pub const DEFAULT_LOADING_FILL: u8 = 40;
+/// Budget of the tile cache unless the settings ask for another.
+pub const DEFAULT_TILE_CACHE_MB: u32 = 256;
+// the overview covers the page grid at this fraction of the page resolution per side,
+// a few percent of the pixels of the grid
+const OVERVIEW_DOWNSCALE: u32 = 16;
+// relative size change below which the texture is not rescaled
+const SCALE_EPSILON: f64 = 1e-9;
+/// Linear rgb of points inside the set.
+pub const DEFAULT_INTERIOR_COLOR: Vec3 = Vec3::ZERO;
+const PUSH_CONSTANT_DECLARATION: &str = "var<push_constant> pc: PushConstant;";
+const UNIFORM_DECLARATION: &str = "@group(1) @binding(0) var<uniform> pc: PushConstant;";
+// the texture grows only once the window size has been stable this long
+pub const RESIZE_SETTLE_TIME: Duration = Duration::from_millis(250);
+// seconds a tile fades in over the preview once it is computed
+const TILE_FADE_DURATION: f32 = 0.15;
+/// Pages up to this size on both sides are computed whole, as a single tile uploaded at once;
+/// for small windows scheduling, cancelling and uploading tile by tile costs more than the
+/// tiles save by showing up early.
+pub const WHOLE_PAGE_MAX_SIZE: u32 = 256;
+// side of the atlas of the tile pyramid unless the device limit is lower, 256 tiles of the
+// default size
+const PYRAMID_ATLAS_SIZE: u32 = 2048;
+
+/// How much `MandelTexture::trim_memory` frees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrimLevel {
+    /// Spare tile buffers, the data for continuing tiles, the tile cache and the tiles computing
+    /// around the view.
+    Caches,
+    /// Also shrinks the textures to the smallest size that covers the window.
+    Textures,
+}
+
+/// How escape counts are stored in the fractal texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColoringMode {
+    /// Integer iteration counts, fast and compact.
+    #[default]
+    Banded,
+    /// Continuous escape counts without banding, twice the memory.
+    Smooth,
+}
+
+/// The values of one texel recomputed on the CPU, see `MandelTexture::sample_at`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointSample {
+    /// The point the texel was computed for, the lower left corner of its cell.
+    pub point: DVec2,
+    /// The texel within its page.
+    pub pixel: UVec2,
+    /// Iterations before escaping as the banded mode stores them, wrapping past `u16::MAX`,
+    /// `None` inside the set.
+    pub iterations: Option<u32>,
+    /// Continuous escape count of the smooth mode, zero inside the set.
+    pub smooth: f32,
+}
+
+type TileBuffer = Arc<Mutex<Vec<u8>>>;
+type TileCallback = Arc<dyn Fn(usize, Result<(), String>) + Send + Sync>;
 
 #[derive(Debug, Default)]
 pub enum TileState {
     #[default]
     Idle,
+    // waits in the work queue until a worker claims it
+    Queued {
+        cancel_token: CancelToken,
+    },
     Computing {
-        task_handle: JoinHandle<()>,
-        cancel_token: Arc<AtomicBool>,
+        cancel_token: CancelToken,
     },
     WaitForUpload {
-        buffer: Arc<Mutex<Vec<u8>>>,
+        buffer: TileBuffer,
+        coloring_mode: ColoringMode,
+        completed: Instant,
+        compute_time: Duration,
+    },
+    Ready {
+        // from a worker picking the job up until the kernel finished
+        compute_time: Duration,
+    },
+    Failed {
+        reason: String,
     },
 }
 
 #[derive(Debug)]
 pub struct Tile {
     pub index: usize,
+    pub page: usize,
     pub tex_rect: URect,
     pub state: Arc<Mutex<TileState>>,
+    // hands out the token of each job, a new job cancels the ones before it
+    cancel_token: CancelToken,
+    // kept for visible tiles only, so a higher iteration limit continues where the last job stopped
+    resume: Arc<Mutex<Option<TileResume>>>,
+    // of the last job, a coarser one than the current scale is recomputed
+    render_scale: u32,
+    // of the last job, see `MandelTexture::tile_progress`
+    generation: u64,
+}
+
+// a finished job with its values and the last `z` of the pixels that didn't escape
+#[derive(Debug)]
+struct TileResume {
+    job: TileJob,
+    values: Vec<u8>,
+    interior: InteriorOrbits,
+}
+
+// a tile job waiting in the work queue, with all a worker needs to compute and report it
+struct TileTask {
+    tile_index: usize,
+    job: TileJob,
+    state: Arc<Mutex<TileState>>,
+    cancel_token: CancelToken,
+    resumable: bool,
+    previous: Option<TileResume>,
+    resume: Arc<Mutex<Option<TileResume>>>,
+    callback: TileCallback,
+    queued: Instant,
+    span: tracing::Span,
+    cache_key: TileKey,
+}
+
+// what the values of a tile depend on: its texels on the grid of its scale, which `lay_out`
+// puts on whole texels from the origin so the keys repeat whenever the scale returns, and
+// the rest of the job as a hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct TileKey {
+    // the log of the texel size, quantized to the precision `scale_changed` tells scales apart at
+    scale: i64,
+    texel: I64Vec2,
+    size: UVec2,
+    options: u64,
+}
+
+// a low resolution frame of the whole page grid below the preview, computed as a single job
+// ahead of the tiles, so places without data show the fractal rather than the loading fill
+#[derive(Debug)]
+struct Overview {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    // texture coordinates differ from the pages', it is smaller
+    screen_rect_buf: wgpu::Buffer,
+    // area the texture holds data for
+    content_rect: Option<DRect>,
+    // the last one scheduled, it is only replaced by a different one
+    job: Option<TileJob>,
+    state: Arc<Mutex<TileState>>,
+    cancel_token: CancelToken,
+}
+
+// one texture of the grid, pages that fall behind when panning move to the free grid positions
+#[derive(Debug)]
+struct Page {
+    // position in the grid in page sizes from `fractal_rect`
+    coord: IVec2,
+    // area the texture holds data for, differs from the page rect until the next render clears it
+    content_rect: Option<DRect>,
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    // per tile, row by row: when it first got data since the page was cleared, `None` until then;
+    // tiles uploaded again over their own data don't fade
+    fade_starts: Vec<Option<f32>>,
+    fade_texture: wgpu::Texture,
+    fade_dirty: bool,
+    // per tile like the fade texture, the `tile_status` the debug overlay tints it by
+    status_texture: wgpu::Texture,
+    overlay_bind_group: wgpu::BindGroup,
+    // of `texture`, the first is the full resolution one
+    mips: Vec<MipLevel>,
+    // the levels after the first are behind it, they are generated once per render
+    mips_dirty: bool,
+}
+
+// the tiles of the power-of-two levels coarser than the pages in the slots of an atlas, drawn
+// below the preview so zooming shows a coarser picture rather than the loading fill; see
+// `tile_pyramid`
+#[derive(Debug)]
+struct Pyramid {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    // a `ScreenRect` per slot, on the texels of the slot
+    screen_rects_buf: wgpu::Buffer,
+    slots_per_side: u32,
+    tile_size: u32,
+    slots: AtlasSlots<TileKey>,
+    tiles: Vec<PyramidTile>,
+    // the slots covering the frame of the last update, the coarsest level first
+    shown: Vec<usize>,
+}
+
+#[derive(Debug, Default)]
+struct PyramidTile {
+    state: Arc<Mutex<TileState>>,
+    cancel_token: CancelToken,
+    // of the last job, the part of the plane the slot shows once it is ready
+    rect: Option<DRect>,
+}
+
+/// How the shaders receive the per-draw `PushConst`.
+#[derive(Debug)]
+enum DrawParams {
+    PushConstants,
+    // for devices without push constants, every draw of a pass writes its own aligned slot
+    // and selects it with a dynamic offset, the writes land at the next submit
+    Uniform {
+        buffer: wgpu::Buffer,
+        bind_group_layout: wgpu::BindGroupLayout,
+        bind_group: wgpu::BindGroup,
+        slot_size: u32,
+    },
 }
 
 #[derive(Debug)]
 pub struct MandelTexture {
+    // ping-pong pair keeping the frame visible at the previous scale while the pages recompute
     texture1: wgpu::Texture,
     texture1_view: wgpu::TextureView,
     bind_group1: wgpu::BindGroup,
@@ -49,34 +281,408 @@ pub struct MandelTexture {
     texture2: wgpu::Texture,
     texture2_view: wgpu::TextureView,
     bind_group2: wgpu::BindGroup,
+    preview_rect: DRect,
+
+    pages: Vec<Page>,
 
     screen_rect_buf: wgpu::Buffer,
     bind_group_layout: wgpu::BindGroupLayout,
     sampler: wgpu::Sampler,
+    palette_texture: wgpu::Texture,
+    palette_view: wgpu::TextureView,
+    // uploaded by the next render
+    pending_palette: Option<Palette>,
+    // bound by the previews, which never fade
+    no_fade_view: wgpu::TextureView,
+    draw_params: DrawParams,
+    pipeline_layout: wgpu::PipelineLayout,
 
     blit_pipeline: wgpu::RenderPipeline,
     screen_pipeline: wgpu::RenderPipeline,
+    overlay_pipeline: wgpu::RenderPipeline,
+    mip_generator: MipGenerator,
 
-    pub(crate) buf_pool: BufferPool,
+    pub(crate) buf_pool: Arc<Mutex<BufferPool>>,
 
     window_size: UVec2,
-    texture_size: u32,
+    texture_size: UVec2,
+    tile_size: u32,
+    max_texture_dimension: u32,
+    window_covered: bool,
+    // grown texture size and when to allocate it
+    pending_texture_size: Option<(UVec2, Instant)>,
 
     runtime: Runtime,
-    semaphore: Arc<Semaphore>,
+    // the tile jobs, taken by `worker_count` tasks on `runtime`, one per thread
+    queue: Arc<WorkQueue<TileTask>>,
+    // values of finished tiles, a tile found here isn't computed again
+    tile_cache: Arc<Mutex<TileCache<TileKey>>>,
+    worker_count: usize,
+    // the threads of `compute_frame_blocking`, started by its first call
+    blocking_pool: Option<rayon::ThreadPool>,
     tiles: Vec<Tile>,
 
     frame_rect: DRect,
+    // radians the frame is turned by counterclockwise about its center on screen
+    rotation: f64,
+    // the part of the plane the turned frame shows, the tiles in it are the visible ones
+    view_bounds: DRect,
+    // tiles of the frame that are not Ready, counted by `update` and decremented as they upload
+    unready_tiles: usize,
+    // rect of the page at grid position zero, the other pages are offset by multiples of its size
     fractal_rect: DRect,
-    fractal_rect_prev: DRect,
-    frame_changed: bool,
+    reproject: bool,
+    // set by `rebuild`, the targets are recreated like after a coloring mode switch
+    targets_lost: bool,
+    invalidated: bool,
+    prefetch_deferred: bool,
+    // no tiles are scheduled, the ones already done keep displaying
+    paused: bool,
+    // counts the updates that scheduled tiles, the tile spans carry it; the view completing
+    // is reported once per generation
+    generation: u64,
+    generation_start: Instant,
+    generation_reported: bool,
+    // the first generation of the work `tile_progress` measures
+    progress_generation: u64,
+    stats: Arc<TileStats>,
+
+    aa_threshold: Option<u16>,
+    kind: FractalKind,
+    power: f64,
+    escape_metric: EscapeMetric,
+    // multiplies the iteration limit that follows from the zoom
+    iteration_factor: f64,
+    dither: bool,
+    edge_detection: bool,
+    exposure: f32,
+    gamma: f32,
+    interior_color: Vec3,
+    palette_mapping: PaletteMapping,
+    tile_fade: bool,
+    // debug overlay tinting every tile of the pages by its state
+    tile_overlay: bool,
+    // sRGB gray of the background
+    loading_fill: u8,
+    // texels per side computed as one
+    render_scale: u32,
+    // None unless enabled by the settings
+    overview: Option<Overview>,
+    pyramid: Option<Pyramid>,
+    // of the last render, and when the last tile fading in is done
+    render_time: f64,
+    fade_end: f64,
+    // mode of the current textures and the one tiles are computed in, they differ until the next render
+    texture_coloring_mode: ColoringMode,
+    coloring_mode: ColoringMode,
+}
+
+// small limits only lower the resolution, a page needs room for a single tile
+fn check_texture_limit(max_texture_dimension: u32, tile_size: u32) -> anyhow::Result<()> {
+    if max_texture_dimension < tile_size {
+        bail!(
+            "The device supports textures up to {}px, a {}px tile doesn't fit",
+            max_texture_dimension,
+            tile_size
+        );
+    }
+    if max_texture_dimension < MIN_TEXTURE_SIZE {
+        tracing::warn!(
+            max_texture_dimension,
+            "The device supports small textures only, the fractal is rendered at reduced resolution"
+        );
+    }
+
+    Ok(())
+}
+
+// a page covers the window at one texel per pixel, rounded up to whole tiles;
+// windows beyond the largest size the device supports get the same coverage on both axes
+fn calc_texture_size(max_texture_dimension: u32, window_size: UVec2, tile_size: u32) -> UVec2 {
+    let max_size = max_texture_dimension.min(MAX_TEXTURE_SIZE) / tile_size * tile_size;
+    debug_assert!(max_size >= tile_size, "checked by check_texture_limit");
+
+    let coverage = (max_size as f64 / window_size.max_element() as f64).min(1.0);
+    let size = (window_size.as_dvec2() * coverage).ceil().as_uvec2();
+
+    UVec2::new(
+        size.x.div_ceil(tile_size) * tile_size,
+        size.y.div_ceil(tile_size) * tile_size,
+    )
+    .min(UVec2::splat(max_size))
+}
+
+// texture size for a resized window, None if the current texture is large enough
+fn grown_texture_size(
+    texture_size: UVec2,
+    max_texture_dimension: u32,
+    window_size: UVec2,
+    tile_size: u32,
+) -> Option<UVec2> {
+    let required = calc_texture_size(max_texture_dimension, window_size, tile_size);
+    if required.cmple(texture_size).all() {
+        return None;
+    }
+
+    Some(required.max(texture_size))
+}
+
+// half the texture size in whole tiles, None once it is a single tile
+fn shrunk_texture_size(
+    texture_size: UVec2,
+    max_texture_dimension: u32,
+    tile_size: u32,
+) -> Option<UVec2> {
+    let half_size = (texture_size / 2).max(UVec2::splat(tile_size));
+    let shrunk = calc_texture_size(max_texture_dimension, half_size, tile_size);
+
+    (shrunk != texture_size).then_some(shrunk)
+}
+
+// slots of the pyramid atlas per side, at least one as a page holds a tile
+fn pyramid_slots_per_side(max_texture_dimension: u32, tile_size: u32) -> u32 {
+    PYRAMID_ATLAS_SIZE.min(max_texture_dimension) / tile_size
+}
+
+// the first texel of `slot` on the pyramid atlas, slot by slot along the rows
+fn atlas_slot_pos(slot: usize, slots_per_side: u32, tile_size: u32) -> UVec2 {
+    let slot = slot as u32;
+    UVec2::new(slot % slots_per_side, slot / slots_per_side) * tile_size
+}
+
+fn is_whole_page(texture_size: UVec2) -> bool {
+    texture_size.cmple(UVec2::splat(WHOLE_PAGE_MAX_SIZE)).all()
+}
+
+// side of the square cells a page fades in by, one per tile; a whole page fades in at once
+fn fade_cell_size(texture_size: UVec2, tile_size: u32) -> u32 {
+    if is_whole_page(texture_size) {
+        texture_size.max_element()
+    } else {
+        tile_size
+    }
+}
+
+// index of the cell of the fade and status textures the tile at `tex_rect` falls in
+fn cell_index(texture_size: UVec2, tile_size: u32, tex_rect: URect) -> usize {
+    let cell_size = fade_cell_size(texture_size, tile_size);
+    let cell = tex_rect.pos / cell_size;
+
+    (cell.y * texture_size.x.div_ceil(cell_size) + cell.x) as usize
+}
+
+// The texel of a tile in the status texture of the debug overlay: the state in the high
+// nibble, in the order the overlay shader colors them, and the low bits of the generation of
+// its last job below, so the tiles of successive updates differ in shade.
+fn tile_status(state: &TileState, generation: u64) -> u8 {
+    let state = match state {
+        TileState::Idle => 0,
+        TileState::Queued { .. } => 1,
+        TileState::Computing { .. } => 2,
+        TileState::WaitForUpload { .. } => 3,
+        TileState::Ready { .. } => 4,
+        TileState::Failed { .. } => 5,
+    };
+
+    (state << 4) | (generation % 16) as u8
+}
+
+// bytes a tile buffer needs, in the widest pixel format
+fn tile_buffer_size(texture_size: UVec2, tile_size: u32) -> usize {
+    let texels = if is_whole_page(texture_size) {
+        texture_size.element_product()
+    } else {
+        tile_size * tile_size
+    };
+
+    texels as usize * size_of::<f32>()
+}
+
+fn create_tiles(page_count: usize, texture_size: UVec2, tile_size: u32) -> Vec<Tile> {
+    let rects: Vec<URect> = if is_whole_page(texture_size) {
+        vec![URect::from_pos_size(UVec2::ZERO, texture_size)]
+    } else {
+        tile_rects(texture_size, tile_size).collect()
+    };
+
+    (0..page_count)
+        .flat_map(|page| rects.iter().map(move |&rect| (page, rect)))
+        .enumerate()
+        .map(|(index, (page, tex_rect))| Tile {
+            index,
+            page,
+            tex_rect,
+            state: Arc::new(Mutex::new(TileState::Idle)),
+            cancel_token: CancelToken::new(),
+            resume: Arc::new(Mutex::new(None)),
+            render_scale: 1,
+            generation: 0,
+        })
+        .collect()
+}
+
+// every texel of every page in exactly one tile, see `MandelTexture::validate_tiling`
+fn validate_tiling(
+    tiles: &[Tile],
+    page_count: usize,
+    texture_size: UVec2,
+    tile_size: u32,
+) -> Result<(), String> {
+    if !texture_size.x.is_multiple_of(tile_size) || !texture_size.y.is_multiple_of(tile_size) {
+        return Err(format!(
+            "Texture size {} is not a multiple of the tile size {}",
+            texture_size, tile_size
+        ));
+    }
+    if let Some(tile) = tiles.iter().find(|tile| {
+        tile.page >= page_count
+            || tile.tex_rect.size.min_element() == 0
+            || !tile.tex_rect.upper_right().cmple(texture_size).all()
+    }) {
+        return Err(format!(
+            "Tile {} at {:?} of page {} is outside of the {} texture",
+            tile.index, tile.tex_rect, tile.page, texture_size
+        ));
+    }
+
+    // every tile edge lies on a grid of this many texels, counting per cell counts every texel
+    let cell = tiles
+        .iter()
+        .flat_map(|tile| [tile.tex_rect.pos, tile.tex_rect.size])
+        .flat_map(|value| value.to_array())
+        .fold(gcd(texture_size.x, texture_size.y), gcd);
+    let cells = texture_size / cell;
+    let mut coverage = vec![0u8; page_count * cells.element_product() as usize];
+    for tile in tiles {
+        let pos = tile.tex_rect.pos / cell;
+        let end = tile.tex_rect.upper_right() / cell;
+        for y in pos.y..end.y {
+            for x in pos.x..end.x {
+                let index = (tile.page as u32 * cells.y + y) * cells.x + x;
+                let count = &mut coverage[index as usize];
+                *count = count.saturating_add(1);
+            }
+        }
+    }
+
+    match coverage.iter().position(|&count| count != 1) {
+        None => Ok(()),
+        Some(index) => {
+            let index = index as u32;
+            let (page, cell_index) = (
+                index / cells.element_product(),
+                index % cells.element_product(),
+            );
+            let texel = UVec2::new(cell_index % cells.x, cell_index / cells.x) * cell;
+            Err(format!(
+                "Texel {} of page {} is covered by {} tiles",
+                texel, page, coverage[index as usize]
+            ))
+        }
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// grid positions around `center`, row by row
+fn grid_coords(center: IVec2) -> impl Iterator<Item = IVec2> {
+    let radius = PAGE_GRID_SIZE / 2;
+    (-radius..=radius).flat_map(move |y| (-radius..=radius).map(move |x| center + IVec2::new(x, y)))
+}
+
+// moves the pages outside the grid around `center` to the free positions,
+// returns the indices of the moved pages
+fn recycle_pages(coords: &mut [IVec2], center: IVec2) -> Vec<usize> {
+    let free: Vec<IVec2> = grid_coords(center)
+        .filter(|coord| !coords.contains(coord))
+        .collect();
+
+    let radius = PAGE_GRID_SIZE / 2;
+    let mut free = free.into_iter();
+    let mut moved = Vec::new();
+    for (index, coord) in coords.iter_mut().enumerate() {
+        if (*coord - center).abs().max_element() > radius {
+            *coord = free.next().unwrap();
+            moved.push(index);
+        }
+    }
+
+    moved
+}
+
+// maps the screen quad of a texture showing `rect` into `target` turned by `rotation` about its
+// center, rows of texture render targets go downwards, so their offset is flipped
+fn quad_transform(rect: DRect, target: DRect, rotation: f64, flip_y: bool) -> Mat4 {
+    // the plane turns the other way under the frame; in f64 up to the ratios of the sizes,
+    // which stay near one at any zoom
+    let turn = DVec2::from_angle(-rotation);
+    let x_axis = turn.rotate(DVec2::X * rect.size.x) / target.size;
+    let y_axis = turn.rotate(DVec2::Y * rect.size.y) / target.size;
+    let mut offset = 2.0 * turn.rotate(rect.center() - target.center()) / target.size;
+    if flip_y {
+        offset.y = -offset.y;
+    }
+
+    Mat4::from_cols(
+        x_axis.as_vec2().extend(0.0).extend(0.0),
+        y_axis.as_vec2().extend(0.0).extend(0.0),
+        Vec4::Z,
+        offset.as_vec2().extend(0.0).extend(1.0),
+    )
+}
+
+fn covers_window(texture_size: UVec2, window_size: UVec2) -> bool {
+    window_size.cmple(texture_size).all()
+}
+
+// warns once when the window outgrows the largest texture, not again until it fits and outgrows it again
+fn update_window_covered(window_covered: bool, texture_size: UVec2, window_size: UVec2) -> bool {
+    let covered = covers_window(texture_size, window_size);
+    if window_covered && !covered {
+        tracing::warn!(
+            "Window {}x{} is larger than the {}x{} fractal texture, rendering at reduced resolution",
+            window_size.x,
+            window_size.y,
+            texture_size.x,
+            texture_size.y
+        );
+    }
+
+    covered
+}
+
+// size of the fractal rect mapped onto the texture, one texel per pixel
+// unless the texture is smaller than the window, then it is stretched to cover the frame
+fn fractal_size(texture_size: UVec2, window_size: UVec2, frame_size: DVec2) -> DVec2 {
+    let texture_size = texture_size.as_dvec2();
+    let pixel_size = frame_size.y / window_size.y as f64;
+    let texel_size = pixel_size.max((frame_size / texture_size).max_element());
+
+    texture_size * texel_size
+}
+
+// compares in log space so zooming in and out by the same factor is treated alike
+fn scale_changed(old_size: DVec2, new_size: DVec2) -> bool {
+    if old_size.y <= 0.0 || new_size.y <= 0.0 {
+        return old_size.y != new_size.y;
+    }
+
+    (new_size.y / old_size.y).ln().abs() > SCALE_EPSILON
+}
+
+fn scaled_max_iters(fractal_rect: DRect, iteration_factor: f64) -> u32 {
+    let max_iters = calc_max_iters(fractal_rect) as f64 * iteration_factor;
+    (max_iters.round() as u32).max(1)
 }
 
-fn calc_max_iters(fractal_rect: DRect) -> u32 {
-    let max_iterations =
-        (1000 + ((1.0 / fractal_rect.size.length_squared()).log2() * 50.0) as u32).min(MAX_ITER);
-    // println!("max_iterations: {}", max_iterations);
-    max_iterations
+pub(crate) fn calc_max_iters(fractal_rect: DRect) -> u32 {
+    (1000 + ((1.0 / fractal_rect.size.length_squared()).log2() * 50.0) as u32).min(MAX_ITER)
 }
 
 impl MandelTexture {
@@ -85,84 +691,31 @@ impl MandelTexture {
         queue: &wgpu::Queue,
         surface_config: &wgpu::SurfaceConfiguration,
         window_size: UVec2,
-    ) -> Self {
-        let texture_size = TEXTURE_SIZE;
-        assert!(texture_size >= 2048);
-        assert_eq!(texture_size % TILE_SIZE, 0);
+        settings: &Settings,
+        palette: &Palette,
+    ) -> anyhow::Result<Self> {
+        let tile_size = settings.tile_size;
+        let max_texture_dimension = device.limits().max_texture_dimension_2d;
+        check_texture_limit(max_texture_dimension, tile_size)?;
+        let texture_size = calc_texture_size(max_texture_dimension, window_size, tile_size);
+        let window_covered = update_window_covered(true, texture_size, window_size);
 
-        let texture_extent = wgpu::Extent3d {
-            width: texture_size,
-            height: texture_size,
-            depth_or_array_layers: 1,
-        };
-
-        let texture1 = device.create_texture(&wgpu::TextureDescriptor {
-            size: texture_extent,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::R16Uint,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::RENDER_ATTACHMENT
-                | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-            label: None,
-        });
-        let texture1_view = texture1.create_view(&wgpu::TextureViewDescriptor::default());
+        let coloring_mode = ColoringMode::default();
 
-        let texture2 = device.create_texture(&wgpu::TextureDescriptor {
-            size: texture_extent,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::R16Uint,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::RENDER_ATTACHMENT
-                | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-            label: None,
-        });
-        let texture2_view = texture2.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let tile_count = texture_size / TILE_SIZE;
-        let mut tiles = Vec::with_capacity(tile_count as usize * tile_count as usize);
-        for i in 0..tile_count {
-            for j in 0..tile_count {
-                let index = tiles.len();
-                let rect = URect {
-                    pos: UVec2::new(i * TILE_SIZE, j * TILE_SIZE),
-                    size: UVec2::new(TILE_SIZE, TILE_SIZE),
-                };
-                tiles.push(Tile {
-                    index,
-                    tex_rect: rect,
-                    state: Arc::new(Mutex::new(TileState::Idle)),
-                });
-            }
-        }
+        let page_count = grid_coords(IVec2::ZERO).count();
+        let tiles = create_tiles(page_count, texture_size, tile_size);
+        debug_assert_eq!(
+            validate_tiling(&tiles, page_count, texture_size, tile_size),
+            Ok(())
+        );
 
-        let runtime = Runtime::new().unwrap();
-        let cpu_core_count = num_cpus::get_physical();
-        let semaphore = Arc::new(Semaphore::new(cpu_core_count * 2));
+        // the kernels get a runtime of their own, apart from the one of the app's timers
+        let worker_count = settings.worker_count();
+        let runtime = Runtime::new(worker_count, "fractal-worker")
+            .context("Failed to start the worker threads")?;
 
-        let vertex_buffers = [wgpu::VertexBufferLayout {
-            array_stride: ScreenRect::vert_size() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x4,
-                    offset: 0,
-                    shader_location: 0,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x2,
-                    offset: 4 * 4,
-                    shader_location: 1,
-                },
-            ],
-        }];
         let screen_rect_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            contents: ScreenRect::with_texture_size(UVec2::splat(texture_size)).as_bytes(),
+            contents: ScreenRect::with_texture_size(texture_size).as_bytes(),
             usage: wgpu::BufferUsages::VERTEX,
             label: None,
         });
@@ -178,7 +731,7 @@ impl MandelTexture {
 
         let palette_texture = device.create_texture(&wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
-                width: 256,
+                width: PALETTE_SIZE as u32,
                 height: 1,
                 depth_or_array_layers: 1,
             },
@@ -191,173 +744,124 @@ impl MandelTexture {
             label: None,
         });
         let palette_view = palette_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        write_palette(queue, &palette_texture, palette);
 
-        let img = image::open("palette.png").unwrap();
-        let img = img.into_rgba8();
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: &palette_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &img.as_raw(),
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(256 * 4),
-                rows_per_image: Some(1),
-            },
-            wgpu::Extent3d {
-                width: 256,
-                height: 1,
-                depth_or_array_layers: 1,
-            },
+        // the overview, every pyramid tile, the preview and every page are drawn once per pass,
+        // the pages once more by the tile overlay
+        let pyramid_slots = if settings.tile_pyramid {
+            pyramid_slots_per_side(max_texture_dimension, tile_size).pow(2) as usize
+        } else {
+            0
+        };
+        let draw_params = DrawParams::new(device, 2 * page_count + 2 + pyramid_slots);
+        let bind_group_layout = create_bind_group_layout(device);
+        let pipeline_layout = create_pipeline_layout(device, &bind_group_layout, &draw_params);
+        let blit_pipeline = create_pipeline(
+            device,
+            &pipeline_layout,
+            &draw_params.shader_source(include_str!("blit_shader.wgsl")),
+            coloring_mode.texture_format(),
+            None,
+        );
+        // pages fading in blend over the preview
+        let screen_pipeline = create_pipeline(
+            device,
+            &pipeline_layout,
+            &draw_params.shader_source(include_str!("screen_shader.wgsl")),
+            surface_config.view_formats[0],
+            Some(wgpu::BlendState::ALPHA_BLENDING),
+        );
+        let overlay_pipeline = create_pipeline(
+            device,
+            &pipeline_layout,
+            &draw_params.shader_source(include_str!("tile_overlay_shader.wgsl")),
+            surface_config.view_formats[0],
+            Some(wgpu::BlendState::ALPHA_BLENDING),
         );
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Uint,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D1,
-                    },
-                    count: None,
-                },
-            ],
-            label: None,
-        });
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[wgpu::PushConstantRange {
-                stages: wgpu::ShaderStages::VERTEX,
-                range: 0..PushConst::size_in_bytes(),
-            }],
-            label: None,
-        });
-
-        let bind_group1 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&texture1_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&palette_view),
-                },
-            ],
-            label: None,
+        let no_fade_view = create_cell_texture(device, UVec2::ONE, wgpu::TextureFormat::R32Float)
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let (texture1, texture1_view, bind_group1) = create_target(
+            device,
+            texture_size,
+            coloring_mode,
+            1,
+            &bind_group_layout,
+            &sampler,
+            &palette_view,
+            &no_fade_view,
+        );
+        let (texture2, texture2_view, bind_group2) = create_target(
+            device,
+            texture_size,
+            coloring_mode,
+            1,
+            &bind_group_layout,
+            &sampler,
+            &palette_view,
+            &no_fade_view,
+        );
+        let mip_generator = MipGenerator::new(device);
+        let pages = grid_coords(IVec2::ZERO)
+            .map(|coord| {
+                Page::new(
+                    device,
+                    coord,
+                    texture_size,
+                    fade_cell_size(texture_size, tile_size),
+                    coloring_mode,
+                    &bind_group_layout,
+                    &sampler,
+                    &palette_view,
+                    &mip_generator,
+                )
+            })
+            .collect();
+        let overview = settings.overview_fill.then(|| {
+            Overview::new(
+                device,
+                texture_size,
+                coloring_mode,
+                &bind_group_layout,
+                &sampler,
+                &palette_view,
+                &no_fade_view,
+            )
         });
-        let bind_group2 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&texture2_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&palette_view),
-                },
-            ],
-            label: None,
+        let pyramid = settings.tile_pyramid.then(|| {
+            Pyramid::new(
+                device,
+                pyramid_slots_per_side(max_texture_dimension, tile_size),
+                tile_size,
+                coloring_mode,
+                &bind_group_layout,
+                &sampler,
+                &palette_view,
+                &no_fade_view,
+            )
         });
 
-        let blit_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: None,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("blit_shader.wgsl"))),
-        });
-        let blit_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &blit_shader,
-                entry_point: Some("vs_main"),
-                compilation_options: Default::default(),
-                buffers: &vertex_buffers,
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &blit_shader,
-                entry_point: Some("fs_main"),
-                compilation_options: Default::default(),
-                targets: &[Some(wgpu::TextureFormat::R16Uint.into())],
-            }),
-            primitive: wgpu::PrimitiveState {
-                cull_mode: None,
-                front_face: wgpu::FrontFace::Cw,
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
+        let buffer_size = tile_buffer_size(texture_size, tile_size);
+        // all tiles of a page can wait for upload at once, the pool grows beyond that if needed
+        let reserved_buffers = tiles.len() / page_count;
+        let buf_pool = Arc::new(Mutex::new(BufferPool::new(buffer_size, reserved_buffers)));
+        let stats = Arc::new(TileStats::default());
 
-        let screen_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: None,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("screen_shader.wgsl"))),
-        });
-        let screen_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &screen_shader,
-                entry_point: Some("vs_main"),
-                compilation_options: Default::default(),
-                buffers: &vertex_buffers,
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &screen_shader,
-                entry_point: Some("fs_main"),
-                compilation_options: Default::default(),
-                targets: &[Some(surface_config.view_formats[0].into())],
-            }),
-            primitive: wgpu::PrimitiveState {
-                cull_mode: None,
-                front_face: wgpu::FrontFace::Cw,
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
+        let tile_cache = Arc::new(Mutex::new(TileCache::new(
+            settings.tile_cache_mb as usize * 1024 * 1024,
+        )));
 
-        let buffer_size = (TILE_SIZE * TILE_SIZE) as usize * size_of::<Pixel>();
+        let queue = Arc::new(WorkQueue::new());
+        for _ in 0..worker_count {
+            runtime.spawn(run_worker(
+                queue.clone(),
+                buf_pool.clone(),
+                stats.clone(),
+                tile_cache.clone(),
+            ));
+        }
 
-        Self {
+        Ok(Self {
             texture1,
             texture1_view,
             bind_group1,
@@ -365,148 +869,623 @@ impl MandelTexture {
             texture2,
             texture2_view,
             bind_group2,
+            preview_rect: DRect::zeroed(),
+
+            pages,
 
             blit_pipeline,
+            mip_generator,
             window_size,
 
             runtime,
-            semaphore,
+            queue,
+            tile_cache,
+            worker_count,
+            blocking_pool: None,
 
             texture_size,
+            tile_size,
+            max_texture_dimension,
+            window_covered,
+            pending_texture_size: None,
             tiles,
 
             frame_rect: DRect::zeroed(),
+            rotation: 0.0,
+            view_bounds: DRect::zeroed(),
+            unready_tiles: 0,
             fractal_rect: DRect::zeroed(),
-            fractal_rect_prev: DRect::zeroed(),
-            frame_changed: false,
+            reproject: false,
+            targets_lost: false,
+            invalidated: false,
+            prefetch_deferred: false,
+            paused: false,
+            generation: 0,
+            generation_start: Instant::now(),
+            generation_reported: true,
+            progress_generation: 0,
+            stats,
+
+            aa_threshold: None,
+            kind: FractalKind::default(),
+            power: MANDELBROT_POWER,
+            escape_metric: EscapeMetric::default(),
+            iteration_factor: settings.iteration_factor,
+            dither: true,
+            edge_detection: false,
+            exposure: 1.0,
+            gamma: 1.0,
+            interior_color: settings.interior_color,
+            palette_mapping: settings.palette_mapping,
+            tile_fade: settings.tile_fade,
+            tile_overlay: false,
+            loading_fill: settings.loading_fill,
+            render_scale: 1,
+            overview,
+            pyramid,
+            render_time: 0.0,
+            fade_end: 0.0,
+            texture_coloring_mode: coloring_mode,
+            coloring_mode,
 
             screen_rect_buf,
             bind_group_layout,
             screen_pipeline,
+            overlay_pipeline,
             sampler,
+            palette_texture,
+            palette_view,
+            pending_palette: None,
+            no_fade_view,
+            draw_params,
+            pipeline_layout,
+
+            buf_pool,
+        })
+    }
+
+    /// Computes every tile of `frame_rect` with `max_iter` iterations before it returns, split
+    /// over the worker count on rayon's threads, and leaves them for the next `render` to upload
+    /// at once. What that render shows doesn't depend on how the tiles were scheduled, unlike
+    /// with `update`, so captures come out the same on every run. The tiles around the frame
+    /// are left idle for the next `update`, and all of them if one fails.
+    pub fn compute_frame_blocking(
+        &mut self,
+        frame_rect: DRect,
+        max_iter: u32,
+    ) -> anyhow::Result<()> {
+        self.lay_out(frame_rect);
+        self.invalidated = false;
+        // nothing streams into the tiles computed here
+        self.queue.supersede();
+        let cancelled = self
+            .tiles
+            .iter()
+            .filter(|tile| tile.state.lock().cancel())
+            .count();
+        self.stats.record_cancelled(cancelled);
+
+        let generation = self.generation + 1;
+        let options = KernelOptions {
+            max_iter,
+            power: self.power,
+        };
+        let aa_threshold = self.applied_aa_threshold();
+        let mut jobs = Vec::new();
+        for tile in self.tiles.iter_mut() {
+            tile.resume.lock().take();
+            let page_rect = page_rect(self.fractal_rect, self.pages[tile.page].coord);
+            if !self
+                .view_bounds
+                .intersects(&tile.fractal_rect(self.texture_size, page_rect))
+            {
+                continue;
+            }
+
+            tile.render_scale = 1;
+            tile.generation = generation;
+            let job = TileJob {
+                grid: PixelGrid::new(page_rect, self.texture_size),
+                pixels: tile.tex_rect,
+                options,
+                kind: self.kind,
+                escape_metric: self.escape_metric,
+                aa_threshold,
+                coloring_mode: self.coloring_mode,
+                render_scale: 1,
+            };
+            jobs.push((
+                tile.index,
+                tile.state.clone(),
+                job,
+                self.buf_pool.lock().take(),
+            ));
+        }
+        self.prefetch_deferred = jobs.len() < self.tiles.len();
 
-            buf_pool: BufferPool::new(buffer_size, 1000),
+        if self.blocking_pool.is_none() {
+            let thread_pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(self.worker_count)
+                .build()
+                .context("Failed to start the capture threads")?;
+            self.blocking_pool = Some(thread_pool);
         }
+        let thread_pool = self.blocking_pool.as_ref().expect("started above");
+        let stats = &self.stats;
+        let results: Vec<anyhow::Result<Duration>> = thread_pool.install(|| {
+            jobs.par_iter()
+                .map(|(tile_index, _, job, buffer)| {
+                    let start = Instant::now();
+                    job.compute(&job.kernel(), CancelToken::new(), &mut buffer.lock())
+                        .with_context(|| format!("Tile {} failed", tile_index))?;
+                    let compute_time = start.elapsed();
+                    stats.compute.record(compute_time);
+
+                    Ok(compute_time)
+                })
+                .collect()
+        });
+        let compute_times = results.into_iter().collect::<anyhow::Result<Vec<_>>>()?;
+        // the states change in tile order once all are done, none is uploaded before the others
+        for ((_, state, job, buffer), compute_time) in jobs.into_iter().zip(compute_times) {
+            *state.lock() = TileState::WaitForUpload {
+                buffer,
+                coloring_mode: job.coloring_mode,
+                completed: Instant::now(),
+                compute_time,
+            };
+        }
+
+        self.unready_tiles = self.count_unready_tiles();
+        self.progress_generation = generation;
+        self.generation = generation;
+        self.generation_start = Instant::now();
+        self.generation_reported = false;
+        tracing::debug!(generation, max_iter, "Frame computed blocking");
+
+        Ok(())
     }
 
-    pub fn update<F>(&mut self, frame_rect: DRect, focus: DVec2, tile_ready_callback: F)
-    where
-        F: Fn(usize) + Clone + Send + Sync + 'static,
-    {
+    // moves the page grid under `frame_rect`, true if the scale changed and every tile needs
+    // recomputing
+    fn lay_out(&mut self, frame_rect: DRect) -> bool {
         self.frame_rect = frame_rect;
+        self.view_bounds = frame_rect.rotated_bounds(self.rotation);
 
-        let new_fractal_rect = DRect::from_center_size(
-            frame_rect.center(),
-            DVec2::new(
-                frame_rect.size.x * self.texture_size as f64 / self.window_size.x as f64,
-                frame_rect.size.y * self.texture_size as f64 / self.window_size.y as f64,
-            ),
-        );
+        let page_size = fractal_size(self.texture_size, self.window_size, frame_rect.size);
+        let scale_changed = scale_changed(self.fractal_rect.size, page_size);
+
+        if scale_changed {
+            // the grid is laid out anew around the frame, the preview keeps the old one visible;
+            // on whole texels from the origin, so the tiles of the scale repeat their cache keys
+            let texel_size = page_size / self.texture_size.as_dvec2();
+            let pos = ((frame_rect.center() - page_size / 2.0) / texel_size).round() * texel_size;
+            self.fractal_rect = DRect::from_pos_size(pos, page_size);
+            self.reproject = true;
+            self.pages
+                .iter_mut()
+                .zip(grid_coords(IVec2::ZERO))
+                .for_each(|(page, coord)| page.coord = coord);
+        } else {
+            let mut coords: Vec<IVec2> = self.pages.iter().map(|page| page.coord).collect();
+            let center = page_coord(self.fractal_rect, frame_rect.center());
+            let moved = recycle_pages(&mut coords, center);
+
+            self.pages
+                .iter_mut()
+                .zip(coords)
+                .for_each(|(page, coord)| page.coord = coord);
+            let cancelled = self
+                .tiles
+                .iter()
+                .filter(|tile| moved.contains(&tile.page))
+                .filter(|tile| tile.state.lock().cancel())
+                .count();
+            self.stats.record_cancelled(cancelled);
+            if cancelled > 0 {
+                tracing::debug!(cancelled, "Cancelled tiles of recycled pages");
+            }
+        }
+
+        scale_changed
+    }
 
-        let frame_changed = !self.fractal_rect.contains(&frame_rect)
-            || self.fractal_rect.size != new_fractal_rect.size;
+    pub fn update<F>(&mut self, frame_rect: DRect, focus: DVec2, tile_ready_callback: F)
+    where
+        F: Fn(usize, Result<(), String>) + Clone + Send + Sync + 'static,
+    {
+        let scale_changed = self.lay_out(frame_rect);
 
-        if frame_changed {
-            self.frame_changed = true;
-            self.fractal_rect_prev = self.fractal_rect;
-            self.fractal_rect = new_fractal_rect;
-            // println!("frame_rect:   {:?}, center: {:?}", frame_rect, frame_rect.center());
-            // println!("fractal_rect: {:?}, center: {:?}", self.fractal_rect, self.fractal_rect.center());
+        if self.paused {
+            // the grid follows the frame, tiles of the old scale are recomputed once resumed
+            self.invalidated |= scale_changed;
+            self.unready_tiles = self.count_unready_tiles();
+            return;
         }
 
-        let max_iters = calc_max_iters(self.fractal_rect);
+        let recompute = scale_changed || std::mem::take(&mut self.invalidated);
+        let max_iters = self.max_iters();
+        let aa_threshold = self.applied_aa_threshold();
+        let kind = self.kind;
+        let power = self.power;
+        let escape_metric = self.escape_metric;
+        let coloring_mode = self.coloring_mode;
+        let render_scale = self.render_scale;
+        let texture_size = self.texture_size;
+        let view_bounds = self.view_bounds;
+        let page_rects: Vec<DRect> = self
+            .pages
+            .iter()
+            .map(|page| page_rect(self.fractal_rect, page.coord))
+            .collect();
+
+        // queued ahead of the tiles, it is done long before them
+        self.schedule_overview(
+            KernelOptions {
+                max_iter: max_iters,
+                power,
+            },
+            tile_ready_callback.clone(),
+        );
 
+        // visible tiles first, then the ones around the frame, each nearest to the focus first;
+        // the order is the priority of the jobs in the queue
         self.tiles.sort_unstable_by(|a, b| {
-            let a_center = a
-                .fractal_rect(self.texture_size, self.fractal_rect)
-                .center();
-            let b_center = b
-                .fractal_rect(self.texture_size, self.fractal_rect)
-                .center();
+            let a_rect = a.fractal_rect(texture_size, page_rects[a.page]);
+            let b_rect = b.fractal_rect(texture_size, page_rects[b.page]);
 
-            let a_dist = (a_center - focus).length_squared();
-            let b_dist = (b_center - focus).length_squared();
+            let a_key = (
+                !view_bounds.intersects(&a_rect),
+                (a_rect.center() - focus).length_squared(),
+            );
+            let b_key = (
+                !view_bounds.intersects(&b_rect),
+                (b_rect.center() - focus).length_squared(),
+            );
 
-            a_dist.partial_cmp(&b_dist).unwrap()
+            a_key.partial_cmp(&b_key).unwrap()
         });
 
-        self.tiles.iter_mut().for_each(|tile| {
+        // the ring waits until the view is complete, so zooming and morphing,
+        // which recompute everything on every step, don't queue it over and over
+        let mut visible_pending = false;
+        self.prefetch_deferred = false;
+        let generation = self.generation + 1;
+        let mut scheduled = 0;
+        let mut cached = 0;
+        let mut interior = 0;
+        let mut cancelled = 0;
+
+        // the jobs no worker took yet come back, the ones still needed are queued again in
+        // the new order and the rest are dropped without ever starting
+        let (queue_generation, stale) = self.queue.supersede();
+        let mut stale: HashMap<usize, TileTask> = stale
+            .into_iter()
+            .map(|task| (task.tile_index, task))
+            .collect();
+        let callback: TileCallback = Arc::new(tile_ready_callback);
+        // the coarse levels are cheap, they go first
+        let pyramid_jobs = self.schedule_pyramid(queue_generation, &mut stale, &callback);
+        let queue = &self.queue;
+
+        self.tiles.iter_mut().zip(0..).for_each(|(tile, priority)| {
+            // queued behind the pyramid tiles
+            let priority = pyramid_jobs + priority;
             let mut tile_state = tile.state.lock();
 
-            let tile_rect = tile.fractal_rect(self.texture_size, self.fractal_rect);
-            let tile_in_view = frame_rect.intersects(&tile_rect);
+            let tile_rect = tile.fractal_rect(texture_size, page_rects[tile.page]);
+            let in_view = view_bounds.intersects(&tile_rect);
+            if !in_view {
+                tile.resume.lock().take();
+            }
 
-            if !tile_in_view {
-                tile_state.cancel();
+            let mut keep = tile_state.is_computing_or_ready()
+                && !recompute
+                && tile.render_scale <= render_scale;
+            if keep && matches!(*tile_state, TileState::Queued { .. }) {
+                match stale.remove(&tile.index) {
+                    Some(task) => queue.push(
+                        queue_generation,
+                        priority,
+                        TileTask {
+                            callback: callback.clone(),
+                            ..task
+                        },
+                    ),
+                    // a worker took the job just before, it finds it stale and skips it
+                    None => {
+                        tile_state.cancel();
+                        keep = false;
+                    }
+                }
+            }
+
+            if !in_view && visible_pending {
+                if recompute || (!keep && matches!(*tile_state, TileState::Queued { .. })) {
+                    cancelled += tile_state.cancel() as usize;
+                }
+                self.prefetch_deferred |=
+                    matches!(*tile_state, TileState::Idle | TileState::Failed { .. });
+                tracing::trace!(index = tile.index, "Tile waits for the visible ones");
                 return;
             }
 
-            if tile_state.is_computing() && !frame_changed {
-                // when panning, tile could be already in progress
-                // or
-                // not in view, skip
+            if keep {
+                // when panning, tile could be already in progress or done
+                visible_pending |= in_view && tile_state.is_pending();
                 return;
             }
+            visible_pending |= in_view;
 
-            tile_state.cancel();
+            // re-arms failed tiles as well
+            cancelled += tile_state.cancel() as usize;
+            scheduled += 1;
 
-            let img_size = self.texture_size;
-            let tex_rect = tile.tex_rect;
+            let job = TileJob {
+                grid: PixelGrid::new(page_rects[tile.page], texture_size),
+                pixels: tile.tex_rect,
+                options: KernelOptions {
+                    max_iter: max_iters,
+                    power,
+                },
+                kind,
+                escape_metric,
+                aa_threshold,
+                coloring_mode,
+                render_scale,
+            };
             let tile_index = tile.index;
-            let fractal_rect = self.fractal_rect;
-
-            let callback = tile_ready_callback.clone();
-            let cancel_token = Arc::new(AtomicBool::new(false));
-            let cancel_token_clone = cancel_token.clone();
-            let tile_state_clone = tile.state.clone();
-            let semaphore = self.semaphore.clone();
-
-            let buffer = self.buf_pool.take();
-
-            let task_handle = self.runtime.spawn(async move {
-                let _permit = semaphore.acquire().await.unwrap();
-
-                let compute_ok = {
-                    let buffer = &mut *buffer.lock();
-                    let buffer: &mut [Pixel] = bytemuck::cast_slice_mut(buffer);
-
-                    mandelbrot_simd(
-                        img_size,
-                        tex_rect,
-                        -fractal_rect.center(),
-                        1.0 / fractal_rect.size.y,
-                        max_iters,
-                        cancel_token_clone,
-                        buffer,
-                    )
-                    .is_ok()
+            tile.render_scale = render_scale;
+            tile.generation = generation;
+
+            // whole tiles inside the cardioid or the bulb never escape, there's nothing to iterate
+            if kind == FractalKind::Mandelbrot
+                && power == MANDELBROT_POWER
+                && is_interior_rect(tile_rect)
+            {
+                tile.resume.lock().take();
+                let buffer = self.buf_pool.lock().take();
+                job.fill_interior(&mut buffer.lock());
+                *tile_state = TileState::WaitForUpload {
+                    buffer,
+                    coloring_mode,
+                    completed: Instant::now(),
+                    compute_time: Duration::ZERO,
                 };
+                tracing::trace!(index = tile_index, "Tile inside the set");
+                callback(tile_index, Ok(()));
+                interior += 1;
+                return;
+            }
 
-                let mut tile_state = tile_state_clone.lock();
-                if compute_ok {
-                    *tile_state = TileState::WaitForUpload { buffer };
-                    (callback)(tile_index);
-                }
-            });
+            let cache_key = job.cache_key();
+            if let Some(buffer) =
+                find_cached(&self.tile_cache, &self.buf_pool, &self.stats, &cache_key)
+            {
+                tile.resume.lock().take();
+                *tile_state = TileState::WaitForUpload {
+                    buffer,
+                    coloring_mode,
+                    completed: Instant::now(),
+                    compute_time: Duration::ZERO,
+                };
+                tracing::trace!(index = tile_index, "Tile found in the cache");
+                callback(tile_index, Ok(()));
+                cached += 1;
+                return;
+            }
 
-            *tile_state = TileState::Computing {
-                task_handle,
-                cancel_token,
+            // anti-aliasing averages sub-pixel samples, those can't be continued, nor can
+            // coarse values
+            let resumable = in_view && aa_threshold.is_none() && render_scale == 1;
+            let previous = tile
+                .resume
+                .lock()
+                .take()
+                .filter(|previous| resumable && job.continues(&previous.job));
+            let span = tracing::debug_span!(
+                "tile",
+                index = tile_index,
+                generation,
+                resumed = previous.is_some()
+            );
+
+            tracing::trace!(
+                parent: &span,
+                page = tile.page,
+                pos = %tile.tex_rect.pos,
+                in_view,
+                render_scale,
+                resumable,
+                "Tile scheduled"
+            );
+
+            let cancel_token = tile.cancel_token.next_generation();
+            *tile_state = TileState::Queued {
+                cancel_token: cancel_token.clone(),
             };
+            queue.push(
+                queue_generation,
+                priority,
+                TileTask {
+                    tile_index,
+                    job,
+                    state: tile.state.clone(),
+                    cancel_token,
+                    resumable,
+                    previous,
+                    resume: tile.resume.clone(),
+                    callback: callback.clone(),
+                    queued: Instant::now(),
+                    span,
+                    cache_key,
+                },
+            );
         });
+
+        self.stats.record_cancelled(cancelled);
+        self.unready_tiles = self.count_unready_tiles();
+        if scheduled > 0 {
+            // panning before the view completes adds to the work in progress, anything
+            // else starts measuring anew
+            if recompute || self.generation_reported {
+                self.progress_generation = generation;
+            }
+            self.generation = generation;
+            self.generation_start = Instant::now();
+            self.generation_reported = false;
+
+            tracing::debug!(
+                generation,
+                scheduled,
+                cached,
+                interior,
+                cancelled,
+                max_iters,
+                recompute,
+                "Tiles scheduled"
+            );
+        }
     }
 
     pub fn render(&mut self, render_info: &RenderContext) {
-        self.blit_textures(render_info);
+        self.render_time = render_info.time;
+        if let Some(palette) = self.pending_palette.take() {
+            write_palette(render_info.queue, &self.palette_texture, &palette);
+        }
+        self.recreate_targets(render_info);
+        self.reproject_preview(render_info);
+        self.clear_moved_pages(render_info);
         self.upload_tiles(render_info);
+        self.generate_mips(render_info);
+        self.upload_overview(render_info);
+        self.upload_pyramid(render_info);
+        self.write_tile_statuses(render_info);
         self.surface_render(render_info);
+        self.report_completion();
+    }
+
+    // once per generation, when its last visible tile is on screen
+    fn report_completion(&mut self) {
+        if self.generation_reported || self.pending_tile_count() > 0 {
+            return;
+        }
+        self.generation_reported = true;
+
+        tracing::info!(
+            generation = self.generation,
+            elapsed_ms = self.generation_start.elapsed().as_millis() as u64,
+            max_iters = self.max_iters(),
+            "View complete"
+        );
+    }
+
+    fn recreate_targets(&mut self, render_info: &RenderContext) {
+        let targets_lost = std::mem::take(&mut self.targets_lost);
+        if !targets_lost && self.texture_coloring_mode == self.coloring_mode {
+            return;
+        }
+
+        self.create_targets(render_info.device);
+    }
+
+    // pages are cleared before their first use, the preview starts out empty
+    fn create_targets(&mut self, device: &wgpu::Device) {
+        self.texture_coloring_mode = self.coloring_mode;
+
+        self.blit_pipeline = create_pipeline(
+            device,
+            &self.pipeline_layout,
+            &self
+                .draw_params
+                .shader_source(include_str!("blit_shader.wgsl")),
+            self.texture_coloring_mode.texture_format(),
+            None,
+        );
+
+        (self.texture1, self.texture1_view, self.bind_group1) = create_target(
+            device,
+            self.texture_size,
+            self.texture_coloring_mode,
+            1,
+            &self.bind_group_layout,
+            &self.sampler,
+            &self.palette_view,
+            &self.no_fade_view,
+        );
+        (self.texture2, self.texture2_view, self.bind_group2) = create_target(
+            device,
+            self.texture_size,
+            self.texture_coloring_mode,
+            1,
+            &self.bind_group_layout,
+            &self.sampler,
+            &self.palette_view,
+            &self.no_fade_view,
+        );
+        self.preview_rect = DRect::zeroed();
+
+        for page in self.pages.iter_mut() {
+            *page = Page::new(
+                device,
+                page.coord,
+                self.texture_size,
+                fade_cell_size(self.texture_size, self.tile_size),
+                self.texture_coloring_mode,
+                &self.bind_group_layout,
+                &self.sampler,
+                &self.palette_view,
+                &self.mip_generator,
+            );
+        }
+
+        if let Some(overview) = self.overview.as_mut() {
+            overview.state.lock().cancel();
+            *overview = Overview::new(
+                device,
+                self.texture_size,
+                self.texture_coloring_mode,
+                &self.bind_group_layout,
+                &self.sampler,
+                &self.palette_view,
+                &self.no_fade_view,
+            );
+        }
+
+        if let Some(pyramid) = self.pyramid.as_mut() {
+            for tile in &pyramid.tiles {
+                tile.state.lock().cancel();
+            }
+            *pyramid = Pyramid::new(
+                device,
+                pyramid.slots_per_side,
+                pyramid.tile_size,
+                self.texture_coloring_mode,
+                &self.bind_group_layout,
+                &self.sampler,
+                &self.palette_view,
+                &self.no_fade_view,
+            );
+        }
+    }
+
+    // draws of the pyramid tiles a pass has room for
+    fn pyramid_capacity(&self) -> usize {
+        self.pyramid
+            .as_ref()
+            .map_or(0, |pyramid| pyramid.slots.capacity())
     }
 
-    fn blit_textures(&mut self, render_info: &RenderContext) {
-        if !self.frame_changed {
+    // after a scale change resamples the previous preview and pages around the frame,
+    // the preview stays visible below the pages until their tiles are computed
+    fn reproject_preview(&mut self, render_info: &RenderContext) {
+        if !std::mem::take(&mut self.reproject) {
             return;
         }
 
+        let preview_rect =
+            DRect::from_center_size(self.frame_rect.center(), self.fractal_rect.size);
+
         let mut command_encoder = render_info
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
@@ -518,7 +1497,7 @@ impl MandelTexture {
                     view: &self.texture2_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        load: wgpu::LoadOp::Clear(self.texture_coloring_mode.no_data_color()),
                         store: wgpu::StoreOp::Store,
                     },
                 })],
@@ -530,20 +1509,29 @@ impl MandelTexture {
             render_pass.set_pipeline(&self.blit_pipeline);
             render_pass.set_vertex_buffer(0, self.screen_rect_buf.slice(..));
 
-            let offset = (self.fractal_rect_prev.center() - self.fractal_rect.center())
-                / self.fractal_rect_prev.size;
-            let offset = 2.0 * DVec2::new(offset.x, -offset.y);
-            let scale = self.fractal_rect_prev.size / self.fractal_rect.size;
-
             let mut pc = PushConst::new();
-            pc.proj_mat = Mat4::from_scale(Vec3::new(scale.x as f32, scale.y as f32, 1.0))
-                * Mat4::from_translation(Vec3::new(offset.x as f32, offset.y as f32, 0.0));
-            pc.texture_size = Vec2::splat(self.texture_size as f32);
+            pc.texture_size = self.texture_size.as_vec2();
+            pc.no_data = self.texture_coloring_mode.no_data();
 
-            render_pass.set_push_constants(wgpu::ShaderStages::VERTEX, 0, pc.as_bytes());
+            // after a texture reallocation the preview has nothing to carry over
+            let preview =
+                (self.preview_rect.size.y > 0.0).then_some((self.preview_rect, &self.bind_group1));
+            let pages = self
+                .pages
+                .iter()
+                .filter_map(|page| Some((page.content_rect?, &page.bind_group)));
+
+            for (slot, (rect, bind_group)) in preview.into_iter().chain(pages).enumerate() {
+                if !rect.intersects(&preview_rect) {
+                    continue;
+                }
 
-            render_pass.set_bind_group(0, &self.bind_group1, &[]);
-            render_pass.draw(0..ScreenRect::vert_count(), 0..1);
+                pc.proj_mat = quad_transform(rect, preview_rect, 0.0, true);
+                self.draw_params
+                    .set(&mut render_pass, render_info.queue, slot, &pc);
+                render_pass.set_bind_group(0, bind_group, &[]);
+                render_pass.draw(0..ScreenRect::vert_count(), 0..1);
+            }
         }
 
         render_info.queue.submit(Some(command_encoder.finish()));
@@ -552,25 +1540,82 @@ impl MandelTexture {
         swap(&mut self.texture1_view, &mut self.texture2_view);
         swap(&mut self.bind_group1, &mut self.bind_group2);
 
-        self.frame_changed = false;
-        self.fractal_rect_prev = self.fractal_rect;
+        self.preview_rect = preview_rect;
+    }
+
+    // pages moved to another grid position or scale drop their old data
+    fn clear_moved_pages(&mut self, render_info: &RenderContext) {
+        let mut command_encoder = render_info
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        for page in self.pages.iter_mut() {
+            let rect = page_rect(self.fractal_rect, page.coord);
+            if page.content_rect == Some(rect) {
+                continue;
+            }
+
+            command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &page.mips[0].view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.texture_coloring_mode.no_data_color()),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            page.content_rect = Some(rect);
+            page.mips_dirty = true;
+            // cleared texels aren't drawn, the fade texture can wait for the next upload
+            page.fade_starts.fill(None);
+        }
+
+        render_info.queue.submit(Some(command_encoder.finish()));
     }
 
     fn upload_tiles(&mut self, render_info: &RenderContext) {
+        if let Some(profiler) = render_info.profiler {
+            profiler.write_marker(
+                render_info.device,
+                render_info.queue,
+                GpuScope::Upload,
+                false,
+            );
+        }
+
+        let mut uploaded = 0;
+        let mut fade_end = self.fade_end;
         self.tiles.iter().for_each(|tile| {
             let mut tile_state = tile.state.lock();
             if let TileState::WaitForUpload { .. } = *tile_state {
                 let mut ready = TileState::Idle;
                 swap(&mut ready, &mut *tile_state);
 
-                let TileState::WaitForUpload { buffer } = ready else {
+                let TileState::WaitForUpload {
+                    buffer,
+                    coloring_mode,
+                    completed,
+                    compute_time,
+                } = ready
+                else {
                     panic!();
                 };
+                if coloring_mode != self.texture_coloring_mode {
+                    // computed before a mode switch, doesn't fit the texture anymore
+                    return;
+                }
+
+                let pixel_size = coloring_mode.pixel_size();
                 let buffer = buffer.lock();
-                let buffer = buffer.as_slice();
+                let buffer = &buffer[..tile.tex_rect.size.element_product() as usize * pixel_size];
                 render_info.queue.write_texture(
                     wgpu::TexelCopyTextureInfo {
-                        texture: &self.texture1,
+                        texture: &self.pages[tile.page].texture,
                         mip_level: 0,
                         origin: wgpu::Origin3d {
                             x: tile.tex_rect.pos.x,
@@ -582,7 +1627,7 @@ impl MandelTexture {
                     buffer,
                     wgpu::TexelCopyBufferLayout {
                         offset: 0,
-                        bytes_per_row: Some(size_of::<Pixel>() as u32 * tile.tex_rect.size.x),
+                        bytes_per_row: Some(pixel_size as u32 * tile.tex_rect.size.x),
                         rows_per_image: Some(tile.tex_rect.size.y),
                     },
                     wgpu::Extent3d {
@@ -591,24 +1636,122 @@ impl MandelTexture {
                         depth_or_array_layers: 1,
                     },
                 );
+                self.stats.upload.record(completed.elapsed());
+                *tile_state = TileState::Ready { compute_time };
+
+                let cell = cell_index(self.texture_size, self.tile_size, tile.tex_rect);
+                let page = &mut self.pages[tile.page];
+                page.mips_dirty = true;
+                let fade_start = &mut page.fade_starts[cell];
+                if fade_start.is_none() {
+                    *fade_start = Some(render_info.time as f32);
+                    page.fade_dirty = true;
+                    fade_end = render_info.time + TILE_FADE_DURATION as f64;
+                }
+
+                let page_rect = page_rect(self.fractal_rect, self.pages[tile.page].coord);
+                let tile_rect = tile.fractal_rect(self.texture_size, page_rect);
+                uploaded += self.view_bounds.intersects(&tile_rect) as usize;
             }
         });
+        self.unready_tiles = self.unready_tiles.saturating_sub(uploaded);
+        if self.tile_fade {
+            self.fade_end = fade_end;
+            for page in self.pages.iter_mut().filter(|page| page.fade_dirty) {
+                page.write_fade_starts(render_info.queue);
+            }
+        }
+
+        if let Some(profiler) = render_info.profiler {
+            profiler.write_marker(
+                render_info.device,
+                render_info.queue,
+                GpuScope::Upload,
+                true,
+            );
+        }
     }
 
-    fn surface_render(&self, render_info: &RenderContext) {
-        let tex_size = Vec2::splat(self.texture_size as f32);
-        let win_size = Vec2::new(self.window_size.x as f32, self.window_size.y as f32);
-        let scale = tex_size / win_size;
-        let offset =
-            2.0 * (self.fractal_rect.center() - self.frame_rect.center()) / self.frame_rect.size;
+    // of the pages cleared or uploaded to since the last render, whole rather than per tile
+    fn generate_mips(&mut self, render_info: &RenderContext) {
+        if !self.pages.iter().any(|page| page.mips_dirty) {
+            return;
+        }
+
+        let format = MipFormat {
+            format: self.texture_coloring_mode.texture_format(),
+            no_data: self.texture_coloring_mode.no_data(),
+            smooth: self.texture_coloring_mode == ColoringMode::Smooth,
+            average: !self.kind.colors_by_root(),
+        };
+        let mut command_encoder = render_info
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        for page in self.pages.iter_mut().filter(|page| page.mips_dirty) {
+            self.mip_generator.generate(
+                render_info.device,
+                &mut command_encoder,
+                format,
+                &page.mips,
+            );
+            page.mips_dirty = false;
+        }
+
+        render_info.queue.submit(Some(command_encoder.finish()));
+    }
+
+    // the state of every tile for the debug overlay, written anew each render while it is shown
+    fn write_tile_statuses(&self, render_info: &RenderContext) {
+        if !self.tile_overlay {
+            return;
+        }
+
+        let mut statuses: Vec<Vec<u8>> = self
+            .pages
+            .iter()
+            .map(|page| vec![0; page.fade_starts.len()])
+            .collect();
+        for tile in self.tiles.iter() {
+            let cell = cell_index(self.texture_size, self.tile_size, tile.tex_rect);
+            statuses[tile.page][cell] = tile_status(&tile.state.lock(), tile.generation);
+        }
+
+        for (page, statuses) in self.pages.iter().zip(statuses) {
+            let size = page.status_texture.size();
+            render_info.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &page.status_texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &statuses,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size.width),
+                    rows_per_image: Some(size.height),
+                },
+                size,
+            );
+        }
+    }
 
+    fn surface_render(&self, render_info: &RenderContext) {
         let mut command_encoder = render_info
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
         {
             let mut pc = PushConst::new();
-            pc.proj_mat = Mat4::from_translation(Vec3::new(offset.x as f32, offset.y as f32, 0.0))
-                * Mat4::from_scale(Vec3::new(scale.x, scale.y, 1.0));
+            pc.dither = self.dither as u32;
+            pc.exposure = self.exposure;
+            pc.gamma = self.gamma;
+            pc.interior_color = self.interior_color.extend(1.0);
+            pc.palette_mapping = self.palette_mapping as u32;
+            pc.coloring_mode = self.texture_coloring_mode as u32;
+            pc.newton = self.kind.colors_by_root() as u32;
+            pc.no_data = self.texture_coloring_mode.no_data();
+            pc.time = self.render_time as f32;
+            pc.tile_size = fade_cell_size(self.texture_size, self.tile_size);
 
             let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
@@ -616,57 +1759,3095 @@ impl MandelTexture {
                     view: render_info.view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        load: wgpu::LoadOp::Clear(loading_fill_color(self.loading_fill)),
                         store: wgpu::StoreOp::Store,
                     },
                 })],
                 depth_stencil_attachment: None,
-                timestamp_writes: None,
+                timestamp_writes: render_info
+                    .profiler
+                    .map(|profiler| profiler.render_pass_writes(GpuScope::Screen)),
                 occlusion_query_set: None,
             });
             render_pass.set_pipeline(&self.screen_pipeline);
-            render_pass.set_vertex_buffer(0, self.screen_rect_buf.slice(..));
-            render_pass.set_push_constants(wgpu::ShaderStages::VERTEX, 0, pc.as_bytes());
-            render_pass.set_bind_group(0, &self.bind_group1, &[]);
-            render_pass.draw(0..ScreenRect::vert_count(), 0..1);
+            render_info.apply_viewport(&mut render_pass);
+
+            // the overview goes first, then the pyramid from its coarsest level on and the
+            // preview, pages cover all of them where they hold data; only the pages have fade
+            // textures of their own, and the neighbors of a pyramid texel across its slot
+            // belong to other tiles, edges aren't detected there
+            let overview = self.overview.as_ref().and_then(|overview| {
+                Some((
+                    overview.content_rect?,
+                    &overview.bind_group,
+                    overview.screen_rect_buf.slice(..),
+                    false,
+                    true,
+                ))
+            });
+            let pyramid = self.pyramid.iter().flat_map(|pyramid| {
+                pyramid.shown.iter().filter_map(|&slot| {
+                    let tile = &pyramid.tiles[slot];
+                    if !matches!(*tile.state.lock(), TileState::Ready { .. }) {
+                        return None;
+                    }
+                    Some((
+                        tile.rect?,
+                        &pyramid.bind_group,
+                        pyramid.slot_vertices(slot),
+                        false,
+                        false,
+                    ))
+                })
+            });
+            let preview = (self.preview_rect.size.y > 0.0).then_some((
+                self.preview_rect,
+                &self.bind_group1,
+                self.screen_rect_buf.slice(..),
+                false,
+                true,
+            ));
+            let pages = self.pages.iter().filter_map(|page| {
+                Some((
+                    page.content_rect?,
+                    &page.bind_group,
+                    self.screen_rect_buf.slice(..),
+                    true,
+                    true,
+                ))
+            });
+
+            for (slot, (rect, bind_group, vertices, fades, edges)) in overview
+                .into_iter()
+                .chain(pyramid)
+                .chain(preview)
+                .chain(pages)
+                .enumerate()
+            {
+                if !rect.intersects(&self.view_bounds) {
+                    continue;
+                }
+
+                pc.fade_duration = if self.tile_fade && fades {
+                    TILE_FADE_DURATION
+                } else {
+                    0.0
+                };
+                pc.edge_detection = (self.edge_detection && edges) as u32;
+                pc.proj_mat = quad_transform(rect, self.frame_rect, self.rotation, false);
+                self.draw_params
+                    .set(&mut render_pass, render_info.queue, slot, &pc);
+                render_pass.set_vertex_buffer(0, vertices);
+                render_pass.set_bind_group(0, bind_group, &[]);
+                render_pass.draw(0..ScreenRect::vert_count(), 0..1);
+            }
+
+            // over whole pages, tiles without data yet are tinted as well
+            if self.tile_overlay {
+                render_pass.set_pipeline(&self.overlay_pipeline);
+                for (index, page) in self.pages.iter().enumerate() {
+                    let rect = page_rect(self.fractal_rect, page.coord);
+                    if !rect.intersects(&self.view_bounds) {
+                        continue;
+                    }
+
+                    pc.proj_mat = quad_transform(rect, self.frame_rect, self.rotation, false);
+                    let slot = self.pages.len() + 2 + self.pyramid_capacity() + index;
+                    self.draw_params
+                        .set(&mut render_pass, render_info.queue, slot, &pc);
+                    render_pass.set_vertex_buffer(0, self.screen_rect_buf.slice(..));
+                    render_pass.set_bind_group(0, &page.overlay_bind_group, &[]);
+                    render_pass.draw(0..ScreenRect::vert_count(), 0..1);
+                }
+            }
         }
 
         render_info.queue.submit(Some(command_encoder.finish()));
     }
 
-    pub fn resize_window(&mut self, window_size: UVec2) {
+    // the grid around the page of the frame center, computed anew when the frame moves to
+    // another page or anything else about the job changes
+    fn schedule_overview<F>(&mut self, options: KernelOptions, callback: F)
+    where
+        F: Fn(usize, Result<(), String>) + Send + 'static,
+    {
+        let Some(overview) = self.overview.as_mut() else {
+            return;
+        };
+
+        let radius = IVec2::splat(PAGE_GRID_SIZE / 2);
+        let center = page_coord(self.fractal_rect, self.frame_rect.center());
+        let first = page_rect(self.fractal_rect, center - radius);
+        let last = page_rect(self.fractal_rect, center + radius);
+        let grid = PixelGrid::new(
+            DRect::from_pos_size(first.pos, last.pos + last.size - first.pos),
+            overview_size(self.texture_size),
+        );
+        let job = TileJob {
+            grid,
+            pixels: grid.all(),
+            options,
+            kind: self.kind,
+            escape_metric: self.escape_metric,
+            aa_threshold: None,
+            coloring_mode: self.coloring_mode,
+            render_scale: 1,
+        };
+
+        let mut state = overview.state.lock();
+        // a cancelled job is left idle, e.g. by pausing
+        if overview.job == Some(job) && !matches!(*state, TileState::Idle) {
+            return;
+        }
+        state.cancel();
+        overview.job = Some(job);
+
+        let tile_index = self.tiles.len();
+        let cancel_token = overview.cancel_token.next_generation();
+        let cancel_token_clone = cancel_token.clone();
+        let state_clone = overview.state.clone();
+        let buffer = Arc::new(Mutex::new(vec![
+            0u8;
+            grid.size.element_product() as usize
+                * job.coloring_mode.pixel_size()
+        ]));
+
+        // spawned apart from the queue, it is a single job and runs ahead of all tiles
+        self.runtime.spawn(
+            async move {
+                let start = Instant::now();
+
+                let compute_cancel_token = cancel_token_clone.clone();
+                let result = runtime::isolate(async move {
+                    let compute_ok = job
+                        .compute(&job.kernel(), compute_cancel_token, &mut buffer.lock())
+                        .is_ok();
+                    (compute_ok, buffer)
+                })
+                .await;
+
+                let mut state = state_clone.lock();
+                let cancelled = cancel_token_clone.is_cancelled();
+                let compute_time = start.elapsed();
+                if let Some(outcome) =
+                    state.complete(result, cancelled, job.coloring_mode, compute_time)
+                {
+                    let elapsed_ms = compute_time.as_millis() as u64;
+                    tracing::debug!(elapsed_ms, "Overview computed");
+                    callback(tile_index, outcome);
+                }
+            }
+            .in_current_span(),
+        );
+
+        *state = TileState::Computing { cancel_token };
+    }
+
+    // The pyramid tiles over the frame, queued from priority zero on, the coarsest level first;
+    // returns the priorities taken. Tiles a slot holds already are kept, whatever level they
+    // are, so zooming back and forth finds them again.
+    fn schedule_pyramid(
+        &mut self,
+        queue_generation: u64,
+        stale: &mut HashMap<usize, TileTask>,
+        callback: &TileCallback,
+    ) -> usize {
+        let Some(pyramid) = self.pyramid.as_mut() else {
+            return 0;
+        };
+        pyramid.slots.begin_update();
+        pyramid.shown.clear();
+
+        let page_texel_size = self.fractal_rect.size.y / self.texture_size.y as f64;
+        if page_texel_size.is_nan() || page_texel_size <= 0.0 {
+            return 0;
+        }
+        let tile_size = pyramid.tile_size;
+        // past the pages, their callbacks tell the tiles apart only for logging
+        let first_index = self.tiles.len() + 1;
+        let mut priority = 0;
+        let mut scheduled = 0;
+        for level in fallback_levels(page_texel_size) {
+            // the limit of pages of the level's texels, the same whenever the level returns
+            let level_page = self.texture_size.as_dvec2() * texel_size(level);
+            let options = KernelOptions {
+                max_iter: scaled_max_iters(
+                    DRect::from_pos_size(DVec2::ZERO, level_page),
+                    self.iteration_factor,
+                ),
+                power: self.power,
+            };
+
+            for key in covering_keys(self.view_bounds, level, tile_size) {
+                let grid = PixelGrid::new(key.rect(tile_size), UVec2::splat(tile_size));
+                let job = TileJob {
+                    grid,
+                    pixels: grid.all(),
+                    options,
+                    kind: self.kind,
+                    escape_metric: self.escape_metric,
+                    aa_threshold: None,
+                    coloring_mode: self.coloring_mode,
+                    render_scale: 1,
+                };
+                let cache_key = job.cache_key();
+                let (slot, fresh) = match pyramid.slots.get(&cache_key) {
+                    Some(slot) => (slot, false),
+                    None => match pyramid.slots.insert(cache_key) {
+                        Some(slot) => (slot, true),
+                        // every slot is shown, the finer levels go without
+                        None => continue,
+                    },
+                };
+                pyramid.shown.push(slot);
+
+                let tile = &mut pyramid.tiles[slot];
+                let tile_index = first_index + slot;
+                let mut state = tile.state.lock();
+                if fresh {
+                    // the slot held another tile
+                    state.cancel();
+                    tile.rect = Some(grid.frame);
+                }
+                match *state {
+                    TileState::Computing { .. }
+                    | TileState::WaitForUpload { .. }
+                    | TileState::Ready { .. } => continue,
+                    TileState::Queued { .. } => match stale.remove(&tile_index) {
+                        Some(task) => {
+                            self.queue.push(
+                                queue_generation,
+                                priority,
+                                TileTask {
+                                    callback: callback.clone(),
+                                    ..task
+                                },
+                            );
+                            priority += 1;
+                            continue;
+                        }
+                        // a worker took the job just before, it finds it stale and skips it
+                        None => {
+                            state.cancel();
+                        }
+                    },
+                    TileState::Idle | TileState::Failed { .. } => {}
+                }
+
+                if let Some(buffer) =
+                    find_cached(&self.tile_cache, &self.buf_pool, &self.stats, &cache_key)
+                {
+                    *state = TileState::WaitForUpload {
+                        buffer,
+                        coloring_mode: job.coloring_mode,
+                        completed: Instant::now(),
+                        compute_time: Duration::ZERO,
+                    };
+                    callback(tile_index, Ok(()));
+                    continue;
+                }
+
+                let cancel_token = tile.cancel_token.next_generation();
+                *state = TileState::Queued {
+                    cancel_token: cancel_token.clone(),
+                };
+                self.queue.push(
+                    queue_generation,
+                    priority,
+                    TileTask {
+                        tile_index,
+                        job,
+                        state: tile.state.clone(),
+                        cancel_token,
+                        resumable: false,
+                        previous: None,
+                        resume: Arc::default(),
+                        callback: callback.clone(),
+                        queued: Instant::now(),
+                        span: tracing::debug_span!("pyramid_tile", level, slot),
+                        cache_key,
+                    },
+                );
+                priority += 1;
+                scheduled += 1;
+            }
+        }
+
+        if scheduled > 0 {
+            tracing::debug!(
+                scheduled,
+                shown = pyramid.shown.len(),
+                "Pyramid tiles scheduled"
+            );
+        }
+
+        priority
+    }
+
+    fn upload_pyramid(&mut self, render_info: &RenderContext) {
+        let Some(pyramid) = self.pyramid.as_mut() else {
+            return;
+        };
+
+        for (slot, tile) in pyramid.tiles.iter().enumerate() {
+            let mut state = tile.state.lock();
+            if !matches!(*state, TileState::WaitForUpload { .. }) {
+                continue;
+            }
+            let TileState::WaitForUpload {
+                buffer,
+                coloring_mode,
+                compute_time,
+                ..
+            } = std::mem::take(&mut *state)
+            else {
+                unreachable!();
+            };
+            if coloring_mode != self.texture_coloring_mode {
+                // computed before a mode switch, left idle for the next update
+                continue;
+            }
+
+            let pixel_size = coloring_mode.pixel_size();
+            let size = pyramid.tile_size;
+            let pos = pyramid.slot_pos(slot);
+            render_info.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &pyramid.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: pos.x,
+                        y: pos.y,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &buffer.lock()[..(size * size) as usize * pixel_size],
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(pixel_size as u32 * size),
+                    rows_per_image: Some(size),
+                },
+                wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+            );
+            *state = TileState::Ready { compute_time };
+        }
+    }
+
+    fn upload_overview(&mut self, render_info: &RenderContext) {
+        let Some(overview) = self.overview.as_mut() else {
+            return;
+        };
+        let mut state = overview.state.lock();
+        let ready = TileState::Ready {
+            compute_time: Duration::ZERO,
+        };
+        let TileState::WaitForUpload {
+            buffer,
+            coloring_mode,
+            compute_time,
+            ..
+        } = std::mem::replace(&mut *state, ready)
+        else {
+            return;
+        };
+        *state = TileState::Ready { compute_time };
+        let job = overview.job.expect("scheduled before it completed");
+        if coloring_mode != self.texture_coloring_mode {
+            // computed before a mode switch, the next update computes it again
+            *state = TileState::Idle;
+            return;
+        }
+
+        let size = job.grid.size;
+        render_info.queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &overview.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &buffer.lock(),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size.x * coloring_mode.pixel_size() as u32),
+                rows_per_image: Some(size.y),
+            },
+            overview.texture.size(),
+        );
+        overview.content_rect = Some(job.grid.frame);
+    }
+
+    /// Returns the delay after which `apply_pending_resize` grows the texture,
+    /// if the window outgrew it. Shrinking keeps the current texture.
+    pub fn resize_window(&mut self, window_size: UVec2) -> Option<Duration> {
         self.window_size = window_size;
+
+        let grown_size = grown_texture_size(
+            self.texture_size,
+            self.max_texture_dimension,
+            window_size,
+            self.tile_size,
+        );
+        let texture_size = grown_size.unwrap_or(self.texture_size);
+        self.window_covered = update_window_covered(self.window_covered, texture_size, window_size);
+
+        let Some(texture_size) = grown_size else {
+            self.pending_texture_size = None;
+            return None;
+        };
+
+        // until then the current texture is stretched over the window
+        self.pending_texture_size = Some((texture_size, Instant::now() + RESIZE_SETTLE_TIME));
+
+        Some(RESIZE_SETTLE_TIME)
     }
-}
 
-impl Tile {
-    pub(crate) fn fractal_rect(&self, tex_size: u32, fractal_rect: DRect) -> DRect {
-        let abs_frame_size = DVec2::splat(tex_size as f64);
-        let abs_tile_pos = DVec2::from(self.tex_rect.pos);
-        let abs_tile_size = DVec2::from(self.tex_rect.size);
+    /// Reallocates the textures and tile grid once the window stopped resizing.
+    /// Returns true if it did, all tiles have to be scheduled again with `update`.
+    pub fn apply_pending_resize(&mut self, render_info: &RenderContext) -> bool {
+        let Some((texture_size, deadline)) = self.pending_texture_size else {
+            return false;
+        };
+        if Instant::now() < deadline {
+            return false;
+        }
+        self.pending_texture_size = None;
 
-        let tile_size = fractal_rect.size * abs_tile_size / abs_frame_size;
-        let tile_pos = fractal_rect.pos + fractal_rect.size * abs_tile_pos / abs_frame_size;
+        self.reallocate(render_info.device, texture_size);
 
-        DRect::from_pos_size(tile_pos, tile_size)
+        true
     }
-}
 
-impl TileState {
-    fn cancel(&mut self) {
-        if let TileState::Computing {
-            task_handle,
-            cancel_token,
-        } = self
-        {
-            cancel_token.store(true, std::sync::atomic::Ordering::Relaxed);
-            task_handle.abort();
+    /// Reallocates the textures at half their size to free GPU memory, the frame is stretched
+    /// over the window at reduced resolution. Returns false if they can't get any smaller,
+    /// otherwise all tiles have to be scheduled again with `update`.
+    pub fn shrink_texture(&mut self, device: &wgpu::Device) -> bool {
+        let Some(texture_size) = shrunk_texture_size(
+            self.texture_size,
+            self.max_texture_dimension,
+            self.tile_size,
+        ) else {
+            return false;
+        };
+
+        self.pending_texture_size = None;
+        self.window_covered =
+            update_window_covered(self.window_covered, texture_size, self.window_size);
+        self.reallocate(device, texture_size);
+
+        true
+    }
+
+    /// Frees memory under pressure, the visible tiles are kept unless the textures shrink.
+    /// Returns true if they did, all tiles have to be scheduled again with `update`.
+    pub fn trim_memory(&mut self, device: &wgpu::Device, level: TrimLevel) -> bool {
+        // tiles waiting for upload hold their buffer, only pending ones outside the view
+        // let go of it
+        for tile in self.tiles.iter() {
+            tile.resume.lock().take();
+
+            let page_rect = page_rect(self.fractal_rect, self.pages[tile.page].coord);
+            let tile_rect = tile.fractal_rect(self.texture_size, page_rect);
+            let mut tile_state = tile.state.lock();
+            if !self.view_bounds.intersects(&tile_rect) && tile_state.is_pending() {
+                tile_state.cancel();
+            }
         }
+        let released = self.buf_pool.lock().release_free();
+        tracing::info!(released, "Released tile buffers");
+        self.tile_cache.lock().clear();
 
-        *self = TileState::Idle;
+        if level == TrimLevel::Caches {
+            return false;
+        }
+        let texture_size =
+            calc_texture_size(self.max_texture_dimension, self.window_size, self.tile_size);
+        if texture_size == self.texture_size {
+            return false;
+        }
+
+        self.pending_texture_size = None;
+        self.reallocate(device, texture_size);
+
+        true
+    }
+
+    fn reallocate(&mut self, device: &wgpu::Device, texture_size: UVec2) {
+        let cancelled = self
+            .tiles
+            .iter()
+            .filter(|tile| tile.state.lock().cancel())
+            .count();
+        tracing::info!(
+            width = texture_size.x,
+            height = texture_size.y,
+            cancelled,
+            "Reallocating the fractal textures"
+        );
+        self.tiles = create_tiles(self.pages.len(), texture_size, self.tile_size);
+        self.buf_pool
+            .lock()
+            .grow(tile_buffer_size(texture_size, self.tile_size));
+        // the grid is unplaced until the next update counts the frame again
+        self.unready_tiles = self.tiles.len();
+        self.texture_size = texture_size;
+        debug_assert_eq!(self.validate_tiling(), Ok(()));
+
+        self.screen_rect_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            contents: ScreenRect::with_texture_size(texture_size).as_bytes(),
+            usage: wgpu::BufferUsages::VERTEX,
+            label: None,
+        });
+        self.create_targets(device);
+
+        self.fractal_rect = DRect::zeroed();
+    }
+
+    pub fn render_scale(&self) -> u32 {
+        self.render_scale
+    }
+
+    /// Computes one texel of every block of `render_scale` texels per side and repeats it over
+    /// the block, from 1 for every texel to `MAX_RENDER_SCALE`. The next `update` recomputes
+    /// the tiles computed coarser than that and keeps the finer ones.
+    pub fn set_render_scale(&mut self, render_scale: u32) {
+        assert!(
+            render_scale.is_power_of_two() && render_scale <= MAX_RENDER_SCALE,
+            "render scale {} is not a power of two up to {}",
+            render_scale,
+            MAX_RENDER_SCALE
+        );
+        self.render_scale = render_scale;
+    }
+
+    pub fn loading_fill(&self) -> u8 {
+        self.loading_fill
+    }
+
+    /// Sets the sRGB gray level shown where neither the pages, the preview nor the overview
+    /// hold data yet.
+    pub fn set_loading_fill(&mut self, gray: u8) {
+        self.loading_fill = gray;
+    }
+
+    pub fn rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    /// Turns the frame on screen by `rotation` radians counterclockwise about its center, the
+    /// tiles of the plane it uncovers are scheduled by the next `update`.
+    pub fn set_rotation(&mut self, rotation: f64) {
+        self.rotation = rotation;
+        self.view_bounds = self.frame_rect.rotated_bounds(rotation);
+    }
+
+    pub fn dither(&self) -> bool {
+        self.dither
+    }
+
+    pub fn set_dither(&mut self, dither: bool) {
+        self.dither = dither;
+    }
+
+    pub fn edge_detection(&self) -> bool {
+        self.edge_detection
     }
 
-    fn is_computing(&self) -> bool {
-        matches!(self, TileState::Computing { .. })
+    /// Draws only the escaped points where the escape count changes sharply, the boundary of
+    /// the set and its filaments as lines. Only affects rendering, no tiles are recomputed.
+    pub fn set_edge_detection(&mut self, edge_detection: bool) {
+        self.edge_detection = edge_detection;
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    /// Multiplies the linear palette color of escaped points, before `gamma`.
+    /// Only affects rendering, no tiles are recomputed.
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure;
+    }
+
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    /// Raises the exposed color of escaped points to `1 / gamma`, values above one lift the
+    /// dark bands. Exposure and gamma of one leave the colors as they are.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.gamma = gamma;
+    }
+
+    pub fn interior_color(&self) -> Vec3 {
+        self.interior_color
+    }
+
+    /// Solid linear rgb color of points that never escape, independent of the palette.
+    /// Only affects rendering, no tiles are recomputed.
+    pub fn set_interior_color(&mut self, interior_color: Vec3) {
+        self.interior_color = interior_color;
+    }
+
+    pub fn palette_mapping(&self) -> PaletteMapping {
+        self.palette_mapping
+    }
+
+    /// Replaces the colors from the next render on, nothing is recomputed.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.pending_palette = Some(palette);
+    }
+
+    /// Only changes how the screen pass colors escape counts, nothing is recomputed.
+    pub fn set_palette_mapping(&mut self, palette_mapping: PaletteMapping) {
+        self.palette_mapping = palette_mapping;
+    }
+
+    pub fn coloring_mode(&self) -> ColoringMode {
+        self.coloring_mode
+    }
+
+    /// Switches the texel format; textures are recreated on the next render and all tiles recomputed.
+    pub fn set_coloring_mode(&mut self, coloring_mode: ColoringMode) {
+        if self.coloring_mode != coloring_mode {
+            self.coloring_mode = coloring_mode;
+            self.invalidate();
+        }
+    }
+
+    /// Timings of all tiles since the start, cheap enough to poll every frame.
+    pub fn tile_stats(&self) -> TileStatsSummary {
+        TileStatsSummary {
+            workers: self.worker_count,
+            ..self.stats.summary()
+        }
+    }
+
+    /// Visible tiles not uploaded yet, as counted by the last `update` and the uploads since.
+    pub fn unready_tile_count(&self) -> usize {
+        self.unready_tiles
+    }
+
+    /// Number of visible tiles still computing or waiting for upload, zero once the view is complete.
+    /// Tiles prefetched around the view are not counted.
+    pub fn pending_tile_count(&self) -> usize {
+        self.tiles
+            .iter()
+            .filter(|tile| {
+                let page_rect = page_rect(self.fractal_rect, self.pages[tile.page].coord);
+                let tile_rect = tile.fractal_rect(self.texture_size, page_rect);
+
+                self.view_bounds.intersects(&tile_rect) && tile.state.lock().is_pending()
+            })
+            .count()
+    }
+
+    /// How long the kernel took for every tile on the texture, by tile index; the tiles along
+    /// the boundary of the set usually dominate.
+    pub fn tile_timings(&self) -> Vec<(usize, Duration)> {
+        self.tiles
+            .iter()
+            .filter_map(|tile| match *tile.state.lock() {
+                TileState::Ready { compute_time } => Some((tile.index, compute_time)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The visible tiles finished and to be finished since the view was last complete or
+    /// recomputed, equal once it is complete again. Tiles that were done before, and tiles
+    /// scrolled out of view, count as neither.
+    pub fn tile_progress(&self) -> (usize, usize) {
+        let mut done = 0;
+        let mut total = 0;
+        for tile in &self.tiles {
+            let page_rect = page_rect(self.fractal_rect, self.pages[tile.page].coord);
+            if !self
+                .view_bounds
+                .intersects(&tile.fractal_rect(self.texture_size, page_rect))
+            {
+                continue;
+            }
+
+            let current = tile.generation >= self.progress_generation;
+            match *tile.state.lock() {
+                // failed tiles are retried on the next recompute, nothing waits for them
+                TileState::Ready { .. } | TileState::Failed { .. } if current => {
+                    done += 1;
+                    total += 1;
+                }
+                TileState::Ready { .. } | TileState::Failed { .. } => {}
+                TileState::Idle
+                | TileState::Queued { .. }
+                | TileState::Computing { .. }
+                | TileState::WaitForUpload { .. } => total += 1,
+            }
+        }
+
+        (done, total)
+    }
+
+    /// True once every tile of `frame_rect` is on the texture, as of the last render. Only the
+    /// frame of the last `update` is scheduled, any other one is incomplete.
+    pub fn is_frame_complete(&self, frame_rect: DRect) -> bool {
+        frame_rect == self.frame_rect && !self.invalidated && self.unready_tiles == 0
+    }
+
+    fn count_unready_tiles(&self) -> usize {
+        self.tiles
+            .iter()
+            .filter(|tile| {
+                let page_rect = page_rect(self.fractal_rect, self.pages[tile.page].coord);
+                let tile_rect = tile.fractal_rect(self.texture_size, page_rect);
+
+                self.view_bounds.intersects(&tile_rect)
+                    && !matches!(*tile.state.lock(), TileState::Ready { .. })
+            })
+            .count()
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stops scheduling tiles and cancels the ones computing, so no CPU time is spent until
+    /// resumed. Finished tiles keep displaying, the next `update` after resuming re-arms the rest.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if paused {
+            self.tiles.iter().for_each(|tile| {
+                let mut tile_state = tile.state.lock();
+                if matches!(
+                    *tile_state,
+                    TileState::Queued { .. } | TileState::Computing { .. }
+                ) {
+                    tile_state.cancel();
+                }
+            });
+            // the tiles are idle, their jobs would be skipped anyway
+            self.queue.supersede();
+            if let Some(overview) = self.overview.as_ref() {
+                overview.state.lock().cancel();
+            }
+            for tile in self.pyramid.iter().flat_map(|pyramid| &pyramid.tiles) {
+                let mut tile_state = tile.state.lock();
+                if matches!(
+                    *tile_state,
+                    TileState::Queued { .. } | TileState::Computing { .. }
+                ) {
+                    tile_state.cancel();
+                }
+            }
+        }
+    }
+
+    /// Checks that the tiles cover every texel of every page exactly once, within the texture,
+    /// and that the texture size is a multiple of the tile size. Debug builds check it whenever
+    /// the tiles are created, a gap or overlap would show as a seam.
+    pub fn validate_tiling(&self) -> Result<(), String> {
+        validate_tiling(
+            &self.tiles,
+            self.pages.len(),
+            self.texture_size,
+            self.tile_size,
+        )
+    }
+
+    /// Whether tiles around the view were held back until the visible ones finish,
+    /// `update` schedules them once `pending_tile_count` is zero.
+    pub fn prefetch_deferred(&self) -> bool {
+        self.prefetch_deferred
+    }
+
+    /// Iteration limit of the tiles currently being computed.
+    pub fn max_iters(&self) -> u32 {
+        self.max_iters_at(self.iteration_factor)
+    }
+
+    /// Recomputes the texel that displays `point` with `max_iter` iterations, like its tile
+    /// computes it: at the same point and precision, anti-aliased by the same rule.
+    pub fn sample_at(&self, point: DVec2, max_iter: u32) -> PointSample {
+        let grid = PixelGrid::new(
+            page_rect(self.fractal_rect, page_coord(self.fractal_rect, point)),
+            self.texture_size,
+        );
+        // the texel the screen shader loads
+        let pixel = ((point - grid.frame.pos) / grid.pixel_size())
+            .floor()
+            .as_uvec2()
+            .min(self.texture_size - 1);
+        let job = TileJob {
+            grid,
+            pixels: URect::from_pos_size(pixel, UVec2::ONE),
+            options: KernelOptions {
+                max_iter,
+                power: self.power,
+            },
+            kind: self.kind,
+            escape_metric: self.escape_metric,
+            aa_threshold: self.applied_aa_threshold(),
+            coloring_mode: self.coloring_mode,
+            render_scale: 1,
+        };
+        let texel = URect::from_pos_size(pixel, UVec2::ONE);
+        let tile = self
+            .tiles
+            .iter()
+            .map(|tile| tile.tex_rect)
+            .find(|rect| rect.intersects(&texel))
+            .unwrap();
+
+        let banded: Pixel = job.sample_pixel(tile);
+        let smooth: f32 = job.sample_pixel(tile);
+
+        PointSample {
+            point: grid.point(pixel),
+            pixel,
+            iterations: (banded.value() != 0.0).then(|| banded.value() as u32 - 1),
+            smooth,
+        }
+    }
+
+    /// Iteration limit of the current view with `iteration_factor` applied.
+    pub fn max_iters_at(&self, iteration_factor: f64) -> u32 {
+        scaled_max_iters(self.fractal_rect, iteration_factor)
+    }
+
+    /// The limit `update` would compute `frame_rect` with, before it was laid out.
+    pub fn max_iters_for(&self, frame_rect: DRect) -> u32 {
+        let page_size = fractal_size(self.texture_size, self.window_size, frame_rect.size);
+        scaled_max_iters(
+            DRect::from_center_size(frame_rect.center(), page_size),
+            self.iteration_factor,
+        )
+    }
+
+    pub fn iteration_factor(&self) -> f64 {
+        self.iteration_factor
+    }
+
+    /// Multiplies the iteration limit, tiles are updated on the next `update`.
+    /// Visible tiles continue from where they stopped when the limit grows.
+    pub fn set_iteration_factor(&mut self, iteration_factor: f64) {
+        if iteration_factor.is_nan() || iteration_factor <= 0.0 {
+            return;
+        }
+        if self.iteration_factor != iteration_factor {
+            self.iteration_factor = iteration_factor;
+            self.invalidate();
+        }
+    }
+
+    /// Computes `fractal_rect` at `size` pixels on the runtime and writes the raw escape counts
+    /// to `path`, see [`raw_export::export_raw`] for the format. `callback` gets the result.
+    pub fn export_raw<F>(
+        &self,
+        path: PathBuf,
+        fractal_rect: DRect,
+        size: UVec2,
+        max_iter: u32,
+        callback: F,
+    ) where
+        F: FnOnce(anyhow::Result<()>) + Send + 'static,
+    {
+        let kind = self.kind;
+        let power = self.power;
+        let escape_metric = self.escape_metric;
+        let aa_threshold = self.aa_threshold;
+
+        // the export splits itself over all cores, it must not hold up the tile workers
+        self.runtime.spawn_blocking(move || {
+            let result = raw_export::export_raw(
+                &path,
+                fractal_rect,
+                size,
+                max_iter,
+                kind,
+                power,
+                escape_metric,
+                aa_threshold,
+            );
+            callback(result);
+        });
+    }
+
+    /// Forces all visible tiles to be recomputed on the next `update`.
+    pub fn invalidate(&mut self) {
+        tracing::debug!("Tiles invalidated");
+        self.invalidated = true;
+    }
+
+    /// Recreates the textures and pipelines on the next render and recomputes all tiles,
+    /// for when a device error left them in an unknown state.
+    pub fn rebuild(&mut self) {
+        tracing::info!("Rebuilding the fractal textures and pipelines");
+        self.targets_lost = true;
+        self.invalidate();
+    }
+
+    pub fn kind(&self) -> FractalKind {
+        self.kind
+    }
+
+    /// Switches the fractal without touching the frame, the pages or their textures. Every tile
+    /// job in flight is cancelled at once and the tiles are re-armed, they are recomputed on the
+    /// next `update` and the old picture keeps displaying until then.
+    pub fn set_kind(&mut self, kind: FractalKind) {
+        if self.kind == kind {
+            return;
+        }
+        self.kind = kind;
+
+        for tile in &mut self.tiles {
+            tile.cancel_token.next_generation();
+            tile.state.lock().cancel();
+            // the orbits of the old kind lead nowhere for the new one
+            tile.resume.lock().take();
+        }
+        self.invalidate();
+    }
+
+    pub fn power(&self) -> f64 {
+        self.power
+    }
+
+    /// Sets the exponent of `z = z^power + c`, all tiles are recomputed on the next `update`.
+    pub fn set_power(&mut self, power: f64) {
+        if self.power != power {
+            self.power = power;
+            self.invalidate();
+        }
+    }
+
+    pub fn escape_metric(&self) -> EscapeMetric {
+        self.escape_metric
+    }
+
+    /// Sets the bailout test of the kernel, all tiles are recomputed on the next `update`.
+    pub fn set_escape_metric(&mut self, escape_metric: EscapeMetric) {
+        if self.escape_metric != escape_metric {
+            self.escape_metric = escape_metric;
+            self.invalidate();
+        }
+    }
+
+    pub fn aa_threshold(&self) -> Option<u16> {
+        self.aa_threshold
+    }
+
+    // the kinds that color by root ignore the threshold, it applies again after switching back
+    fn applied_aa_threshold(&self) -> Option<u16> {
+        self.aa_threshold.filter(|_| !self.kind.colors_by_root())
+    }
+
+    /// Shows or hides the debug overlay tinting every tile by its state.
+    pub fn set_tile_overlay(&mut self, enabled: bool) {
+        self.tile_overlay = enabled;
+    }
+
+    pub fn tile_overlay(&self) -> bool {
+        self.tile_overlay
+    }
+
+    /// Whether tiles uploaded recently are still fading in, the screen needs redrawing until then.
+    pub fn is_fading(&self) -> bool {
+        self.tile_fade && self.render_time < self.fade_end
+    }
+
+    /// Enables boundary anti-aliasing for pixels differing from a neighbor by more than `threshold` iterations.
+    pub fn set_aa_threshold(&mut self, threshold: Option<u16>) {
+        if self.aa_threshold != threshold {
+            self.aa_threshold = threshold;
+            self.invalidate();
+        }
+    }
+}
+
+fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Uint,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D1,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            },
+        ],
+        label: None,
+    })
+}
+
+fn create_pipeline_layout(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    draw_params: &DrawParams,
+) -> wgpu::PipelineLayout {
+    match draw_params {
+        DrawParams::PushConstants => {
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                bind_group_layouts: &[bind_group_layout],
+                push_constant_ranges: &[wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    range: 0..PushConst::size_in_bytes(),
+                }],
+                label: None,
+            })
+        }
+        DrawParams::Uniform {
+            bind_group_layout: params_layout,
+            ..
+        } => device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[bind_group_layout, params_layout],
+            push_constant_ranges: &[],
+            label: None,
+        }),
+    }
+}
+
+impl DrawParams {
+    fn new(device: &wgpu::Device, slot_count: usize) -> Self {
+        if device.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+            return Self::PushConstants;
+        }
+
+        let slot_size = PushConst::size_in_bytes()
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            size: slot_size as u64 * slot_count as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+            label: None,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(PushConst::size_in_bytes() as u64),
+                },
+                count: None,
+            }],
+            label: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(PushConst::size_in_bytes() as u64),
+                }),
+            }],
+            label: None,
+        });
+
+        Self::Uniform {
+            buffer,
+            bind_group_layout,
+            bind_group,
+            slot_size,
+        }
+    }
+
+    // the shaders declare push constants, which become a uniform binding without them
+    fn shader_source<'a>(&self, source: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::PushConstants => Cow::Borrowed(source),
+            Self::Uniform { .. } => {
+                debug_assert!(source.contains(PUSH_CONSTANT_DECLARATION));
+                Cow::Owned(source.replace(PUSH_CONSTANT_DECLARATION, UNIFORM_DECLARATION))
+            }
+        }
+    }
+
+    // `slot` is the index of the draw within its render pass
+    fn set(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        queue: &wgpu::Queue,
+        slot: usize,
+        pc: &PushConst,
+    ) {
+        match self {
+            Self::PushConstants => {
+                render_pass.set_push_constants(
+                    wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    0,
+                    pc.as_bytes(),
+                );
+            }
+            Self::Uniform {
+                buffer,
+                bind_group,
+                slot_size,
+                ..
+            } => {
+                let offset = slot as u32 * slot_size;
+                queue.write_buffer(buffer, offset as u64, pc.as_bytes());
+                render_pass.set_bind_group(1, bind_group, &[offset]);
+            }
+        }
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    pipeline_layout: &wgpu::PipelineLayout,
+    shader_source: &str,
+    target_format: wgpu::TextureFormat,
+    blend: Option<wgpu::BlendState>,
+) -> wgpu::RenderPipeline {
+    let vertex_buffers = [wgpu::VertexBufferLayout {
+        array_stride: ScreenRect::vert_size() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x4,
+                offset: 0,
+                shader_location: 0,
+            },
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x2,
+                offset: 4 * 4,
+                shader_location: 1,
+            },
+        ],
+    }];
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(shader_source)),
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &vertex_buffers,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: target_format,
+                blend,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            cull_mode: None,
+            front_face: wgpu::FrontFace::Cw,
+            topology: wgpu::PrimitiveTopology::TriangleStrip,
+
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn write_palette(queue: &wgpu::Queue, texture: &wgpu::Texture, palette: &Palette) {
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        palette.as_bytes(),
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(PALETTE_SIZE as u32 * 4),
+            rows_per_image: Some(1),
+        },
+        wgpu::Extent3d {
+            width: PALETTE_SIZE as u32,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_target(
+    device: &wgpu::Device,
+    texture_size: UVec2,
+    coloring_mode: ColoringMode,
+    mip_level_count: u32,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    palette_view: &wgpu::TextureView,
+    fade_view: &wgpu::TextureView,
+) -> (wgpu::Texture, wgpu::TextureView, wgpu::BindGroup) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: texture_size.x,
+            height: texture_size.y,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: coloring_mode.texture_format(),
+        usage: wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+        label: None,
+    });
+    let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let bind_group = create_bind_group(
+        device,
+        bind_group_layout,
+        sampler,
+        &texture_view,
+        palette_view,
+        fade_view,
+    );
+
+    (texture, texture_view, bind_group)
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    texture_view: &wgpu::TextureView,
+    palette_view: &wgpu::TextureView,
+    fade_view: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(palette_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(fade_view),
+            },
+        ],
+        label: None,
+    })
+}
+
+// one texel per tile, like the time it started fading in; read unfiltered
+fn create_cell_texture(
+    device: &wgpu::Device,
+    tile_count: UVec2,
+    format: wgpu::TextureFormat,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: tile_count.x,
+            height: tile_count.y,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+        label: None,
+    })
+}
+
+impl Page {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        device: &wgpu::Device,
+        coord: IVec2,
+        texture_size: UVec2,
+        tile_size: u32,
+        coloring_mode: ColoringMode,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        palette_view: &wgpu::TextureView,
+        mip_generator: &MipGenerator,
+    ) -> Self {
+        let tile_count = texture_size.map(|size| size.div_ceil(tile_size));
+        // zero-initialized, so the tiles start out faded in until `write_fade_starts`
+        let fade_texture = create_cell_texture(device, tile_count, wgpu::TextureFormat::R32Float);
+        let fade_view = fade_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let status_texture = create_cell_texture(device, tile_count, wgpu::TextureFormat::R8Unorm);
+        let status_view = status_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let (texture, texture_view, bind_group) = create_target(
+            device,
+            texture_size,
+            coloring_mode,
+            mip_level_count(texture_size),
+            bind_group_layout,
+            sampler,
+            palette_view,
+            &fade_view,
+        );
+        // the overlay reads the statuses where the screen pass reads the fade starts
+        let overlay_bind_group = create_bind_group(
+            device,
+            bind_group_layout,
+            sampler,
+            &texture_view,
+            palette_view,
+            &status_view,
+        );
+        let mips = mip_generator.levels(device, &texture);
+
+        Self {
+            coord,
+            content_rect: None,
+            texture,
+            bind_group,
+            fade_starts: vec![None; tile_count.element_product() as usize],
+            fade_texture,
+            fade_dirty: false,
+            status_texture,
+            overlay_bind_group,
+            mips,
+            mips_dirty: false,
+        }
+    }
+
+    fn write_fade_starts(&mut self, queue: &wgpu::Queue) {
+        let tile_count = UVec2::new(self.fade_texture.width(), self.fade_texture.height());
+        let starts: Vec<f32> = self
+            .fade_starts
+            .iter()
+            .map(|start| start.unwrap_or(0.0))
+            .collect();
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.fade_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&starts),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(tile_count.x * size_of::<f32>() as u32),
+                rows_per_image: Some(tile_count.y),
+            },
+            self.fade_texture.size(),
+        );
+        self.fade_dirty = false;
+    }
+}
+
+impl Overview {
+    fn new(
+        device: &wgpu::Device,
+        texture_size: UVec2,
+        coloring_mode: ColoringMode,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        palette_view: &wgpu::TextureView,
+        no_fade_view: &wgpu::TextureView,
+    ) -> Self {
+        let size = overview_size(texture_size);
+        let (texture, _, bind_group) = create_target(
+            device,
+            size,
+            coloring_mode,
+            1,
+            bind_group_layout,
+            sampler,
+            palette_view,
+            no_fade_view,
+        );
+        let screen_rect_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            contents: ScreenRect::with_texture_size(size).as_bytes(),
+            usage: wgpu::BufferUsages::VERTEX,
+            label: None,
+        });
+
+        Self {
+            texture,
+            bind_group,
+            screen_rect_buf,
+            content_rect: None,
+            job: None,
+            state: Arc::default(),
+            cancel_token: CancelToken::new(),
+        }
+    }
+}
+
+impl Pyramid {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        device: &wgpu::Device,
+        slots_per_side: u32,
+        tile_size: u32,
+        coloring_mode: ColoringMode,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        palette_view: &wgpu::TextureView,
+        no_fade_view: &wgpu::TextureView,
+    ) -> Self {
+        let (texture, _, bind_group) = create_target(
+            device,
+            UVec2::splat(slots_per_side * tile_size),
+            coloring_mode,
+            1,
+            bind_group_layout,
+            sampler,
+            palette_view,
+            no_fade_view,
+        );
+        let slot_count = slots_per_side.pow(2) as usize;
+        let screen_rects: Vec<u8> = (0..slot_count)
+            .flat_map(|slot| {
+                let pos = atlas_slot_pos(slot, slots_per_side, tile_size);
+                ScreenRect::with_texture_rect(pos, UVec2::splat(tile_size))
+                    .as_bytes()
+                    .to_vec()
+            })
+            .collect();
+        let screen_rects_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            contents: &screen_rects,
+            usage: wgpu::BufferUsages::VERTEX,
+            label: None,
+        });
+
+        Self {
+            texture,
+            bind_group,
+            screen_rects_buf,
+            slots_per_side,
+            tile_size,
+            slots: AtlasSlots::new(slot_count),
+            tiles: (0..slot_count).map(|_| PyramidTile::default()).collect(),
+            shown: Vec::new(),
+        }
+    }
+
+    fn slot_pos(&self, slot: usize) -> UVec2 {
+        atlas_slot_pos(slot, self.slots_per_side, self.tile_size)
+    }
+
+    fn slot_vertices(&self, slot: usize) -> wgpu::BufferSlice<'_> {
+        let size = ScreenRect::size_in_bytes() as u64;
+        let start = slot as u64 * size;
+        self.screen_rects_buf.slice(start..start + size)
+    }
+}
+
+fn overview_size(texture_size: UVec2) -> UVec2 {
+    (texture_size * PAGE_GRID_SIZE as u32 / OVERVIEW_DOWNSCALE).max(UVec2::ONE)
+}
+
+fn loading_fill_color(gray: u8) -> wgpu::Color {
+    let linear = env::srgb_to_linear(Vec3::splat(gray as f32 / 255.0)).x as f64;
+
+    wgpu::Color {
+        r: linear,
+        g: linear,
+        b: linear,
+        a: 1.0,
+    }
+}
+
+impl Tile {
+    pub(crate) fn fractal_rect(&self, tex_size: UVec2, fractal_rect: DRect) -> DRect {
+        let abs_frame_size = tex_size.as_dvec2();
+        let abs_tile_pos = DVec2::from(self.tex_rect.pos);
+        let abs_tile_size = DVec2::from(self.tex_rect.size);
+
+        let tile_size = fractal_rect.size * abs_tile_size / abs_frame_size;
+        let tile_pos = fractal_rect.pos + fractal_rect.size * abs_tile_pos / abs_frame_size;
+
+        debug_assert!(
+            tile_size.is_finite() && tile_size.cmpgt(DVec2::ZERO).all(),
+            "degenerate tile size {:?} in frame {:?}",
+            tile_size,
+            fractal_rect
+        );
+
+        DRect::from_pos_size(tile_pos, tile_size)
+    }
+}
+
+impl ColoringMode {
+    // smooth values are stored as raw f32 bits, float formats aren't renderable everywhere
+    fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            ColoringMode::Banded => wgpu::TextureFormat::R16Uint,
+            ColoringMode::Smooth => wgpu::TextureFormat::R32Uint,
+        }
+    }
+
+    fn pixel_size(self) -> usize {
+        match self {
+            ColoringMode::Banded => size_of::<Pixel>(),
+            ColoringMode::Smooth => size_of::<f32>(),
+        }
+    }
+
+    // texel value marking regions no tile has been computed for yet, never produced by the kernel
+    fn no_data(self) -> u32 {
+        match self {
+            ColoringMode::Banded => u16::MAX as u32,
+            ColoringMode::Smooth => u32::MAX,
+        }
+    }
+
+    fn no_data_color(self) -> wgpu::Color {
+        wgpu::Color {
+            r: self.no_data() as f64,
+            g: 0.0,
+            b: 0.0,
+            a: 0.0,
+        }
+    }
+}
+
+impl TileState {
+    // true if a queued or running job was stopped
+    fn cancel(&mut self) -> bool {
+        let pending = match self {
+            TileState::Queued { cancel_token } | TileState::Computing { cancel_token } => {
+                cancel_token.cancel();
+                true
+            }
+            _ => false,
+        };
+
+        *self = TileState::Idle;
+        pending
+    }
+
+    // a worker starts the queued job of `cancel_token` only while it is still the tile's job
+    fn claim(&mut self, cancel_token: &CancelToken) -> bool {
+        let TileState::Queued {
+            cancel_token: queued,
+        } = self
+        else {
+            return false;
+        };
+        if queued.generation() != cancel_token.generation() || cancel_token.is_cancelled() {
+            return false;
+        }
+
+        *self = TileState::Computing {
+            cancel_token: cancel_token.clone(),
+        };
+        true
+    }
+
+    // stores the result of a finished compute task, returns what to report unless nothing changed
+    fn complete(
+        &mut self,
+        result: Result<(bool, TileBuffer), String>,
+        cancelled: bool,
+        coloring_mode: ColoringMode,
+        compute_time: Duration,
+    ) -> Option<Result<(), String>> {
+        if cancelled {
+            return None;
+        }
+
+        match result {
+            Ok((true, buffer)) => {
+                *self = TileState::WaitForUpload {
+                    buffer,
+                    coloring_mode,
+                    completed: Instant::now(),
+                    compute_time,
+                };
+                Some(Ok(()))
+            }
+            Ok((false, _)) => None,
+            Err(reason) => {
+                *self = TileState::Failed {
+                    reason: reason.clone(),
+                };
+                Some(Err(reason))
+            }
+        }
+    }
+
+    // computed tiles waiting for the next render count as ready, an update before it
+    // would otherwise throw their data away and compute them again
+    fn is_computing_or_ready(&self) -> bool {
+        matches!(
+            self,
+            TileState::Queued { .. }
+                | TileState::Computing { .. }
+                | TileState::WaitForUpload { .. }
+                | TileState::Ready { .. }
+        )
+    }
+
+    fn is_pending(&self) -> bool {
+        matches!(
+            self,
+            TileState::Queued { .. }
+                | TileState::Computing { .. }
+                | TileState::WaitForUpload { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TileJob {
+    // the page, its tiles sample the points a single frame of the page would
+    grid: PixelGrid,
+    pixels: URect,
+    options: KernelOptions,
+    kind: FractalKind,
+    escape_metric: EscapeMetric,
+    aa_threshold: Option<u16>,
+    coloring_mode: ColoringMode,
+    // texels per side computed as one, see `MandelTexture::set_render_scale`
+    render_scale: u32,
+}
+
+impl TileJob {
+    fn kernel(&self) -> SimdKernel {
+        tile_kernel(self.grid, self.kind, self.escape_metric)
+    }
+
+    fn compute<K: FractalKernel>(
+        &self,
+        kernel: &K,
+        cancel_token: CancelToken,
+        buffer: &mut [u8],
+    ) -> anyhow::Result<()> {
+        match self.coloring_mode {
+            ColoringMode::Banded => self.compute_pixels::<K, Pixel>(kernel, cancel_token, buffer),
+            ColoringMode::Smooth => self.compute_pixels::<K, f32>(kernel, cancel_token, buffer),
+        }
+    }
+
+    fn compute_pixels<K: FractalKernel, P: KernelPixel>(
+        &self,
+        kernel: &K,
+        cancel_token: CancelToken,
+        buffer: &mut [u8],
+    ) -> anyhow::Result<()> {
+        let len = self.pixels.size.element_product() as usize * size_of::<P>();
+        let buffer: &mut [P] = bytemuck::cast_slice_mut(&mut buffer[..len]);
+
+        if self.render_scale > 1 {
+            return self.compute_coarse(kernel, cancel_token, buffer);
+        }
+        self.compute_values(kernel, cancel_token, buffer)
+    }
+
+    // the texels at the lower left of each block of `render_scale` texels per side, repeated
+    // over their block; a grid with fewer texels over the same page samples exactly those
+    fn compute_coarse<K: FractalKernel, P: KernelPixel>(
+        &self,
+        kernel: &K,
+        cancel_token: CancelToken,
+        buffer: &mut [P],
+    ) -> anyhow::Result<()> {
+        let scale = self.render_scale;
+        let coarse_job = TileJob {
+            grid: PixelGrid::new(self.grid.frame, self.grid.size / scale),
+            pixels: URect::from_pos_size(self.pixels.pos / scale, self.pixels.size / scale),
+            // blocks are too coarse for sub-pixel samples to matter
+            aa_threshold: None,
+            render_scale: 1,
+            ..*self
+        };
+        let coarse_width = coarse_job.pixels.size.x;
+        let mut coarse = vec![P::default(); coarse_job.pixels.size.element_product() as usize];
+        coarse_job.compute_values(kernel, cancel_token, &mut coarse)?;
+
+        for (row, values) in buffer
+            .chunks_exact_mut(self.pixels.size.x as usize)
+            .enumerate()
+        {
+            let coarse_row = row as u32 / scale * coarse_width;
+            for (x, value) in values.iter_mut().enumerate() {
+                *value = coarse[(coarse_row + x as u32 / scale) as usize];
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compute_values<K: FractalKernel, P: KernelPixel, C: CancelSignal + Clone>(
+        &self,
+        kernel: &K,
+        cancel_token: C,
+        buffer: &mut [P],
+    ) -> anyhow::Result<()> {
+        compute_tile(
+            kernel,
+            self.grid,
+            self.pixels,
+            self.options,
+            self.aa_threshold,
+            cancel_token,
+            buffer,
+        )
+    }
+
+    // the value of the single pixel of `self.pixels` as computing all of `tile` gives it;
+    // anti-aliasing compares it with its neighbors inside the tile and always refines the border
+    fn sample_pixel<P: KernelPixel>(&self, tile: URect) -> P {
+        let pixel = self.pixels.pos;
+        let tile_end = tile.pos + tile.size - 1;
+        let on_border = pixel.cmpeq(tile.pos).any() || pixel.cmpeq(tile_end).any();
+        let pixels = if on_border {
+            self.pixels
+        } else {
+            URect::from_pos_size(pixel - 1, UVec2::splat(3))
+        };
+        let job = TileJob { pixels, ..*self };
+
+        let mut buffer = vec![P::default(); pixels.size.element_product() as usize];
+        job.compute_values(&job.kernel(), NeverCancelled, &mut buffer)
+            .expect("never cancelled");
+
+        buffer[buffer.len() / 2]
+    }
+
+    fn cache_key(&self) -> TileKey {
+        let texel_size = self.grid.pixel_size();
+        let origin = (self.grid.frame.pos / texel_size).round().as_i64vec2();
+
+        let mut hasher = DefaultHasher::new();
+        self.options.max_iter.hash(&mut hasher);
+        self.options.power.to_bits().hash(&mut hasher);
+        std::mem::discriminant(&self.kind).hash(&mut hasher);
+        if let FractalKind::Julia(c) = self.kind {
+            c.to_array().map(f64::to_bits).hash(&mut hasher);
+        }
+        std::mem::discriminant(&self.escape_metric).hash(&mut hasher);
+        self.aa_threshold.hash(&mut hasher);
+        std::mem::discriminant(&self.coloring_mode).hash(&mut hasher);
+        self.render_scale.hash(&mut hasher);
+
+        TileKey {
+            scale: (texel_size.y.ln() / SCALE_EPSILON).round() as i64,
+            texel: origin + self.pixels.pos.as_i64vec2(),
+            size: self.pixels.size,
+            options: hasher.finish(),
+        }
+    }
+
+    // the values of a tile all inside the set, see `is_interior_rect`
+    fn fill_interior(&self, buffer: &mut [u8]) {
+        let buffer = &mut buffer[..self.values_len()];
+        match self.coloring_mode {
+            ColoringMode::Banded => bytemuck::cast_slice_mut(buffer).fill(Pixel::interior()),
+            ColoringMode::Smooth => bytemuck::cast_slice_mut(buffer).fill(f32::interior()),
+        }
+    }
+
+    // bytes of the values in a tile buffer
+    fn values_len(&self) -> usize {
+        self.pixels.size.element_product() as usize * self.coloring_mode.pixel_size()
+    }
+
+    // whether this job continues `previous`, which stopped at a lower or the same iteration limit
+    fn continues(&self, previous: &TileJob) -> bool {
+        previous.options.max_iter <= self.options.max_iter
+            && previous.grid == self.grid
+            && previous.pixels == self.pixels
+            && previous.options.power == self.options.power
+            && previous.kind == self.kind
+            && previous.escape_metric == self.escape_metric
+            && previous.aa_threshold == self.aa_threshold
+            && previous.coloring_mode == self.coloring_mode
+    }
+
+    // computes without anti-aliasing and keeps what a later job with more iterations needs
+    fn compute_resumable<K: FractalKernel>(
+        &self,
+        kernel: &K,
+        cancel_token: CancelToken,
+        buffer: &mut [u8],
+        previous: Option<TileResume>,
+    ) -> anyhow::Result<TileResume> {
+        let len = self.pixels.size.element_product() as usize * self.coloring_mode.pixel_size();
+        let buffer = &mut buffer[..len];
+
+        let interior = match previous {
+            Some(previous) => {
+                buffer.copy_from_slice(&previous.values);
+                previous.interior
+            }
+            None => InteriorOrbits::start(self.pixels.size),
+        };
+
+        let interior = match self.coloring_mode {
+            ColoringMode::Banded => {
+                self.resume_pixels::<K, Pixel>(kernel, cancel_token, buffer, interior)
+            }
+            ColoringMode::Smooth => {
+                self.resume_pixels::<K, f32>(kernel, cancel_token, buffer, interior)
+            }
+        }?;
+
+        Ok(TileResume {
+            job: *self,
+            values: buffer.to_vec(),
+            interior,
+        })
+    }
+
+    fn resume_pixels<K: FractalKernel, P: KernelPixel>(
+        &self,
+        kernel: &K,
+        cancel_token: CancelToken,
+        buffer: &mut [u8],
+        mut interior: InteriorOrbits,
+    ) -> anyhow::Result<InteriorOrbits> {
+        resume_frame(
+            kernel,
+            self.grid,
+            self.pixels,
+            self.options,
+            cancel_token,
+            &mut interior,
+            bytemuck::cast_slice_mut::<u8, P>(buffer),
+        )?;
+
+        Ok(interior)
+    }
+}
+
+// takes the most urgent tile job of the queue, one at a time, for as long as the runtime runs
+async fn run_worker(
+    queue: Arc<WorkQueue<TileTask>>,
+    buf_pool: Arc<Mutex<BufferPool>>,
+    stats: Arc<TileStats>,
+    tile_cache: Arc<Mutex<TileCache<TileKey>>>,
+) {
+    loop {
+        let (generation, task) = queue.pop().await;
+        let span = task.span.clone();
+        task.run(generation, &queue, &buf_pool, &stats, &tile_cache)
+            .instrument(span)
+            .await;
+    }
+}
+
+// a buffer with the values of `key` if the cache has them, counted in the hit rate
+fn find_cached(
+    tile_cache: &Mutex<TileCache<TileKey>>,
+    buf_pool: &Mutex<BufferPool>,
+    stats: &TileStats,
+    key: &TileKey,
+) -> Option<TileBuffer> {
+    let mut tile_cache = tile_cache.lock();
+    if !tile_cache.is_enabled() {
+        return None;
+    }
+    let values = tile_cache.get(key);
+    stats.record_cache_lookup(values.is_some());
+
+    let values = values?;
+    let buffer = buf_pool.lock().take();
+    buffer.lock()[..values.len()].copy_from_slice(values);
+    Some(buffer)
+}
+
+impl TileTask {
+    async fn run(
+        self,
+        generation: u64,
+        queue: &WorkQueue<TileTask>,
+        buf_pool: &Mutex<BufferPool>,
+        stats: &Arc<TileStats>,
+        tile_cache: &Arc<Mutex<TileCache<TileKey>>>,
+    ) {
+        let TileTask {
+            tile_index,
+            job,
+            state,
+            cancel_token,
+            resumable,
+            previous,
+            resume,
+            callback,
+            queued,
+            cache_key,
+            ..
+        } = self;
+
+        // an update since it was queued either queued it again or doesn't need it anymore
+        if !queue.is_current(generation) || !state.lock().claim(&cancel_token) {
+            tracing::trace!("Stale tile skipped");
+            return;
+        }
+        stats.queue_wait.record(queued.elapsed());
+        // taken only once running, the queue of prefetched tiles holds no buffers
+        let buffer = buf_pool.lock().take();
+        let start = Instant::now();
+
+        // the kernel runs in its own task so a panic surfaces as an error
+        let compute_cancel_token = cancel_token.clone();
+        let stats_cancel_token = cancel_token.clone();
+        let compute_stats = stats.clone();
+        let tile_cache = tile_cache.clone();
+        let compute_task = runtime::isolate(
+            async move {
+                let compute_ok = if resumable {
+                    job.compute_resumable(
+                        &job.kernel(),
+                        compute_cancel_token,
+                        &mut buffer.lock(),
+                        previous,
+                    )
+                    .map(|previous| *resume.lock() = Some(previous))
+                    .is_ok()
+                } else {
+                    job.compute(&job.kernel(), compute_cancel_token, &mut buffer.lock())
+                        .is_ok()
+                };
+
+                // a cancellation that came too late to stop the kernel wastes its time
+                // all the same
+                let elapsed = start.elapsed();
+                if compute_ok && !stats_cancel_token.is_cancelled() {
+                    compute_stats.compute.record(elapsed);
+                    let mut tile_cache = tile_cache.lock();
+                    if tile_cache.is_enabled() {
+                        tile_cache.insert(cache_key, buffer.lock()[..job.values_len()].to_vec());
+                    }
+                } else {
+                    compute_stats.record_wasted(elapsed);
+                }
+
+                (compute_ok, buffer)
+            }
+            .in_current_span(),
+        );
+        let result = compute_task.await;
+
+        // cancel() sets the token under the same lock, so a tile cancelled after the
+        // kernel finished doesn't overwrite the state of the job replacing it
+        let mut tile_state = state.lock();
+        let cancelled = cancel_token.is_cancelled();
+        let compute_time = start.elapsed();
+        let elapsed_ms = compute_time.as_millis() as u64;
+        match tile_state.complete(result, cancelled, job.coloring_mode, compute_time) {
+            Some(outcome) => {
+                if outcome.is_ok() {
+                    tracing::debug!(elapsed_ms, "Tile computed");
+                }
+                callback(tile_index, outcome);
+            }
+            None => tracing::debug!(elapsed_ms, "Tile cancelled"),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    #[test]
+    fn shaders_declare_replaceable_push_constants() {
+        for source in [
+            include_str!("blit_shader.wgsl"),
+            include_str!("screen_shader.wgsl"),
+            include_str!("tile_overlay_shader.wgsl"),
+        ] {
+            assert_eq!(source.matches(PUSH_CONSTANT_DECLARATION).count(), 1);
+        }
+    }
+
+    #[test]
+    fn texture_size_follows_device_limit() {
+        assert_eq!(
+            calc_texture_size(16 * 1024, UVec2::new(1920, 1080), 128),
+            UVec2::new(1920, 1152)
+        );
+        assert_eq!(
+            calc_texture_size(16 * 1024, UVec2::new(1080, 1920), 256),
+            UVec2::new(1280, 2048)
+        );
+        assert_eq!(
+            calc_texture_size(2200, UVec2::new(3840, 2160), 128),
+            UVec2::new(17 * 128, 10 * 128)
+        );
+        assert_eq!(
+            calc_texture_size(16 * 1024, UVec2::new(5120, 2880), 128),
+            UVec2::new(MAX_TEXTURE_SIZE, 12 * 128)
+        );
+    }
+
+    #[test]
+    fn textures_follow_the_window_aspect() {
+        // ultrawide, tall and square windows, the texture is within a tile of the window on
+        // each axis rather than a square of the longer side
+        for (window_size, texture_size) in [
+            (UVec2::new(3440, 1440), UVec2::new(2560, 1152)),
+            (UVec2::new(1080, 2400), UVec2::new(1152, 2432)),
+            (UVec2::new(2560, 1080), UVec2::new(2560, 1152)),
+            (UVec2::new(1000, 1000), UVec2::new(1024, 1024)),
+        ] {
+            let tile_size = 128;
+            let size = calc_texture_size(16 * 1024, window_size, tile_size);
+            assert_eq!(size, texture_size, "window {}", window_size);
+            assert_eq!(size % tile_size, UVec2::ZERO);
+
+            let coverage = (MAX_TEXTURE_SIZE as f64 / window_size.max_element() as f64).min(1.0);
+            let covered = (window_size.as_dvec2() * coverage).ceil().as_uvec2();
+            assert!(
+                size.cmpge(covered).all() && (size - covered).cmplt(UVec2::splat(tile_size)).all()
+            );
+
+            // one tile per `tile_size` texels on each axis, and square texels on the plane
+            let tiles = create_tiles(1, size, tile_size);
+            assert_eq!(tiles.len() as u32, (size / tile_size).element_product());
+            let frame_size = DVec2::new(window_size.x as f64 / window_size.y as f64, 1.0) * 2.5;
+            let page_size = fractal_size(size, window_size, frame_size);
+            let texel_size = page_size / size.as_dvec2();
+            assert!((texel_size.x - texel_size.y).abs() < 1e-15 * texel_size.y);
+            assert!(page_size.cmpge(frame_size).all());
+
+            let page = DRect::from_pos_size(DVec2::ZERO, page_size);
+            let last = tiles.last().unwrap().fractal_rect(size, page);
+            assert_eq!(last.pos + last.size, page_size);
+        }
+    }
+
+    #[test]
+    fn small_texture_limits_lower_the_resolution() {
+        // a limit below MIN_TEXTURE_SIZE still fits whole tiles
+        check_texture_limit(1000, 128).unwrap();
+        assert_eq!(
+            calc_texture_size(1000, UVec2::new(1920, 1080), 128),
+            UVec2::new(7 * 128, 4 * 128)
+        );
+        check_texture_limit(128, 128).unwrap();
+        assert_eq!(
+            calc_texture_size(128, UVec2::new(1920, 1080), 128),
+            UVec2::new(128, 128)
+        );
+
+        assert!(check_texture_limit(100, 128).is_err());
+    }
+
+    #[test]
+    fn texture_shrinks_down_to_one_tile() {
+        assert_eq!(
+            shrunk_texture_size(UVec2::new(1920, 1152), 16 * 1024, 128),
+            Some(UVec2::new(1024, 640))
+        );
+        assert_eq!(
+            shrunk_texture_size(UVec2::new(256, 128), 16 * 1024, 128),
+            Some(UVec2::new(128, 128))
+        );
+        assert_eq!(shrunk_texture_size(UVec2::splat(128), 16 * 1024, 128), None);
+    }
+
+    #[test]
+    fn small_texture_is_stretched_over_large_window() {
+        let window_size = UVec2::new(3840, 2160);
+        let frame_size = DVec2::new(3840.0 / 2160.0 * 2.5, 2.5);
+        let texture_size = calc_texture_size(2048, window_size, 128);
+
+        assert_eq!(texture_size, UVec2::new(2048, 1152));
+        assert!(!covers_window(texture_size, window_size));
+        let fractal_size = fractal_size(texture_size, window_size, frame_size);
+        assert!(fractal_size.cmpge(frame_size).all());
+        assert!((fractal_size / frame_size).max_element() < 1.0 + 1e-9);
+
+        let window_size = UVec2::new(1920, 1080);
+        let frame_size = DVec2::new(1920.0 / 1080.0 * 2.5, 2.5);
+        let texture_size = calc_texture_size(16 * 1024, window_size, 128);
+        assert!(covers_window(texture_size, window_size));
+        let fractal_size = super::fractal_size(texture_size, window_size, frame_size);
+        let expected = texture_size.as_dvec2() * 2.5 / 1080.0;
+        assert!((fractal_size - expected).abs().max_element() < 1e-12);
+    }
+
+    #[test]
+    fn turned_frame_turns_the_plane_the_other_way() {
+        let frame_rect = DRect::from_center_size(DVec2::ZERO, DVec2::new(4.0, 2.0));
+        let rect = DRect::from_pos_size(DVec2::ZERO, DVec2::ONE);
+        // the corner of the quad at the plane point (1, 1)
+        let corner = |rotation: f64| {
+            let position =
+                quad_transform(rect, frame_rect, rotation, false) * Vec4::new(1.0, 1.0, 0.0, 1.0);
+            position.truncate().truncate()
+        };
+
+        assert!(corner(0.0).abs_diff_eq(glam::Vec2::new(0.5, 1.0), 1e-6));
+        // a quarter turn counterclockwise shows the point to the right of the center, at the
+        // bottom edge
+        let quarter = corner(std::f64::consts::FRAC_PI_2);
+        assert!(
+            quarter.abs_diff_eq(glam::Vec2::new(0.5, -1.0), 1e-6),
+            "{}",
+            quarter
+        );
+
+        // the turned frame reaches past its own rect
+        let bounds = frame_rect.rotated_bounds(std::f64::consts::FRAC_PI_4);
+        assert!(bounds.contains(&frame_rect));
+        let half = 3.0 / std::f64::consts::SQRT_2;
+        assert!((bounds.size - DVec2::splat(2.0 * half)).abs().max_element() < 1e-12);
+        assert_eq!(frame_rect.rotated_bounds(0.0), frame_rect);
+    }
+
+    #[test]
+    fn texture_grows_but_never_shrinks() {
+        let texture_size = calc_texture_size(16 * 1024, UVec2::new(1920, 1080), 128);
+        assert_eq!(texture_size, UVec2::new(1920, 1152));
+
+        let grow = |window_size| grown_texture_size(texture_size, 16 * 1024, window_size, 128);
+        assert_eq!(grow(UVec2::new(960, 540)), None);
+        assert_eq!(grow(UVec2::new(1920, 1100)), None);
+        assert_eq!(grow(UVec2::new(3840, 2160)), Some(UVec2::new(2560, 1536)));
+        // a taller window needs more vertical coverage
+        assert_eq!(grow(UVec2::new(1920, 1440)), Some(UVec2::new(1920, 1536)));
+        // portrait keeps the width of the wide texture
+        assert_eq!(grow(UVec2::new(1080, 1920)), Some(UVec2::new(1920, 1920)));
+    }
+
+    #[test]
+    fn tile_grid_covers_non_square_texture() {
+        let texture_size = UVec2::new(3072, 2048);
+        let tiles = create_tiles(1, texture_size, 64);
+
+        assert_eq!(tiles.len(), 48 * 32);
+        assert_eq!(validate_tiling(&tiles, 1, texture_size, 64), Ok(()));
+        for (index, tile) in tiles.iter().enumerate() {
+            assert_eq!(tile.index, index);
+            assert_eq!(tile.tex_rect.size, UVec2::splat(64));
+        }
+
+        let fractal_rect = DRect::from_pos_size(DVec2::new(-3.0, -1.0), DVec2::new(3.0, 2.0));
+        let last = tiles
+            .last()
+            .unwrap()
+            .fractal_rect(texture_size, fractal_rect);
+        assert_eq!(last.pos + last.size, fractal_rect.pos + fractal_rect.size);
+    }
+
+    #[test]
+    fn small_pages_are_a_single_tile() {
+        let texture_size = UVec2::new(256, 128);
+        let tiles = create_tiles(9, texture_size, 32);
+
+        assert_eq!(tiles.len(), 9);
+        assert_eq!(validate_tiling(&tiles, 9, texture_size, 32), Ok(()));
+        for tile in &tiles {
+            assert_eq!(
+                tile.tex_rect,
+                URect::from_pos_size(UVec2::ZERO, texture_size)
+            );
+        }
+        assert_eq!(fade_cell_size(texture_size, 32), 256);
+        assert_eq!(tile_buffer_size(texture_size, 32), 256 * 128 * 4);
+
+        // a side beyond the crossover is tiled as usual
+        let texture_size = UVec2::new(WHOLE_PAGE_MAX_SIZE * 2, 128);
+        assert_eq!(create_tiles(1, texture_size, 32).len(), 16 * 4);
+        assert_eq!(fade_cell_size(texture_size, 32), 32);
+        assert_eq!(tile_buffer_size(texture_size, 32), 32 * 32 * 4);
+    }
+
+    #[test]
+    fn tile_status_packs_state_and_generation() {
+        let queued = TileState::Queued {
+            cancel_token: CancelToken::new(),
+        };
+        let computing = TileState::Computing {
+            cancel_token: CancelToken::new(),
+        };
+
+        assert_eq!(tile_status(&TileState::Idle, 0), 0x00);
+        assert_eq!(tile_status(&queued, 3), 0x13);
+        assert_eq!(tile_status(&computing, 3), 0x23);
+        // only the low bits of the generation fit
+        let ready = TileState::Ready {
+            compute_time: Duration::ZERO,
+        };
+        assert_eq!(tile_status(&ready, 17), 0x41);
+    }
+
+    #[test]
+    fn tiles_fill_their_cells() {
+        let texture_size = UVec2::new(512, 128);
+        let tiles = create_tiles(1, texture_size, 32);
+        let mut cells: Vec<usize> = tiles
+            .iter()
+            .map(|tile| cell_index(texture_size, 32, tile.tex_rect))
+            .collect();
+        cells.sort_unstable();
+        assert_eq!(cells, (0..16 * 4).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn broken_tilings_are_reported() {
+        let texture_size = UVec2::new(512, 128);
+        for (tile_size, page_count) in [(16, 1), (64, 9), (128, 3)] {
+            let tiles = create_tiles(page_count, texture_size, tile_size);
+            assert_eq!(
+                validate_tiling(&tiles, page_count, texture_size, tile_size),
+                Ok(())
+            );
+        }
+        let check = |tiles: &[Tile]| validate_tiling(tiles, 2, texture_size, 64);
+
+        // the last tile one texel short leaves a gap
+        let mut tiles = create_tiles(2, texture_size, 64);
+        tiles.last_mut().unwrap().tex_rect.size.x -= 1;
+        assert_eq!(
+            check(&tiles).unwrap_err(),
+            "Texel [511, 64] of page 1 is covered by 0 tiles"
+        );
+
+        // a tile placed over its neighbor
+        let mut tiles = create_tiles(2, texture_size, 64);
+        tiles[1].tex_rect = tiles[0].tex_rect;
+        assert_eq!(
+            check(&tiles).unwrap_err(),
+            "Texel [0, 0] of page 0 is covered by 2 tiles"
+        );
+
+        let mut tiles = create_tiles(2, texture_size, 64);
+        tiles.last_mut().unwrap().tex_rect.pos.y += 64;
+        assert!(check(&tiles).unwrap_err().contains("outside"));
+
+        let tiles = create_tiles(2, texture_size, 64);
+        assert!(check(&tiles[1..]).is_err());
+        assert!(validate_tiling(&tiles, 2, UVec2::new(512, 96), 64)
+            .unwrap_err()
+            .contains("multiple"));
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn tile_size_must_be_power_of_two() {
+        create_tiles(1, UVec2::new(3072, 2048), 96);
+    }
+
+    #[test]
+    fn scale_change_is_symmetric() {
+        let size = DVec2::new(2.0, 2.5);
+
+        assert!(!scale_changed(size, size));
+        assert!(!scale_changed(size, size * (1.0 + 1e-12)));
+        assert!(scale_changed(size, size * 1.001));
+        assert!(scale_changed(size, size / 1.001));
+        assert!(scale_changed(DVec2::ZERO, size));
+    }
+
+    #[test]
+    fn pages_follow_the_frame() {
+        let fractal_rect = DRect::from_pos_size(DVec2::new(-1.0, -0.5), DVec2::new(2.0, 1.0));
+        let mut coords: Vec<IVec2> = grid_coords(IVec2::ZERO).collect();
+        assert_eq!(coords.len(), 9);
+
+        let center = page_coord(fractal_rect, DVec2::new(0.5, 0.0));
+        assert_eq!(center, IVec2::ZERO);
+        assert!(recycle_pages(&mut coords, center).is_empty());
+
+        // one page to the right, the left column moves to the right side
+        let center = page_coord(fractal_rect, DVec2::new(1.5, 0.2));
+        assert_eq!(center, IVec2::new(1, 0));
+        assert_eq!(recycle_pages(&mut coords, center).len(), 3);
+        let mut expected: Vec<IVec2> = grid_coords(center).collect();
+        let mut sorted = coords.clone();
+        expected.sort_by_key(|coord| (coord.x, coord.y));
+        sorted.sort_by_key(|coord| (coord.x, coord.y));
+        assert_eq!(sorted, expected);
+
+        // far away, nothing stays
+        assert_eq!(recycle_pages(&mut coords, IVec2::new(-20, 7)).len(), 9);
+        assert!(coords
+            .iter()
+            .all(|coord| (*coord - IVec2::new(-20, 7)).abs().max_element() <= 1));
+
+        // neighbor pages share their edges
+        let left = page_rect(fractal_rect, IVec2::new(-3, 0));
+        let right = page_rect(fractal_rect, IVec2::new(-2, 0));
+        assert_eq!(left.pos.x + left.size.x, right.pos.x);
+    }
+
+    #[test]
+    fn panicking_tile_fails_with_reason() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(runtime::isolate(async {
+            if true {
+                panic!("kernel exploded at {}", 42);
+            }
+            (true, Arc::new(Mutex::new(Vec::new())))
+        }));
+
+        let mut tile_state = TileState::Idle;
+        let outcome = tile_state.complete(result, false, ColoringMode::Banded, Duration::ZERO);
+
+        let reason = "kernel exploded at 42".to_string();
+        assert_eq!(outcome, Some(Err(reason.clone())));
+        assert!(matches!(tile_state, TileState::Failed { reason: r } if r == reason));
+    }
+
+    #[test]
+    fn cancelled_tile_keeps_its_state() {
+        let compute_time = Duration::from_millis(3);
+        let mut tile_state = TileState::Ready { compute_time };
+        let result = Ok((true, Arc::new(Mutex::new(Vec::new()))));
+
+        assert_eq!(
+            tile_state.complete(result, true, ColoringMode::Banded, Duration::ZERO),
+            None
+        );
+        assert!(matches!(tile_state, TileState::Ready { compute_time: t } if t == compute_time));
+    }
+
+    #[test]
+    fn workers_compute_each_queued_tile_once() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(4)
+            .build()
+            .unwrap();
+        let queue = Arc::new(WorkQueue::new());
+        let buf_pool = Arc::new(Mutex::new(BufferPool::new(32 * 32 * 4, 0)));
+        for _ in 0..4 {
+            runtime.spawn(run_worker(
+                queue.clone(),
+                buf_pool.clone(),
+                Arc::default(),
+                Arc::new(Mutex::new(TileCache::new(0))),
+            ));
+        }
+
+        let computed = Arc::new(Mutex::new(Vec::new()));
+        let callback: TileCallback = {
+            let computed = computed.clone();
+            Arc::new(move |tile_index, result| {
+                assert_eq!(result, Ok(()));
+                computed.lock().push(tile_index);
+            })
+        };
+        let job = TileJob {
+            grid: PixelGrid::new(
+                DRect::from_pos_size(DVec2::new(-2.0, -1.0), DVec2::splat(2.0)),
+                UVec2::splat(32),
+            ),
+            pixels: URect::from_pos_size(UVec2::ZERO, UVec2::splat(32)),
+            options: KernelOptions {
+                max_iter: 200,
+                power: MANDELBROT_POWER,
+            },
+            kind: FractalKind::default(),
+            escape_metric: EscapeMetric::default(),
+            aa_threshold: None,
+            coloring_mode: ColoringMode::Banded,
+            render_scale: 1,
+        };
+
+        let tile_count = 64;
+        let states: Vec<Arc<Mutex<TileState>>> = (0..tile_count).map(|_| Arc::default()).collect();
+        // with the tile state locked, as `update` queues a job
+        let queue_tile = |tile_state: &mut TileState, generation, tile_index, priority| {
+            let cancel_token = CancelToken::new().next_generation();
+            *tile_state = TileState::Queued {
+                cancel_token: cancel_token.clone(),
+            };
+            let task = TileTask {
+                tile_index,
+                job,
+                state: states[tile_index].clone(),
+                cancel_token,
+                resumable: false,
+                previous: None,
+                resume: Arc::default(),
+                callback: callback.clone(),
+                queued: Instant::now(),
+                span: tracing::Span::none(),
+                cache_key: job.cache_key(),
+            };
+            queue.push(generation, priority, task);
+        };
+        let (generation, _) = queue.supersede();
+        for (tile_index, state) in states.iter().enumerate() {
+            queue_tile(&mut state.lock(), generation, tile_index, tile_index);
+        }
+
+        // reordered over and over while the workers take jobs, like the updates of a drag
+        for round in 0..200 {
+            let (generation, stale) = queue.supersede();
+            let mut stale: HashMap<usize, TileTask> = stale
+                .into_iter()
+                .map(|task| (task.tile_index, task))
+                .collect();
+            for (tile_index, state) in states.iter().enumerate() {
+                let priority = (tile_index * 7 + round) % tile_count;
+                let mut tile_state = state.lock();
+                if !matches!(*tile_state, TileState::Queued { .. }) {
+                    continue;
+                }
+                match stale.remove(&tile_index) {
+                    Some(task) => queue.push(generation, priority, task),
+                    // a worker took the job just before and skips it as stale, the tile is
+                    // queued anew
+                    None => {
+                        tile_state.cancel();
+                        queue_tile(&mut tile_state, generation, tile_index, priority);
+                    }
+                }
+            }
+        }
+
+        let start = Instant::now();
+        // computed tiles wait for an upload that never comes
+        let waiting = |state: &Arc<Mutex<TileState>>| {
+            matches!(
+                *state.lock(),
+                TileState::Queued { .. } | TileState::Computing { .. }
+            )
+        };
+        while states.iter().any(waiting) {
+            assert!(
+                start.elapsed() < Duration::from_secs(30),
+                "tiles left waiting"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let mut computed = computed.lock().clone();
+        computed.sort_unstable();
+        assert_eq!(computed, (0..tile_count).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn raised_iteration_limit_continues_tile() {
+        let job = |max_iter| TileJob {
+            grid: PixelGrid::new(
+                DRect::from_pos_size(DVec2::new(-0.8, 0.05), DVec2::splat(0.2)),
+                UVec2::splat(64),
+            ),
+            pixels: URect::from_pos_size(UVec2::new(32, 0), UVec2::splat(32)),
+            options: KernelOptions {
+                max_iter,
+                power: MANDELBROT_POWER,
+            },
+            kind: FractalKind::default(),
+            escape_metric: EscapeMetric::default(),
+            aa_threshold: None,
+            coloring_mode: ColoringMode::Smooth,
+            render_scale: 1,
+        };
+        let cancel_token = CancelToken::new();
+        let mut buffer = vec![0u8; 32 * 32 * size_of::<f32>()];
+
+        let mut expected = buffer.clone();
+        job(1000)
+            .compute(&SimdKernel::default(), cancel_token.clone(), &mut expected)
+            .unwrap();
+
+        let first = job(100)
+            .compute_resumable(
+                &SimdKernel::default(),
+                cancel_token.clone(),
+                &mut buffer,
+                None,
+            )
+            .unwrap();
+        assert!(job(1000).continues(&first.job));
+        assert!(!job(50).continues(&first.job));
+        let moved = TileJob {
+            pixels: URect::from_pos_size(UVec2::new(32, 32), UVec2::splat(32)),
+            ..job(1000)
+        };
+        assert!(!moved.continues(&first.job));
+
+        buffer.fill(0);
+        let second = job(1000)
+            .compute_resumable(
+                &SimdKernel::default(),
+                cancel_token,
+                &mut buffer,
+                Some(first),
+            )
+            .unwrap();
+        assert_eq!(buffer, expected);
+        assert_eq!(second.values, expected);
+        assert_eq!(second.interior.iterations, 1000);
+    }
+
+    #[test]
+    fn tiles_match_the_full_frame() {
+        // wide enough to be tiled, see `WHOLE_PAGE_MAX_SIZE`
+        let texture_size = UVec2::new(512, 128);
+        let options = KernelOptions {
+            max_iter: 2000,
+            power: MANDELBROT_POWER,
+        };
+
+        // the overview, and seahorse valley deep enough for rounding to show
+        for frame in [
+            DRect::from_center_size(DVec2::new(-0.74, 0.0), DVec2::new(3.0, 1.5)),
+            DRect::from_center_size(
+                DVec2::new(-0.743_643_887_037_151, 0.131_825_904_205_330),
+                DVec2::new(2e-9, 1e-9),
+            ),
+        ] {
+            // without anti-aliasing, which refines the borders of tiles and bands alike
+            let grid = PixelGrid::new(frame, texture_size);
+            // in the precision the tiles pick for the grid, f32 for the overview
+            let kernel = tile_kernel(grid, FractalKind::default(), EscapeMetric::default());
+            let expected: Vec<f32> = raw_export::compute(&kernel, grid, options, None).unwrap();
+
+            for tile in create_tiles(1, texture_size, 32) {
+                let job = TileJob {
+                    grid,
+                    pixels: tile.tex_rect,
+                    options,
+                    kind: FractalKind::default(),
+                    escape_metric: EscapeMetric::default(),
+                    aa_threshold: None,
+                    coloring_mode: ColoringMode::Smooth,
+                    render_scale: 1,
+                };
+                let mut buffer = vec![0u8; 32 * 32 * size_of::<f32>()];
+                job.compute(&job.kernel(), CancelToken::new(), &mut buffer)
+                    .unwrap();
+
+                // the export has the top row first
+                let values: &[f32] = bytemuck::cast_slice(&buffer);
+                for (y, row) in values.chunks_exact(32).enumerate() {
+                    let image_y = texture_size.y - 1 - tile.tex_rect.pos.y - y as u32;
+                    let start = (image_y * texture_size.x + tile.tex_rect.pos.x) as usize;
+                    assert_eq!(
+                        row,
+                        &expected[start..start + 32],
+                        "tile {:?}, row {}",
+                        tile.tex_rect,
+                        y
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn coarse_tiles_repeat_every_few_texels() {
+        let full = TileJob {
+            grid: PixelGrid::new(
+                DRect::from_pos_size(DVec2::new(-0.8, 0.05), DVec2::splat(0.2)),
+                UVec2::splat(128),
+            ),
+            pixels: URect::from_pos_size(UVec2::new(32, 64), UVec2::splat(32)),
+            options: KernelOptions {
+                max_iter: 500,
+                power: MANDELBROT_POWER,
+            },
+            kind: FractalKind::default(),
+            escape_metric: EscapeMetric::default(),
+            aa_threshold: Some(2),
+            coloring_mode: ColoringMode::Smooth,
+            render_scale: 1,
+        };
+        let mut expected = vec![0u8; 32 * 32 * size_of::<f32>()];
+        TileJob {
+            aa_threshold: None,
+            ..full
+        }
+        .compute(&full.kernel(), CancelToken::new(), &mut expected)
+        .unwrap();
+        let expected: &[f32] = bytemuck::cast_slice(&expected);
+
+        for render_scale in [2, MAX_RENDER_SCALE] {
+            let job = TileJob {
+                render_scale,
+                ..full
+            };
+            let mut buffer = vec![0u8; 32 * 32 * size_of::<f32>()];
+            job.compute(&job.kernel(), CancelToken::new(), &mut buffer)
+                .unwrap();
+            let values: &[f32] = bytemuck::cast_slice(&buffer);
+
+            for y in 0..32 {
+                for x in 0..32 {
+                    let block = (y / render_scale * 32 + x / render_scale) * render_scale;
+                    assert_eq!(
+                        values[(y * 32 + x) as usize].to_bits(),
+                        expected[block as usize].to_bits(),
+                        "scale {}, texel {}, {}",
+                        render_scale,
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn loading_fill_defaults_to_a_dark_gray() {
+        // close to the fixed background it replaces
+        let color = loading_fill_color(DEFAULT_LOADING_FILL);
+        assert!((color.r - 0.02).abs() < 0.002, "{:?}", color);
+        assert_eq!((color.r, color.g), (color.b, color.b));
+        assert_eq!(loading_fill_color(0).r, 0.0);
+        assert!((loading_fill_color(255).r - 1.0).abs() < 1e-6);
+
+        // the whole grid at a sixteenth of the resolution, never empty
+        assert_eq!(overview_size(UVec2::new(2560, 1440)), UVec2::new(480, 270));
+        assert_eq!(overview_size(UVec2::splat(4)), UVec2::ONE);
+    }
+
+    #[test]
+    fn sampled_pixels_match_their_tile() {
+        let tile = URect::from_pos_size(UVec2::new(32, 64), UVec2::splat(32));
+        let job = TileJob {
+            grid: PixelGrid::new(
+                DRect::from_pos_size(DVec2::new(-0.8, 0.05), DVec2::splat(0.2)),
+                UVec2::splat(128),
+            ),
+            pixels: tile,
+            options: KernelOptions {
+                max_iter: 500,
+                power: MANDELBROT_POWER,
+            },
+            kind: FractalKind::default(),
+            escape_metric: EscapeMetric::default(),
+            aa_threshold: Some(2),
+            coloring_mode: ColoringMode::Smooth,
+            render_scale: 1,
+        };
+        let mut buffer = vec![0u8; 32 * 32 * size_of::<f32>()];
+        job.compute(&job.kernel(), CancelToken::new(), &mut buffer)
+            .unwrap();
+        let values: &[f32] = bytemuck::cast_slice(&buffer);
+
+        // the corners and edges are refined whatever their neighbors, the inside by comparing
+        for (x, y) in [
+            (0, 0),
+            (31, 31),
+            (0, 17),
+            (17, 31),
+            (1, 1),
+            (15, 16),
+            (30, 2),
+        ] {
+            let sampled: f32 = TileJob {
+                pixels: URect::from_pos_size(tile.pos + UVec2::new(x, y), UVec2::ONE),
+                ..job
+            }
+            .sample_pixel(tile);
+            assert_eq!(sampled, values[(y * 32 + x) as usize], "pixel {}, {}", x, y);
+        }
+    }
+
+    const TEST_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    // `None` on machines without an adapter, the tests needing one are skipped there
+    pub(crate) fn request_test_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: crate::instance_backends(),
+            flags: Default::default(),
+            backend_options: Default::default(),
+        });
+        let adapter = crate::request_adapter(&instance, None, wgpu::PowerPreference::LowPower)?;
+
+        Some(pollster::block_on(crate::request_device(&adapter, false)).unwrap())
+    }
+
+    pub(crate) fn test_surface_config(window_size: UVec2) -> wgpu::SurfaceConfiguration {
+        wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: TEST_FORMAT,
+            width: window_size.x,
+            height: window_size.y,
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![TEST_FORMAT],
+        }
+    }
+
+    #[test]
+    fn blocking_computes_are_identical() {
+        let Some((device, queue)) = request_test_device() else {
+            eprintln!("No adapter, skipping the blocking compute test");
+            return;
+        };
+        let window_size = UVec2::new(512, 128);
+        let settings = Settings {
+            tile_size: 32,
+            ..Settings::default()
+        };
+        let mut texture = MandelTexture::new(
+            &device,
+            &queue,
+            &test_surface_config(window_size),
+            window_size,
+            &settings,
+            &Palette::builtin(),
+        )
+        .unwrap();
+        let frame_rect = DRect::from_center_size(DVec2::new(-0.5, 0.0), DVec2::new(3.0, 1.5));
+        let max_iter = texture.max_iters_for(frame_rect);
+
+        // the values every visible tile waits to upload with, by tile
+        let mut compute = || {
+            texture
+                .compute_frame_blocking(frame_rect, max_iter)
+                .unwrap();
+            assert_eq!(texture.max_iters(), max_iter);
+            let mut buffers: Vec<(usize, Vec<u8>)> = texture
+                .tiles
+                .iter()
+                .filter_map(|tile| match &*tile.state.lock() {
+                    TileState::WaitForUpload { buffer, .. } => {
+                        Some((tile.index, buffer.lock().clone()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            buffers.sort_unstable_by_key(|&(index, _)| index);
+            buffers
+        };
+
+        let first = compute();
+        assert!(first.len() > 1);
+        assert!(first
+            .iter()
+            .any(|(_, values)| values.iter().any(|&v| v != 0)));
+        assert!(first == compute());
+        assert_eq!(texture.count_unready_tiles(), first.len());
+    }
+
+    #[test]
+    fn returning_to_a_view_finds_its_tiles_cached() {
+        let Some((device, queue)) = request_test_device() else {
+            eprintln!("No adapter, skipping the tile cache test");
+            return;
+        };
+        let window_size = UVec2::new(512, 128);
+        let settings = Settings {
+            tile_size: 32,
+            overview_fill: false,
+            tile_pyramid: false,
+            ..Settings::default()
+        };
+        let mut texture = MandelTexture::new(
+            &device,
+            &queue,
+            &test_surface_config(window_size),
+            window_size,
+            &settings,
+            &Palette::builtin(),
+        )
+        .unwrap();
+        let wait = |texture: &MandelTexture| {
+            let start = Instant::now();
+            while texture.tiles.iter().any(|tile| {
+                matches!(
+                    *tile.state.lock(),
+                    TileState::Queued { .. } | TileState::Computing { .. }
+                )
+            }) {
+                assert!(start.elapsed() < Duration::from_secs(30));
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        };
+
+        // above the cardioid, none of the tiles is filled as inside the set without computing
+        let frame_rect = DRect::from_center_size(DVec2::new(-0.7, 1.0), DVec2::new(2.0, 0.5));
+        texture.update(frame_rect, frame_rect.center(), |_, _| {});
+        wait(&texture);
+        let computed = texture.tile_stats().compute.count;
+        assert_eq!(texture.tile_stats().cache_lookups, computed);
+        assert_eq!(texture.tile_stats().cache_hits, 0);
+
+        // zooming by a step that doesn't divide exactly and back lays the grid out the same
+        let zoomed = DRect::from_center_size(frame_rect.center(), frame_rect.size / 1.15);
+        texture.update(zoomed, zoomed.center(), |_, _| {});
+        wait(&texture);
+        let zoomed_computed = texture.tile_stats().compute.count;
+        let back = DRect::from_center_size(frame_rect.center(), zoomed.size * 1.15);
+        texture.update(back, back.center(), |_, _| {});
+
+        let stats = texture.tile_stats();
+        assert_eq!(stats.cache_hits, computed);
+        assert_eq!(stats.compute.count, zoomed_computed);
+        assert_eq!(texture.count_unready_tiles(), computed as usize);
+        assert!(texture.tiles.iter().all(|tile| !matches!(
+            *tile.state.lock(),
+            TileState::Queued { .. } | TileState::Computing { .. }
+        )));
+    }
+
+    #[test]
+    fn zooming_in_shows_the_coarser_levels_at_once() {
+        let Some((device, queue)) = request_test_device() else {
+            eprintln!("No adapter, skipping the tile pyramid test");
+            return;
+        };
+        let window_size = UVec2::new(512, 128);
+        let surface_config = test_surface_config(window_size);
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: window_size.x,
+                height: window_size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TEST_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+            label: None,
+        });
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let render_context = RenderContext {
+            device: &device,
+            queue: &queue,
+            view: &view,
+            time: 0.0,
+            profiler: None,
+            viewport: None,
+        };
+        // nothing comes back from the tile cache, only the atlas keeps the levels
+        let settings = Settings {
+            tile_size: 32,
+            overview_fill: false,
+            tile_cache_mb: 0,
+            ..Settings::default()
+        };
+        let mut texture = MandelTexture::new(
+            &device,
+            &queue,
+            &surface_config,
+            window_size,
+            &settings,
+            &Palette::builtin(),
+        )
+        .unwrap();
+        let wait = |texture: &MandelTexture| {
+            let start = Instant::now();
+            let pyramid = texture.pyramid.as_ref().unwrap();
+            let tile_states = texture.tiles.iter().map(|tile| &tile.state);
+            let pyramid_states = pyramid.tiles.iter().map(|tile| &tile.state);
+            let states: Vec<_> = tile_states.chain(pyramid_states).collect();
+            while states.iter().any(|state| {
+                matches!(
+                    *state.lock(),
+                    TileState::Queued { .. } | TileState::Computing { .. }
+                )
+            }) {
+                assert!(start.elapsed() < Duration::from_secs(30));
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        };
+        let shown_ready = |texture: &MandelTexture| {
+            let pyramid = texture.pyramid.as_ref().unwrap();
+            pyramid
+                .shown
+                .iter()
+                .map(|&slot| matches!(*pyramid.tiles[slot].state.lock(), TileState::Ready { .. }))
+                .collect::<Vec<bool>>()
+        };
+
+        let frame_rect = DRect::from_center_size(DVec2::new(-0.7, 0.2), DVec2::new(2.0, 0.5));
+        texture.update(frame_rect, frame_rect.center(), |_, _| {});
+        let page_texel_size = texture.fractal_rect.size.y / texture.texture_size.y as f64;
+        let levels: Vec<i32> = fallback_levels(page_texel_size).collect();
+        // the coarsest level first, every level covers the frame
+        let keys: Vec<usize> = levels
+            .iter()
+            .map(|&level| covering_keys(frame_rect, level, 32).count())
+            .collect();
+        assert_eq!(
+            texture.pyramid.as_ref().unwrap().shown.len(),
+            keys.iter().sum::<usize>()
+        );
+        assert!(keys[0] <= keys[1] && keys[1] <= keys[2]);
+        wait(&texture);
+        texture.render(&render_context);
+        assert!(shown_ready(&texture).iter().all(|&ready| ready));
+
+        // at twice the zoom the two finer levels are the coarser ones now, only the new
+        // finest level computes
+        let zoomed = DRect::from_center_size(frame_rect.center(), frame_rect.size / 2.0);
+        texture.update(zoomed, zoomed.center(), |_, _| {});
+        let page_texel_size = texture.fractal_rect.size.y / texture.texture_size.y as f64;
+        let zoomed_levels: Vec<i32> = fallback_levels(page_texel_size).collect();
+        assert_eq!(
+            zoomed_levels,
+            levels.iter().map(|level| level + 1).collect::<Vec<_>>()
+        );
+        let kept: usize = zoomed_levels[..2]
+            .iter()
+            .map(|&level| covering_keys(zoomed, level, 32).count())
+            .sum();
+        let ready = shown_ready(&texture);
+        assert!(ready[..kept].iter().all(|&ready| ready));
+        assert!(!ready[kept..].iter().all(|&ready| ready));
+        wait(&texture);
+        texture.render(&render_context);
+        assert!(shown_ready(&texture).iter().all(|&ready| ready));
+    }
+
+    #[test]
+    fn frame_completes_with_its_last_tile() {
+        let Some((device, queue)) = request_test_device() else {
+            eprintln!("No adapter, skipping the frame completion test");
+            return;
+        };
+
+        let format = TEST_FORMAT;
+        // wide enough to be tiled, see `WHOLE_PAGE_MAX_SIZE`
+        let window_size = UVec2::new(512, 128);
+        let surface_config = test_surface_config(window_size);
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: window_size.x,
+                height: window_size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+            label: None,
+        });
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let render_context = RenderContext {
+            device: &device,
+            queue: &queue,
+            view: &view,
+            time: 0.0,
+            profiler: None,
+            viewport: None,
+        };
+
+        // one worker and nothing but the tiles of the pages, every callback is one of them
+        let settings = Settings {
+            tile_size: 32,
+            worker_threads: Some(1),
+            overview_fill: false,
+            tile_pyramid: false,
+            ..Settings::default()
+        };
+        let mut texture = MandelTexture::new(
+            &device,
+            &queue,
+            &surface_config,
+            window_size,
+            &settings,
+            &Palette::builtin(),
+        )
+        .unwrap();
+        let frame_rect = DRect::from_center_size(DVec2::new(-0.5, 0.0), DVec2::new(3.0, 1.5));
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let callback = move |tile_index, result| {
+            let _ = sender.send((tile_index, result));
+        };
+
+        texture.update(frame_rect, frame_rect.center(), callback.clone());
+        texture.render(&render_context);
+        assert!(!texture.is_frame_complete(frame_rect));
+        let (done, total) = texture.tile_progress();
+        assert!(done < total, "{} of {}", done, total);
+
+        // paused after the first tile the rest stays unfinished, the frame shows some of its
+        // tiles and isn't complete
+        let (tile_index, result): (usize, Result<(), String>) = receiver.recv().unwrap();
+        assert_eq!(result, Ok(()), "tile {}", tile_index);
+        texture.set_paused(true);
+        texture.render(&render_context);
+        let (done, _) = texture.tile_progress();
+        assert!(0 < done && done < total, "{} of {}", done, total);
+        assert!(!texture.is_frame_complete(frame_rect));
+        texture.set_paused(false);
+        // the progress of the update resuming counts the tiles left
+        texture.update(frame_rect, frame_rect.center(), callback.clone());
+        let (_, resumed) = texture.tile_progress();
+        assert_eq!(resumed, total - done);
+
+        // every render uploads the tiles finished so far, the frame is complete with the last
+        // visible one and not a render earlier, however many tiles one render picks up
+        while !texture.is_frame_complete(frame_rect) {
+            assert!(texture.pending_tile_count() > 0);
+            let (tile_index, result): (usize, Result<(), String>) = receiver.recv().unwrap();
+            assert_eq!(result, Ok(()), "tile {}", tile_index);
+
+            texture.render(&render_context);
+            let (done, tiles) = texture.tile_progress();
+            assert_eq!(tiles, resumed);
+            assert_eq!(texture.is_frame_complete(frame_rect), done == resumed);
+        }
+        assert_eq!(texture.tile_progress(), (resumed, resumed));
+        assert!(texture.tile_timings().len() >= total);
+        assert_eq!(texture.pending_tile_count(), 0);
+        assert_eq!(texture.count_unready_tiles(), 0);
+
+        // another frame isn't the one scheduled, a recompute starts over
+        let moved = DRect::from_center_size(DVec2::new(-0.4, 0.0), frame_rect.size);
+        assert!(!texture.is_frame_complete(moved));
+        texture.invalidate();
+        assert!(!texture.is_frame_complete(frame_rect));
+        texture.update(frame_rect, frame_rect.center(), callback.clone());
+        assert!(!texture.is_frame_complete(frame_rect));
+        assert_eq!(texture.tile_progress(), (0, total));
+
+        // invalidated again halfway, the count starts over rather than adding up
+        let _ = receiver.recv().unwrap();
+        texture.render(&render_context);
+        texture.invalidate();
+        texture.update(frame_rect, frame_rect.center(), callback);
+        assert_eq!(texture.tile_progress(), (0, total));
     }
 }
@@ -1,160 +1,631 @@
 #![allow(non_camel_case_types)]
 
+use std::ops::{Add, BitOrAssign, Div, Mul, Sub};
 use std::simd::prelude::*;
-use std::sync::atomic::AtomicBool;
-use std::sync::Arc;
-use std::time::Instant;
-use std::usize;
 
-use anyhow::anyhow;
 use bytemuck::{Pod, Zeroable};
 use glam::DVec2;
+use serde::{Deserialize, Serialize};
 
-use crate::env::is_test_build;
-use crate::math::{DRect, URect};
+use crate::kernel::{complex_pow, FractalKernel};
 
 const SIMD_LANE_COUNT: usize = 8;
 pub const MAX_ITER: u32 = 4500;
+// |z|^2 >= 5, a bit beyond the minimal 2 to make smooth values more accurate
+pub const ESCAPE_RADIUS: f64 = 2.236_067_977_499_79;
+/// Exponent of `z = z^power + c` giving the Mandelbrot set, other values give Multibrot sets.
+pub const MANDELBROT_POWER: f64 = 2.0;
+// |z|^2 at which the product metric gives up on orbits running along an axis
+const PRODUCT_GUARD_NORM: f64 = 1e8;
+
+// pixels beyond this many f32 steps at |c| = 2 are computed in f32, the rounding errors that
+// orbits near the boundary amplify stay well below a pixel
+const F32_MIN_PIXEL_STEPS: f64 = 64.0;
 
 type f64simd = Simd<f64, SIMD_LANE_COUNT>;
+type f32simd = Simd<f32, SIMD_LANE_COUNT>;
 type i64simd = Simd<i64, SIMD_LANE_COUNT>;
 type mask64simd = Mask<i64, SIMD_LANE_COUNT>;
-type CountSimd = [Pixel; SIMD_LANE_COUNT];
+type mask32simd = Mask<i32, SIMD_LANE_COUNT>;
+type CountSimd<P> = [P; SIMD_LANE_COUNT];
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
-pub(crate) struct Pixel {
+pub struct Pixel {
     r: u16,
 }
 
-const CX_INIT: [f64; SIMD_LANE_COUNT] = {
-    let mut r = [0.0; SIMD_LANE_COUNT];
-    let mut i = 0;
-    while i < SIMD_LANE_COUNT {
-        r[i] = i as f64;
-        i += 1;
+/// Output value of the kernel for a single pixel. Zero marks points inside the set,
+/// escaped points are stored as `1 + iterations`.
+pub trait KernelPixel: Pod + Default {
+    /// Whether the value depends on `|z|` at escape, which costs an extra select per iteration.
+    const SMOOTH: bool;
+
+    fn interior() -> Self;
+    fn escaped(iterations: u32, norm_sqr: f64, power: f64) -> Self;
+
+    fn value(&self) -> f64;
+    fn from_value(value: f64) -> Self;
+}
+
+impl KernelPixel for Pixel {
+    const SMOOTH: bool = false;
+
+    fn interior() -> Self {
+        Pixel { r: 0 }
+    }
+    fn escaped(iterations: u32, _norm_sqr: f64, _power: f64) -> Self {
+        Pixel {
+            r: 1 + (iterations % u16::MAX as u32) as u16,
+        }
     }
-    r
-};
 
-//noinspection RsConstantConditionIf
-pub fn mandelbrot_simd(
-    image_size: u32,
-    tex_rect: URect,
-    fractal_offset: DVec2,
-    fractal_scale: f64,
-    max_iterations: u32,
-    cancel_token: Arc<AtomicBool>,
-    buffer: &mut [Pixel],
-) -> anyhow::Result<()> {
-    assert_eq!(buffer.len(), (tex_rect.size.x * tex_rect.size.y) as usize);
-
-    let now = Instant::now();
-    let buffer_frame = {
-        let image_size = image_size as f64;
-        let fractal_offset = DVec2::new(fractal_offset.x, fractal_offset.y);
-
-        DRect::from_pos_size(
-            (DVec2::from(tex_rect.pos) / image_size - 0.5) / fractal_scale - fractal_offset,
-            (DVec2::from(tex_rect.size) / image_size) / fractal_scale,
-        )
-    };
+    fn value(&self) -> f64 {
+        self.r as f64
+    }
+    fn from_value(value: f64) -> Self {
+        Pixel {
+            r: value.round() as u16,
+        }
+    }
+}
+
+/// Continuous escape count for smooth coloring without banding.
+impl KernelPixel for f32 {
+    const SMOOTH: bool = true;
+
+    fn interior() -> Self {
+        0.0
+    }
+    // |z| grows by the power each iteration, the fraction is measured in the same base
+    fn escaped(iterations: u32, norm_sqr: f64, power: f64) -> Self {
+        let log_z = 0.5 * norm_sqr.ln();
+        let smooth = iterations as f64 + 1.0 - log_z.ln() / power.ln();
+
+        (1.0 + smooth).max(1.0) as f32
+    }
+
+    fn value(&self) -> f64 {
+        *self as f64
+    }
+    fn from_value(value: f64) -> Self {
+        value as f32
+    }
+}
+
+/// Measure of `z` compared against the escape radius. Every metric only escapes outside the
+/// circle of that radius, so none escapes earlier than `Euclidean` and the set is the same for
+/// all of them, only the bands around it take different shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EscapeMetric {
+    /// `|z|`, round bands.
+    #[default]
+    Euclidean,
+    /// `(|re| + |im|) / sqrt(2)`, a diamond around the circle, the bands stretch along the axes.
+    Manhattan,
+    /// `max(|re|, |im|)`, a square around the circle, the bands stretch along the diagonals.
+    Chebyshev,
+    /// `sqrt(|re| * |im|)`, hyperbolas that never reach the axes, orbits along them escape
+    /// only much further out, which grows spikes on the bands.
+    Product,
+}
+
+impl EscapeMetric {
+    pub const ALL: [EscapeMetric; 4] = [
+        EscapeMetric::Euclidean,
+        EscapeMetric::Manhattan,
+        EscapeMetric::Chebyshev,
+        EscapeMetric::Product,
+    ];
 
-    for y in 0..tex_rect.size.y {
-        if cancel_token.load(std::sync::atomic::Ordering::Relaxed) {
-            return Err(anyhow!("Cancelled"));
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&metric| metric == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    // compares squares where that saves the square root
+    fn escaped<L: Lanes>(self, zx: L, zy: L, norm_sqr: L) -> L::Mask {
+        let radius = L::splat(ESCAPE_RADIUS);
+        let radius_sqr = L::splat(ESCAPE_RADIUS * ESCAPE_RADIUS);
+
+        match self {
+            EscapeMetric::Euclidean => norm_sqr.ge(radius_sqr),
+            EscapeMetric::Manhattan => {
+                (zx.abs() + zy.abs()).ge(radius * L::splat(std::f64::consts::SQRT_2))
+            }
+            EscapeMetric::Chebyshev => zx.abs().max(zy.abs()).ge(radius),
+            // an orbit diverging along an axis keeps the product small
+            EscapeMetric::Product => {
+                let mut escaped = (zx * zy).abs().ge(radius_sqr);
+                escaped |= norm_sqr.ge(L::splat(PRODUCT_GUARD_NORM));
+                escaped
+            }
         }
-        for x in 0..tex_rect.size.x / SIMD_LANE_COUNT as u32 {
-            let cx = f64simd::from_slice(CX_INIT.as_slice())
-                + f64simd::splat((x * SIMD_LANE_COUNT as u32) as f64);
-            let cx = cx * f64simd::splat(buffer_frame.size.x / tex_rect.size.x as f64);
-            let cx = cx + f64simd::splat(buffer_frame.pos.x);
+    }
+}
 
-            let cy = f64simd::splat(
-                buffer_frame.pos.y + buffer_frame.size.y * (y as f64 / tex_rect.size.y as f64),
-            );
+/// Which iteration the kernel runs, the escape test and the coloring are the same for all.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FractalKind {
+    /// `z = z^power + c` from `z = 0`, `c` is the point.
+    #[default]
+    Mandelbrot,
+    /// `z = z^power + c` from `z` at the point, with the same `c` everywhere.
+    Julia(DVec2),
+    /// Like `Mandelbrot` with both parts of `z` made positive before every step.
+    BurningShip,
+    /// Newton's method for the roots of `z^3 - 1` from `z` at the point, the values tell the
+    /// root it converges to and how fast, see `newton_value`. Ignores the power and the escape
+    /// metric.
+    Newton,
+}
+
+/// The `c` of the Julia set the kind key switches to, a connected one full of spirals.
+pub const DEFAULT_JULIA_C: DVec2 = DVec2::new(-0.8, 0.156);
+
+/// The roots of `z^3 - 1`, a Newton value names one by its index here.
+pub const NEWTON_ROOTS: [DVec2; 3] = [
+    DVec2::new(1.0, 0.0),
+    DVec2::new(-0.5, 0.866_025_403_784_438_6),
+    DVec2::new(-0.5, -0.866_025_403_784_438_6),
+];
+// the squared distance to a root that counts as converged
+const NEWTON_TOLERANCE: f64 = 1e-6;
+// steps beyond this look the same, the largest count whose value fits the banded texels
+const MAX_NEWTON_STEPS: u32 = (u16::MAX as u32 - NEWTON_ROOTS.len() as u32) / 3;
+
+/// The value of a point that reached `NEWTON_ROOTS[root]` after `steps` steps. Zero stays the
+/// value of points that converge to none within the iteration limit, like the interior of the
+/// other kinds, and the values grow with the steps like escape counts do.
+pub fn newton_value(root: usize, steps: u32) -> f64 {
+    let packed = steps.min(MAX_NEWTON_STEPS) as usize * NEWTON_ROOTS.len() + root;
+    1.0 + packed as f64
+}
+
+/// The root and the steps of a value `newton_value` made, none for a point that didn't converge.
+pub fn newton_root(value: f64) -> Option<(usize, u32)> {
+    let packed = (value.round() as u64).checked_sub(1)?;
+    let root_count = NEWTON_ROOTS.len() as u64;
+
+    Some(((packed % root_count) as usize, (packed / root_count) as u32))
+}
+
+impl FractalKind {
+    /// The next one of the cycle the kind key steps through, a Julia set keeps its `c`
+    /// only until the cycle comes back to it.
+    pub fn next(self) -> Self {
+        match self {
+            FractalKind::Mandelbrot => FractalKind::Julia(DEFAULT_JULIA_C),
+            FractalKind::Julia(_) => FractalKind::BurningShip,
+            FractalKind::BurningShip => FractalKind::Newton,
+            FractalKind::Newton => FractalKind::Mandelbrot,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FractalKind::Mandelbrot => "Mandelbrot",
+            FractalKind::Julia(_) => "Julia",
+            FractalKind::BurningShip => "Burning Ship",
+            FractalKind::Newton => "Newton",
+        }
+    }
+
+    /// Whether the values name roots rather than escape counts. Averaging two of them
+    /// gives neither root, so there is no anti-aliasing.
+    pub fn colors_by_root(self) -> bool {
+        self == FractalKind::Newton
+    }
+}
+
+/// Float type the lanes iterate in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    /// Twice the lanes per instruction of `F64`, for pixels far larger than the f32 steps.
+    F32,
+    #[default]
+    F64,
+}
+
+impl Precision {
+    /// The faster precision whose rounding stays invisible at this pixel size.
+    pub fn for_pixel_size(pixel_size: f64) -> Self {
+        let f32_step = 2.0 * f32::EPSILON as f64;
+        if pixel_size >= F32_MIN_PIXEL_STEPS * f32_step {
+            Precision::F32
+        } else {
+            Precision::F64
+        }
+    }
+}
+
+/// Evaluates the points eight at a time in SIMD lanes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimdKernel {
+    pub kind: FractalKind,
+    pub escape_metric: EscapeMetric,
+    pub precision: Precision,
+}
+
+// lanes of either precision, the iteration is written once for both
+trait Lanes:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self>
+{
+    type Mask: Copy + BitOrAssign;
+
+    fn splat(value: f64) -> Self;
+    fn from_fn(value: impl FnMut(usize) -> f64) -> Self;
+    fn to_f64(self) -> [f64; SIMD_LANE_COUNT];
+
+    fn abs(self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn ge(self, other: Self) -> Self::Mask;
+    fn select(mask: Self::Mask, if_true: Self, if_false: Self) -> Self;
+
+    fn no_lanes() -> Self::Mask;
+    // the escape counts are i64 lanes for both precisions
+    fn count_mask(mask: Self::Mask) -> mask64simd;
+}
+
+impl Lanes for f64simd {
+    type Mask = mask64simd;
+
+    fn splat(value: f64) -> Self {
+        Simd::splat(value)
+    }
+    fn from_fn(value: impl FnMut(usize) -> f64) -> Self {
+        Simd::from_array(std::array::from_fn(value))
+    }
+    fn to_f64(self) -> [f64; SIMD_LANE_COUNT] {
+        self.to_array()
+    }
+
+    fn abs(self) -> Self {
+        SimdFloat::abs(self)
+    }
+    fn max(self, other: Self) -> Self {
+        self.simd_max(other)
+    }
+    fn ge(self, other: Self) -> Self::Mask {
+        self.simd_ge(other)
+    }
+    fn select(mask: Self::Mask, if_true: Self, if_false: Self) -> Self {
+        mask.select(if_true, if_false)
+    }
+
+    fn no_lanes() -> Self::Mask {
+        Mask::splat(false)
+    }
+    fn count_mask(mask: Self::Mask) -> mask64simd {
+        mask
+    }
+}
+
+impl Lanes for f32simd {
+    type Mask = mask32simd;
+
+    fn splat(value: f64) -> Self {
+        Simd::splat(value as f32)
+    }
+    fn from_fn(mut value: impl FnMut(usize) -> f64) -> Self {
+        Simd::from_array(std::array::from_fn(|lane| value(lane) as f32))
+    }
+    fn to_f64(self) -> [f64; SIMD_LANE_COUNT] {
+        self.cast::<f64>().to_array()
+    }
+
+    fn abs(self) -> Self {
+        SimdFloat::abs(self)
+    }
+    fn max(self, other: Self) -> Self {
+        self.simd_max(other)
+    }
+    fn ge(self, other: Self) -> Self::Mask {
+        self.simd_ge(other)
+    }
+    fn select(mask: Self::Mask, if_true: Self, if_false: Self) -> Self {
+        mask.select(if_true, if_false)
+    }
+
+    fn no_lanes() -> Self::Mask {
+        Mask::splat(false)
+    }
+    fn count_mask(mask: Self::Mask) -> mask64simd {
+        mask.cast()
+    }
+}
+
+impl FractalKernel for SimdKernel {
+    fn escape<P: KernelPixel>(&self, points: &[DVec2], max_iter: u32, power: f64, out: &mut [P]) {
+        match self.precision {
+            Precision::F32 => self.escape_lanes::<P, f32simd>(points, max_iter, power, out),
+            Precision::F64 => self.escape_lanes::<P, f64simd>(points, max_iter, power, out),
+        }
+    }
+
+    fn resume<P: KernelPixel>(
+        &self,
+        points: &[DVec2],
+        orbits: &mut [DVec2],
+        start_iter: u32,
+        max_iter: u32,
+        power: f64,
+        out: &mut [P],
+    ) {
+        match self.precision {
+            Precision::F32 => {
+                self.resume_lanes::<P, f32simd>(points, orbits, start_iter, max_iter, power, out)
+            }
+            Precision::F64 => {
+                self.resume_lanes::<P, f64simd>(points, orbits, start_iter, max_iter, power, out)
+            }
+        }
+    }
+}
+
+impl SimdKernel {
+    fn escape_lanes<P: KernelPixel, L: Lanes>(
+        &self,
+        points: &[DVec2],
+        max_iter: u32,
+        power: f64,
+        out: &mut [P],
+    ) {
+        assert_eq!(points.len(), out.len());
+
+        for (points, out) in points
+            .chunks(SIMD_LANE_COUNT)
+            .zip(out.chunks_mut(SIMD_LANE_COUNT))
+        {
+            // spare lanes of a narrower last chunk repeat its last point, so they escape with it
+            let point = |lane: usize| points[lane.min(points.len() - 1)];
+            let cx = L::from_fn(|lane| point(lane).x);
+            let cy = L::from_fn(|lane| point(lane).y);
+
+            let values = pixel::<P, L>(max_iter, power, self.kind, self.escape_metric, cx, cy);
+            out.copy_from_slice(&values[..out.len()]);
+        }
+    }
 
-            let values_simd = pixel(max_iterations, cx, cy);
-            let idx = (y * tex_rect.size.x + x * SIMD_LANE_COUNT as u32) as usize;
-            buffer[idx..idx + SIMD_LANE_COUNT].copy_from_slice(values_simd.as_slice());
+    fn resume_lanes<P: KernelPixel, L: Lanes>(
+        &self,
+        points: &[DVec2],
+        orbits: &mut [DVec2],
+        start_iter: u32,
+        max_iter: u32,
+        power: f64,
+        out: &mut [P],
+    ) {
+        assert_eq!(points.len(), out.len());
+        assert_eq!(points.len(), orbits.len());
+
+        for ((points, orbits), out) in points
+            .chunks(SIMD_LANE_COUNT)
+            .zip(orbits.chunks_mut(SIMD_LANE_COUNT))
+            .zip(out.chunks_mut(SIMD_LANE_COUNT))
+        {
+            let lane = |lane: usize| lane.min(points.len() - 1);
+            let cx = L::from_fn(|i| points[lane(i)].x);
+            let cy = L::from_fn(|i| points[lane(i)].y);
+            let zx = L::from_fn(|i| orbits[lane(i)].x);
+            let zy = L::from_fn(|i| orbits[lane(i)].y);
+
+            let (values, zx, zy) = iterate::<P, L>(
+                start_iter,
+                max_iter,
+                power,
+                self.kind,
+                self.escape_metric,
+                cx,
+                cy,
+                zx,
+                zy,
+            );
+            out.copy_from_slice(&values[..out.len()]);
+            let (zx, zy) = (zx.to_f64(), zy.to_f64());
+            for (i, orbit) in orbits.iter_mut().enumerate() {
+                *orbit = DVec2::new(zx[i], zy[i]);
+            }
         }
     }
+}
+
+// std::simd has no atan2, fractional powers go lane by lane
+fn complex_pow_simd<L: Lanes>(zx: L, zy: L, power: f64) -> (L, L) {
+    let (zx, zy) = (zx.to_f64(), zy.to_f64());
+    let z: [DVec2; SIMD_LANE_COUNT] =
+        std::array::from_fn(|lane| complex_pow(DVec2::new(zx[lane], zy[lane]), power));
+
+    (L::from_fn(|lane| z[lane].x), L::from_fn(|lane| z[lane].y))
+}
+
+fn pixel<P: KernelPixel, L: Lanes>(
+    max_iterations: u32,
+    power: f64,
+    kind: FractalKind,
+    escape_metric: EscapeMetric,
+    x: L,
+    y: L,
+) -> CountSimd<P> {
+    let zero = L::splat(0.0);
+    iterate(
+        0,
+        max_iterations,
+        power,
+        kind,
+        escape_metric,
+        x,
+        y,
+        zero,
+        zero,
+    )
+    .0
+}
 
-    if is_test_build() {
-        let elapsed = now.elapsed();
-        println!("Elapsed: {}ms", elapsed.as_millis());
-        println!("Total pixels: {}", tex_rect.size.x * tex_rect.size.y);
+// continues from `z` after `start_iterations` iterations, returns the values and the last `z`,
+// which is only meaningful for lanes still inside the set; `x` and `y` are the points
+#[allow(clippy::too_many_arguments)]
+fn iterate<P: KernelPixel, L: Lanes>(
+    start_iterations: u32,
+    max_iterations: u32,
+    power: f64,
+    kind: FractalKind,
+    escape_metric: EscapeMetric,
+    x: L,
+    y: L,
+    mut zx: L,
+    mut zy: L,
+) -> (CountSimd<P>, L, L) {
+    let (cx, cy) = match kind {
+        FractalKind::Julia(c) => {
+            if start_iterations == 0 {
+                (zx, zy) = (x, y);
+            }
+            (L::splat(c.x), L::splat(c.y))
+        }
+        FractalKind::Mandelbrot | FractalKind::BurningShip => (x, y),
+        FractalKind::Newton => {
+            return newton(start_iterations, max_iterations, x, y, zx, zy);
+        }
+    };
+    let burning_ship = kind == FractalKind::BurningShip;
+
+    let mut cnt = i64simd::splat(start_iterations as i64);
+    let mut escaped = L::no_lanes();
+    let mut escaped_norm = L::splat(0.0);
+
+    let i64_0 = i64simd::splat(0);
+    let i64_1 = i64simd::splat(1);
+
+    for _ in start_iterations..max_iterations {
+        if burning_ship {
+            (zx, zy) = (zx.abs(), zy.abs());
+        }
+        (zx, zy) = if power == MANDELBROT_POWER {
+            (zx * zx - zy * zy + cx, zx * zy + zx * zy + cy)
+        } else {
+            let (px, py) = complex_pow_simd(zx, zy, power);
+            (px + cx, py + cy)
+        };
+        let norm = zx * zx + zy * zy;
+        if P::SMOOTH {
+            escaped_norm = L::select(escaped, escaped_norm, norm);
+        }
+        escaped |= escape_metric.escaped(zx, zy, norm);
+
+        let counted = L::count_mask(escaped);
+        if counted.all() {
+            break;
+        }
 
-        // let target = Duration::from_millis(100);
-        // if elapsed < target {
-        //     tokio::time::sleep(target - elapsed).await;
-        //     thread::sleep(target - elapsed);
-        // }
+        cnt += counted.select(i64_0, i64_1);
     }
 
-    Ok(())
+    let cnt = cnt.to_array();
+    let escaped_norm = escaped_norm.to_f64();
+    let values = std::array::from_fn(|lane| {
+        let iters = cnt[lane] as u32;
+        if iters == max_iterations {
+            P::interior()
+        } else {
+            P::escaped(iters, escaped_norm[lane], power)
+        }
+    });
+
+    (values, zx, zy)
 }
 
-fn pixel(max_iterations: u32, cx: f64simd, cy: f64simd) -> CountSimd {
-    let mut zx = f64simd::splat(0.0);
-    let mut zy = f64simd::splat(0.0);
-    let mut cnt = i64simd::splat(0);
-    let mut escaped = mask64simd::splat(false);
+// Newton's method for z^3 - 1, continues from `z` like `iterate` or starts at the point
+fn newton<P: KernelPixel, L: Lanes>(
+    start_iterations: u32,
+    max_iterations: u32,
+    x: L,
+    y: L,
+    mut zx: L,
+    mut zy: L,
+) -> (CountSimd<P>, L, L) {
+    if start_iterations == 0 {
+        (zx, zy) = (x, y);
+    }
+
+    let mut cnt = i64simd::splat(start_iterations as i64);
+    let mut root = i64simd::splat(0);
+    let mut converged = mask64simd::splat(false);
 
-    let f64_4_0 = f64simd::splat(5.0);
     let i64_0 = i64simd::splat(0);
     let i64_1 = i64simd::splat(1);
+    let tolerance = L::splat(NEWTON_TOLERANCE);
+    let third = L::splat(1.0 / 3.0);
+    let two_thirds = L::splat(2.0 / 3.0);
 
-    for _ in 0..max_iterations {
-        (zx, zy) = (zx * zx - zy * zy + cx, zx * zy + zx * zy + cy);
-        escaped |= (zx * zx + zy * zy).simd_ge(f64_4_0);
+    for _ in start_iterations..max_iterations {
+        // z - (z^3 - 1) / 3z^2 = 2z/3 + 1/3z^2, zero has no step and never converges
+        let (sx, sy) = (zx * zx - zy * zy, zx * zy + zx * zy);
+        let scale = third / (sx * sx + sy * sy);
+        (zx, zy) = (two_thirds * zx + sx * scale, two_thirds * zy - sy * scale);
 
-        if escaped.all() {
+        for (index, target) in NEWTON_ROOTS.iter().enumerate() {
+            let (dx, dy) = (zx - L::splat(target.x), zy - L::splat(target.y));
+            let reached = L::count_mask(tolerance.ge(dx * dx + dy * dy)) & !converged;
+            root = reached.select(i64simd::splat(index as i64), root);
+            converged |= reached;
+        }
+        if converged.all() {
             break;
         }
 
-        cnt += escaped.select(i64_0, i64_1);
+        cnt += converged.select(i64_0, i64_1);
     }
 
-    cnt.as_array().map(|iters| {
-        if iters as u32 == max_iterations {
-            Pixel { r: 0 }
+    let cnt = cnt.to_array();
+    let root = root.to_array();
+    let values = std::array::from_fn(|lane| {
+        let iters = cnt[lane] as u32;
+        if iters == max_iterations {
+            P::interior()
         } else {
-            Pixel {
-                r: 1 + (iters % u16::MAX as i64) as u16,
-            }
+            P::from_value(newton_value(root[lane] as usize, iters))
         }
-    })
+    });
+
+    (values, zx, zy)
 }
 
 #[cfg(test)]
 mod test {
-    use std::sync::Arc;
+    use std::time::Instant;
 
     use glam::UVec2;
 
     use super::*;
+    use crate::kernel::{compute_frame, refine_boundary, CancelToken, KernelOptions, PixelGrid};
+    use crate::math::DRect;
 
     #[test]
     fn draw_mandelbrot() {
         let image_size = 2048;
-        let tile_rect = URect::from_pos_size(UVec2::new(0, 0), UVec2::new(image_size, image_size));
-        let fractal_offset = DVec2::new(0.10486747136388758, 0.9244368813525663);
-        let fractal_scale = 32.0;
-        let max_iterations = 1024;
-        let cancel_token = Arc::new(AtomicBool::new(false));
+        let frame = DRect::from_center_size(
+            DVec2::new(-0.10486747136388758, -0.9244368813525663),
+            DVec2::splat(1.0 / 32.0),
+        );
+        let grid = PixelGrid::new(frame, UVec2::splat(image_size));
+        let options = KernelOptions {
+            max_iter: 1024,
+            power: MANDELBROT_POWER,
+        };
+        let cancel_token = CancelToken::new();
         let mut buffer = vec![Pixel::default(); (image_size * image_size) as usize];
 
         let new = Instant::now();
         let retry = 5;
 
         for _ in 0..retry {
-            mandelbrot_simd(
-                image_size,
-                tile_rect,
-                fractal_offset,
-                fractal_scale,
-                max_iterations,
+            compute_frame(
+                &SimdKernel::default(),
+                grid,
+                grid.all(),
+                options,
                 cancel_token.clone(),
                 &mut buffer,
             )
@@ -175,4 +646,360 @@ mod test {
         }
         image.save("test_output/mandelbrot.png").unwrap();
     }
+
+    #[test]
+    fn refine_boundary_pixels() {
+        let image_size = 256;
+        // seahorse valley
+        let frame = DRect::from_center_size(DVec2::new(-0.745, 0.1), DVec2::splat(1.0 / 20.0));
+        let grid = PixelGrid::new(frame, UVec2::splat(image_size));
+        let options = KernelOptions {
+            max_iter: 1024,
+            power: MANDELBROT_POWER,
+        };
+        let cancel_token = CancelToken::new();
+        let mut buffer = vec![Pixel::default(); (image_size * image_size) as usize];
+
+        compute_frame(
+            &SimdKernel::default(),
+            grid,
+            grid.all(),
+            options,
+            cancel_token.clone(),
+            &mut buffer,
+        )
+        .unwrap();
+        let before = buffer.clone();
+
+        let refined = refine_boundary(
+            &SimdKernel::default(),
+            grid,
+            grid.all(),
+            options,
+            8,
+            cancel_token,
+            &mut buffer,
+        )
+        .unwrap();
+
+        let total = image_size * image_size;
+        let border = 4 * (image_size - 1);
+        println!(
+            "Refined pixels: {} of {} ({:.1}%)",
+            refined,
+            total,
+            100.0 * refined as f64 / total as f64
+        );
+        assert!(refined > border);
+        assert!(refined < total / 2);
+
+        for (name, buffer) in [("before", &before), ("after", &buffer)] {
+            let mut image = image::ImageBuffer::new(image_size, image_size);
+            for y in 0..image_size {
+                for x in 0..image_size {
+                    let pixel = (buffer[(y * image_size + x) as usize].r % 256) as u8;
+                    image.put_pixel(x, y, image::Rgb([pixel, pixel, pixel]));
+                }
+            }
+            image
+                .save(format!("test_output/refine_boundary_{}.png", name))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn smooth_gradient_has_no_steps() {
+        // a horizontal line far enough from the set that every pixel escapes
+        let width = 256;
+        let grid = PixelGrid::new(
+            DRect::from_pos_size(DVec2::new(-1.5, 1.0), DVec2::new(1.0, 1.0 / 256.0)),
+            UVec2::new(width, 1),
+        );
+        let options = KernelOptions {
+            max_iter: 1024,
+            power: MANDELBROT_POWER,
+        };
+        let cancel_token = CancelToken::new();
+
+        let mut banded = vec![Pixel::default(); width as usize];
+        compute_frame(
+            &SimdKernel::default(),
+            grid,
+            grid.all(),
+            options,
+            cancel_token.clone(),
+            &mut banded,
+        )
+        .unwrap();
+
+        let mut smooth = vec![0.0f32; width as usize];
+        compute_frame(
+            &SimdKernel::default(),
+            grid,
+            grid.all(),
+            options,
+            cancel_token,
+            &mut smooth,
+        )
+        .unwrap();
+
+        let banded_steps = banded.windows(2).filter(|w| w[0].r == w[1].r).count();
+        assert!(banded_steps > width as usize / 2);
+
+        assert!(smooth.iter().all(|&value| value >= 1.0));
+        for w in smooth.windows(2) {
+            assert_ne!(w[0], w[1]);
+            // neighbor pixels never jump by a whole band
+            assert!((w[0] - w[1]).abs() < 1.0, "{} -> {}", w[0], w[1]);
+        }
+    }
+
+    #[test]
+    fn smooth_multibrot_has_no_steps() {
+        let width = 256;
+        let grid = PixelGrid::new(
+            DRect::from_pos_size(DVec2::new(-1.5, 1.0), DVec2::new(1.0, 1.0 / 256.0)),
+            UVec2::new(width, 1),
+        );
+        let cancel_token = CancelToken::new();
+
+        for power in [3.0, 4.5] {
+            let mut smooth = vec![0.0f32; width as usize];
+            compute_frame(
+                &SimdKernel::default(),
+                grid,
+                grid.all(),
+                KernelOptions {
+                    max_iter: 1024,
+                    power,
+                },
+                cancel_token.clone(),
+                &mut smooth,
+            )
+            .unwrap();
+
+            assert!(smooth.iter().all(|&value| value >= 1.0));
+            // crossing into the next iteration count is no larger a step than any other
+            let steps: Vec<f32> = smooth.windows(2).map(|w| (w[1] - w[0]).abs()).collect();
+            let mean = steps.iter().sum::<f32>() / steps.len() as f32;
+            let max = steps.iter().copied().fold(0.0, f32::max);
+            assert!(max < 4.0 * mean, "{}: step {} mean {}", power, max, mean);
+        }
+    }
+
+    #[test]
+    fn escape_metrics_reshape_the_bands() {
+        let size = UVec2::new(96, 64);
+        let frame = DRect::from_center_size(DVec2::new(-0.74, 0.0), DVec2::new(3.75, 2.5));
+        let cancel_token = CancelToken::new();
+        let counts = |escape_metric, frame, size: UVec2| {
+            let mut buffer = vec![Pixel::default(); size.element_product() as usize];
+            let grid = PixelGrid::new(frame, size);
+            compute_frame(
+                &SimdKernel {
+                    escape_metric,
+                    precision: Precision::F64,
+                    ..SimdKernel::default()
+                },
+                grid,
+                grid.all(),
+                KernelOptions {
+                    max_iter: 1024,
+                    power: MANDELBROT_POWER,
+                },
+                cancel_token.clone(),
+                &mut buffer,
+            )
+            .unwrap();
+            buffer.iter().map(|pixel| pixel.r).collect::<Vec<_>>()
+        };
+
+        let euclidean = counts(EscapeMetric::Euclidean, frame, size);
+        for escape_metric in EscapeMetric::ALL.into_iter().skip(1) {
+            let values = counts(escape_metric, frame, size);
+            // the same set, escaping no earlier, and not everywhere at the same time
+            assert!(euclidean
+                .iter()
+                .zip(&values)
+                .all(|(&e, &v)| (e == 0) == (v == 0) && v >= e));
+            assert_ne!(values, euclidean, "{:?}", escape_metric);
+        }
+
+        // orbits on the real axis stay there, where the square touches the circle
+        // and the hyperbolas never get to
+        let axis = DRect::from_pos_size(DVec2::new(0.3, 0.0), DVec2::new(1.0, 1e-9));
+        let axis_size = UVec2::new(64, 1);
+        let euclidean = counts(EscapeMetric::Euclidean, axis, axis_size);
+        assert_eq!(counts(EscapeMetric::Chebyshev, axis, axis_size), euclidean);
+        let product = counts(EscapeMetric::Product, axis, axis_size);
+        assert!(euclidean.iter().zip(&product).all(|(&e, &p)| p > e));
+    }
+
+    #[test]
+    fn kinds_iterate_their_own_formula() {
+        let values = |kind, points: &[DVec2]| {
+            let mut out = vec![0.0f32; points.len()];
+            SimdKernel {
+                kind,
+                ..SimdKernel::default()
+            }
+            .escape(points, 200, MANDELBROT_POWER, &mut out);
+            out
+        };
+        // one point at a time with plain f64
+        let reference = |kind, point: DVec2| {
+            let (mut z, c) = match kind {
+                FractalKind::Julia(c) => (point, c),
+                _ => (DVec2::ZERO, point),
+            };
+            for iteration in 0..200 {
+                if kind == FractalKind::BurningShip {
+                    z = z.abs();
+                }
+                z = DVec2::new(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+                if z.length_squared() >= ESCAPE_RADIUS * ESCAPE_RADIUS {
+                    return f32::escaped(iteration, z.length_squared(), MANDELBROT_POWER);
+                }
+            }
+            0.0
+        };
+
+        let points: Vec<DVec2> = (0..40)
+            .map(|i| DVec2::new(-2.0 + 0.1 * i as f64, 0.6 - 0.035 * i as f64))
+            .collect();
+        for kind in [
+            FractalKind::Julia(DEFAULT_JULIA_C),
+            FractalKind::BurningShip,
+        ] {
+            let actual = values(kind, &points);
+            for (point, value) in points.iter().zip(actual) {
+                let expected = reference(kind, *point);
+                assert!(
+                    (value - expected).abs() <= 1e-4 * expected.max(1.0),
+                    "{:?} at {}: {} != {}",
+                    kind,
+                    point,
+                    value,
+                    expected
+                );
+            }
+            assert_ne!(
+                values(kind, &points),
+                values(FractalKind::Mandelbrot, &points)
+            );
+        }
+
+        // the Julia set of 0 is the unit disk, the Burning Ship is the Mandelbrot set
+        // on the real axis
+        let julia = values(
+            FractalKind::Julia(DVec2::ZERO),
+            &[DVec2::new(0.9, 0.3), DVec2::new(0.9, 0.5)],
+        );
+        assert_eq!(julia[0], 0.0);
+        assert!(julia[1] > 0.0);
+        let axis: Vec<DVec2> = (0..32)
+            .map(|i| DVec2::new(-2.2 + 0.1 * i as f64, 0.0))
+            .collect();
+        assert_eq!(
+            values(FractalKind::BurningShip, &axis),
+            values(FractalKind::Mandelbrot, &axis)
+        );
+
+        assert_eq!(
+            FractalKind::Mandelbrot.next().next().next().next(),
+            FractalKind::Mandelbrot
+        );
+    }
+
+    #[test]
+    fn newton_points_reach_their_roots() {
+        fn values<P: KernelPixel>(precision: Precision, points: &[DVec2]) -> Vec<f64> {
+            let mut out = vec![P::default(); points.len()];
+            SimdKernel {
+                kind: FractalKind::Newton,
+                precision,
+                ..SimdKernel::default()
+            }
+            .escape(points, 100, MANDELBROT_POWER, &mut out);
+            out.iter().map(P::value).collect()
+        }
+
+        // each next to its root, one far out past the first, and zero, which has no step
+        let points = [
+            DVec2::new(2.0, 0.0),
+            DVec2::new(-1.0, 1.5),
+            DVec2::new(-1.0, -1.5),
+            DVec2::new(100.0, 0.0),
+            DVec2::ZERO,
+        ];
+        for precision in [Precision::F32, Precision::F64] {
+            for values in [
+                values::<Pixel>(precision, &points),
+                values::<f32>(precision, &points),
+            ] {
+                let roots: Vec<Option<usize>> = values
+                    .iter()
+                    .map(|&value| newton_root(value).map(|(root, _)| root))
+                    .collect();
+                assert_eq!(roots, [Some(0), Some(1), Some(2), Some(0), None]);
+
+                let steps = |value| newton_root(value).unwrap().1;
+                assert!(steps(values[3]) > steps(values[0]), "{:?}", values);
+            }
+        }
+
+        assert_eq!(newton_root(newton_value(2, 17)), Some((2, 17)));
+        assert_eq!(newton_root(0.0), None);
+    }
+
+    #[test]
+    fn precision_follows_the_pixel_size() {
+        // the overview of a 1080 pixel high window, and seahorse valley at 1e10
+        assert_eq!(Precision::for_pixel_size(2.5 / 1080.0), Precision::F32);
+        assert_eq!(Precision::for_pixel_size(2.5e-10 / 1080.0), Precision::F64);
+    }
+
+    #[test]
+    fn f32_lanes_agree_at_the_overview() {
+        let grid = PixelGrid::new(
+            DRect::from_center_size(DVec2::new(-0.74, 0.0), DVec2::new(3.75, 2.5)),
+            UVec2::new(96, 64),
+        );
+        assert_eq!(
+            Precision::for_pixel_size(grid.pixel_size().max_element()),
+            Precision::F32
+        );
+        let options = KernelOptions {
+            max_iter: 1000,
+            power: MANDELBROT_POWER,
+        };
+        let counts = |precision| {
+            let mut buffer = vec![Pixel::default(); grid.size.element_product() as usize];
+            let kernel = SimdKernel {
+                escape_metric: EscapeMetric::Euclidean,
+                precision,
+                ..SimdKernel::default()
+            };
+            compute_frame(
+                &kernel,
+                grid,
+                grid.all(),
+                options,
+                CancelToken::new(),
+                &mut buffer,
+            )
+            .unwrap();
+            buffer.iter().map(|pixel| pixel.r).collect::<Vec<_>>()
+        };
+
+        let expected = counts(Precision::F64);
+        let actual = counts(Precision::F32);
+        // orbits close to the boundary amplify any rounding, only a few of them end elsewhere
+        let mismatches = expected.iter().zip(&actual).filter(|(e, a)| e != a).count();
+        assert!(
+            mismatches * 100 < expected.len(),
+            "{} pixels differ",
+            mismatches
+        );
+    }
 }
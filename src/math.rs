@@ -92,6 +92,20 @@ impl DRect {
     pub fn upper_right(&self) -> DVec2 {
         self.pos + self.size
     }
+    /// The axis-aligned rect holding this one turned by `angle` radians about its center.
+    pub fn rotated_bounds(&self, angle: f64) -> Self {
+        if angle == 0.0 {
+            return *self;
+        }
+
+        let (sin, cos) = angle.sin_cos();
+        let size = DVec2::new(
+            self.size.x * cos.abs() + self.size.y * sin.abs(),
+            self.size.x * sin.abs() + self.size.y * cos.abs(),
+        );
+
+        Self::from_center_size(self.center(), size)
+    }
 }
 
 impl std::fmt::Debug for DRect {
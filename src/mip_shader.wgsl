@@ -0,0 +1,80 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+
+// a single triangle covering the level
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var result: VertexOutput;
+    result.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+
+    return result;
+}
+
+
+// the texel without data, left out
+override NO_DATA: u32;
+// texels hold f32 bits
+override SMOOTH: bool;
+// escape counts are averaged, otherwise the first texel with data is kept
+override AVERAGE: bool;
+
+const INTERIOR_TEXEL: u32 = 0u;
+
+@group(0)
+@binding(0)
+var source: texture_2d<u32>;
+
+fn texel_value(texel: u32) -> f32 {
+    if (SMOOTH) {
+        return bitcast<f32>(texel);
+    }
+    return f32(texel);
+}
+
+fn value_texel(value: f32) -> u32 {
+    if (SMOOTH) {
+        return bitcast<u32>(value);
+    }
+    return u32(round(value));
+}
+
+// The 2x2 texels of the level above. Without data only if none of them has any, inside the
+// set if most of those with data are; otherwise the mean escape count of the escaped ones.
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) u32 {
+    let last = textureDimensions(source) - 1u;
+    let origin = vec2<u32>(vertex.position.xy) * 2u;
+
+    var first = NO_DATA;
+    var interior = 0u;
+    var escaped = 0u;
+    var sum = 0.0;
+    for (var i = 0u; i < 4u; i++) {
+        let coord = min(origin + vec2<u32>(i % 2u, i / 2u), last);
+        let texel = textureLoad(source, coord, 0).r;
+        if (texel == NO_DATA) {
+            continue;
+        }
+        if (first == NO_DATA) {
+            first = texel;
+        }
+        if (texel == INTERIOR_TEXEL) {
+            interior += 1u;
+        } else {
+            escaped += 1u;
+            sum += texel_value(texel);
+        }
+    }
+
+    if (!AVERAGE || first == NO_DATA) {
+        return first;
+    }
+    if (interior > escaped) {
+        return INTERIOR_TEXEL;
+    }
+    return value_texel(sum / f32(escaped));
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use glam::UVec2;
+
+/// Mip levels of a page texture, including the full resolution one; enough to zoom out by 8x
+/// before the pages are recomputed at the new scale.
+pub const MIP_LEVEL_COUNT: u32 = 4;
+
+/// How the texels of a level are reduced to the next one, they hold escape counts rather than
+/// colors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MipFormat {
+    pub format: wgpu::TextureFormat,
+    /// The texel that holds no data yet, left out of the reduction.
+    pub no_data: u32,
+    /// Texels hold f32 bits rather than whole counts.
+    pub smooth: bool,
+    /// Escape counts are averaged, otherwise the first texel with data is kept; for values
+    /// that don't mix, like the roots of the Newton fractal.
+    pub average: bool,
+}
+
+/// One level of a texture: a view to render it and a bind group reading it for the next one.
+#[derive(Debug)]
+pub struct MipLevel {
+    pub view: wgpu::TextureView,
+    source: wgpu::BindGroup,
+}
+
+/// Reduces every level of a texture to the next one with a 2x2 box filter. Point sampling a
+/// minified texture shimmers wherever filaments are thinner than a texel, the screen pass reads
+/// the levels matching the minification instead.
+#[derive(Debug)]
+pub struct MipGenerator {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    shader: wgpu::ShaderModule,
+    // built for the format of the current textures
+    pipeline: Option<(MipFormat, wgpu::RenderPipeline)>,
+}
+
+/// Levels a texture of `size` gets, up to `MIP_LEVEL_COUNT`; the last one is at least a texel.
+pub fn mip_level_count(size: UVec2) -> u32 {
+    MIP_LEVEL_COUNT.min(size.min_element().max(1).ilog2() + 1)
+}
+
+impl MipGenerator {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Uint,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+            label: None,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+            label: None,
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(include_str!("mip_shader.wgsl").into()),
+        });
+
+        Self {
+            bind_group_layout,
+            pipeline_layout,
+            shader,
+            pipeline: None,
+        }
+    }
+
+    /// The levels of `texture` to pass to `generate`, the first is the full resolution one.
+    pub fn levels(&self, device: &wgpu::Device, texture: &wgpu::Texture) -> Vec<MipLevel> {
+        (0..texture.mip_level_count())
+            .map(|level| {
+                let view = texture.create_view(&wgpu::TextureViewDescriptor {
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                });
+                let source = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &self.bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    }],
+                    label: None,
+                });
+
+                MipLevel { view, source }
+            })
+            .collect()
+    }
+
+    /// Records the passes writing every level after the first from the one before it.
+    pub fn generate(
+        &mut self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        format: MipFormat,
+        levels: &[MipLevel],
+    ) {
+        if self
+            .pipeline
+            .as_ref()
+            .is_none_or(|&(current, _)| current != format)
+        {
+            self.pipeline = Some((format, self.create_pipeline(device, format)));
+        }
+        let pipeline = &self.pipeline.as_ref().unwrap().1;
+
+        for pair in levels.windows(2) {
+            let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &pair[1].view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &pair[0].source, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+
+    fn create_pipeline(&self, device: &wgpu::Device, format: MipFormat) -> wgpu::RenderPipeline {
+        // module-wide, every stage needs them
+        let constants = HashMap::from([
+            ("NO_DATA".to_string(), format.no_data as f64),
+            ("SMOOTH".to_string(), format.smooth as u32 as f64),
+            ("AVERAGE".to_string(), format.average as u32 as f64),
+        ]);
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&self.pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &self.shader,
+                entry_point: Some("vs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &constants,
+                    ..Default::default()
+                },
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &self.shader,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &constants,
+                    ..Default::default()
+                },
+                targets: &[Some(format.format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_stop_at_a_texel() {
+        assert_eq!(mip_level_count(UVec2::new(1920, 1024)), MIP_LEVEL_COUNT);
+        assert_eq!(mip_level_count(UVec2::new(256, 4)), 3);
+        assert_eq!(mip_level_count(UVec2::ONE), 1);
+    }
+}
@@ -0,0 +1,234 @@
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+/// Texels of the palette texture, the sampler interpolates between neighbors.
+pub const PALETTE_SIZE: usize = 256;
+
+/// Colors the screen shader picks by normalized escape count, one rgba texel per entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    texels: Vec<[u8; 4]>,
+}
+
+/// How escape counts advance along the palette, independent of its colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteMapping {
+    /// A pass through the palette every 768 iterations.
+    #[default]
+    Linear,
+    /// Every pass takes longer than the last, the first one 768 iterations.
+    Sqrt,
+    /// Passes take exponentially longer, low counts far from the set get most of the colors.
+    Log,
+}
+
+impl PaletteMapping {
+    pub const ALL: [PaletteMapping; 3] = [
+        PaletteMapping::Linear,
+        PaletteMapping::Sqrt,
+        PaletteMapping::Log,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|&mapping| mapping == self)
+            .unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PaletteFile {
+    stops: Vec<Stop>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Stop {
+    position: f32,
+    color: [u8; 3],
+}
+
+impl Palette {
+    /// The palette that ships with the app.
+    pub fn builtin() -> Self {
+        let image = image::load_from_memory(include_bytes!("../palette.png"))
+            .expect("Built-in palette is a valid image")
+            .into_rgba8();
+        assert_eq!(image.dimensions(), (PALETTE_SIZE as u32, 1));
+
+        Self {
+            texels: image.pixels().map(|pixel| pixel.0).collect(),
+        }
+    }
+
+    /// Reads color stops from JSON, positions go from 0 to 1 and colors are sRGB bytes:
+    /// `{"stops": [{"position": 0.0, "color": [0, 7, 100]}, {"position": 1.0, "color": [255, 170, 0]}]}`
+    /// A `.png` file is a strip like the built-in palette, its top row from left to right.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let is_image = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+        if is_image {
+            let image = image::open(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .into_rgba8();
+            if image.width() == 0 || image.height() == 0 {
+                bail!("The palette {} is empty", path.display());
+            }
+            return Ok(Self::from_strip(&image));
+        }
+
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Self::from_json(&text).with_context(|| format!("Invalid palette {}", path.display()))
+    }
+
+    /// The palette at `path` if there is one and it is valid, the built-in palette otherwise.
+    pub fn load(path: Option<&Path>) -> Self {
+        let Some(path) = path else {
+            return Self::builtin();
+        };
+
+        Self::from_file(path).unwrap_or_else(|error| {
+            tracing::warn!("{:#}, using the built-in palette", error);
+            Self::builtin()
+        })
+    }
+
+    pub fn texels(&self) -> &[[u8; 4]] {
+        &self.texels
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.texels)
+    }
+
+    // strips of any width are resampled to the nearest pixel, alpha is ignored like for stops
+    fn from_strip(image: &image::RgbaImage) -> Self {
+        let width = image.width();
+        let texels = (0..PALETTE_SIZE)
+            .map(|index| {
+                let x = (index as u64 * width as u64 / PALETTE_SIZE as u64) as u32;
+                let [r, g, b, _] = image.get_pixel(x, 0).0;
+                [r, g, b, u8::MAX]
+            })
+            .collect();
+
+        Self { texels }
+    }
+
+    fn from_json(text: &str) -> anyhow::Result<Self> {
+        let file: PaletteFile = serde_json::from_str(text)?;
+
+        Self::from_stops(file.stops)
+    }
+
+    // stops at the same position make a hard edge, the later one wins from there on
+    fn from_stops(mut stops: Vec<Stop>) -> anyhow::Result<Self> {
+        if stops.is_empty() {
+            bail!("A palette needs at least one stop");
+        }
+        if let Some(stop) = stops
+            .iter()
+            .find(|stop| !(0.0..=1.0).contains(&stop.position))
+        {
+            bail!("Stop position {} is outside of 0 to 1", stop.position);
+        }
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+
+        let texels = (0..PALETTE_SIZE)
+            .map(|index| {
+                let position = index as f32 / (PALETTE_SIZE - 1) as f32;
+                let next = stops.partition_point(|stop| stop.position <= position);
+
+                let color = match (
+                    next.checked_sub(1).map(|i| stops[i]),
+                    stops.get(next).copied(),
+                ) {
+                    (Some(before), Some(after)) => {
+                        let t = (position - before.position) / (after.position - before.position);
+                        std::array::from_fn(|channel| {
+                            let (a, b) =
+                                (before.color[channel] as f32, after.color[channel] as f32);
+                            (a + (b - a) * t).round() as u8
+                        })
+                    }
+                    (Some(stop), None) | (None, Some(stop)) => stop.color,
+                    (None, None) => unreachable!(),
+                };
+
+                [color[0], color[1], color[2], u8::MAX]
+            })
+            .collect();
+
+        Ok(Self { texels })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_are_interpolated() {
+        let palette = Palette::from_json(
+            r#"{"stops": [
+                {"position": 1.0, "color": [255, 0, 100]},
+                {"position": 0.2, "color": [0, 255, 100]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let texels = palette.texels();
+        assert_eq!(texels.len(), PALETTE_SIZE);
+        // before the first stop its color holds
+        assert_eq!(texels[0], [0, 255, 100, 255]);
+        assert_eq!(texels[51], [0, 255, 100, 255]);
+        // halfway between the stops
+        let [r, g, b, _] = texels[153];
+        assert!(r.abs_diff(128) <= 1 && g.abs_diff(127) <= 1 && b == 100);
+        assert_eq!(texels[255], [255, 0, 100, 255]);
+    }
+
+    #[test]
+    fn invalid_palettes_are_rejected() {
+        assert!(Palette::from_json(r#"{"stops": []}"#).is_err());
+        assert!(
+            Palette::from_json(r#"{"stops": [{"position": 1.5, "color": [0, 0, 0]}]}"#).is_err()
+        );
+        assert!(
+            Palette::from_json(r#"{"stops": [{"position": 0.5, "color": [0, 0, 300]}]}"#).is_err()
+        );
+        assert!(Palette::from_json("stops").is_err());
+
+        let missing = Palette::load(Some(Path::new("missing_palette.json")));
+        assert_eq!(missing, Palette::builtin());
+    }
+
+    #[test]
+    fn strips_are_resampled() {
+        let mut image = image::RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, image::Rgba([10, 20, 30, 0]));
+        image.put_pixel(1, 0, image::Rgba([200, 100, 0, 255]));
+
+        let texels = Palette::from_strip(&image).texels;
+        assert_eq!(texels[0], [10, 20, 30, 255]);
+        assert_eq!(texels[PALETTE_SIZE / 2 - 1], [10, 20, 30, 255]);
+        assert_eq!(texels[PALETTE_SIZE / 2], [200, 100, 0, 255]);
+        assert_eq!(texels[PALETTE_SIZE - 1], [200, 100, 0, 255]);
+
+        let builtin = Palette::from_file(Path::new("palette.png")).unwrap();
+        assert_eq!(builtin, Palette::builtin());
+    }
+
+    #[test]
+    fn example_palette_loads() {
+        let palette = Palette::from_file(Path::new("palettes/sunset.json")).unwrap();
+        assert_ne!(palette, Palette::builtin());
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::bail;
+use glam::DVec2;
+
+use crate::math::DRect;
+use crate::tiled_fractal_app::{DEFAULT_CENTER, DEFAULT_FRAME_HEIGHT};
+
+/// A well-known spot of the set, L cycles through them and `--preset` starts at one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Preset {
+    /// Identifier on the command line.
+    pub name: &'static str,
+    pub title: &'static str,
+    pub center: DVec2,
+    /// Vertical extent of the frame, the horizontal one follows the window aspect.
+    pub height: f64,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "overview",
+        title: "Overview",
+        center: DEFAULT_CENTER,
+        height: DEFAULT_FRAME_HEIGHT,
+    },
+    Preset {
+        name: "seahorse",
+        title: "Seahorse Valley",
+        center: DVec2::new(-0.7453, 0.1127),
+        height: 6.5e-3,
+    },
+    Preset {
+        name: "elephant",
+        title: "Elephant Valley",
+        center: DVec2::new(0.2925, 0.0149),
+        height: 2.0e-2,
+    },
+    Preset {
+        name: "triple-spiral",
+        title: "Triple Spiral Valley",
+        center: DVec2::new(-0.0886, 0.655),
+        height: 3.6e-3,
+    },
+    Preset {
+        name: "scepter",
+        title: "Scepter Valley",
+        center: DVec2::new(-1.36, 0.0),
+        height: 0.12,
+    },
+    Preset {
+        name: "minibrot",
+        title: "Mini Mandelbrot",
+        center: DVec2::new(-1.7549, 0.0),
+        height: 4.5e-2,
+    },
+];
+
+impl Preset {
+    /// The preset called `name`, the error lists the valid names.
+    pub fn find(name: &str) -> anyhow::Result<&'static Preset> {
+        let Some(preset) = PRESETS.iter().find(|preset| preset.name == name) else {
+            let names: Vec<&str> = PRESETS.iter().map(|preset| preset.name).collect();
+            bail!("Unknown preset {}, available: {}", name, names.join(", "));
+        };
+
+        Ok(preset)
+    }
+
+    pub fn frame_rect(&self, aspect: DVec2) -> DRect {
+        DRect::from_center_size(self.center, aspect * self.height)
+    }
+
+    /// Magnification relative to the overview, as `--zoom` takes it.
+    pub fn zoom(&self) -> f64 {
+        DEFAULT_FRAME_HEIGHT / self.height
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_found_by_name() {
+        assert_eq!(Preset::find("seahorse").unwrap().title, "Seahorse Valley");
+
+        let error = Preset::find("seahorses").unwrap_err().to_string();
+        assert!(error.contains("seahorses"));
+        assert!(PRESETS.iter().all(|preset| error.contains(preset.name)));
+
+        // names are unique and the frames valid
+        for (index, preset) in PRESETS.iter().enumerate() {
+            assert!(PRESETS[..index]
+                .iter()
+                .all(|other| other.name != preset.name));
+            assert!(preset.height > 0.0 && preset.zoom() >= 1.0);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use glam::UVec2;
+
+use crate::RenderContext;
+
+// logical pixels, multiplied by the scale factor of the monitor
+const BAR_HEIGHT: f64 = 3.0;
+
+/// A thin bar along the bottom edge of the window, filled as far as the share of the visible
+/// tiles computed so far.
+#[derive(Debug)]
+pub struct ProgressBar {
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl ProgressBar {
+    /// `format` is the view format of the surface.
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+            label: None,
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(include_str!("progress_bar_shader.wgsl").into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self { pipeline }
+    }
+
+    /// Draws the bar `done` of `total` across over `render_info.view`, which is `window_size`
+    /// pixels, nothing when there is nothing left to do.
+    pub fn render(
+        &self,
+        render_info: &RenderContext,
+        window_size: UVec2,
+        scale_factor: f64,
+        (done, total): (usize, usize),
+    ) {
+        if done >= total || window_size.min_element() == 0 {
+            return;
+        }
+
+        let height = ((BAR_HEIGHT * scale_factor).round() as u32).clamp(1, window_size.y);
+        let top = window_size.y - height;
+        let width = (window_size.x as u64 * done as u64 / total as u64) as u32;
+
+        let mut command_encoder = render_info
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: render_info.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_scissor_rect(0, top, window_size.x, height);
+            render_pass.draw(0..3, 0..1);
+            if width > 0 {
+                render_pass.set_scissor_rect(0, top, width, height);
+                render_pass.draw(0..3, 1..2);
+            }
+        }
+
+        render_info.queue.submit(Some(command_encoder.finish()));
+    }
+}
@@ -0,0 +1,34 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // 0 for the track along the whole width, 1 for the part done
+    @location(0) @interpolate(flat) part: u32,
+};
+
+
+// a single triangle covering the screen, the scissor rect cuts the bar out of it
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+    @builtin(instance_index) part: u32,
+) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var result: VertexOutput;
+    result.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    result.part = part;
+
+    return result;
+}
+
+
+// light enough to show over dark regions, translucent enough not to hide the bright ones
+const DONE_COLOR: vec4<f32> = vec4<f32>(0.9, 0.9, 0.9, 0.6);
+const TRACK_COLOR: vec4<f32> = vec4<f32>(0.0, 0.0, 0.0, 0.4);
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    if (vertex.part == 0u) {
+        return TRACK_COLOR;
+    }
+    return DONE_COLOR;
+}
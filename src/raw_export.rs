@@ -0,0 +1,320 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::anyhow;
+use glam::UVec2;
+use web_time::Instant;
+
+use crate::kernel::{
+    compute_frame, refine_boundary, FractalKernel, KernelOptions, NeverCancelled, PixelGrid,
+};
+use crate::mandelbrot_simd::{
+    EscapeMetric, FractalKind, KernelPixel, Pixel, Precision, SimdKernel,
+};
+use crate::math::{DRect, URect};
+
+const VALUE_ENCODING: &str = "0 = inside the set, otherwise 1 + escape count";
+const NEWTON_VALUE_ENCODING: &str =
+    "0 = converged to no root, otherwise 1 + root index + 3 * steps to converge";
+
+/// Writes the raw escape counts of `fractal_rect` rendered at `size`.
+/// `.exr` files get continuous f32 values, anything else a 16-bit grayscale PNG
+/// with integer counts. The first image row is the top of `fractal_rect`.
+#[allow(clippy::too_many_arguments)]
+pub fn export_raw(
+    path: &Path,
+    fractal_rect: DRect,
+    size: UVec2,
+    max_iter: u32,
+    kind: FractalKind,
+    power: f64,
+    escape_metric: EscapeMetric,
+    aa_threshold: Option<u16>,
+) -> anyhow::Result<()> {
+    assert!(size.x > 0 && size.y > 0);
+    // exports are for analysis, they keep full precision at any zoom
+    let kernel = SimdKernel {
+        kind,
+        escape_metric,
+        precision: Precision::F64,
+    };
+
+    let metadata = [
+        (
+            "frame_pos",
+            format!("{} {}", fractal_rect.pos.x, fractal_rect.pos.y),
+        ),
+        (
+            "frame_size",
+            format!("{} {}", fractal_rect.size.x, fractal_rect.size.y),
+        ),
+        ("max_iter", max_iter.to_string()),
+        ("kind", format!("{:?}", kind)),
+        ("power", power.to_string()),
+        ("escape_metric", format!("{:?}", escape_metric)),
+        (
+            "encoding",
+            if kind.colors_by_root() {
+                NEWTON_VALUE_ENCODING
+            } else {
+                VALUE_ENCODING
+            }
+            .to_string(),
+        ),
+    ];
+
+    let grid = PixelGrid::new(fractal_rect, size);
+    let options = KernelOptions { max_iter, power };
+    let aa_threshold = aa_threshold.filter(|_| !kind.colors_by_root());
+    let is_exr = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+    if is_exr {
+        let values = compute::<_, f32>(&kernel, grid, options, aa_threshold)?;
+        write_exr(path, size, values, &metadata)
+    } else {
+        let values = compute::<_, Pixel>(&kernel, grid, options, aa_threshold)?;
+        write_png16(path, size, &values, &metadata)
+    }
+}
+
+/// `<prefix>_<unix time>.<extension>` in `dir`, with a counter appended if that file
+/// already exists, so exports never overwrite each other.
+pub fn unique_path(dir: &Path, prefix: &str, extension: &str) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let mut path = dir.join(format!("{}_{}.{}", prefix, timestamp, extension));
+    let mut counter = 1;
+    while path.exists() {
+        path = dir.join(format!(
+            "{}_{}_{}.{}",
+            prefix, timestamp, counter, extension
+        ));
+        counter += 1;
+    }
+
+    path
+}
+
+/// Computes the whole `grid` in horizontal bands on all cores, the first row of the result is
+/// the top one. The bands sample the points of the grid, the values don't depend on the core count.
+pub fn compute<K: FractalKernel + Sync, P: KernelPixel + Send>(
+    kernel: &K,
+    grid: PixelGrid,
+    options: KernelOptions,
+    aa_threshold: Option<u16>,
+) -> anyhow::Result<Vec<P>> {
+    let start = Instant::now();
+    let size = grid.size;
+    let mut buffer = vec![P::default(); size.element_product() as usize];
+
+    let band_count = (num_cpus::get_physical() as u32).clamp(1, size.y);
+    let band_height = size.y.div_ceil(band_count);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = buffer
+            .chunks_mut((band_height * size.x) as usize)
+            .enumerate()
+            .map(|(index, band)| {
+                let pixels = URect::from_pos_size(
+                    UVec2::new(0, index as u32 * band_height),
+                    UVec2::new(size.x, band.len() as u32 / size.x),
+                );
+
+                scope.spawn(move || -> anyhow::Result<()> {
+                    compute_frame(kernel, grid, pixels, options, NeverCancelled, band)?;
+                    if let Some(threshold) = aa_threshold {
+                        refine_boundary(
+                            kernel,
+                            grid,
+                            pixels,
+                            options,
+                            threshold,
+                            NeverCancelled,
+                            band,
+                        )?;
+                    }
+
+                    Ok(())
+                })
+            })
+            .collect();
+
+        handles.into_iter().try_for_each(|handle| {
+            handle
+                .join()
+                .map_err(|_| anyhow!("Export worker panicked"))?
+        })
+    })?;
+
+    // kernel rows go upwards, image rows downwards
+    let width = size.x as usize;
+    let flipped = buffer.chunks(width).rev().flatten().copied().collect();
+    tracing::debug!(
+        elapsed_ms = start.elapsed().as_millis() as u64,
+        width = size.x,
+        height = size.y,
+        bands = band_count,
+        "Export computed"
+    );
+
+    Ok(flipped)
+}
+
+fn write_png16(
+    path: &Path,
+    size: UVec2,
+    values: &[Pixel],
+    metadata: &[(&str, String)],
+) -> anyhow::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+
+    let mut encoder = png::Encoder::new(file, size.x, size.y);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Sixteen);
+    for (key, value) in metadata {
+        encoder.add_text_chunk(key.to_string(), value.clone())?;
+    }
+
+    let data: Vec<u8> = values
+        .iter()
+        .flat_map(|value| (value.value() as u16).to_be_bytes())
+        .collect();
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+fn write_exr(
+    path: &Path,
+    size: UVec2,
+    values: Vec<f32>,
+    metadata: &[(&str, String)],
+) -> anyhow::Result<()> {
+    use exr::prelude::*;
+
+    let mut attributes = LayerAttributes::named("fractal");
+    for (key, value) in metadata {
+        attributes.other.insert(
+            Text::from(*key),
+            AttributeValue::Text(Text::from(value.as_str())),
+        );
+    }
+
+    let channel = AnyChannel::new("Y", FlatSamples::F32(values));
+    let layer = Layer::new(
+        (size.x as usize, size.y as usize),
+        attributes,
+        Encoding::SMALL_LOSSLESS,
+        AnyChannels::sort(SmallVec::from_vec(vec![channel])),
+    );
+
+    Image::from_layer(layer).write().to_file(path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec2;
+
+    use super::*;
+    use crate::mandelbrot_simd::MANDELBROT_POWER;
+
+    #[test]
+    fn export_preserves_values_and_metadata() {
+        std::fs::create_dir_all("test_output").unwrap();
+        let fractal_rect = DRect::from_center_size(DVec2::new(-0.74, 0.0), DVec2::new(3.0, 2.0));
+        let size = UVec2::new(300, 200);
+        let max_iter = 1024;
+
+        let path = Path::new("test_output/raw_export.png");
+        export_raw(
+            path,
+            fractal_rect,
+            size,
+            max_iter,
+            FractalKind::Mandelbrot,
+            MANDELBROT_POWER,
+            EscapeMetric::default(),
+            None,
+        )
+        .unwrap();
+
+        let decoder = png::Decoder::new(std::io::BufReader::new(File::open(path).unwrap()));
+        let mut reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (size.x, size.y));
+        assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+        let max_iter_text = info
+            .uncompressed_latin1_text
+            .iter()
+            .find(|chunk| chunk.keyword == "max_iter")
+            .unwrap();
+        assert_eq!(max_iter_text.text, "1024");
+
+        let mut data = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut data).unwrap();
+        let values: Vec<u16> = data
+            .chunks(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .collect();
+        let expected = compute::<_, Pixel>(
+            &SimdKernel::default(),
+            PixelGrid::new(fractal_rect, size),
+            KernelOptions {
+                max_iter,
+                power: MANDELBROT_POWER,
+            },
+            None,
+        )
+        .unwrap();
+        assert!(values
+            .iter()
+            .zip(&expected)
+            .all(|(&value, expected)| value as f64 == expected.value()));
+        // counts beyond 8 bits survive
+        assert!(values.iter().any(|&value| value > 255));
+
+        let path = Path::new("test_output/raw_export.exr");
+        export_raw(
+            path,
+            fractal_rect,
+            size,
+            max_iter,
+            FractalKind::Mandelbrot,
+            MANDELBROT_POWER,
+            EscapeMetric::default(),
+            None,
+        )
+        .unwrap();
+        let image = exr::prelude::read_all_flat_layers_from_file(path).unwrap();
+        let layer = &image.layer_data[0];
+        assert!(layer
+            .attributes
+            .other
+            .contains_key(&exr::prelude::Text::from("frame_size")));
+    }
+
+    #[test]
+    fn unique_path_never_overwrites() {
+        let dir = Path::new("test_output");
+        std::fs::create_dir_all(dir).unwrap();
+
+        let first = unique_path(dir, "unique_path", "txt");
+        std::fs::write(&first, "").unwrap();
+        let second = unique_path(dir, "unique_path", "txt");
+        std::fs::remove_file(&first).unwrap();
+
+        assert_ne!(first, second);
+        assert!(!second.exists());
+        assert_eq!(second.extension().unwrap(), "txt");
+    }
+}
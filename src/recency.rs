@@ -0,0 +1,88 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// The order in which keys were last used, the bookkeeping of the least recently used
+/// eviction of `TileCache` and `AtlasSlots`.
+#[derive(Debug)]
+pub struct Recency<K> {
+    // counts the uses, the recency of a key is the count of its last one
+    tick: u64,
+    ticks: HashMap<K, u64>,
+    // the keys by recency, the least recently used first
+    keys: BTreeMap<u64, K>,
+}
+
+impl<K: Hash + Eq + Clone> Recency<K> {
+    pub fn new() -> Self {
+        Self {
+            tick: 0,
+            ticks: HashMap::new(),
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Makes `key` the most recently used, adding it if it isn't tracked yet.
+    pub fn touch(&mut self, key: &K) {
+        self.tick += 1;
+        if let Some(last) = self.ticks.insert(key.clone(), self.tick) {
+            self.keys.remove(&last);
+        }
+        self.keys.insert(self.tick, key.clone());
+    }
+
+    pub fn remove(&mut self, key: &K) {
+        if let Some(last) = self.ticks.remove(key) {
+            self.keys.remove(&last);
+        }
+    }
+
+    /// The least recently used key with the count of its last use.
+    pub fn oldest(&self) -> Option<(&K, u64)> {
+        self.keys.first_key_value().map(|(&tick, key)| (key, tick))
+    }
+
+    /// Stops tracking the least recently used key and returns it.
+    pub fn pop_oldest(&mut self) -> Option<K> {
+        let (_, key) = self.keys.pop_first()?;
+        self.ticks.remove(&key);
+
+        Some(key)
+    }
+
+    /// The count of the last use, the next one counts one more.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn clear(&mut self) {
+        self.ticks.clear();
+        self.keys.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_come_out_least_recently_used_first() {
+        let mut recency = Recency::new();
+        recency.touch(&"a");
+        recency.touch(&"b");
+        recency.touch(&"c");
+        recency.touch(&"a");
+        assert_eq!(recency.oldest(), Some((&"b", 2)));
+        assert_eq!(recency.tick(), 4);
+
+        recency.remove(&"b");
+        assert_eq!(recency.pop_oldest(), Some("c"));
+        assert_eq!(recency.pop_oldest(), Some("a"));
+        assert_eq!(recency.pop_oldest(), None);
+
+        recency.touch(&"d");
+        recency.clear();
+        assert_eq!(recency.oldest(), None);
+        // the count goes on, later uses stay more recent than any before
+        assert_eq!(recency.tick(), 5);
+    }
+}
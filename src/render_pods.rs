@@ -1,7 +1,7 @@
 use std::mem::size_of;
 
 use bytemuck::{Pod, Zeroable};
-use glam::{Mat4, UVec2, Vec2};
+use glam::{Mat4, UVec2, Vec2, Vec4};
 
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
@@ -19,7 +19,24 @@ pub struct ScreenRect([Vert; 4]);
 pub struct PushConst {
     pub proj_mat: Mat4,
     pub texture_size: Vec2,
-    _padding: Vec2,
+    pub dither: u32,
+    pub coloring_mode: u32,
+    pub no_data: u32,
+    pub palette_mapping: u32,
+    pub edge_detection: u32,
+    pub exposure: f32,
+    pub gamma: f32,
+    /// Seconds since the start, tiles fade in from the time they were uploaded.
+    pub time: f32,
+    /// Seconds a tile takes to fade in, zero draws it right away.
+    pub fade_duration: f32,
+    pub tile_size: u32,
+    /// Nonzero when the values name the roots of the Newton fractal.
+    pub newton: u32,
+    // the vec4 below is 16-byte aligned in the shader as well
+    _padding: [u32; 3],
+    // linear rgb, alpha is unused
+    pub interior_color: Vec4,
 }
 
 impl Default for ScreenRect {
@@ -61,23 +78,31 @@ impl ScreenRect {
     }
 
     pub fn with_texture_size(size: UVec2) -> Self {
+        Self::with_texture_rect(UVec2::ZERO, size)
+    }
+
+    /// The quad showing the `size` texels from `pos` on, for a part of an atlas.
+    pub fn with_texture_rect(pos: UVec2, size: UVec2) -> Self {
+        let start = pos.as_vec2();
+        let end = (pos + size).as_vec2();
+
         ScreenRect([
             // @formatter:off
             Vert {
                 pos: [-1.0, -1.0, 0.0, 1.0],
-                uw: [0.0, 0.0],
+                uw: [start.x, start.y],
             },
             Vert {
                 pos: [-1.0, 1.0, 0.0, 1.0],
-                uw: [0.0, size.y as f32],
+                uw: [start.x, end.y],
             },
             Vert {
                 pos: [1.0, -1.0, 0.0, 1.0],
-                uw: [size.x as f32, 0.0],
+                uw: [end.x, start.y],
             },
             Vert {
                 pos: [1.0, 1.0, 0.0, 1.0],
-                uw: [size.x as f32, size.y as f32],
+                uw: [end.x, end.y],
             },
             // @formatter:on
         ])
@@ -89,7 +114,19 @@ impl PushConst {
         Self {
             proj_mat: Mat4::default(),
             texture_size: Vec2::default(),
-            _padding: Vec2::default(),
+            dither: 0,
+            coloring_mode: 0,
+            no_data: 0,
+            palette_mapping: 0,
+            edge_detection: 0,
+            exposure: 1.0,
+            gamma: 1.0,
+            time: 0.0,
+            fade_duration: 0.0,
+            tile_size: 1,
+            newton: 0,
+            _padding: [0; 3],
+            interior_color: Vec4::ZERO,
         }
     }
     pub fn as_bytes(&self) -> &[u8] {
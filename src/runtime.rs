@@ -0,0 +1,152 @@
+use std::future::Future;
+use std::time::Duration;
+
+/// Runs the tasks of the app, on threads of its own on native platforms. The browser has no
+/// threads, tasks take turns on the event loop of the page there, and the page gets a turn
+/// before every blocking one so it keeps drawing frames meanwhile.
+#[derive(Debug)]
+pub struct Runtime {
+    #[cfg(not(target_arch = "wasm32"))]
+    runtime: tokio::runtime::Runtime,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Runtime {
+    pub fn new(thread_count: usize, thread_name: &str) -> std::io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(thread_count)
+            .thread_name(thread_name)
+            .enable_all()
+            .build()?;
+
+        Ok(Self { runtime })
+    }
+
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.runtime.spawn(future);
+    }
+
+    pub fn spawn_blocking<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.runtime.spawn_blocking(f);
+    }
+
+    /// Calls `step` until it returns false, a long job in steps short enough for the browser.
+    pub fn spawn_steps<F>(&self, mut step: F)
+    where
+        F: FnMut() -> bool + Send + 'static,
+    {
+        self.runtime.spawn_blocking(move || while step() {});
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Runtime {
+    pub fn new(thread_count: usize, thread_name: &str) -> std::io::Result<Self> {
+        let _ = (thread_count, thread_name);
+
+        Ok(Self {})
+    }
+
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        wasm_bindgen_futures::spawn_local(future);
+    }
+
+    pub fn spawn_blocking<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        wasm_bindgen_futures::spawn_local(async move {
+            yield_to_page().await;
+            f();
+        });
+    }
+
+    /// Calls `step` until it returns false, a long job in steps short enough for the browser.
+    pub fn spawn_steps<F>(&self, mut step: F)
+    where
+        F: FnMut() -> bool + Send + 'static,
+    {
+        wasm_bindgen_futures::spawn_local(async move {
+            loop {
+                yield_to_page().await;
+                if !step() {
+                    return;
+                }
+            }
+        });
+    }
+}
+
+/// Runs `future` as a task of its own, so a panic in it comes back as an error with its
+/// message. In the browser a panic aborts the app, the future just runs after the page had
+/// its turn.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn isolate<T, F>(future: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: Future<Output = T> + Send + 'static,
+{
+    tokio::spawn(future).await.map_err(join_error_reason)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub async fn isolate<T, F>(future: F) -> Result<T, String>
+where
+    F: Future<Output = T>,
+{
+    yield_to_page().await;
+
+    Ok(future.await)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+#[cfg(target_arch = "wasm32")]
+pub async fn sleep(duration: Duration) {
+    set_timeout(duration.as_millis().min(i32::MAX as u128) as i32).await;
+}
+
+// a timeout of zero still waits for the events, rendering and input the page has queued
+#[cfg(target_arch = "wasm32")]
+async fn yield_to_page() {
+    set_timeout(0).await;
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn set_timeout(timeout_ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        web_sys::window()
+            .expect("runs on a page")
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, timeout_ms)
+            .expect("setTimeout doesn't fail");
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn join_error_reason(join_error: tokio::task::JoinError) -> String {
+    if !join_error.is_panic() {
+        return join_error.to_string();
+    }
+
+    let payload = join_error.into_panic();
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
@@ -6,6 +6,19 @@ struct VertexOutput {
 
 struct PushConstant {
     proj_mat: mat4x4<f32>,
+    texture_size: vec2<f32>,
+    dither: u32,
+    coloring_mode: u32,
+    no_data: u32,
+    palette_mapping: u32,
+    edge_detection: u32,
+    exposure: f32,
+    gamma: f32,
+    time: f32,
+    fade_duration: f32,
+    tile_size: u32,
+    newton: u32,
+    interior_color: vec4<f32>,
 };
 var<push_constant> pc: PushConstant;
 
@@ -32,16 +45,191 @@ var color: texture_2d<u32>;
 @group(0)
 @binding(2)
 var palette: texture_1d<f32>;
+// per tile of the page, the time it was uploaded to a part without data
+@group(0)
+@binding(3)
+var fade_starts: texture_2d<f32>;
 
 @fragment
 fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
-    let iters = textureLoad(color, vec2<u32>(vertex.tex_coord), 0).r;
-    let norm = f32((iters - 1) % 768) / 768.0;
-    let b = clamp(f32(iters), 0.0, 1.0) * clamp(f32(iters - 1), 0.0, 16.0) / 16.0;
+    // before any fragment is discarded, derivatives need the whole quad
+    let lod = mip_lod(vertex.tex_coord);
+    let texel = textureLoad(color, vec2<u32>(vertex.tex_coord), 0).r;
+    // lets the preview below show through until the tile is computed
+    if (texel == pc.no_data) {
+        discard;
+    }
+
+    let alpha = fade_in(vertex.tex_coord);
+    var rgb = texel_color(texel, vec2<i32>(vertex.tex_coord), true);
+    // minified, the colors of the two levels around the minification are blended
+    if (lod > 0.0) {
+        let level = u32(lod);
+        var lower = rgb;
+        if (level > 0u) {
+            lower = level_color(level, vertex.tex_coord, rgb);
+        }
+        let last_level = textureNumLevels(color) - 1u;
+        let upper = level_color(min(level + 1u, last_level), vertex.tex_coord, lower);
+        rgb = mix(lower, upper, fract(lod));
+    }
+    if (pc.dither != 0u && texel != INTERIOR_TEXEL) {
+        rgb = dither(rgb, vec2<u32>(vertex.tex_coord));
+    }
+    return vec4<f32>(rgb, alpha);
+
+}
 
-    let u = pow(norm, 0.4);
-    let rgb = textureSample(palette, the_sampler, u).rgb;
-    return vec4<f32>(rgb * b, 1.0);
+// Points inside the set are flagged by a zero texel in both coloring modes, escaped points
+// start at one. Edge detection looks at the neighbors of `coord` on the first level.
+fn texel_color(texel: u32, coord: vec2<i32>, edges: bool) -> vec3<f32> {
+    if (texel == INTERIOR_TEXEL) {
+        return pc.interior_color.rgb;
+    }
+
+    let iters = texel_value(texel);
+    var rgb: vec3<f32>;
+    if (pc.newton != 0u) {
+        rgb = newton_color(iters);
+    } else {
+        let b = clamp(iters, 0.0, 1.0) * clamp(iters - 1.0, 0.0, 16.0) / 16.0;
+        let u = palette_position(iters);
+        rgb = textureSampleLevel(palette, the_sampler, u, 0.0).rgb * b;
+    }
+    if (edges && pc.edge_detection != 0u) {
+        rgb *= edge_strength(coord, log2(iters));
+    }
+    // skipped at the defaults, pow isn't exact at one
+    if (pc.exposure != 1.0 || pc.gamma != 1.0) {
+        rgb = pow(rgb * pc.exposure, vec3<f32>(1.0 / pc.gamma));
+    }
+    return rgb;
+}
+
+// the color at `tex_coord` on mip `level`, `fallback` where the level has no data yet
+fn level_color(level: u32, tex_coord: vec2<f32>, fallback: vec3<f32>) -> vec3<f32> {
+    let mip = i32(level);
+    let last = textureDimensions(color, mip) - 1u;
+    let coord = min(vec2<u32>(tex_coord) >> vec2<u32>(level), last);
+    let texel = textureLoad(color, coord, mip).r;
+    if (texel == pc.no_data) {
+        return fallback;
+    }
+    return texel_color(texel, vec2<i32>(coord), false);
+}
+
+// the mip level of the texels per pixel at `tex_coord`, fractional between two levels;
+// zero unless the texture is minified and has levels beyond the first
+fn mip_lod(tex_coord: vec2<f32>) -> f32 {
+    let texels_per_pixel = max(length(dpdx(tex_coord)), length(dpdy(tex_coord)));
+    let last_level = f32(textureNumLevels(color) - 1u);
+    return clamp(log2(texels_per_pixel), 0.0, last_level);
+}
+
+
+
+const COLORING_MODE_SMOOTH: u32 = 1u;
+const PALETTE_MAPPING_SQRT: u32 = 1u;
+const PALETTE_MAPPING_LOG: u32 = 2u;
+// escape counts per pass through the palette, for the square root and log mappings
+// the length of the first pass
+const PALETTE_PERIOD: f32 = 768.0;
+const INTERIOR_TEXEL: u32 = 0u;
+
+// smooth escape counts are stored as f32 bits
+fn texel_value(texel: u32) -> f32 {
+    if (pc.coloring_mode == COLORING_MODE_SMOOTH) {
+        return bitcast<f32>(texel);
+    }
+    return f32(texel);
+}
 
+// none of the mappings depends on the iteration limit, so colors don't shift while zooming
+fn palette_position(iters: f32) -> f32 {
+    let n = max(iters - 1.0, 0.0);
+    // every pass through the palette takes longer than the last one
+    if (pc.palette_mapping == PALETTE_MAPPING_SQRT) {
+        return fract(sqrt(n / PALETTE_PERIOD));
+    }
+    if (pc.palette_mapping == PALETTE_MAPPING_LOG) {
+        return fract(log2(n + 1.0) / log2(PALETTE_PERIOD + 1.0));
+    }
+    return pow((n % PALETTE_PERIOD) / PALETTE_PERIOD, 0.4);
 }
 
+const NEWTON_ROOT_COUNT: u32 = 3u;
+// steps to a root that halve the brightness
+const NEWTON_HALF_STEPS: f32 = 8.0;
+
+// a hue per root of the Newton fractal, darker the more steps it took to get there; the values
+// are 1 + root + 3 * steps
+fn newton_color(value: f32) -> vec3<f32> {
+    let packed = u32(round(value)) - 1u;
+    let hue = f32(packed % NEWTON_ROOT_COUNT) / f32(NEWTON_ROOT_COUNT);
+    let steps = f32(packed / NEWTON_ROOT_COUNT);
+
+    let wave = abs(fract(hue + vec3<f32>(1.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0) - 1.0;
+    let rgb = clamp(wave, vec3<f32>(0.0), vec3<f32>(1.0));
+    return mix(vec3<f32>(1.0), rgb, 0.8) * exp2(-steps / NEWTON_HALF_STEPS);
+}
+
+// log2 of the escape count, points inside the set lie above any count the u32 texels hold
+const EDGE_INTERIOR_LEVEL: f32 = 32.0;
+// Sobel magnitudes of the log2 escape count where lines start and reach full brightness;
+// a unit step is 4, neighboring bands past the first few iterations stay below the start
+const EDGE_LOW: f32 = 0.75;
+const EDGE_HIGH: f32 = 3.0;
+
+// The level of the texel at `coord` for edge detection. All tiles of a page share its texture,
+// so neighbors across tile seams are real data; texels without data yet and those past the
+// page border repeat `center_level`, neither draws a line.
+fn edge_level(coord: vec2<i32>, center_level: f32) -> f32 {
+    let size = vec2<i32>(textureDimensions(color));
+    if (any(coord < vec2<i32>(0)) || any(coord >= size)) {
+        return center_level;
+    }
+    let texel = textureLoad(color, vec2<u32>(coord), 0).r;
+    if (texel == pc.no_data) {
+        return center_level;
+    }
+    if (texel == INTERIOR_TEXEL) {
+        return EDGE_INTERIOR_LEVEL;
+    }
+    return log2(texel_value(texel));
+}
+
+// how sharply the escape count changes around `center`, from 0 to 1
+fn edge_strength(center: vec2<i32>, center_level: f32) -> f32 {
+    var levels: array<f32, 9>;
+    for (var i = 0; i < 9; i++) {
+        levels[i] = edge_level(center + vec2<i32>(i % 3 - 1, i / 3 - 1), center_level);
+    }
+
+    let gx = levels[2] + 2.0 * levels[5] + levels[8] - levels[0] - 2.0 * levels[3] - levels[6];
+    let gy = levels[6] + 2.0 * levels[7] + levels[8] - levels[0] - 2.0 * levels[1] - levels[2];
+    return smoothstep(EDGE_LOW, EDGE_HIGH, length(vec2<f32>(gx, gy)));
+}
+
+// opacity of the tile at `tex_coord`, blended over what was shown there before it had data
+fn fade_in(tex_coord: vec2<f32>) -> f32 {
+    if (pc.fade_duration == 0.0) {
+        return 1.0;
+    }
+    let start = textureLoad(fade_starts, vec2<u32>(tex_coord) / pc.tile_size, 0).r;
+    return clamp((pc.time - start) / pc.fade_duration, 0.0, 1.0);
+}
+
+const BAYER_4X4: array<f32, 16> = array<f32, 16>(
+     0.0,  8.0,  2.0, 10.0,
+    12.0,  4.0, 14.0,  6.0,
+     3.0, 11.0,  1.0,  9.0,
+    15.0,  7.0, 13.0,  5.0,
+);
+
+// Offsets the color by up to half an 8-bit step of the sRGB output.
+// The pattern is anchored to texels so it moves with the fractal when panning.
+fn dither(rgb: vec3<f32>, texel: vec2<u32>) -> vec3<f32> {
+    let threshold = (BAYER_4X4[(texel.y % 4u) * 4u + texel.x % 4u] + 0.5) / 16.0 - 0.5;
+    let srgb = pow(rgb, vec3<f32>(1.0 / 2.2)) + threshold / 255.0;
+    return pow(clamp(srgb, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(2.2));
+}
@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::tile_stats::TileStatsSummary;
+
+/// Metrics of a whole session for performance work, kept only with `--stats` and written as
+/// JSON on exit.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SessionStats {
+    /// Tiles that were computed and not cancelled, over all windows.
+    pub tiles_computed: u64,
+    /// Kernel time of those tiles, summed over the workers.
+    pub compute_seconds: f64,
+    /// Of the tile cache, the file has their ratio too, see `cache_hit_rate`.
+    pub cache_hits: u64,
+    pub cache_lookups: u64,
+    /// Greatest magnification relative to the initial view, as `--zoom` takes it.
+    pub deepest_zoom: f64,
+    pub frames_rendered: u64,
+}
+
+impl SessionStats {
+    /// Adds the tiles of a window, once as it closes.
+    pub fn add_tiles(&mut self, summary: &TileStatsSummary) {
+        self.tiles_computed += summary.compute.count;
+        self.compute_seconds += summary.compute.total.as_secs_f64();
+        self.cache_hits += summary.cache_hits;
+        self.cache_lookups += summary.cache_lookups;
+    }
+
+    /// `None` without a tile cache.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        (self.cache_lookups > 0).then(|| self.cache_hits as f64 / self.cache_lookups as f64)
+    }
+
+    /// Counts a frame rendered at `zoom`.
+    pub fn add_frame(&mut self, zoom: f64) {
+        self.frames_rendered += 1;
+        self.deepest_zoom = self.deepest_zoom.max(zoom);
+    }
+
+    /// Writes the fields with the cache hit rate of the whole session, which a reader
+    /// would otherwise work out from the counts.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(&SavedStats {
+            stats: self,
+            cache_hit_rate: self.cache_hit_rate(),
+        })?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write the statistics to {}", path.display()))
+    }
+}
+
+// the rate is derived once here, nothing stored can disagree with the counts
+#[derive(Serialize)]
+struct SavedStats<'a> {
+    #[serde(flatten)]
+    stats: &'a SessionStats,
+    cache_hit_rate: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::tile_stats::TileStats;
+
+    #[test]
+    fn stats_round_trip_through_json() {
+        let tile_stats = TileStats::default();
+        tile_stats.compute.record(Duration::from_millis(250));
+        tile_stats.compute.record(Duration::from_millis(750));
+        tile_stats.record_cache_lookup(true);
+        tile_stats.record_cache_lookup(false);
+
+        let mut stats = SessionStats::default();
+        stats.add_tiles(&tile_stats.summary());
+        stats.add_frame(4.0);
+        stats.add_frame(1e6);
+        stats.add_frame(2.0);
+        assert_eq!(stats.tiles_computed, 2);
+        assert_eq!(stats.compute_seconds, 1.0);
+        assert_eq!(stats.cache_hit_rate(), Some(0.5));
+        assert_eq!(stats.deepest_zoom, 1e6);
+        assert_eq!(stats.frames_rendered, 3);
+
+        let path = std::env::temp_dir().join(format!("fractal_stats_{}.json", std::process::id()));
+        stats.save(&path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(json["cache_hit_rate"], 0.5);
+        assert_eq!(serde_json::from_value::<SessionStats>(json).unwrap(), stats);
+
+        assert_eq!(SessionStats::default().cache_hit_rate(), None);
+    }
+}
@@ -0,0 +1,777 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use glam::Vec3;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use winit::keyboard::KeyCode;
+
+use crate::bindings::Bindings;
+use crate::buddhabrot_renderer::DEFAULT_BUDDHABROT_SAMPLES;
+use crate::env;
+use crate::fxaa::DEFAULT_EDGE_THRESHOLD;
+use crate::mandel_texture::{
+    DEFAULT_INTERIOR_COLOR, DEFAULT_LOADING_FILL, DEFAULT_TILE_CACHE_MB, DEFAULT_TILE_SIZE,
+    MAX_RENDER_SCALE,
+};
+use crate::palette::PaletteMapping;
+use crate::tiled_fractal_app::{
+    ResizeAnchor, DEFAULT_PERFORMANCE_SCALE, MAX_ITERATION_FACTOR, MIN_ITERATION_FACTOR, ZOOM_BASE,
+    ZOOM_DIVISOR,
+};
+
+const FILE_NAME: &str = "config.toml";
+
+/// Written to the user's config directory on first run, every setting at its default.
+pub const DEFAULT_FILE: &str = r#"# Settings of the Mandelbrot explorer, uncomment a line to change it.
+# FRACTAL_TILE_SIZE, FRACTAL_PRESENT_MODE, FRACTAL_INTERIOR_COLOR and FRACTAL_WORKER_THREADS
+# override the file, command line flags override both.
+
+# Color stops replacing the built-in palette, relative to this file, see palettes/sunset.json
+# palette = "sunset.json"
+# How escape counts advance along the palette: linear, sqrt or log; log shows the most
+# structure far from the set at high iteration limits, G cycles through them
+# palette_mapping = "linear"
+
+# Texels computed by one task, a power of two from 16 to 512
+# tile_size = 128
+
+# Multiplies the iteration limit that follows from the zoom, from 0.125 to 8
+# iteration_factor = 1.0
+
+# A wheel step zooms by zoom_base^(3 / zoom_divisor), notched wheels usually want further steps
+# than free-spinning ones; ; and ' change it while running
+# zoom_base = 1.15
+# zoom_divisor = 5.0
+# Multiplies how far one wheel step zooms, holding Shift zooms 4x as far and Ctrl a quarter
+# zoom_speed = 1.0
+# Eases each wheel step in over 100 ms around where the scrolling started, Z toggles it
+# zoom_smoothing = false
+# What stays put when the window is resized: "center" of the view, or "cursor" for the point
+# the mouse was last over
+# resize_anchor = "center"
+# Fades tiles in over the preview as they finish
+# tile_fade = true
+# sRGB gray level from 0 to 255 shown where nothing is computed yet
+# loading_fill = 40
+# Computes a low resolution overview around the view first, which shows there instead
+# overview_fill = true
+# Keeps tiles of the three power-of-two zoom levels coarser than the view, which show while
+# its own tiles compute and come back at once when zooming returns to them
+# tile_pyramid = true
+# Megabytes of computed tiles kept, a view returned to shows them again without computing;
+# 0 turns the cache off
+# tile_cache_mb = 256
+# Computes the tiles at a lower resolution while the view moves and at full resolution once it
+# holds still, for machines that can't keep up otherwise; K toggles it
+# performance_mode = false
+# Texels per side computed as one in performance mode, 2 or 4
+# performance_scale = 2
+
+# Random points sampled for a frame of the Buddhabrot, which H shows in place of the tiles
+# buddhabrot_samples = 100000000
+
+# Smooths the band edges of the finished frame, F toggles it
+# fxaa = false
+# Contrast relative to the brighter side from which an edge is smoothed, from 0 to 1;
+# lower values smooth more and soften thin filaments
+# fxaa_edge_threshold = 0.125
+
+# fifo, fifo-relaxed, mailbox or immediate; mailbox if supported, fifo otherwise by default.
+# fifo waits for the display and saves power, immediate and mailbox draw as fast as they can
+# present_mode = "fifo"
+
+# Frames per second at most, on top of what the present mode allows; unlimited by default
+# max_frame_rate = 60
+
+# Logs how long the GPU takes for the tile uploads and the screen pass every few seconds,
+# where timestamp queries are supported
+# gpu_timing = false
+
+# Color of the points inside the set, rrggbb hex sRGB
+# interior_color = "000000"
+
+# Threads computing tiles, every logical CPU core but one by default, which is left to the
+# compositor; FRACTAL_WORKER_THREADS and --threads override it, changes apply on restart
+# worker_threads = 8
+
+# Keys by action, named like winit's KeyCode; a list binds several, an empty one none
+[keys]
+# toggle_antialiasing = "KeyQ"
+# toggle_dither = "KeyB"
+# toggle_edge_detection = "KeyO"
+# export_raw = "KeyE"
+# toggle_zoom_smoothing = "KeyZ"
+# slower_zoom = "Semicolon"
+# faster_zoom = "Quote"
+# toggle_morph = "KeyM"
+# slower_morph = "BracketLeft"
+# faster_morph = "BracketRight"
+# next_escape_metric = "KeyN"
+# next_present_mode = "KeyV"
+# double_iterations = "KeyI"
+# halve_iterations = "KeyU"
+# more_iterations = "PageUp"
+# fewer_iterations = "PageDown"
+# toggle_coloring_mode = "KeyC"
+# next_palette_mapping = "KeyG"
+# more_exposure = "Equal"
+# less_exposure = "Minus"
+# higher_gamma = "Period"
+# lower_gamma = "Comma"
+# toggle_fxaa = "KeyF"
+# toggle_pause = "KeyP"
+# next_preset = "KeyL"
+# reset_view = ["Home", "KeyR"]
+# toggle_performance_mode = "KeyK"
+# Back to the view before the last one recalled from a slot; Ctrl and a digit store the view in
+# that slot, the digit alone recalls it
+# previous_view = "Backspace"
+# toggle_buddhabrot = "KeyH"
+# Mandelbrot, then a Julia set, the Burning Ship and the Newton fractal of z^3 - 1
+# next_fractal_kind = "KeyJ"
+# The Mandelbrot set on the left, the Julia set of the point under the cursor on the right
+# toggle_split_view = "KeyX"
+# Turns the view by 15 degrees about its center, counterclockwise or with Shift clockwise
+# rotate_view = "KeyY"
+# Lists the keys over the fractal, any key hides the list again
+# toggle_help = ["F1", "Slash"]
+
+# Mouse buttons by gesture, Left, Right, Middle, Back or Forward after any of Shift, Ctrl, Alt
+# and Super, or none; a button with more modifiers held picks the binding that needs the most
+[mouse]
+# Drags the view along
+# pan = "Left"
+# Zooms into the dragged box
+# box_zoom = "Shift+Left"
+# Flies to the clicked point
+# context_action = "Middle"
+# Zooms around the pressed point while dragging up or down
+# zoom_axis = "Right"
+# Logs the escape count of the clicked pixel, recomputed the way its tile computes it
+# inspect = "Ctrl+Left"
+"#;
+
+/// Everything that can be configured, the defaults are overridden by the config file,
+/// then by FRACTAL_* environment variables, then by command line flags.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Color stops replacing the built-in palette, see `Palette::from_file`.
+    pub palette: Option<PathBuf>,
+    pub palette_mapping: PaletteMapping,
+    #[serde(deserialize_with = "tile_size")]
+    pub tile_size: u32,
+    /// Multiplies the iteration limit that follows from the zoom.
+    #[serde(deserialize_with = "iteration_factor")]
+    pub iteration_factor: f64,
+    /// A line of scrolling zooms by `zoom_base^(zoom_speed / zoom_divisor)`.
+    #[serde(deserialize_with = "zoom_base")]
+    pub zoom_base: f64,
+    #[serde(deserialize_with = "positive_factor")]
+    pub zoom_divisor: f64,
+    /// Multiplies the zoom of one wheel step, as an exponent.
+    #[serde(deserialize_with = "positive_factor")]
+    pub zoom_speed: f64,
+    pub zoom_smoothing: bool,
+    pub resize_anchor: ResizeAnchor,
+    /// Finished tiles fade in over the preview instead of appearing at once.
+    pub tile_fade: bool,
+    /// sRGB gray level of the background, see `MandelTexture::set_loading_fill`.
+    pub loading_fill: u8,
+    pub overview_fill: bool,
+    /// Coarser levels below the pages, see `tile_pyramid`.
+    pub tile_pyramid: bool,
+    /// Budget of the tile cache, see `TileCache`.
+    pub tile_cache_mb: u32,
+    /// Coarse tiles while the view moves, see `MandelTexture::set_render_scale`.
+    pub performance_mode: bool,
+    #[serde(deserialize_with = "performance_scale")]
+    pub performance_scale: u32,
+    /// Samples of a Buddhabrot frame, see `BuddhabrotRenderer`.
+    #[serde(deserialize_with = "sample_count")]
+    pub buddhabrot_samples: u64,
+    /// Post-process anti-aliasing, see `Fxaa`.
+    pub fxaa: bool,
+    #[serde(deserialize_with = "edge_threshold")]
+    pub fxaa_edge_threshold: f32,
+    /// `None` picks the lowest latency mode that doesn't tear.
+    #[serde(deserialize_with = "present_mode")]
+    pub present_mode: Option<wgpu::PresentMode>,
+    /// `None` draws frames as fast as the present mode lets it.
+    #[serde(deserialize_with = "frame_rate")]
+    pub max_frame_rate: Option<f64>,
+    /// Timestamp queries around the GPU work of a frame, see `GpuProfiler`.
+    pub gpu_timing: bool,
+    /// Linear rgb, the file has it as hex sRGB.
+    #[serde(deserialize_with = "interior_color")]
+    pub interior_color: Vec3,
+    /// Threads computing tiles, `None` for `default_worker_threads`; see `worker_count`.
+    #[serde(deserialize_with = "worker_threads")]
+    pub worker_threads: Option<usize>,
+    pub keys: KeyBindings,
+    /// Invalid ones are reported and replaced by the defaults, see `Bindings`.
+    pub mouse: Bindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            palette: None,
+            palette_mapping: PaletteMapping::Linear,
+            tile_size: DEFAULT_TILE_SIZE,
+            iteration_factor: 1.0,
+            zoom_base: ZOOM_BASE,
+            zoom_divisor: ZOOM_DIVISOR,
+            zoom_speed: 1.0,
+            zoom_smoothing: false,
+            resize_anchor: ResizeAnchor::Center,
+            tile_fade: true,
+            loading_fill: DEFAULT_LOADING_FILL,
+            overview_fill: true,
+            tile_pyramid: true,
+            tile_cache_mb: DEFAULT_TILE_CACHE_MB,
+            performance_mode: false,
+            performance_scale: DEFAULT_PERFORMANCE_SCALE,
+            buddhabrot_samples: DEFAULT_BUDDHABROT_SAMPLES,
+            fxaa: false,
+            fxaa_edge_threshold: DEFAULT_EDGE_THRESHOLD,
+            present_mode: None,
+            max_frame_rate: None,
+            gpu_timing: false,
+            interior_color: DEFAULT_INTERIOR_COLOR,
+            worker_threads: None,
+            keys: KeyBindings::default(),
+            mouse: Bindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// The file next to the executable if there is one, otherwise the one in the user's config
+    /// directory, which is created on first run. Environment variables are applied on top.
+    pub fn load() -> anyhow::Result<Self> {
+        let mut settings = match config_path() {
+            Some(path) if path.exists() => Self::from_file(&path)?,
+            Some(path) => {
+                match write_default_file(&path) {
+                    Ok(()) => tracing::info!("Wrote the default settings to {}", path.display()),
+                    Err(error) => tracing::warn!("{:#}", error),
+                }
+                Self::default()
+            }
+            None => Self::default(),
+        };
+        settings.apply_env();
+
+        Ok(settings)
+    }
+
+    /// Settings from a TOML file, missing keys keep their defaults.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut settings: Self = toml::from_str(&text)
+            .with_context(|| format!("Invalid settings {}", path.display()))?;
+
+        // a palette next to the file is found wherever the app starts from
+        if let (Some(palette), Some(dir)) = (settings.palette.as_mut(), path.parent()) {
+            *palette = dir.join(&*palette);
+        }
+
+        Ok(settings)
+    }
+
+    fn apply_env(&mut self) {
+        if let Some(tile_size) = env::tile_size() {
+            self.tile_size = tile_size;
+        }
+        if let Some(present_mode) = env::present_mode() {
+            self.present_mode = Some(present_mode);
+        }
+        if let Some(interior_color) = env::interior_color() {
+            self.interior_color = interior_color;
+        }
+        if let Some(worker_threads) = env::worker_threads() {
+            self.worker_threads = Some(worker_threads);
+        }
+    }
+
+    /// Threads computing tiles and Buddhabrot samples, also the tiles computed at once.
+    pub fn worker_count(&self) -> usize {
+        self.worker_threads.unwrap_or_else(default_worker_threads)
+    }
+}
+
+// next to the executable for portable installs, the user's config directory of the platform
+// otherwise: XDG_CONFIG_HOME or ~/.config, %APPDATA% or ~/Library/Application Support
+pub(crate) fn config_path() -> Option<PathBuf> {
+    let local = std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(FILE_NAME)));
+    if let Some(local) = local.filter(|path| path.exists()) {
+        return Some(local);
+    }
+
+    let base_dirs = directories::BaseDirs::new()?;
+
+    Some(base_dirs.config_dir().join("fractal").join(FILE_NAME))
+}
+
+fn write_default_file(path: &Path) -> anyhow::Result<()> {
+    let write = || -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, DEFAULT_FILE)
+    };
+
+    write().with_context(|| format!("Failed to write the default settings {}", path.display()))
+}
+
+/// What a key does, the names are the keys of the `[keys]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    ToggleAntialiasing,
+    ToggleDither,
+    ToggleEdgeDetection,
+    ExportRaw,
+    ToggleZoomSmoothing,
+    SlowerZoom,
+    FasterZoom,
+    ToggleMorph,
+    SlowerMorph,
+    FasterMorph,
+    NextEscapeMetric,
+    NextPresentMode,
+    DoubleIterations,
+    HalveIterations,
+    MoreIterations,
+    FewerIterations,
+    ToggleColoringMode,
+    NextPaletteMapping,
+    MoreExposure,
+    LessExposure,
+    HigherGamma,
+    LowerGamma,
+    ToggleFxaa,
+    TogglePause,
+    NextPreset,
+    ResetView,
+    TogglePerformanceMode,
+    PreviousView,
+    ToggleBuddhabrot,
+    NextFractalKind,
+    ToggleSplitView,
+    RotateView,
+    ToggleHelp,
+}
+
+impl Action {
+    /// What the key list shows for the action.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::ToggleAntialiasing => "Antialiasing on or off",
+            Action::ToggleDither => "Dithering on or off",
+            Action::ToggleEdgeDetection => "Edge detection on or off",
+            Action::ExportRaw => "Export the raw escape counts",
+            Action::ToggleZoomSmoothing => "Zoom smoothing on or off",
+            Action::SlowerZoom => "Slower zoom",
+            Action::FasterZoom => "Faster zoom",
+            Action::ToggleMorph => "Morph the power on or off",
+            Action::SlowerMorph => "Slower morph",
+            Action::FasterMorph => "Faster morph",
+            Action::NextEscapeMetric => "Next escape metric",
+            Action::NextPresentMode => "Next present mode",
+            Action::DoubleIterations => "Double the iterations",
+            Action::HalveIterations => "Halve the iterations",
+            Action::MoreIterations => "More iterations",
+            Action::FewerIterations => "Fewer iterations",
+            Action::ToggleColoringMode => "Banded or smooth coloring",
+            Action::NextPaletteMapping => "Next palette mapping",
+            Action::MoreExposure => "More exposure",
+            Action::LessExposure => "Less exposure",
+            Action::HigherGamma => "Higher gamma",
+            Action::LowerGamma => "Lower gamma",
+            Action::ToggleFxaa => "FXAA on or off",
+            Action::TogglePause => "Pause or resume computing",
+            Action::NextPreset => "Fly to the next preset",
+            Action::ResetView => "Reset the view",
+            Action::TogglePerformanceMode => "Performance mode on or off",
+            Action::PreviousView => "Back to the view before the last recalled",
+            Action::ToggleBuddhabrot => "Buddhabrot on or off",
+            Action::NextFractalKind => "Next fractal",
+            Action::ToggleSplitView => "Julia split view on or off",
+            Action::RotateView => "Turn the view, with Shift the other way",
+            Action::ToggleHelp => "Show or hide this list",
+        }
+    }
+}
+
+// an action may have several keys
+const DEFAULT_KEYS: [(Action, KeyCode); 35] = [
+    (Action::ToggleAntialiasing, KeyCode::KeyQ),
+    (Action::ToggleDither, KeyCode::KeyB),
+    (Action::ToggleEdgeDetection, KeyCode::KeyO),
+    (Action::ExportRaw, KeyCode::KeyE),
+    (Action::ToggleZoomSmoothing, KeyCode::KeyZ),
+    (Action::SlowerZoom, KeyCode::Semicolon),
+    (Action::FasterZoom, KeyCode::Quote),
+    (Action::ToggleMorph, KeyCode::KeyM),
+    (Action::SlowerMorph, KeyCode::BracketLeft),
+    (Action::FasterMorph, KeyCode::BracketRight),
+    (Action::NextEscapeMetric, KeyCode::KeyN),
+    (Action::NextPresentMode, KeyCode::KeyV),
+    (Action::DoubleIterations, KeyCode::KeyI),
+    (Action::HalveIterations, KeyCode::KeyU),
+    (Action::MoreIterations, KeyCode::PageUp),
+    (Action::FewerIterations, KeyCode::PageDown),
+    (Action::ToggleColoringMode, KeyCode::KeyC),
+    (Action::NextPaletteMapping, KeyCode::KeyG),
+    (Action::MoreExposure, KeyCode::Equal),
+    (Action::LessExposure, KeyCode::Minus),
+    (Action::HigherGamma, KeyCode::Period),
+    (Action::LowerGamma, KeyCode::Comma),
+    (Action::ToggleFxaa, KeyCode::KeyF),
+    (Action::TogglePause, KeyCode::KeyP),
+    (Action::NextPreset, KeyCode::KeyL),
+    (Action::ResetView, KeyCode::Home),
+    (Action::ResetView, KeyCode::KeyR),
+    (Action::TogglePerformanceMode, KeyCode::KeyK),
+    (Action::PreviousView, KeyCode::Backspace),
+    (Action::ToggleBuddhabrot, KeyCode::KeyH),
+    (Action::NextFractalKind, KeyCode::KeyJ),
+    (Action::ToggleSplitView, KeyCode::KeyX),
+    (Action::RotateView, KeyCode::KeyY),
+    (Action::ToggleHelp, KeyCode::F1),
+    (Action::ToggleHelp, KeyCode::Slash),
+];
+
+/// The key of every action, the file only lists the ones that differ from the defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBindings {
+    actions: HashMap<KeyCode, Action>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            actions: DEFAULT_KEYS
+                .into_iter()
+                .map(|(action, key)| (key, action))
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.actions.get(&key).copied()
+    }
+
+    /// The keys of every bound action, in the order of the actions and then of the keys.
+    pub fn keys(&self) -> BTreeMap<Action, Vec<KeyCode>> {
+        let mut keys = BTreeMap::<Action, Vec<KeyCode>>::new();
+        for (&key, &action) in &self.actions {
+            keys.entry(action).or_default().push(key);
+        }
+        keys.values_mut().for_each(|keys| keys.sort_unstable());
+
+        keys
+    }
+
+    // an override replaces all default keys of its action, a key taken from another action
+    // is an error rather than a silent unbinding
+    fn with_overrides(overrides: HashMap<Action, Keys>) -> Result<Self, String> {
+        let mut keys: Vec<(Action, KeyCode)> = DEFAULT_KEYS
+            .into_iter()
+            .filter(|(action, _)| !overrides.contains_key(action))
+            .collect();
+        keys.extend(
+            overrides.into_iter().flat_map(|(action, keys)| {
+                keys.into_vec().into_iter().map(move |key| (action, key))
+            }),
+        );
+        keys.sort_by_key(|&(action, key)| (key, action));
+
+        if let Some(pair) = keys.windows(2).find(|pair| pair[0].1 == pair[1].1) {
+            return Err(format!(
+                "{:?} is bound to both {} and {}",
+                pair[0].1,
+                action_name(pair[0].0),
+                action_name(pair[1].0)
+            ));
+        }
+
+        Ok(Self {
+            actions: keys
+                .into_iter()
+                .map(|(action, key)| (key, action))
+                .collect(),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBindings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let overrides = HashMap::<Action, Keys>::deserialize(deserializer)?;
+        Self::with_overrides(overrides).map_err(D::Error::custom)
+    }
+}
+
+// the keys of an action in the file, one or a list
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(KeyCode),
+    Many(Vec<KeyCode>),
+}
+
+impl Keys {
+    fn into_vec(self) -> Vec<KeyCode> {
+        match self {
+            Keys::One(key) => vec![key],
+            Keys::Many(keys) => keys,
+        }
+    }
+}
+
+// as in the file
+fn action_name(action: Action) -> String {
+    let name = format!("{:?}", action);
+    let mut snake_case = String::with_capacity(name.len() + 4);
+    for (index, char) in name.char_indices() {
+        if char.is_ascii_uppercase() && index > 0 {
+            snake_case.push('_');
+        }
+        snake_case.push(char.to_ascii_lowercase());
+    }
+
+    snake_case
+}
+
+fn tile_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let value = u32::deserialize(deserializer)?;
+    env::parse_tile_size(&value.to_string()).ok_or_else(|| {
+        D::Error::custom(format!(
+            "expected a power of two from {} to {}",
+            env::TILE_SIZE_RANGE.start(),
+            env::TILE_SIZE_RANGE.end()
+        ))
+    })
+}
+
+fn iteration_factor<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = f64::deserialize(deserializer)?;
+    if !(MIN_ITERATION_FACTOR..=MAX_ITERATION_FACTOR).contains(&value) {
+        return Err(D::Error::custom(format!(
+            "expected a factor from {} to {}",
+            MIN_ITERATION_FACTOR, MAX_ITERATION_FACTOR
+        )));
+    }
+
+    Ok(value)
+}
+
+fn positive_factor<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = f64::deserialize(deserializer)?;
+    if !value.is_finite() || value <= 0.0 {
+        return Err(D::Error::custom("expected a positive factor"));
+    }
+
+    Ok(value)
+}
+
+// at most 1 would stop zooming or turn it around
+fn zoom_base<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = f64::deserialize(deserializer)?;
+    if !value.is_finite() || value <= 1.0 {
+        return Err(D::Error::custom("expected a factor above 1"));
+    }
+
+    Ok(value)
+}
+
+fn performance_scale<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let value = u32::deserialize(deserializer)?;
+    if value != 2 && value != MAX_RENDER_SCALE {
+        return Err(D::Error::custom("expected 2 or 4"));
+    }
+
+    Ok(value)
+}
+
+fn sample_count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = u64::deserialize(deserializer)?;
+    if value == 0 {
+        return Err(D::Error::custom("expected at least one sample"));
+    }
+
+    Ok(value)
+}
+
+fn edge_threshold<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let value = f32::deserialize(deserializer)?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err(D::Error::custom("expected a contrast from 0 to 1"));
+    }
+
+    Ok(value)
+}
+
+fn present_mode<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<wgpu::PresentMode>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    env::parse_present_mode(&value)
+        .map(Some)
+        .ok_or_else(|| D::Error::custom("expected fifo, fifo-relaxed, mailbox or immediate"))
+}
+
+fn frame_rate<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    let value = f64::deserialize(deserializer)?;
+    if !value.is_finite() || value <= 0.0 {
+        return Err(D::Error::custom(
+            "expected a positive number of frames per second",
+        ));
+    }
+
+    Ok(Some(value))
+}
+
+fn interior_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec3, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    env::parse_color(&value).ok_or_else(|| D::Error::custom("expected an rrggbb hex color"))
+}
+
+// every logical core but one, a machine with all of them busy stutters
+fn default_worker_threads() -> usize {
+    num_cpus::get().saturating_sub(1).max(1)
+}
+
+fn worker_threads<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<usize>, D::Error> {
+    let value = usize::deserialize(deserializer)?;
+    if value == 0 {
+        return Err(D::Error::custom("expected at least one thread"));
+    }
+
+    Ok(Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Settings, String> {
+        toml::from_str(text).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn default_file_lists_the_defaults() {
+        assert_eq!(parse(DEFAULT_FILE).unwrap(), Settings::default());
+
+        let uncommented: String = DEFAULT_FILE
+            .lines()
+            .filter(|line| line.contains(" = ") || line.starts_with('['))
+            .map(|line| format!("{}\n", line.trim_start_matches("# ")))
+            .collect();
+        let settings = parse(&uncommented).unwrap();
+        assert_eq!(settings.palette, Some(PathBuf::from("sunset.json")));
+        assert_eq!(settings.present_mode, Some(wgpu::PresentMode::Fifo));
+        assert_eq!(settings.max_frame_rate, Some(60.0));
+        assert_eq!(settings.worker_threads, Some(8));
+        assert_eq!(settings.worker_count(), 8);
+        let cursor = parse("resize_anchor = \"cursor\"").unwrap();
+        assert_eq!(cursor.resize_anchor, ResizeAnchor::Cursor);
+        assert!(Settings::default().worker_count() >= 1);
+        assert_eq!(
+            Settings {
+                palette: None,
+                present_mode: None,
+                max_frame_rate: None,
+                worker_threads: None,
+                ..settings
+            },
+            Settings::default()
+        );
+    }
+
+    #[test]
+    fn invalid_values_name_their_key() {
+        let settings =
+            parse("tile_size = 256\nzoom_speed = 2.5\n[keys]\ntoggle_pause = \"F5\"").unwrap();
+        assert_eq!(settings.tile_size, 256);
+        assert_eq!(settings.zoom_speed, 2.5);
+        assert_eq!(settings.keys.action(KeyCode::F5), Some(Action::TogglePause));
+        assert_eq!(settings.keys.action(KeyCode::KeyP), None);
+        assert_eq!(
+            settings.keys.action(KeyCode::KeyQ),
+            Some(Action::ToggleAntialiasing)
+        );
+
+        let error = parse("zoom_speed = 1.0\ntile_size = 96").unwrap_err();
+        assert!(error.contains("tile_size = 96"), "{}", error);
+        assert!(error.contains("power of two from 16 to 512"), "{}", error);
+
+        let error = parse("present_mode = \"vsync\"").unwrap_err();
+        assert!(
+            error.contains("present_mode") && error.contains("mailbox"),
+            "{}",
+            error
+        );
+        assert!(parse("iteration_factor = 16.0").is_err());
+        assert!(parse("resize_anchor = \"corner\"").is_err());
+        assert!(parse("zoom_speed = 0.0").is_err());
+        assert!(parse("zoom_base = 1.0").is_err());
+        assert!(parse("zoom_divisor = -5.0").is_err());
+        assert!(parse("fxaa_edge_threshold = -0.1").is_err());
+        assert!(parse("interior_color = \"red\"").is_err());
+        assert!(parse("worker_threads = 0").is_err());
+        assert!(parse("performance_scale = 3").is_err());
+        assert!(parse("performance_scale = 1").is_err());
+        assert!(parse("buddhabrot_samples = 0").is_err());
+        assert!(parse("max_frame_rate = 0").is_err());
+        assert_eq!(
+            parse("max_frame_rate = 30").unwrap().max_frame_rate,
+            Some(30.0)
+        );
+
+        let error = parse("tile_sise = 64").unwrap_err();
+        assert!(error.contains("tile_sise"), "{}", error);
+
+        let error = parse("[keys]\nnext_preset = \"KeyP\"").unwrap_err();
+        assert!(
+            error.contains("KeyP is bound to both toggle_pause and next_preset"),
+            "{}",
+            error
+        );
+        assert!(parse("[keys]\nfly = \"KeyF\"").is_err());
+        assert!(parse("[keys]\ntoggle_pause = \"Pause!\"").is_err());
+        // swapping two keys is fine
+        let settings = parse("[keys]\ntoggle_pause = \"KeyL\"\nnext_preset = \"KeyP\"").unwrap();
+        assert_eq!(
+            settings.keys.action(KeyCode::KeyL),
+            Some(Action::TogglePause)
+        );
+
+        // a single key or a list replaces all default keys of the action
+        let keys = Settings::default().keys;
+        assert_eq!(keys.action(KeyCode::Home), Some(Action::ResetView));
+        assert_eq!(keys.action(KeyCode::KeyR), Some(Action::ResetView));
+        let settings =
+            parse("[keys]\nreset_view = \"F2\"\nexport_raw = [\"KeyE\", \"KeyR\"]").unwrap();
+        assert_eq!(settings.keys.action(KeyCode::F2), Some(Action::ResetView));
+        assert_eq!(settings.keys.action(KeyCode::Home), None);
+        assert_eq!(settings.keys.action(KeyCode::KeyR), Some(Action::ExportRaw));
+        let settings = parse("[keys]\nreset_view = []").unwrap();
+        assert_eq!(settings.keys.action(KeyCode::Home), None);
+        assert!(!settings.keys.keys().contains_key(&Action::ResetView));
+        assert_eq!(
+            keys.keys()[&Action::ToggleHelp],
+            [KeyCode::Slash, KeyCode::F1]
+        );
+        assert!(parse("[keys]\nnext_preset = [\"KeyL\", \"KeyR\"]").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use glam::{DVec3, UVec2, Vec3};
+
+use crate::kernel::{KernelOptions, PixelGrid};
+use crate::mandel_texture::calc_max_iters;
+use crate::mandelbrot_simd::{KernelPixel, Pixel, SimdKernel, MANDELBROT_POWER};
+use crate::math::DRect;
+use crate::palette::{Palette, PaletteMapping};
+use crate::raw_export;
+
+// same constants as the screen shader
+const PALETTE_PERIOD: f64 = 768.0;
+const PALETTE_EXPONENT: f64 = 0.4;
+const FADE_IN_ITERATIONS: f64 = 16.0;
+
+/// Renders `fractal_rect` at `size` pixels without a GPU, the kernel runs on all cores
+/// and the coloring of the screen shader is applied on the CPU. The result only depends
+/// on the arguments, dithering is left out.
+pub fn render(
+    fractal_rect: DRect,
+    size: UVec2,
+    palette: &Palette,
+    mapping: PaletteMapping,
+    interior_color: Vec3,
+) -> anyhow::Result<image::RgbaImage> {
+    let options = KernelOptions {
+        max_iter: calc_max_iters(fractal_rect),
+        power: MANDELBROT_POWER,
+    };
+    let values = raw_export::compute::<_, Pixel>(
+        &SimdKernel::default(),
+        PixelGrid::new(fractal_rect, size),
+        options,
+        None,
+    )?;
+
+    Ok(colorize(&values, size, palette, mapping, interior_color))
+}
+
+/// The colors the screen shader gives the escape counts `values` of an image of `size` pixels,
+/// the top row first.
+pub fn colorize<P: KernelPixel>(
+    values: &[P],
+    size: UVec2,
+    palette: &Palette,
+    mapping: PaletteMapping,
+    interior_color: Vec3,
+) -> image::RgbaImage {
+    let pixels = values
+        .iter()
+        .flat_map(|value| color(value.value(), palette, mapping, interior_color))
+        .collect();
+
+    image::RgbaImage::from_raw(size.x, size.y, pixels).unwrap()
+}
+
+fn color(
+    iterations: f64,
+    palette: &Palette,
+    mapping: PaletteMapping,
+    interior_color: Vec3,
+) -> [u8; 4] {
+    // zero flags points inside the set
+    let rgb = if iterations == 0.0 {
+        interior_color.as_dvec3()
+    } else {
+        escape_color(iterations, palette, mapping)
+    };
+
+    // the shader writes linear values into an sRGB target
+    let rgb = rgb
+        .to_array()
+        .map(|value| (linear_to_srgb(value) * 255.0).round() as u8);
+
+    [rgb[0], rgb[1], rgb[2], u8::MAX]
+}
+
+fn escape_color(iterations: f64, palette: &Palette, mapping: PaletteMapping) -> DVec3 {
+    let brightness = iterations.clamp(0.0, 1.0) * (iterations - 1.0).clamp(0.0, FADE_IN_ITERATIONS)
+        / FADE_IN_ITERATIONS;
+
+    sample(palette, palette_position(iterations, mapping)) * brightness
+}
+
+fn palette_position(iterations: f64, mapping: PaletteMapping) -> f64 {
+    let n = (iterations - 1.0).max(0.0);
+    match mapping {
+        PaletteMapping::Linear => ((n % PALETTE_PERIOD) / PALETTE_PERIOD).powf(PALETTE_EXPONENT),
+        PaletteMapping::Sqrt => (n / PALETTE_PERIOD).sqrt().fract(),
+        PaletteMapping::Log => ((n + 1.0).log2() / (PALETTE_PERIOD + 1.0).log2()).fract(),
+    }
+}
+
+// linear filtering with clamped edges, like the shader's sampler
+fn sample(palette: &Palette, u: f64) -> DVec3 {
+    let texels = palette.texels();
+    let width = texels.len() as u32;
+    let texel = |x: u32| {
+        let [r, g, b, _] = texels[x.min(width - 1) as usize];
+        DVec3::new(r as f64, g as f64, b as f64) / 255.0
+    };
+
+    let position = (u * width as f64 - 0.5).max(0.0);
+    let x = position.floor();
+    let t = position - x;
+
+    texel(x as u32).lerp(texel(x as u32 + 1), t)
+}
+
+fn linear_to_srgb(value: f64) -> f64 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.003_130_8 {
+        12.92 * value
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec2;
+
+    use super::*;
+    use crate::mandel_texture::DEFAULT_INTERIOR_COLOR;
+
+    #[test]
+    fn software_render_is_deterministic() {
+        let palette = Palette::builtin();
+        let size = UVec2::new(96, 64);
+        let frame_rect = DRect::from_center_size(DVec2::new(-0.74, 0.0), DVec2::new(3.75, 2.5));
+
+        let linear = PaletteMapping::Linear;
+        let image = render(frame_rect, size, &palette, linear, DEFAULT_INTERIOR_COLOR).unwrap();
+        assert_eq!(image.dimensions(), (size.x, size.y));
+        assert_eq!(
+            render(frame_rect, size, &palette, linear, DEFAULT_INTERIOR_COLOR).unwrap(),
+            image
+        );
+
+        // the center of the main cardioid is inside the set
+        let center = (size.x * 64 / 100, size.y / 2);
+        assert_eq!(image.get_pixel(center.0, center.1).0, [0, 0, 0, 255]);
+        let white_interior = render(frame_rect, size, &palette, linear, Vec3::ONE).unwrap();
+        assert_eq!(
+            white_interior.get_pixel(center.0, center.1).0,
+            [255, 255, 255, 255]
+        );
+        // escaped pixels get palette colors
+        assert!(image
+            .pixels()
+            .any(|pixel| pixel.0[..3].iter().any(|&c| c > 64)));
+    }
+
+    #[test]
+    fn log_mapping_spreads_low_counts() {
+        let spread = |mapping| palette_position(20.0, mapping) - palette_position(2.0, mapping);
+        assert!(spread(PaletteMapping::Log) > 2.0 * spread(PaletteMapping::Linear));
+
+        for mapping in PaletteMapping::ALL {
+            for iterations in [1.0, 1.5, 2.0, 767.0, 769.0, 5000.0, 36000.0] {
+                let u = palette_position(iterations, mapping);
+                assert!(
+                    (0.0..1.0).contains(&u),
+                    "{:?} {} {}",
+                    mapping,
+                    iterations,
+                    u
+                );
+            }
+            // the first pass ends where linear wraps around
+            assert!(palette_position(PALETTE_PERIOD + 0.5, mapping) > 0.99);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::recency::Recency;
+
+/// Computed tile values by what they were computed for, kept so a view that returns shows
+/// them without computing again. Once the values add up to more than the budget the least
+/// recently used ones go.
+#[derive(Debug)]
+pub struct TileCache<K> {
+    budget: usize,
+    used: usize,
+    entries: HashMap<K, Vec<u8>>,
+    recency: Recency<K>,
+}
+
+impl<K: Hash + Eq + Clone> TileCache<K> {
+    /// Keeps up to `budget` bytes of values, zero turns the cache off.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            used: 0,
+            entries: HashMap::new(),
+            recency: Recency::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.budget > 0
+    }
+
+    /// The values of `key`, which makes them the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&[u8]> {
+        let values = self.entries.get(key)?;
+        self.recency.touch(key);
+
+        Some(values)
+    }
+
+    /// Stores `values` as the most recently used, values larger than the whole budget
+    /// aren't kept.
+    pub fn insert(&mut self, key: K, values: Vec<u8>) {
+        self.remove(&key);
+        if values.len() > self.budget {
+            return;
+        }
+        while self.used + values.len() > self.budget {
+            let oldest = self
+                .recency
+                .pop_oldest()
+                .expect("the used bytes are in entries");
+            let values = self
+                .entries
+                .remove(&oldest)
+                .expect("every recency has an entry");
+            self.used -= values.len();
+        }
+
+        self.used += values.len();
+        self.recency.touch(&key);
+        self.entries.insert(key, values);
+    }
+
+    fn remove(&mut self, key: &K) {
+        if let Some(values) = self.entries.remove(key) {
+            self.recency.remove(key);
+            self.used -= values.len();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.used = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Bytes of values kept.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_recently_used_values_go_first() {
+        let mut cache = TileCache::new(10);
+        cache.insert("a", vec![1; 4]);
+        cache.insert("b", vec![2; 4]);
+        assert_eq!(cache.get(&"a"), Some(&[1u8; 4][..]));
+
+        // "b" wasn't looked up since "a" was, it makes room
+        cache.insert("c", vec![3; 4]);
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.used(), 8);
+
+        // replacing values frees the old ones first, "c" still fits
+        cache.insert("a", vec![4; 6]);
+        assert_eq!(cache.get(&"a"), Some(&[4u8; 6][..]));
+        assert_eq!(cache.get(&"c"), Some(&[3u8; 4][..]));
+        assert_eq!(cache.used(), 10);
+
+        // more than the budget isn't kept and doesn't evict anything
+        cache.insert("d", vec![5; 11]);
+        assert_eq!(cache.get(&"d"), None);
+        assert_eq!(cache.len(), 2);
+
+        cache.clear();
+        assert_eq!((cache.len(), cache.used()), (0, 0));
+        assert!(!TileCache::<u32>::new(0).is_enabled());
+    }
+}
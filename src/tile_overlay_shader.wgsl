@@ -0,0 +1,93 @@
+struct VertexOutput {
+    @location(0) tex_coord: vec2<f32> ,
+    @builtin(position) position: vec4<f32>,
+};
+
+
+struct PushConstant {
+    proj_mat: mat4x4<f32>,
+    texture_size: vec2<f32>,
+    dither: u32,
+    coloring_mode: u32,
+    no_data: u32,
+    palette_mapping: u32,
+    edge_detection: u32,
+    exposure: f32,
+    gamma: f32,
+    time: f32,
+    fade_duration: f32,
+    tile_size: u32,
+    newton: u32,
+    interior_color: vec4<f32>,
+};
+var<push_constant> pc: PushConstant;
+
+
+@vertex
+fn vs_main(
+    @location(0) position: vec4<f32>,
+    @location(1) tex_coord: vec2<f32>,
+) -> VertexOutput {
+    var result: VertexOutput;
+    result.position = pc.proj_mat * position;
+    result.tex_coord = tex_coord;
+
+    return result;
+}
+
+
+// per tile of the page, its state in the high nibble and the low bits of its generation below
+@group(0)
+@binding(3)
+var statuses: texture_2d<f32>;
+
+const GRID_COLOR: vec4<f32> = vec4<f32>(1.0, 1.0, 1.0, 0.5);
+
+// the tint of each state in the order `tile_status` numbers them, ready tiles are left clear
+fn state_color(state: u32) -> vec4<f32> {
+    switch state {
+        // idle
+        case 0u: {
+            return vec4<f32>(0.5, 0.5, 0.5, 0.4);
+        }
+        // queued
+        case 1u: {
+            return vec4<f32>(1.0, 0.9, 0.0, 0.4);
+        }
+        // computing
+        case 2u: {
+            return vec4<f32>(1.0, 0.35, 0.0, 0.45);
+        }
+        // waiting for upload
+        case 3u: {
+            return vec4<f32>(0.1, 0.3, 1.0, 0.4);
+        }
+        // failed
+        case 5u: {
+            return vec4<f32>(1.0, 0.0, 0.0, 0.6);
+        }
+        default: {
+            return vec4<f32>(0.0);
+        }
+    }
+}
+
+@fragment
+fn fs_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    // a line about a pixel wide along the tile borders, whatever the zoom
+    let tile_coord = vertex.tex_coord / f32(pc.tile_size);
+    let line_width = fwidth(tile_coord);
+    let on_border = any(fract(tile_coord) < line_width);
+
+    let last = textureDimensions(statuses) - 1u;
+    let cell = min(vec2<u32>(tile_coord), last);
+    let status = u32(round(textureLoad(statuses, cell, 0).r * 255.0));
+    var color = state_color(status >> 4u);
+    // successive generations in a shade darker, cycling every 16
+    color = vec4<f32>(color.rgb * (0.6 + 0.4 * f32(status & 15u) / 15.0), color.a);
+
+    if (on_border) {
+        return GRID_COLOR;
+    }
+    return color;
+}
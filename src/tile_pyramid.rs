@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use glam::{DVec2, I64Vec2};
+
+use crate::math::DRect;
+use crate::recency::Recency;
+
+/// Levels kept below the pages of a view, each with texels twice the size of the next.
+pub const PYRAMID_LEVELS: i32 = 3;
+
+/// A tile of the quadtree over the plane, like the tiles of a slippy map: level `level` has
+/// texels of `2^-level` per side and the tile at `coord` starts `coord` tiles from the origin.
+/// The four tiles of the next level split it exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PyramidKey {
+    pub level: i32,
+    pub coord: I64Vec2,
+}
+
+impl PyramidKey {
+    /// The part of the plane the tile covers with `tile_size` texels per side.
+    pub fn rect(self, tile_size: u32) -> DRect {
+        let size = tile_size as f64 * texel_size(self.level);
+
+        DRect::from_pos_size(self.coord.as_dvec2() * size, DVec2::splat(size))
+    }
+}
+
+pub fn texel_size(level: i32) -> f64 {
+    2f64.powi(-level)
+}
+
+/// The levels shown while the pages of `texel_size` compute, the coarsest first: the
+/// `PYRAMID_LEVELS` levels with texels at least twice as large. The level just below the pages
+/// would take nearly as long as the pages themselves.
+pub fn fallback_levels(texel_size: f64) -> impl Iterator<Item = i32> {
+    // the finest level with texels no smaller than the pages'
+    let finest = (-texel_size.log2()).floor() as i32 - 1;
+
+    finest - PYRAMID_LEVELS + 1..=finest
+}
+
+/// The tiles of `level` that `rect` overlaps, row by row from the lower left.
+pub fn covering_keys(rect: DRect, level: i32, tile_size: u32) -> impl Iterator<Item = PyramidKey> {
+    let size = tile_size as f64 * texel_size(level);
+    let first = (rect.pos / size).floor().as_i64vec2();
+    // a rect ending exactly on a tile edge doesn't overlap the tile after it
+    let last = ((rect.pos + rect.size) / size).ceil().as_i64vec2() - 1;
+
+    (first.y..=last.y).flat_map(move |y| {
+        (first.x..=last.x).map(move |x| PyramidKey {
+            level,
+            coord: I64Vec2::new(x, y),
+        })
+    })
+}
+
+/// The slots of a texture atlas by the key of the tile each holds. When all are taken a new
+/// key gets the least recently used slot, unless the current update used that one as well.
+#[derive(Debug)]
+pub struct AtlasSlots<K> {
+    capacity: usize,
+    // the recency of the first use in the current update
+    update_start: u64,
+    slots: HashMap<K, usize>,
+    recency: Recency<K>,
+}
+
+impl<K: Hash + Eq + Clone> AtlasSlots<K> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            update_start: 0,
+            slots: HashMap::new(),
+            recency: Recency::new(),
+        }
+    }
+
+    /// The keys looked up or inserted from here on keep their slots until the next update.
+    pub fn begin_update(&mut self) {
+        self.update_start = self.recency.tick() + 1;
+    }
+
+    /// The slot of `key`, which makes it the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<usize> {
+        let slot = *self.slots.get(key)?;
+        self.recency.touch(key);
+
+        Some(slot)
+    }
+
+    /// A slot for `key`, which isn't in the atlas yet: an unused one, or the least recently
+    /// used one, which loses its key. `None` when the current update uses every slot.
+    pub fn insert(&mut self, key: K) -> Option<usize> {
+        debug_assert!(!self.slots.contains_key(&key));
+        let slot = if self.slots.len() < self.capacity {
+            self.slots.len()
+        } else {
+            let (_, oldest) = self.recency.oldest()?;
+            if oldest >= self.update_start {
+                return None;
+            }
+            let oldest = self.recency.pop_oldest().expect("there is an oldest key");
+            self.slots
+                .remove(&oldest)
+                .expect("every recency has a slot")
+        };
+
+        self.recency.touch(&key);
+        self.slots.insert(key, slot);
+
+        Some(slot)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_split_their_tiles_in_four() {
+        // pages of 1/1000 per texel show the levels of 1/256, 1/128 and 1/64
+        let levels: Vec<i32> = fallback_levels(1.0 / 1000.0).collect();
+        assert_eq!(levels, [6, 7, 8]);
+        // exactly twice the page texels is the finest level
+        assert_eq!(fallback_levels(1.0 / 512.0).last(), Some(8));
+
+        let key = PyramidKey {
+            level: 2,
+            coord: I64Vec2::new(-1, 3),
+        };
+        assert_eq!(key.rect(4).pos, DVec2::new(-1.0, 3.0));
+        assert_eq!(key.rect(4).size, DVec2::ONE);
+        let children: Vec<PyramidKey> = covering_keys(key.rect(4), 3, 4).collect();
+        assert_eq!(children.len(), 4);
+        assert!(children
+            .iter()
+            .all(|child| child.rect(4).size == DVec2::splat(0.5)));
+        assert_eq!(children[0].coord, I64Vec2::new(-2, 6));
+        assert_eq!(children[3].coord, I64Vec2::new(-1, 7));
+
+        // one tile of 1 per side, the rect pokes into a second column
+        let rect = DRect::from_pos_size(DVec2::new(0.5, 0.25), DVec2::new(1.0, 0.5));
+        let keys: Vec<I64Vec2> = covering_keys(rect, 2, 4).map(|key| key.coord).collect();
+        assert_eq!(keys, [I64Vec2::new(0, 0), I64Vec2::new(1, 0)]);
+    }
+
+    #[test]
+    fn slots_of_the_current_update_are_kept() {
+        let mut slots = AtlasSlots::new(2);
+        slots.begin_update();
+        assert_eq!(slots.insert("a"), Some(0));
+        assert_eq!(slots.insert("b"), Some(1));
+        // both are shown, a third tile waits for room
+        assert_eq!(slots.insert("c"), None);
+
+        slots.begin_update();
+        assert_eq!(slots.get(&"b"), Some(1));
+        // "a" wasn't used by this update, "c" takes its slot
+        assert_eq!(slots.insert("c"), Some(0));
+        assert_eq!(slots.get(&"a"), None);
+        assert_eq!(slots.len(), 2);
+    }
+}
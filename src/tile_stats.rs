@@ -0,0 +1,253 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// buckets per doubling of the duration, a percentile is off by at most 2^(1/4), about 19%
+const BUCKETS_PER_OCTAVE: f64 = 4.0;
+// microseconds up to 2^32, over an hour
+const BUCKET_COUNT: usize = 32 * BUCKETS_PER_OCTAVE as usize;
+
+/// Durations in logarithmic buckets, recording is a few relaxed atomic adds so it stays on
+/// in release builds.
+#[derive(Debug)]
+pub struct Histogram {
+    buckets: [AtomicU64; BUCKET_COUNT],
+    max_us: AtomicU64,
+    total_us: AtomicU64,
+}
+
+/// What one phase of the tiles took so far, percentiles are rounded up to their bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PhaseSummary {
+    pub count: u64,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+    /// All of the durations added up.
+    pub total: Duration,
+}
+
+/// Where the time of the tile system goes, shared by the compute tasks and the render loop.
+#[derive(Debug, Default)]
+pub struct TileStats {
+    /// From scheduling until a worker picks the tile up.
+    pub queue_wait: Histogram,
+    /// Running the kernel, for tiles that were not cancelled.
+    pub compute: Histogram,
+    /// From the kernel finishing until the texture write.
+    pub upload: Histogram,
+    cancelled: AtomicU64,
+    wasted_us: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_lookups: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TileStatsSummary {
+    pub queue_wait: PhaseSummary,
+    pub compute: PhaseSummary,
+    pub upload: PhaseSummary,
+    pub cancelled: u64,
+    /// Kernel time spent on tiles that were cancelled before they were shown.
+    pub wasted: Duration,
+    /// Threads computing the tiles, zero where the stats don't know them.
+    pub workers: usize,
+    /// Tiles found in the tile cache instead of being computed.
+    pub cache_hits: u64,
+    /// Tiles looked up in the tile cache, none while it is off.
+    pub cache_lookups: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            max_us: AtomicU64::new(0),
+            total_us: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Histogram {
+    pub fn record(&self, duration: Duration) {
+        let us = duration.as_micros().min(u64::MAX as u128) as u64;
+        self.buckets[bucket_index(us)].fetch_add(1, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+        self.total_us.fetch_add(us, Ordering::Relaxed);
+    }
+
+    pub fn summary(&self) -> PhaseSummary {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .collect();
+        let count = counts.iter().sum();
+        let max = Duration::from_micros(self.max_us.load(Ordering::Relaxed));
+
+        // the bound of the bucket that holds the percentile, no more than the maximum
+        let percentile = |fraction: f64| {
+            let rank = ((count as f64 * fraction).ceil() as u64).max(1);
+            let mut seen = 0;
+            let index = counts
+                .iter()
+                .position(|&bucket| {
+                    seen += bucket;
+                    seen >= rank
+                })
+                .unwrap_or(BUCKET_COUNT - 1);
+
+            Duration::from_micros(bucket_bound(index)).min(max)
+        };
+
+        if count == 0 {
+            return PhaseSummary::default();
+        }
+        PhaseSummary {
+            count,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max,
+            total: Duration::from_micros(self.total_us.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+fn bucket_index(us: u64) -> usize {
+    (((us as f64 + 1.0).log2() * BUCKETS_PER_OCTAVE) as usize).min(BUCKET_COUNT - 1)
+}
+
+// the largest duration in microseconds that lands in bucket `index`
+fn bucket_bound(index: usize) -> u64 {
+    (2f64.powf((index + 1) as f64 / BUCKETS_PER_OCTAVE) - 1.0) as u64
+}
+
+impl TileStats {
+    pub fn record_cancelled(&self, count: usize) {
+        self.cancelled.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_wasted(&self, duration: Duration) {
+        self.wasted_us
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_cache_lookup(&self, hit: bool) {
+        self.cache_lookups.fetch_add(1, Ordering::Relaxed);
+        self.cache_hits.fetch_add(hit as u64, Ordering::Relaxed);
+    }
+
+    pub fn summary(&self) -> TileStatsSummary {
+        TileStatsSummary {
+            queue_wait: self.queue_wait.summary(),
+            compute: self.compute.summary(),
+            upload: self.upload.summary(),
+            cancelled: self.cancelled.load(Ordering::Relaxed),
+            wasted: Duration::from_micros(self.wasted_us.load(Ordering::Relaxed)),
+            workers: 0,
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_lookups: self.cache_lookups.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl TileStatsSummary {
+    /// Of the tile cache lookups, `None` before the first.
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        (self.cache_lookups > 0).then(|| self.cache_hits as f64 / self.cache_lookups as f64)
+    }
+}
+
+impl fmt::Display for PhaseSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "{} tiles, p50 {:.1}ms, p95 {:.1}ms, max {:.1}ms",
+            self.count,
+            ms(self.p50),
+            ms(self.p95),
+            ms(self.max)
+        )
+    }
+}
+
+impl fmt::Display for TileStatsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "workers:    {} threads", self.workers)?;
+        writeln!(f, "queue wait: {}", self.queue_wait)?;
+        writeln!(f, "compute:    {}", self.compute)?;
+        writeln!(f, "upload:     {}", self.upload)?;
+        writeln!(
+            f,
+            "cancelled:  {} tiles, {:.1}s of compute wasted",
+            self.cancelled,
+            self.wasted.as_secs_f64()
+        )?;
+        match self.cache_hit_rate() {
+            Some(rate) => write!(
+                f,
+                "cache:      {} of {} tiles found, {:.0}%",
+                self.cache_hits,
+                self.cache_lookups,
+                rate * 100.0
+            ),
+            None => write!(f, "cache:      no lookups"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_fall_in_their_bucket() {
+        let histogram = Histogram::default();
+        assert_eq!(histogram.summary(), PhaseSummary::default());
+
+        // 1ms to 100ms
+        for ms in 1..=100 {
+            histogram.record(Duration::from_millis(ms));
+        }
+        let summary = histogram.summary();
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.max, Duration::from_millis(100));
+        assert_eq!(summary.total, Duration::from_millis(5050));
+
+        let within_bucket = |actual: Duration, expected: f64| {
+            let ratio = actual.as_secs_f64() * 1000.0 / expected;
+            (1.0..1.2).contains(&ratio)
+        };
+        assert!(within_bucket(summary.p50, 50.0), "{:?}", summary);
+        assert!(within_bucket(summary.p95, 95.0), "{:?}", summary);
+
+        // zero and huge durations have buckets too
+        histogram.record(Duration::ZERO);
+        histogram.record(Duration::from_secs(1 << 40));
+        assert_eq!(histogram.summary().count, 102);
+    }
+
+    #[test]
+    fn summary_counts_cancelled_work() {
+        let stats = TileStats::default();
+        stats.compute.record(Duration::from_millis(4));
+        stats.record_cancelled(3);
+        stats.record_wasted(Duration::from_millis(1500));
+        stats.record_wasted(Duration::from_millis(500));
+        assert_eq!(stats.summary().cache_hit_rate(), None);
+        stats.record_cache_lookup(true);
+        stats.record_cache_lookup(false);
+        stats.record_cache_lookup(false);
+        stats.record_cache_lookup(true);
+
+        let summary = stats.summary();
+        assert_eq!(summary.compute.count, 1);
+        assert_eq!(summary.queue_wait.count, 0);
+        assert_eq!(summary.cancelled, 3);
+        assert_eq!(summary.wasted, Duration::from_secs(2));
+        assert!(summary.to_string().contains("3 tiles, 2.0s"));
+        assert_eq!(summary.cache_hit_rate(), Some(0.5));
+        assert!(summary.to_string().contains("2 of 4 tiles found, 50%"));
+    }
+}
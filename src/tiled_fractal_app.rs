@@ -1,143 +1,1190 @@
 #![allow(unused_parens)]
 
+use std::f64::consts::TAU;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::Context;
 use bytemuck::Zeroable;
 use glam::{DVec2, IVec2, UVec2};
 use parking_lot::Mutex;
-use tokio::runtime::Runtime;
-use winit::event_loop::EventLoopProxy;
+use serde::Deserialize;
+use web_time::Instant;
+use winit::event_loop::{EventLoopClosed, EventLoopProxy};
+use winit::keyboard::ModifiersState;
+use winit::window::WindowId;
 
+use crate::bindings::{Bindings, Gesture};
+use crate::buddhabrot_renderer::BuddhabrotRenderer;
+use crate::dropped_file::DroppedFile;
 use crate::env::is_debug_build;
-use crate::event::{ElementState, Event, EventResult, MouseButtons};
-use crate::mandel_texture::MandelTexture;
-use crate::math::DRect;
-use crate::{RenderContext, WindowContext};
+use crate::event::{CursorKind, ElementState, Event, EventResult, MouseButtons};
+use crate::fxaa::Fxaa;
+use crate::help_overlay::{key_label, HelpOverlay};
+use crate::julia_preview::{JuliaPreview, JuliaStyle, JULIA_FRAME_HEIGHT};
+use crate::mandel_texture::{ColoringMode, MandelTexture, TrimLevel};
+use crate::mandelbrot_simd::{EscapeMetric, FractalKind, MANDELBROT_POWER};
+use crate::math::{DRect, URect};
+use crate::palette::{Palette, PaletteMapping};
+use crate::presets::{Preset, PRESETS};
+use crate::progress_bar::ProgressBar;
+use crate::raw_export;
+use crate::runtime::{self, Runtime};
+use crate::settings::{Action, KeyBindings, Settings};
+use crate::tile_stats::TileStatsSummary;
+use crate::view_state::{ViewSlots, ViewState};
+use crate::{RenderContext, WindowContext, WINDOW_TITLE};
+
+// iteration difference between neighbor pixels that marks a pixel for anti-aliasing
+const AA_THRESHOLD: u16 = 8;
+
+pub(crate) const DEFAULT_CENTER: DVec2 = DVec2::new(-0.74, 0.0);
+pub(crate) const DEFAULT_FRAME_HEIGHT: f64 = 2.5;
+// below this fraction of the default frame height f64 can no longer resolve adjacent pixels
+pub(crate) const PRECISION_LIMIT_SCALE: f64 = 1e-13;
+// zooming out further only shows a dot, and the size would eventually overflow
+pub(crate) const MAX_FRAME_HEIGHT_SCALE: f64 = 1e3;
+
+// one line of scrolling zooms by ZOOM_BASE^(1/ZOOM_DIVISOR), a wheel step is three lines
+pub(crate) const ZOOM_BASE: f64 = 1.15;
+pub(crate) const ZOOM_DIVISOR: f64 = 5.0;
+// the zoom speed keys multiply or divide the zoom of a wheel step by this
+const ZOOM_SPEED_STEP: f64 = 1.25;
+// seconds over which a smoothed wheel step eases in
+const ZOOM_SMOOTHING: f64 = 0.1;
+// dragging this far with the zoom axis gesture zooms as much as one wheel step
+const ZOOM_AXIS_PIXELS_PER_STEP: f64 = 4.0;
+// zoom speed while Shift or Ctrl is held, for covering depth quickly or framing precisely
+const FAST_ZOOM_SPEED: f64 = 4.0;
+const FINE_ZOOM_SPEED: f64 = 0.25;
+// pan speed while Alt is held, a pixel of the window is many pixels deep in at extreme zoom
+const FINE_PAN_SPEED: f64 = 0.1;
+// smaller boxes are taken for a click rather than a box to zoom into
+const MIN_BOX_ZOOM_PIXELS: i32 = 4;
+// the progress cursor shows while more visible tiles than this are computing, the last few
+// finish too quickly for it to be worth a flicker
+const BUSY_CURSOR_TILES: usize = 8;
+// tiles the debug key lists by their compute time
+const SLOWEST_TILES_LOGGED: usize = 8;
+
+// the iteration limit from the zoom can be scaled within these factors, u16 escape counts
+// only hold a few times the highest limit
+pub(crate) const MIN_ITERATION_FACTOR: f64 = 1.0 / 8.0;
+pub(crate) const MAX_ITERATION_FACTOR: f64 = 8.0;
+// PageUp and PageDown scale the iteration limit by this, I and U by two
+const ITERATION_STEP: f64 = 1.5;
+// every change restarts the visible tiles, key repeats within this time are applied at once
+const ITERATION_SETTLE_TIME: Duration = Duration::from_millis(200);
+// how long the title shows the iteration limit after a change
+const ITERATION_FEEDBACK_TIME: Duration = Duration::from_secs(2);
+pub(crate) const DEFAULT_PERFORMANCE_SCALE: u32 = 2;
+// in performance mode the tiles are computed at full resolution once the view held still this long
+const PERFORMANCE_IDLE_TIME: Duration = Duration::from_millis(300);
+// views the previous view key steps back through, the oldest are dropped
+const MAX_VIEW_HISTORY: usize = 32;
+
+// exposure and gamma move in steps of their log2, so stepping back lands on one exactly,
+// and stay within these powers of two
+const EXPOSURE_STEP: f32 = 0.25;
+const MAX_EXPOSURE_LOG2: f32 = 4.0;
+const GAMMA_STEP: f32 = 0.125;
+const MAX_GAMMA_LOG2: f32 = 2.0;
+
+// the Multibrot morph sweeps the power back and forth between these
+pub(crate) const MORPH_MAX_POWER: f64 = 6.0;
+// full sweeps per second
+const MORPH_SPEED: f64 = 0.05;
+const MORPH_SPEED_STEP: f64 = 1.5;
+// every morph step recomputes all visible tiles, it waits for the previous step
+// and runs at most this often to leave the compute runtime room
+const MORPH_STEP_INTERVAL: f64 = 0.1;
+
+// flights to a preset take this long for every halving or doubling of the frame size
+const FLIGHT_SECONDS_PER_DOUBLING: f64 = 0.2;
+const MIN_FLIGHT_DURATION: f64 = 0.5;
+const MAX_FLIGHT_DURATION: f64 = 6.0;
+// a flight between distant spots rises until the frame is this many times their distance
+const FLIGHT_OVERVIEW_SCALE: f64 = 1.5;
+
+// the view turns by this much per key press, a whole turn takes `ROTATION_STEPS`
+const ROTATION_STEPS: f64 = 24.0;
+const ROTATION_STEP: f64 = std::f64::consts::TAU / ROTATION_STEPS;
+
+// `value` moved by `steps` steps of `step` in log2, rounded to the step grid
+fn log2_step(value: f32, step: f32, steps: f32, max_log2: f32) -> f32 {
+    let log2 = ((value.log2() / step).round() + steps) * step;
+    log2.clamp(-max_log2, max_log2).exp2()
+}
+
+fn aspect(window_size: UVec2) -> DVec2 {
+    let window_size = window_size.max(UVec2::ONE);
+    DVec2::new(window_size.x as f64 / window_size.y as f64, 1.0)
+}
+
+// the Mandelbrot and the Julia half of a split window, side by side
+fn split_halves(window_size: UVec2) -> (URect, URect) {
+    let left = UVec2::new((window_size.x / 2).max(1), window_size.y);
+    let right = UVec2::new(window_size.x.saturating_sub(left.x), window_size.y);
+
+    (
+        URect::from_pos_size(UVec2::ZERO, left),
+        URect::from_pos_size(UVec2::new(left.x, 0), right),
+    )
+}
+
+// the whole of every Julia set of the Mandelbrot set, in a half of `half_size`
+fn initial_julia_frame_rect(half_size: UVec2) -> DRect {
+    DRect::from_center_size(DVec2::ZERO, aspect(half_size) * JULIA_FRAME_HEIGHT)
+}
+
+// the view the app starts with and resets to, the whole set in a window of `window_size`
+fn initial_frame_rect(window_size: UVec2) -> DRect {
+    DRect::from_center_size(DEFAULT_CENTER, aspect(window_size) * DEFAULT_FRAME_HEIGHT)
+}
+
+/// The point of the view that stays put when the window is resized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeAnchor {
+    /// The center of the view.
+    #[default]
+    Center,
+    /// The point under the mouse cursor, at the pixel it was at; the center if the cursor
+    /// never was over the view.
+    Cursor,
+}
+
+// keeps the center and the vertical extent, the horizontal one follows the window aspect,
+// so repeated or degenerate (minimized) resizes don't accumulate error
+fn resized_frame_rect(frame_rect: DRect, window_size: UVec2) -> DRect {
+    DRect::from_center_size(frame_rect.center(), aspect(window_size) * frame_rect.size.y)
+}
+
+// keeps the vertical extent like `resized_frame_rect`, and the point at `position` of the old
+// window at the same pixel of the new one, or the nearest one inside it
+fn resized_frame_rect_at(
+    frame_rect: DRect,
+    rotation: f64,
+    old_size: UVec2,
+    window_size: UVec2,
+    position: UVec2,
+) -> DRect {
+    let point = frame_point(frame_rect, rotation, window_anchor(position, old_size));
+    let size = aspect(window_size) * frame_rect.size.y;
+    let position = position.min(window_size.saturating_sub(UVec2::ONE));
+    let anchor = window_anchor(position, window_size);
+
+    DRect::from_pos_size(point - turned(rotation, anchor * size) - 0.5 * size, size)
+}
+
+// the frame with the current aspect that just holds the box between two window positions,
+// centered on it; the box is upright on screen, so it is measured before the turn
+fn box_frame_rect(
+    frame_rect: DRect,
+    rotation: f64,
+    window_size: UVec2,
+    start: UVec2,
+    end: UVec2,
+) -> DRect {
+    let [start, end] = [start, end].map(|position| window_anchor(position, window_size));
+    let box_size = (end - start).abs() * frame_rect.size;
+    let aspect = aspect(window_size);
+
+    DRect::from_center_size(
+        frame_point(frame_rect, rotation, (start + end) / 2.0),
+        aspect * (box_size / aspect).max_element(),
+    )
+}
+
+// `anchor` and `pan` are fractions of the frame size from its center, the point at `anchor`
+// stays in place while the size is multiplied by `zoom`; the new frame is solved from that
+// point rather than from the center, so repeated steps don't let it drift by rounding
+fn zoomed_frame_rect(
+    frame_rect: DRect,
+    rotation: f64,
+    anchor: DVec2,
+    pan: DVec2,
+    zoom: f64,
+) -> DRect {
+    let new_size = frame_rect.size * zoom;
+    let anchor_point = frame_point(frame_rect, rotation, anchor);
+
+    DRect::from_pos_size(
+        anchor_point - turned(rotation, (anchor + pan) * new_size) - 0.5 * new_size,
+        new_size,
+    )
+}
+
+// the fractal coordinate at `anchor`, a fraction of the frame size from its center, with the
+// frame turned by `rotation` radians counterclockwise about its center
+fn frame_point(frame_rect: DRect, rotation: f64, anchor: DVec2) -> DVec2 {
+    frame_rect.pos + 0.5 * frame_rect.size + turned(rotation, anchor * frame_rect.size)
+}
+
+// `offset` turned by `rotation` radians counterclockwise, exactly itself without a turn
+fn turned(rotation: f64, offset: DVec2) -> DVec2 {
+    DVec2::from_angle(rotation).rotate(offset)
+}
+
+// fraction of the window size from its center, at the center of the pixel `position`
+// counted from the top left, the same point the screen shader samples
+fn window_anchor(position: UVec2, window_size: UVec2) -> DVec2 {
+    let from_bottom_left = DVec2::new(
+        position.x as f64 + 0.5,
+        window_size.y as f64 - position.y as f64 - 0.5,
+    );
+
+    from_bottom_left / window_size.as_dvec2() - 0.5
+}
+
+// start, top and destination of a flight as frame centers and heights; the top shows both
+// ends at once, so nothing streaks past between them
+fn flight_waypoints(from: (DVec2, f64), to: (DVec2, f64)) -> [(DVec2, f64); 3] {
+    let overview_height = from.0.distance(to.0) * FLIGHT_OVERVIEW_SCALE;
+    let top = if from.1 >= to.1.max(overview_height) {
+        from
+    } else if to.1 >= overview_height {
+        to
+    } else {
+        ((from.0 + to.0) / 2.0, overview_height)
+    };
+
+    [from, top, to]
+}
+
+// center and height at `t` from 0 to 1 of a flight; the height changes exponentially and the
+// center follows so that the point the view zooms into or out of keeps its place on screen
+fn flight_position(waypoints: &[(DVec2, f64); 3], t: f64) -> (DVec2, f64) {
+    let [from, top, to] = *waypoints;
+    let rise = (top.1 / from.1).ln();
+    let descent = (top.1 / to.1).ln();
+    // eases in and out
+    let t = t.clamp(0.0, 1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+
+    let (a, b, u) = if rise + descent <= f64::EPSILON {
+        (from, to, t)
+    } else if t * (rise + descent) < rise || descent <= 0.0 {
+        (from, top, (t * (rise + descent) / rise).min(1.0))
+    } else {
+        (top, to, (t * (rise + descent) - rise) / descent)
+    };
+
+    let height = a.1 * (b.1 / a.1).powf(u);
+    let along = if (a.1 - b.1).abs() > a.1.max(b.1) * 1e-9 {
+        (a.1 - height) / (a.1 - b.1)
+    } else {
+        u
+    };
+
+    (a.0 + (b.0 - a.0) * along, height)
+}
+
+fn flight_duration(waypoints: &[(DVec2, f64); 3]) -> f64 {
+    let [from, top, to] = *waypoints;
+    let doublings = (top.1 / from.1).log2() + (top.1 / to.1).log2();
+
+    (doublings * FLIGHT_SECONDS_PER_DOUBLING).clamp(MIN_FLIGHT_DURATION, MAX_FLIGHT_DURATION)
+}
+
+// limits `zoom` so the frame height stays between the precision limit and the zoom-out limit,
+// a frame already outside the limits isn't pushed further out; a zoom that isn't a finite
+// positive factor keeps the size
+fn clamped_zoom(frame_height: f64, zoom: f64) -> f64 {
+    if !zoom.is_finite() || zoom <= 0.0 {
+        return 1.0;
+    }
+
+    let min_height = DEFAULT_FRAME_HEIGHT * PRECISION_LIMIT_SCALE;
+    let max_height = DEFAULT_FRAME_HEIGHT * MAX_FRAME_HEIGHT_SCALE;
+    if frame_height * zoom < min_height {
+        (min_height / frame_height).min(1.0)
+    } else if frame_height * zoom > max_height {
+        (max_height / frame_height).max(1.0)
+    } else {
+        zoom
+    }
+}
+
+// zoom relative to the initial view and the center, with enough decimals to tell pixels apart
+fn window_title(
+    frame_rect: DRect,
+    rotation: f64,
+    window_height: u32,
+    power: f64,
+    present_mode: wgpu::PresentMode,
+    iterations: Option<u32>,
+) -> String {
+    let zoom = DEFAULT_FRAME_HEIGHT / frame_rect.size.y;
+    let zoom = if zoom < 1e5 {
+        format!("{:.1}x", zoom)
+    } else {
+        format!("{:.2e}x", zoom)
+    };
+
+    let pixel_size = frame_rect.size.y / window_height.max(1) as f64;
+    let decimals = (-pixel_size.log10()).ceil().clamp(1.0, 17.0) as usize;
+    let center = frame_rect.center();
+
+    let mut title = format!(
+        "{} - zoom {} at {:.*}, {:.*}",
+        WINDOW_TITLE, zoom, decimals, center.x, decimals, center.y
+    );
+    if rotation != 0.0 {
+        title += &format!(", turned {:.0}°", rotation.to_degrees());
+    }
+    if power != MANDELBROT_POWER {
+        title += &format!(", power {:.2}", power);
+    }
+    if let Some(iterations) = iterations {
+        title += &format!(", iterations {}", iterations);
+    }
+    title += &format!(", present {:?}", present_mode);
+
+    title
+}
+
+// the keys handled before the bindings, by the functions below
+const FIXED_SHORTCUTS: [(&str, &str); 4] = [
+    ("F11, Alt+Enter", "Fullscreen on or off"),
+    ("Ctrl+N", "New window at this view"),
+    ("0-9", "Recall the view in the slot"),
+    ("Ctrl+0-9", "Store the view in the slot"),
+];
+
+// the keys of every bound action and what they do, then the fixed ones
+fn shortcuts(key_bindings: &KeyBindings) -> Vec<(String, &'static str)> {
+    let bound = key_bindings.keys().into_iter().map(|(action, keys)| {
+        let labels: Vec<String> = keys.into_iter().map(key_label).collect();
+        (labels.join(", "), action.description())
+    });
+    let fixed = FIXED_SHORTCUTS
+        .into_iter()
+        .map(|(keys, description)| (keys.to_string(), description));
+
+    bound.chain(fixed).collect()
+}
+
+// held with other keys, releasing one doesn't hide the key list
+fn is_modifier(key: winit::keyboard::KeyCode) -> bool {
+    use winit::keyboard::KeyCode;
+
+    matches!(
+        key,
+        KeyCode::ShiftLeft
+            | KeyCode::ShiftRight
+            | KeyCode::ControlLeft
+            | KeyCode::ControlRight
+            | KeyCode::AltLeft
+            | KeyCode::AltRight
+            | KeyCode::SuperLeft
+            | KeyCode::SuperRight
+    )
+}
+
+// F11 or Alt+Enter
+fn is_fullscreen_shortcut(key: winit::keyboard::KeyCode, modifiers: ModifiersState) -> bool {
+    match key {
+        winit::keyboard::KeyCode::F11 => true,
+        winit::keyboard::KeyCode::Enter | winit::keyboard::KeyCode::NumpadEnter => {
+            modifiers == ModifiersState::ALT
+        }
+        _ => false,
+    }
+}
+
+// Ctrl+N, the new window starts out at the view of this one
+fn is_new_window_shortcut(key: winit::keyboard::KeyCode, modifiers: ModifiersState) -> bool {
+    key == winit::keyboard::KeyCode::KeyN && modifiers == ModifiersState::CONTROL
+}
+
+// the view slot of a digit key and whether it stores rather than recalls, with Ctrl it stores
+fn view_slot_key(
+    key: winit::keyboard::KeyCode,
+    modifiers: ModifiersState,
+) -> Option<(usize, bool)> {
+    use winit::keyboard::KeyCode;
+
+    let slot = [
+        KeyCode::Digit0,
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ]
+    .iter()
+    .position(|&digit| digit == key)?;
+
+    match modifiers {
+        ModifiersState::CONTROL => Some((slot, true)),
+        modifiers if modifiers.is_empty() => Some((slot, false)),
+        _ => None,
+    }
+}
+
+// pan and zoom speed factors of the held modifiers, holding both Shift and Ctrl cancels out
+fn modifier_speeds(modifiers: ModifiersState) -> (f64, f64) {
+    let mut zoom_speed = 1.0;
+    if modifiers.shift_key() {
+        zoom_speed *= FAST_ZOOM_SPEED;
+    }
+    if modifiers.control_key() {
+        zoom_speed *= FINE_ZOOM_SPEED;
+    }
+    let pan_speed = if modifiers.alt_key() {
+        FINE_PAN_SPEED
+    } else {
+        1.0
+    };
+
+    (pan_speed, zoom_speed)
+}
+
+// the part of `pending` scroll to apply after `elapsed` seconds of easing over `duration`,
+// all of it once little is left; the approach is exponential, so it doesn't depend on the
+// frame rate, and nearly done by the end of `duration`
+fn eased_scroll_step(pending: f64, elapsed: f64, duration: f64) -> f64 {
+    if duration <= 0.0 {
+        return pending;
+    }
+
+    let step = pending * (1.0 - (-4.0 * elapsed / duration).exp());
+    if (pending - step).abs() < 0.01 {
+        pending
+    } else {
+        step
+    }
+}
+
+// power of the sweep, starts and turns back at the Mandelbrot power
+fn morph_power(phase: f64) -> f64 {
+    let t = 0.5 - 0.5 * (TAU * phase).cos();
+    MANDELBROT_POWER + (MORPH_MAX_POWER - MANDELBROT_POWER) * t
+}
+
+// the gesture in progress decides, the busy state only shows between gestures
+fn cursor_kind(manipulate_state: &ManipulateState, computing_tiles: usize) -> CursorKind {
+    match manipulate_state {
+        ManipulateState::Drag => CursorKind::Grabbing,
+        ManipulateState::BoxZoom { .. } => CursorKind::Crosshair,
+        ManipulateState::ZoomAxis { .. } => CursorKind::ResizeVertical,
+        ManipulateState::Idle if computing_tiles > BUSY_CURSOR_TILES => CursorKind::Progress,
+        ManipulateState::Idle => CursorKind::Default,
+    }
+}
 
 enum ManipulateState {
     Idle,
     Drag,
+    // the box starts where the button was pressed, `offset` follows the cursor from there
+    BoxZoom { start: UVec2, offset: IVec2 },
+    ZoomAxis { anchor: UVec2 },
+}
+
+struct Flight {
+    waypoints: [(DVec2, f64); 3],
+    duration: f64,
+    // render time of the first step
+    start_time: Option<f64>,
+}
+
+struct Morph {
+    // number of sweeps done, advances with render time
+    phase: f64,
+    last_time: Option<f64>,
+    last_step_time: f64,
 }
 
 pub struct TiledFractalApp {
+    // the part of the window the Mandelbrot set is in, all of `surface_size` unless split
     window_size: UVec2,
-    event_loop_proxy: Arc<Mutex<EventLoopProxy<UserEvent>>>,
+    surface_size: UVec2,
+    event_loop_proxy: Arc<Mutex<AppEventProxy>>,
     runtime: Runtime,
 
     manipulate_state: ManipulateState,
+    modifiers: ModifiersState,
+    // where the mouse last moved over the window, resizes keep the point there with
+    // `ResizeAnchor::Cursor`
+    cursor_position: Option<UVec2>,
+    resize_anchor: ResizeAnchor,
+    // last one sent to the window
+    cursor: CursorKind,
 
     frame_rect: DRect,
+    // radians the view is turned by counterclockwise, a whole number of `ROTATION_STEP`s
+    rotation: f64,
     aspect: DVec2,
+    precision_limit_reached: bool,
+    // physical pixels per logical pixel of the monitor the window is on, overlays multiply
+    // their sizes by it to keep the same physical size on every monitor
+    scale_factor: f64,
+
+    pub zoom_base: f64,
+    pub zoom_divisor: f64,
+    pub zoom_smoothing: Option<f64>,
+    pending_scroll: f64,
+    pending_scroll_position: UVec2,
+    last_scroll_step: Option<Instant>,
+    key_bindings: KeyBindings,
+    mouse_bindings: Bindings,
+
+    pub morph_speed: f64,
+    morph: Option<Morph>,
+
+    flight: Option<Flight>,
+    // last preset flown to with L
+    preset_index: Option<usize>,
+    // stored with Ctrl and a digit, saved with the last view when the app closes
+    view_slots: ViewSlots,
+    // the views left by recalling a slot, the last one first
+    view_history: Vec<ViewState>,
+
+    // tiles are computed neither while paused with P, nor while the app is in the background,
+    // nor while the window is minimized
+    paused: bool,
+    suspended: bool,
+    minimized: bool,
+
+    // the configured iteration factor and palette mapping, resetting the view goes back to them
+    default_iteration_factor: f64,
+    default_palette_mapping: PaletteMapping,
+    // iteration factor chosen with the keys and when to apply it
+    pending_iteration_factor: Option<(f64, Instant)>,
+    // the title shows the iteration limit until then
+    iteration_feedback_until: Option<Instant>,
+
+    // coarse tiles while the view moves, the render scale of those
+    performance_mode: bool,
+    performance_scale: u32,
+    // the view moved in performance mode, back to full resolution then
+    full_resolution_at: Option<Instant>,
+    // the redraw already requested for that, the deadline moves with every step
+    full_resolution_wakeup: Option<Instant>,
+
+    // what the window title shows, it is only formatted again when this changes
+    title_state: Option<(DRect, f64, u32, f64, wgpu::PresentMode, Option<u32>)>,
+    // the surface belongs to the event loop, it reports the mode for the title
+    present_mode: wgpu::PresentMode,
+    // the last palette file dropped on the window, saved with the view in place of the
+    // one the app was started with
+    dropped_palette: Option<PathBuf>,
 
     mandel_texture: MandelTexture,
+    fxaa: Fxaa,
+    buddhabrot: BuddhabrotRenderer,
+    // Buddhabrot workers, the tile workers are busy with their own runtime
+    buddhabrot_workers: usize,
+    progress_bar: ProgressBar,
+    help: HelpOverlay,
+    // the Julia set of the point under the cursor in the right half of a split window
+    julia: JuliaPreview,
+    // a drag that started over the Julia half pans it until the button is released
+    julia_drag: bool,
 }
 
 #[derive(Debug)]
 pub enum UserEvent {
     Redraw,
-    TileReady { tile_index: usize },
+    TileReady {
+        tile_index: usize,
+    },
+    TileFailed {
+        tile_index: usize,
+        reason: String,
+    },
+    ExportFinished {
+        path: PathBuf,
+        result: Result<(), String>,
+    },
+    TitleChanged(String),
+    CursorChanged(CursorKind),
+    FileLoaded {
+        path: PathBuf,
+        result: Result<DroppedFile, String>,
+    },
+    /// Returns to the initial view like the reset key, for other ways of asking for it.
+    ResetView,
+    BuddhabrotProgress {
+        generation: u64,
+        samples: u64,
+    },
+}
+
+/// A `UserEvent` for the app of one window, every window explores on its own.
+#[derive(Debug)]
+pub struct AppEvent {
+    pub window_id: WindowId,
+    pub event: UserEvent,
+}
+
+/// Sends the events of the app of `window_id` to the event loop.
+#[derive(Debug, Clone)]
+pub struct AppEventProxy {
+    proxy: EventLoopProxy<AppEvent>,
+    window_id: WindowId,
+}
+
+impl AppEventProxy {
+    pub fn new(proxy: EventLoopProxy<AppEvent>, window_id: WindowId) -> Self {
+        Self { proxy, window_id }
+    }
+
+    /// Hands the event back if the loop is gone.
+    pub fn send_event(&self, event: UserEvent) -> Result<(), EventLoopClosed<UserEvent>> {
+        self.proxy
+            .send_event(AppEvent {
+                window_id: self.window_id,
+                event,
+            })
+            .map_err(|closed| EventLoopClosed(closed.0.event))
+    }
+}
+
+// what a saved view holds besides the frame
+fn apply_view_settings(mandel_texture: &mut MandelTexture, view: &ViewState) {
+    mandel_texture.set_iteration_factor(view.iteration_factor);
+    mandel_texture.set_power(view.power);
+    mandel_texture.set_escape_metric(view.escape_metric);
+    mandel_texture.set_coloring_mode(view.coloring_mode);
+    mandel_texture.set_palette_mapping(view.palette_mapping);
+    mandel_texture.set_kind(view.fractal_kind());
+    mandel_texture.set_rotation(view.rotation);
+}
+
+// what the reset key puts back besides the frame, every tile is computed anew
+fn reset_view_settings(
+    mandel_texture: &mut MandelTexture,
+    iteration_factor: f64,
+    palette_mapping: PaletteMapping,
+) {
+    mandel_texture.invalidate();
+    mandel_texture.set_kind(FractalKind::Mandelbrot);
+    mandel_texture.set_iteration_factor(iteration_factor);
+    mandel_texture.set_power(MANDELBROT_POWER);
+    mandel_texture.set_escape_metric(EscapeMetric::default());
+    mandel_texture.set_coloring_mode(ColoringMode::default());
+    mandel_texture.set_palette_mapping(palette_mapping);
+    mandel_texture.set_exposure(1.0);
+    mandel_texture.set_gamma(1.0);
+    mandel_texture.set_rotation(0.0);
 }
 
 impl TiledFractalApp {
     pub fn new(
         window_state: &WindowContext,
-        event_loop_proxy: EventLoopProxy<UserEvent>,
-    ) -> TiledFractalApp {
+        event_loop_proxy: AppEventProxy,
+        settings: &Settings,
+        palette: &Palette,
+        view: Option<&ViewState>,
+        view_slots: ViewSlots,
+    ) -> anyhow::Result<TiledFractalApp> {
         let window_size = UVec2::new(
             window_state.surface_config.width,
             window_state.surface_config.height,
         );
 
-        let mandel_texture = MandelTexture::new(
+        let mut mandel_texture = MandelTexture::new(
             &window_state.device,
             &window_state.queue,
             &window_state.surface_config,
             window_size,
+            settings,
+            palette,
+        )?;
+
+        let mut fxaa = Fxaa::new(
+            &window_state.device,
+            window_state.surface_config.view_formats[0],
+            settings.fxaa_edge_threshold,
+        );
+        fxaa.enabled = settings.fxaa;
+        let buddhabrot = BuddhabrotRenderer::new(
+            &window_state.device,
+            window_state.surface_config.view_formats[0],
+            settings.buddhabrot_samples,
+        );
+        let progress_bar = ProgressBar::new(
+            &window_state.device,
+            window_state.surface_config.view_formats[0],
+        );
+        let help = HelpOverlay::new(
+            &window_state.device,
+            window_state.surface_config.view_formats[0],
+            shortcuts(&settings.keys),
+        );
+        let julia = JuliaPreview::new(
+            &window_state.device,
+            window_state.surface_config.view_formats[0],
+            palette,
         );
 
-        let aspect = DVec2::new(window_size.x as f64 / window_size.y as f64, 1.0);
-        let frame_rect = DRect::from_center_size(DVec2::new(-0.74, 0.0), aspect * 2.5);
+        let aspect = aspect(window_size);
+        let mut frame_rect = initial_frame_rect(window_size);
+        if let Some(view) = view {
+            frame_rect = view.frame_rect(aspect);
+            apply_view_settings(&mut mandel_texture, view);
+        }
 
         let mut result = Self {
             window_size,
+            surface_size: window_size,
             event_loop_proxy: Arc::new(Mutex::new(event_loop_proxy)),
-            runtime: Runtime::new().unwrap(),
+            // timers and callbacks only, the tiles are computed on the runtime of `mandel_texture`
+            runtime: Runtime::new(1, "fractal-events")
+                .context("Failed to start the event runtime")?,
 
             manipulate_state: ManipulateState::Idle,
+            modifiers: ModifiersState::empty(),
+            cursor_position: None,
+            resize_anchor: settings.resize_anchor,
+            cursor: CursorKind::Default,
 
             frame_rect,
+            rotation: view.map_or(0.0, |view| view.rotation),
             aspect,
+            precision_limit_reached: false,
+            scale_factor: window_state.window.scale_factor(),
+
+            zoom_base: settings.zoom_base,
+            zoom_divisor: settings.zoom_divisor / settings.zoom_speed,
+            zoom_smoothing: settings.zoom_smoothing.then_some(ZOOM_SMOOTHING),
+            key_bindings: settings.keys.clone(),
+            mouse_bindings: settings.mouse.clone(),
+            pending_scroll: 0.0,
+            pending_scroll_position: UVec2::ZERO,
+            last_scroll_step: None,
+
+            morph_speed: MORPH_SPEED,
+            morph: None,
+
+            flight: None,
+            preset_index: None,
+            view_slots,
+            view_history: Vec::new(),
+
+            paused: false,
+            suspended: false,
+            minimized: false,
+
+            default_iteration_factor: settings.iteration_factor,
+            default_palette_mapping: settings.palette_mapping,
+            pending_iteration_factor: None,
+            iteration_feedback_until: None,
+
+            performance_mode: settings.performance_mode,
+            performance_scale: settings.performance_scale,
+            full_resolution_at: None,
+            full_resolution_wakeup: None,
+
+            title_state: None,
+            present_mode: window_state.surface_config.present_mode,
+            dropped_palette: None,
 
             mandel_texture,
+            fxaa,
+            buddhabrot,
+            buddhabrot_workers: settings.worker_count(),
+            progress_bar,
+            help,
+            julia,
+            julia_drag: false,
         };
         result.update_fractal(result.frame_rect.center());
-        return result;
+        Ok(result)
+    }
+
+    /// What to restore on the next start, `palette` is the one the app was started with
+    /// unless another one was dropped on the window since.
+    pub fn view_state(&self, palette: Option<PathBuf>) -> ViewState {
+        let center = self.frame_rect.center();
+        let (kind, julia_c) = ViewState::kind_fields(self.mandel_texture.kind());
+
+        ViewState {
+            center_x: center.x,
+            center_y: center.y,
+            height: self.frame_rect.size.y,
+            iteration_factor: self.iteration_factor(),
+            power: self.mandel_texture.power(),
+            escape_metric: self.mandel_texture.escape_metric(),
+            coloring_mode: self.mandel_texture.coloring_mode(),
+            palette_mapping: self.mandel_texture.palette_mapping(),
+            kind,
+            julia_c,
+            rotation: self.rotation,
+            palette: self.dropped_palette.clone().or(palette),
+        }
+    }
+
+    pub fn view_slots(&self) -> &ViewSlots {
+        &self.view_slots
+    }
+
+    // the frame and the fractal, the palette stays whatever it is when the slot is recalled
+    fn store_view_slot(&mut self, slot: usize) -> EventResult {
+        let view = ViewState {
+            palette: None,
+            ..self.view_state(None)
+        };
+        self.view_slots.set(slot, view);
+        tracing::info!("Stored the view in slot {}", slot);
+
+        EventResult::Continue
+    }
+
+    fn recall_view_slot(&mut self, slot: usize) -> EventResult {
+        let Some(view) = self.view_slots.get(slot).cloned() else {
+            tracing::info!(
+                "View slot {} is empty, Ctrl+{} stores the view there",
+                slot,
+                slot
+            );
+            return EventResult::Continue;
+        };
+
+        if self.view_history.len() == MAX_VIEW_HISTORY {
+            self.view_history.remove(0);
+        }
+        self.view_history.push(self.view_state(None));
+        tracing::info!("Going to the view in slot {}", slot);
+        self.go_to_view(&view)
+    }
+
+    fn previous_view(&mut self) -> EventResult {
+        match self.view_history.pop() {
+            Some(view) => self.go_to_view(&view),
+            None => EventResult::Continue,
+        }
+    }
+
+    // flies back to the upright overview of the plain Mandelbrot set with the configured
+    // iteration limit and palette mapping and the default coloring, every tile is computed
+    // anew on the way
+    fn reset_view(&mut self) -> EventResult {
+        self.morph = None;
+        self.pending_iteration_factor = None;
+        reset_view_settings(
+            &mut self.mandel_texture,
+            self.default_iteration_factor,
+            self.default_palette_mapping,
+        );
+        self.julia.frame_rect = initial_julia_frame_rect(split_halves(self.surface_size).1.size);
+        self.rotation = 0.0;
+        tracing::info!("View reset");
+        self.animate_to(initial_frame_rect(self.window_size))
+    }
+
+    /// Magnification relative to the initial view, as `--zoom` takes it.
+    pub fn zoom(&self) -> f64 {
+        DEFAULT_FRAME_HEIGHT / self.frame_rect.size.y
+    }
+
+    /// Where the time of the tiles went since the start.
+    pub fn tile_stats(&self) -> TileStatsSummary {
+        self.mandel_texture.tile_stats()
+    }
+
+    /// Physical pixels per logical pixel, for sizing text and boxes drawn over the fractal.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Called once the surface switched to `present_mode`, it is shown in the title.
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) {
+        self.present_mode = present_mode;
+    }
+
+    // the factor the keys step from, including one not applied yet
+    fn iteration_factor(&self) -> f64 {
+        self.pending_iteration_factor
+            .map_or(self.mandel_texture.iteration_factor(), |(factor, _)| factor)
+    }
+
+    // the title follows right away, the tiles restart once the keys are released
+    // or stop repeating for a moment
+    fn set_iteration_factor(&mut self, iteration_factor: f64) -> EventResult {
+        let iteration_factor = iteration_factor.clamp(MIN_ITERATION_FACTOR, MAX_ITERATION_FACTOR);
+        if iteration_factor == self.iteration_factor() {
+            return EventResult::Continue;
+        }
+
+        let now = Instant::now();
+        self.pending_iteration_factor = Some((iteration_factor, now + ITERATION_SETTLE_TIME));
+        self.iteration_feedback_until = Some(now + ITERATION_FEEDBACK_TIME);
+        self.redraw_after(ITERATION_SETTLE_TIME);
+        self.redraw_after(ITERATION_FEEDBACK_TIME);
+        EventResult::Redraw
+    }
+
+    // raising the limit continues the visible tiles, lowering it recomputes them
+    fn apply_iteration_factor(&mut self) {
+        let Some((iteration_factor, deadline)) = self.pending_iteration_factor else {
+            return;
+        };
+        if Instant::now() < deadline {
+            return;
+        }
+        self.pending_iteration_factor = None;
+
+        if iteration_factor != self.mandel_texture.iteration_factor() {
+            self.mandel_texture.set_iteration_factor(iteration_factor);
+            tracing::info!(
+                "Iteration limit x{:.2}: {}",
+                iteration_factor,
+                self.mandel_texture.max_iters()
+            );
+            self.update_fractal(self.frame_rect.center());
+        }
+    }
+
+    // coarse until the view holds still
+    fn lower_render_scale(&mut self) {
+        if !self.performance_mode {
+            return;
+        }
+
+        self.mandel_texture.set_render_scale(self.performance_scale);
+        self.full_resolution_at = Some(Instant::now() + PERFORMANCE_IDLE_TIME);
+    }
+
+    fn apply_full_resolution(&mut self) {
+        let Some(deadline) = self.full_resolution_at else {
+            return;
+        };
+        let now = Instant::now();
+        if now < deadline {
+            // a single redraw is pending at a time, it finds the deadline moved if the view did
+            if self
+                .full_resolution_wakeup
+                .is_none_or(|wakeup| wakeup <= now)
+            {
+                self.full_resolution_wakeup = Some(deadline);
+                self.redraw_after(deadline - now);
+            }
+            return;
+        }
+        self.full_resolution_at = None;
+        self.full_resolution_wakeup = None;
+
+        self.mandel_texture.set_render_scale(1);
+        self.update_fractal(self.frame_rect.center());
+    }
+
+    fn redraw_after(&self, delay: Duration) {
+        let event_loop_proxy = self.event_loop_proxy.clone();
+        self.runtime.spawn(async move {
+            runtime::sleep(delay).await;
+            let _ = event_loop_proxy.lock().send_event(UserEvent::Redraw);
+        });
+    }
+
+    /// Frees GPU memory by rendering at a lower resolution, false if it is already the lowest.
+    pub fn shrink_texture(&mut self, device: &wgpu::Device) -> bool {
+        if !self.mandel_texture.shrink_texture(device) {
+            return false;
+        }
+
+        self.update_fractal(self.frame_rect.center());
+        true
+    }
+
+    /// Sheds memory on a warning from the system, the visible part is computed again if needed.
+    pub fn trim_memory(&mut self, device: &wgpu::Device, level: TrimLevel) -> EventResult {
+        if level == TrimLevel::Textures {
+            self.fxaa.release_target();
+        }
+        self.mandel_texture.trim_memory(device, level);
+        self.update_fractal(self.frame_rect.center());
+        EventResult::Redraw
+    }
+
+    /// Starts over with fresh GPU resources after a device error, the view is kept.
+    pub fn recover(&mut self) {
+        self.fxaa.release_target();
+        self.mandel_texture.rebuild();
+        self.update_fractal(self.frame_rect.center());
     }
 
     pub fn update(&mut self, event: Event<UserEvent>) -> EventResult {
+        let result = self.handle_event(event);
+        // every gesture ends with an event, a release outside the window included
+        self.update_cursor();
+
+        result
+    }
+
+    fn handle_event(&mut self, event: Event<UserEvent>) -> EventResult {
+        if let Event::MouseMove { position, .. } = event {
+            self.cursor_position = Some(position);
+        }
+        let event = match self.split_view_event(event) {
+            ControlFlow::Break(result) => return result,
+            ControlFlow::Continue(event) => event,
+        };
+
         match event {
             Event::WindowClose => EventResult::Exit,
+            Event::FileDropped(path) => {
+                self.load_dropped_file(path);
+                EventResult::Continue
+            }
+            // the resize that usually comes with it updates the frame
+            Event::ScaleFactorChanged(scale_factor) => {
+                self.scale_factor = scale_factor;
+                EventResult::Redraw
+            }
+            // the frame keeps the size it had, there's nothing to see until the window is restored
+            Event::Resized(window_size) if window_size.min_element() == 0 => {
+                self.minimized = true;
+                self.apply_paused();
+
+                EventResult::Continue
+            }
             Event::Resized(window_size) => {
-                if self.window_size == window_size {
-                    return EventResult::Continue;
+                let restored = std::mem::take(&mut self.minimized);
+                if restored {
+                    self.apply_paused();
                 }
+                self.surface_size = window_size;
 
-                self.frame_rect = DRect::from_center_size(
-                    self.frame_rect.center(),
-                    self.frame_rect.size * DVec2::from(window_size) / DVec2::from(self.window_size),
-                );
-                self.window_size = window_size;
-                self.mandel_texture.resize_window(window_size);
+                match self.resize_view() {
+                    EventResult::Continue if restored => EventResult::Redraw,
+                    result => result,
+                }
+            }
 
-                self.update_fractal(self.frame_rect.center());
+            Event::MouseWheel(position, delta) => self.scroll(position, 3.0 * delta),
+            Event::PinchZoom {
+                position,
+                delta,
+                scale_factor,
+            } => {
+                // spreading the fingers enlarges the picture, so the frame shrinks
+                if scale_factor > 0.0 {
+                    self.move_zoom(position, delta.as_dvec2(), 1.0 / scale_factor);
+                }
 
                 EventResult::Redraw
             }
-
-            Event::MouseWheel(position, delta) => {
-                self.move_scale(position, IVec2::zeroed(), 3.0 * delta);
+            Event::TouchpadScroll(position, pan, lines) => {
+                if pan != IVec2::ZERO {
+                    self.move_scale(position, pan, 0.0);
+                }
+                if lines != 0.0 {
+                    self.scroll(position, 3.0 * lines);
+                }
 
                 EventResult::Redraw
             }
-            Event::MouseMove { position, delta } => match self.manipulate_state {
+            // the frames of animations follow from `is_animating`
+            Event::RedrawFinished => self.smooth_scroll_step(),
+            Event::MouseMove { position, delta } => match &mut self.manipulate_state {
                 ManipulateState::Idle => EventResult::Continue,
                 ManipulateState::Drag => {
                     self.move_scale(position, delta, 0.0);
 
                     EventResult::Redraw
                 }
-            },
-            Event::MouseButton(btn, state, _position) => match (btn, state) {
-                (MouseButtons::Left, ElementState::Pressed) => {
-                    self.manipulate_state = ManipulateState::Drag;
+                ManipulateState::BoxZoom { offset, .. } => {
+                    *offset += delta;
                     EventResult::Continue
                 }
-                _ => {
-                    self.manipulate_state = ManipulateState::Idle;
-                    EventResult::Continue
+                &mut ManipulateState::ZoomAxis { anchor } => {
+                    self.move_scale(
+                        anchor,
+                        IVec2::ZERO,
+                        delta.y as f64 / ZOOM_AXIS_PIXELS_PER_STEP,
+                    );
+
+                    EventResult::Redraw
                 }
             },
-            Event::KeyboardInput(key) => {
-                if !is_debug_build() {
-                    return EventResult::Continue;
-                }
+            Event::MouseButton(button, ElementState::Pressed, position) => {
+                let gesture = match button {
+                    MouseButtons::Touch => Some(Gesture::Pan),
+                    button => self.mouse_bindings.gesture(button, self.modifiers),
+                };
+
+                self.manipulate_state = match gesture {
+                    Some(Gesture::Pan) => ManipulateState::Drag,
+                    Some(Gesture::BoxZoom) => ManipulateState::BoxZoom {
+                        start: position,
+                        offset: IVec2::ZERO,
+                    },
+                    Some(Gesture::ZoomAxis) => ManipulateState::ZoomAxis { anchor: position },
+                    Some(Gesture::ContextAction | Gesture::Inspect) | None => ManipulateState::Idle,
+                };
 
+                if gesture == Some(Gesture::ContextAction) {
+                    let anchor = window_anchor(position, self.window_size);
+                    let center = frame_point(self.frame_rect, self.rotation, anchor);
+                    return self.animate_to(DRect::from_center_size(center, self.frame_rect.size));
+                }
+                if gesture == Some(Gesture::Inspect) {
+                    self.inspect(position);
+                }
+                EventResult::Continue
+            }
+            Event::MouseButton(_, ElementState::Released, _) => {
+                match std::mem::replace(&mut self.manipulate_state, ManipulateState::Idle) {
+                    ManipulateState::BoxZoom { start, offset } => self.box_zoom(start, offset),
+                    _ => EventResult::Continue,
+                }
+            }
+            Event::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+                EventResult::Continue
+            }
+            // the release of a modifier held while switching away goes to the other window
+            Event::FocusLost => {
+                self.modifiers = ModifiersState::empty();
+                EventResult::Continue
+            }
+            Event::KeyboardInput(key) => {
                 if key.state != winit::event::ElementState::Released {
                     return EventResult::Continue;
                 }
+                // any key only hides the key list
+                if self.help.visible {
+                    return match key.physical_key {
+                        winit::keyboard::PhysicalKey::Code(code) if is_modifier(code) => {
+                            EventResult::Continue
+                        }
+                        _ => {
+                            self.help.visible = false;
+                            EventResult::Redraw
+                        }
+                    };
+                }
 
                 match key.physical_key {
-                    winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyA) => {
-                        let count = self.mandel_texture.buf_pool.taken_buffer_count();
-                        println!("Taken buffer count: {}", count);
-                        EventResult::Continue
+                    winit::keyboard::PhysicalKey::Code(code)
+                        if is_fullscreen_shortcut(code, self.modifiers) =>
+                    {
+                        EventResult::ToggleFullscreen
                     }
-                    winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyS) => {
-                        EventResult::Redraw
+                    winit::keyboard::PhysicalKey::Code(code)
+                        if is_new_window_shortcut(code, self.modifiers) =>
+                    {
+                        EventResult::NewWindow
                     }
-                    winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyD) => {
-                        self.update_fractal(self.frame_rect.center());
-                        EventResult::Redraw
+                    winit::keyboard::PhysicalKey::Code(code) => {
+                        match self.key_bindings.action(code) {
+                            Some(action) => self.key_action(action),
+                            None => match view_slot_key(code, self.modifiers) {
+                                Some((slot, true)) => self.store_view_slot(slot),
+                                Some((slot, false)) => self.recall_view_slot(slot),
+                                None if is_debug_build() => self.debug_key_input(key.physical_key),
+                                None => EventResult::Continue,
+                            },
+                        }
                     }
                     _ => EventResult::Continue,
                 }
@@ -149,54 +1196,1455 @@ impl TiledFractalApp {
         }
     }
 
-    pub fn render(&mut self, render_info: &RenderContext) {
-        self.mandel_texture.render(render_info);
-    }
-
-    fn move_scale(&mut self, mouse_pos: UVec2, mouse_delta: IVec2, scroll_delta: f32) {
-        let mouse_pos = IVec2::new(
-            mouse_pos.x as i32,
-            self.window_size.y as i32 - mouse_pos.y as i32,
-        );
-        let mouse_pos = DVec2::from(mouse_pos) / DVec2::from(self.window_size);
-        let mouse_pos = mouse_pos - 0.5f64;
-
-        let mouse_delta = DVec2::from(mouse_delta) / DVec2::from(self.window_size);
-        let mouse_delta = DVec2::new(mouse_delta.x, -mouse_delta.y);
-
-        let zoom = 1.15f64.powf(scroll_delta as f64 / 5.0f64);
-
-        let old_size = self.frame_rect.size;
-        let new_size = old_size * zoom;
-
-        let old_offset = self.frame_rect.center();
-        let new_offset = old_offset - mouse_delta * new_size - mouse_pos * (new_size - old_size);
-
-        self.frame_rect = DRect::from_center_size(new_offset, new_size);
+    fn key_action(&mut self, action: Action) -> EventResult {
+        match action {
+            Action::ToggleAntialiasing => {
+                let aa_threshold = match self.mandel_texture.aa_threshold() {
+                    Some(_) => None,
+                    None => Some(AA_THRESHOLD),
+                };
+                self.mandel_texture.set_aa_threshold(aa_threshold);
+                self.update_fractal(self.frame_rect.center());
+                EventResult::Redraw
+            }
+            Action::ToggleDither => {
+                self.mandel_texture
+                    .set_dither(!self.mandel_texture.dither());
+                EventResult::Redraw
+            }
+            Action::ToggleEdgeDetection => {
+                self.mandel_texture
+                    .set_edge_detection(!self.mandel_texture.edge_detection());
+                EventResult::Redraw
+            }
+            Action::ExportRaw => {
+                self.export_raw();
+                EventResult::Continue
+            }
+            Action::ToggleZoomSmoothing => {
+                self.zoom_smoothing = match self.zoom_smoothing {
+                    Some(_) => None,
+                    None => Some(ZOOM_SMOOTHING),
+                };
+                tracing::info!(
+                    "Zoom smoothing {}",
+                    if self.zoom_smoothing.is_some() {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                );
+                EventResult::Continue
+            }
+            Action::SlowerZoom => self.step_zoom_speed(-1.0),
+            Action::FasterZoom => self.step_zoom_speed(1.0),
+            Action::ToggleMorph => {
+                self.morph = match self.morph {
+                    Some(_) => None,
+                    None => Some(Morph {
+                        phase: 0.0,
+                        last_time: None,
+                        last_step_time: f64::NEG_INFINITY,
+                    }),
+                };
+                EventResult::Redraw
+            }
+            Action::SlowerMorph => {
+                self.morph_speed /= MORPH_SPEED_STEP;
+                EventResult::Continue
+            }
+            Action::FasterMorph => {
+                self.morph_speed *= MORPH_SPEED_STEP;
+                EventResult::Continue
+            }
+            Action::NextEscapeMetric => {
+                let escape_metric = self.mandel_texture.escape_metric().next();
+                tracing::info!("Escape metric: {:?}", escape_metric);
+                self.mandel_texture.set_escape_metric(escape_metric);
+                self.update_fractal(self.frame_rect.center());
+                EventResult::Redraw
+            }
+            // Shift turns the other way
+            Action::RotateView if self.modifiers.shift_key() => self.rotate(-1.0),
+            Action::RotateView => self.rotate(1.0),
+            Action::ToggleSplitView => {
+                self.julia.enabled = !self.julia.enabled;
+                self.julia_drag = false;
+                tracing::info!(
+                    "Split view {}",
+                    if self.julia.enabled { "on" } else { "off" }
+                );
+                if self.julia.enabled {
+                    // until the cursor moves over the Mandelbrot half
+                    self.julia.c = self.frame_rect.center();
+                } else {
+                    self.julia.stop();
+                }
+                self.resize_view();
+                EventResult::Redraw
+            }
+            Action::NextFractalKind => {
+                let kind = self.mandel_texture.kind().next();
+                tracing::info!("Fractal: {}", kind.name());
+                self.mandel_texture.set_kind(kind);
+                self.update_fractal(self.frame_rect.center());
+                EventResult::Redraw
+            }
+            // the accumulation is upright, see `rotate`
+            Action::ToggleBuddhabrot if !self.buddhabrot.enabled && self.rotation != 0.0 => {
+                tracing::info!("The Buddhabrot shows upright views only, turn the view back first");
+                EventResult::Continue
+            }
+            Action::ToggleBuddhabrot => {
+                self.buddhabrot.enabled = !self.buddhabrot.enabled;
+                tracing::info!(
+                    "Buddhabrot {}",
+                    if self.buddhabrot.enabled { "on" } else { "off" }
+                );
+                if self.buddhabrot.enabled {
+                    self.apply_paused();
+                    self.start_buddhabrot();
+                } else {
+                    self.buddhabrot.stop();
+                    self.apply_paused();
+                }
+                EventResult::Redraw
+            }
+            Action::ToggleFxaa => {
+                self.fxaa.enabled = !self.fxaa.enabled;
+                if !self.fxaa.enabled {
+                    self.fxaa.release_target();
+                }
+                EventResult::Redraw
+            }
+            Action::TogglePause => {
+                self.paused = !self.paused;
+                tracing::info!(
+                    "Tile computation {}",
+                    if self.paused { "paused" } else { "resumed" }
+                );
+                self.apply_paused();
+                EventResult::Redraw
+            }
+            Action::NextPreset => {
+                let index = self
+                    .preset_index
+                    .map_or(0, |index| (index + 1) % PRESETS.len());
+                self.preset_index = Some(index);
+                self.fly_to_preset(&PRESETS[index])
+            }
+            Action::ResetView => self.reset_view(),
+            Action::PreviousView => self.previous_view(),
+            Action::TogglePerformanceMode => {
+                self.performance_mode = !self.performance_mode;
+                if !self.performance_mode {
+                    self.full_resolution_at = Some(Instant::now());
+                }
+                tracing::info!(
+                    "Performance mode {}",
+                    if self.performance_mode { "on" } else { "off" }
+                );
+                EventResult::Redraw
+            }
+            Action::NextPresentMode => EventResult::CyclePresentMode,
+            Action::DoubleIterations => self.set_iteration_factor(self.iteration_factor() * 2.0),
+            Action::HalveIterations => self.set_iteration_factor(self.iteration_factor() / 2.0),
+            Action::MoreIterations => {
+                self.set_iteration_factor(self.iteration_factor() * ITERATION_STEP)
+            }
+            Action::FewerIterations => {
+                self.set_iteration_factor(self.iteration_factor() / ITERATION_STEP)
+            }
+            Action::NextPaletteMapping => {
+                let palette_mapping = self.mandel_texture.palette_mapping().next();
+                tracing::info!("Palette mapping: {:?}", palette_mapping);
+                self.mandel_texture.set_palette_mapping(palette_mapping);
+                EventResult::Redraw
+            }
+            Action::MoreExposure => self.step_exposure(1.0),
+            Action::LessExposure => self.step_exposure(-1.0),
+            Action::HigherGamma => self.step_gamma(1.0),
+            Action::LowerGamma => self.step_gamma(-1.0),
+            Action::ToggleHelp => {
+                self.help.visible = !self.help.visible;
+                EventResult::Redraw
+            }
+            Action::ToggleColoringMode => {
+                let coloring_mode = match self.mandel_texture.coloring_mode() {
+                    ColoringMode::Banded => ColoringMode::Smooth,
+                    ColoringMode::Smooth => ColoringMode::Banded,
+                };
+                self.mandel_texture.set_coloring_mode(coloring_mode);
+                self.update_fractal(self.frame_rect.center());
+                EventResult::Redraw
+            }
+        }
+    }
+
+    fn step_exposure(&mut self, steps: f32) -> EventResult {
+        let exposure = log2_step(
+            self.mandel_texture.exposure(),
+            EXPOSURE_STEP,
+            steps,
+            MAX_EXPOSURE_LOG2,
+        );
+        tracing::info!("Exposure: {:.2}", exposure);
+        self.mandel_texture.set_exposure(exposure);
+        EventResult::Redraw
+    }
+
+    // turns the view by `steps` steps counterclockwise about its center, a whole turn
+    // comes back to exactly upright
+    fn rotate(&mut self, steps: f64) -> EventResult {
+        // the Buddhabrot accumulates its orbits on an upright grid of the frame
+        if self.buddhabrot.enabled {
+            tracing::info!("The Buddhabrot shows upright views only");
+            return EventResult::Continue;
+        }
+        let steps = ((self.rotation / ROTATION_STEP).round() + steps).rem_euclid(ROTATION_STEPS);
+        self.rotation = steps * ROTATION_STEP;
+        tracing::info!("Rotation: {:.0}°", self.rotation.to_degrees());
+        self.mandel_texture.set_rotation(self.rotation);
+        self.update_fractal(self.frame_rect.center());
+        EventResult::Redraw
+    }
+
+    fn step_gamma(&mut self, steps: f32) -> EventResult {
+        let gamma = log2_step(
+            self.mandel_texture.gamma(),
+            GAMMA_STEP,
+            steps,
+            MAX_GAMMA_LOG2,
+        );
+        tracing::info!("Gamma: {:.2}", gamma);
+        self.mandel_texture.set_gamma(gamma);
+        EventResult::Redraw
+    }
+
+    fn debug_key_input(&mut self, key: winit::keyboard::PhysicalKey) -> EventResult {
+        match key {
+            winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyA) => {
+                let count = self.mandel_texture.buf_pool.lock().taken_buffer_count();
+                tracing::info!("Taken buffer count: {}", count);
+                EventResult::Continue
+            }
+            winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyS) => {
+                EventResult::Redraw
+            }
+            winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyD)
+                if self.modifiers.shift_key() =>
+            {
+                self.update_fractal(self.frame_rect.center());
+                EventResult::Redraw
+            }
+            winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyD) => {
+                let enabled = !self.mandel_texture.tile_overlay();
+                self.mandel_texture.set_tile_overlay(enabled);
+                tracing::info!("Tile overlay: {}", if enabled { "on" } else { "off" });
+                EventResult::Redraw
+            }
+            winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyW) => {
+                EventResult::SimulateMemoryWarning
+            }
+            winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyT) => {
+                let mut timings = self.mandel_texture.tile_timings();
+                timings.sort_by_key(|&(_, compute_time)| std::cmp::Reverse(compute_time));
+                let total: Duration = timings.iter().map(|&(_, compute_time)| compute_time).sum();
+                tracing::info!(
+                    "Tile compute time: {:?} over {} tiles, slowest {:?}",
+                    total,
+                    timings.len(),
+                    &timings[..timings.len().min(SLOWEST_TILES_LOGGED)]
+                );
+                EventResult::Continue
+            }
+            _ => EventResult::Continue,
+        }
+    }
+
+    fn export_raw(&self) {
+        if cfg!(target_arch = "wasm32") {
+            tracing::warn!("The raw export writes files, it isn't available in the browser");
+            return;
+        }
+        if self.rotation != 0.0 {
+            tracing::warn!("The raw export takes upright views only, turn the view back first");
+            return;
+        }
+        let extension = match self.mandel_texture.coloring_mode() {
+            ColoringMode::Banded => "png",
+            ColoringMode::Smooth => "exr",
+        };
+        let path = raw_export::unique_path(Path::new("."), "fractal_raw", extension);
+        tracing::info!("Exporting raw escape counts to {}", path.display());
+
+        let event_loop_proxy = self.event_loop_proxy.clone();
+        self.mandel_texture.export_raw(
+            path.clone(),
+            self.frame_rect,
+            self.window_size,
+            self.mandel_texture.max_iters(),
+            move |result| {
+                let event = UserEvent::ExportFinished {
+                    path,
+                    result: result.map_err(|error| error.to_string()),
+                };
+                let _ = event_loop_proxy.lock().send_event(event);
+            },
+        );
+    }
+
+    pub fn render(&mut self, render_info: &RenderContext) {
+        self.update_julia();
+        let (mandelbrot_viewport, julia_viewport) = if self.julia.enabled {
+            let (mandelbrot, julia) = split_halves(self.surface_size);
+            (Some(mandelbrot), Some(julia))
+        } else {
+            (None, None)
+        };
+        let render_info = &RenderContext {
+            viewport: mandelbrot_viewport,
+            ..*render_info
+        };
+
+        if self.mandel_texture.apply_pending_resize(render_info) {
+            self.update_fractal(self.frame_rect.center());
+        }
+        self.apply_iteration_factor();
+        self.apply_full_resolution();
+        self.flight_step(render_info.time);
+        self.morph_step(render_info.time);
+        if self.buddhabrot.enabled {
+            self.buddhabrot.render(render_info);
+        } else if self.fxaa.enabled {
+            let frame = RenderContext {
+                view: self.fxaa.target_view(render_info.device, self.window_size),
+                ..*render_info
+            };
+            self.mandel_texture.render(&frame);
+            self.fxaa.apply(render_info);
+        } else {
+            self.mandel_texture.render(render_info);
+        }
+        // a paused view isn't getting any further
+        if !self.buddhabrot.enabled && !self.mandel_texture.paused() {
+            self.progress_bar.render(
+                render_info,
+                self.window_size,
+                self.scale_factor,
+                self.mandel_texture.tile_progress(),
+            );
+        }
+        // nothing to draw into in a window a pixel wide
+        if let Some(viewport) = julia_viewport.filter(|viewport| viewport.size.min_element() > 0) {
+            self.julia.render(&RenderContext {
+                viewport: Some(viewport),
+                ..*render_info
+            });
+        }
+        self.help
+            .render(render_info, self.surface_size, self.scale_factor);
+        self.prefetch();
+        self.update_title();
+    }
+
+    // posted as an event, the frame is redrawn in the same update that changes the title
+    fn update_title(&mut self) {
+        let iterations = self
+            .iteration_feedback_until
+            .filter(|&until| Instant::now() < until)
+            .map(|_| self.mandel_texture.max_iters_at(self.iteration_factor()));
+        let title_state = (
+            self.frame_rect,
+            self.rotation,
+            self.window_size.y,
+            self.mandel_texture.power(),
+            self.present_mode,
+            iterations,
+        );
+        if self.title_state == Some(title_state) {
+            return;
+        }
+        self.title_state = Some(title_state);
+
+        let title = window_title(
+            self.frame_rect,
+            self.rotation,
+            self.window_size.y,
+            self.mandel_texture.power(),
+            self.present_mode,
+            iterations,
+        );
+        let _ = self
+            .event_loop_proxy
+            .lock()
+            .send_event(UserEvent::TitleChanged(title));
+    }
+
+    fn update_cursor(&mut self) {
+        let computing_tiles = if self.mandel_texture.paused() {
+            0
+        } else {
+            self.mandel_texture.unready_tile_count()
+        };
+        let cursor = cursor_kind(&self.manipulate_state, computing_tiles);
+        if cursor == self.cursor {
+            return;
+        }
+        self.cursor = cursor;
+
+        let _ = self
+            .event_loop_proxy
+            .lock()
+            .send_event(UserEvent::CursorChanged(cursor));
+    }
+
+    /// Stops computing tiles while the app is in the background, the view and the finished
+    /// tiles are kept and computation continues where it left off once back.
+    pub fn set_suspended(&mut self, suspended: bool) {
+        self.suspended = suspended;
+        self.apply_paused();
+    }
+
+    /// Whether every frame changes the picture, the event loop keeps rendering until it stops.
+    /// Otherwise a frame is only rendered when an event asks for one.
+    pub fn is_animating(&self) -> bool {
+        self.flight.is_some()
+            || self.pending_scroll != 0.0
+            // the morph advances with every frame, unless computation is paused
+            || (self.morph.is_some() && !self.mandel_texture.paused())
+            // a tile stopped halfway through its fade would stay translucent
+            || self.mandel_texture.is_fading()
+            // tiles leave the queue and start computing without an event
+            || (self.mandel_texture.tile_overlay() && self.mandel_texture.pending_tile_count() > 0)
+    }
+
+    /// Whether the window was last resized to a zero size, frames aren't worth rendering then.
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    // the Buddhabrot shows in place of the tiles, they wait until it is turned off
+    fn apply_paused(&mut self) {
+        let paused = self.paused || self.suspended || self.minimized || self.buddhabrot.enabled;
+        if paused == self.mandel_texture.paused() {
+            return;
+        }
+
+        self.mandel_texture.set_paused(paused);
+        if !paused {
+            self.update_fractal(self.frame_rect.center());
+        }
+    }
+
+    // schedules the tiles around the view once it is complete and holds still
+    fn prefetch(&mut self) {
+        if self.mandel_texture.prefetch_deferred()
+            && self.pending_scroll == 0.0
+            && self.morph.is_none()
+            && self.mandel_texture.pending_tile_count() == 0
+        {
+            self.update_fractal(self.frame_rect.center());
+        }
+    }
+
+    /// Flies to `frame_rect` over a moment, zooming out until both ends are in view when they
+    /// are far apart. Its height is kept, the width follows the window. Any zoom or drag stops it.
+    pub fn animate_to(&mut self, frame_rect: DRect) -> EventResult {
+        let waypoints = flight_waypoints(
+            (self.frame_rect.center(), self.frame_rect.size.y),
+            (frame_rect.center(), frame_rect.size.y),
+        );
+        self.pending_scroll = 0.0;
+        self.flight = Some(Flight {
+            waypoints,
+            duration: flight_duration(&waypoints),
+            start_time: None,
+        });
+
+        EventResult::Redraw
+    }
+
+    pub fn fly_to_preset(&mut self, preset: &Preset) -> EventResult {
+        tracing::info!("Flying to {}", preset.title);
+        self.animate_to(preset.frame_rect(self.aspect))
+    }
+
+    fn flight_step(&mut self, time: f64) {
+        let Some(flight) = self.flight.as_mut() else {
+            return;
+        };
+
+        let start_time = *flight.start_time.get_or_insert(time);
+        let t = (time - start_time) / flight.duration;
+        let (center, height) = flight_position(&flight.waypoints, t);
+        if t >= 1.0 {
+            self.flight = None;
+        }
+
+        self.frame_rect = DRect::from_center_size(center, self.aspect * height);
+        self.update_fractal(center);
+    }
+
+    fn morph_step(&mut self, time: f64) {
+        let Some(morph) = self.morph.as_mut() else {
+            return;
+        };
+        if self.mandel_texture.paused() {
+            // the phase picks up where it stopped
+            morph.last_time = None;
+            return;
+        }
+
+        let elapsed = time - morph.last_time.unwrap_or(time);
+        morph.last_time = Some(time);
+        morph.phase += elapsed * self.morph_speed;
+
+        if time - morph.last_step_time < MORPH_STEP_INTERVAL
+            || self.mandel_texture.pending_tile_count() > 0
+        {
+            return;
+        }
+        morph.last_step_time = time;
+
+        self.mandel_texture.set_power(morph_power(morph.phase));
+        self.update_fractal(self.frame_rect.center());
+    }
+
+    fn scroll(&mut self, position: UVec2, scroll_delta: f32) -> EventResult {
+        if self.zoom_smoothing.is_none() {
+            self.move_scale(position, IVec2::zeroed(), scroll_delta as f64);
+            return EventResult::Redraw;
+        }
+
+        // the zoom stays anchored where the scrolling started while the cursor moves on
+        if self.pending_scroll == 0.0 {
+            self.pending_scroll_position = position;
+            self.last_scroll_step = Some(Instant::now());
+        }
+        self.pending_scroll += scroll_delta as f64;
+
+        self.smooth_scroll_step()
+    }
+
+    // zoom factors multiply, so splitting the scroll over frames reaches the same scale
+    fn smooth_scroll_step(&mut self) -> EventResult {
+        if self.pending_scroll == 0.0 {
+            return EventResult::Continue;
+        }
+
+        let now = Instant::now();
+        let elapsed = self
+            .last_scroll_step
+            .map_or(Duration::ZERO, |last| now - last);
+        self.last_scroll_step = Some(now);
+
+        let step = eased_scroll_step(
+            self.pending_scroll,
+            elapsed.as_secs_f64(),
+            self.zoom_smoothing.unwrap_or(0.0),
+        );
+        self.pending_scroll -= step;
+
+        self.move_scale(self.pending_scroll_position, IVec2::zeroed(), step);
+
+        EventResult::Redraw
+    }
+
+    // one step multiplies or divides the zoom of a wheel step by ZOOM_SPEED_STEP
+    fn step_zoom_speed(&mut self, direction: f64) -> EventResult {
+        self.zoom_divisor /= ZOOM_SPEED_STEP.powf(direction);
+        tracing::info!(
+            "A wheel step zooms by {:.3}x",
+            self.zoom_base.powf(3.0 / self.zoom_divisor)
+        );
+
+        EventResult::Continue
+    }
+
+    fn inspect(&self, position: UVec2) {
+        let anchor = window_anchor(position, self.window_size);
+        let point = frame_point(self.frame_rect, self.rotation, anchor);
+        let max_iter = self.mandel_texture.max_iters();
+        let sample = self.mandel_texture.sample_at(point, max_iter);
+
+        let value = match sample.iterations {
+            Some(iterations) => format!(
+                "escapes after {} iterations, smooth {:.6}",
+                iterations, sample.smooth
+            ),
+            None => "inside the set".to_string(),
+        };
+        tracing::info!(
+            "Pixel {} {} at {:.17}, {:.17}: {} of {}",
+            sample.pixel.x,
+            sample.pixel.y,
+            sample.point.x,
+            sample.point.y,
+            value,
+            max_iter
+        );
+    }
+
+    fn box_zoom(&mut self, start: UVec2, offset: IVec2) -> EventResult {
+        if offset.abs().min_element() < MIN_BOX_ZOOM_PIXELS {
+            return EventResult::Continue;
+        }
+
+        let end = (start.as_ivec2() + offset).max(IVec2::ZERO).as_uvec2();
+        let box_rect = box_frame_rect(self.frame_rect, self.rotation, self.window_size, start, end);
+        let height = self.frame_rect.size.y;
+        let zoom = clamped_zoom(height, box_rect.size.y / height);
+
+        self.animate_to(DRect::from_center_size(
+            box_rect.center(),
+            self.aspect * height * zoom,
+        ))
+    }
+
+    // the held modifiers scale both, see `modifier_speeds`
+    fn move_scale(&mut self, mouse_pos: UVec2, mouse_delta: IVec2, scroll_delta: f64) {
+        let (pan_speed, zoom_speed) = modifier_speeds(self.modifiers);
+        let zoom = self
+            .zoom_base
+            .powf(scroll_delta * zoom_speed / self.zoom_divisor);
+        self.move_zoom(mouse_pos, mouse_delta.as_dvec2() * pan_speed, zoom);
+    }
+
+    // pans by `mouse_delta` pixels and multiplies the frame size by `zoom`,
+    // the point under `mouse_pos` stays in place
+    fn move_zoom(&mut self, mouse_pos: UVec2, mouse_delta: DVec2, zoom: f64) {
+        self.flight = None;
+        self.lower_render_scale();
+        let mouse_pos = window_anchor(mouse_pos, self.window_size);
+
+        let mouse_delta = mouse_delta / self.window_size.as_dvec2();
+        let mouse_delta = DVec2::new(mouse_delta.x, -mouse_delta.y);
+
+        let clamped = clamped_zoom(self.frame_rect.size.y, zoom);
+        if clamped != zoom {
+            self.pending_scroll = 0.0;
+        }
+        if clamped > zoom {
+            if !self.precision_limit_reached {
+                self.precision_limit_reached = true;
+                tracing::warn!(
+                    "Precision limit reached: f64 can't resolve deeper zoom, \
+                     extended precision is required to go further"
+                );
+            }
+        } else if zoom > 1.0 {
+            self.precision_limit_reached = false;
+        }
+
+        self.frame_rect = zoomed_frame_rect(
+            self.frame_rect,
+            self.rotation,
+            mouse_pos,
+            mouse_delta,
+            clamped,
+        );
 
-        let focus = self.frame_rect.center() + self.frame_rect.size * mouse_pos;
+        let focus = frame_point(self.frame_rect, self.rotation, mouse_pos);
 
         self.update_fractal(focus);
     }
 
+    // the Mandelbrot set gets the window or its left half, the Julia set the other half
+    fn resize_view(&mut self) -> EventResult {
+        let window_size = if self.julia.enabled {
+            let (mandelbrot, julia) = split_halves(self.surface_size);
+            self.julia.frame_rect = resized_frame_rect(self.julia.frame_rect, julia.size);
+            mandelbrot.size
+        } else {
+            self.surface_size
+        };
+        if self.window_size == window_size {
+            return EventResult::Continue;
+        }
+
+        // the cursor may be over the Julia half
+        let cursor_position = self
+            .cursor_position
+            .filter(|position| position.cmplt(self.window_size).all());
+        self.frame_rect = match (self.resize_anchor, cursor_position) {
+            (ResizeAnchor::Cursor, Some(position)) => resized_frame_rect_at(
+                self.frame_rect,
+                self.rotation,
+                self.window_size,
+                window_size,
+                position,
+            ),
+            _ => resized_frame_rect(self.frame_rect, window_size),
+        };
+        self.aspect = aspect(window_size);
+        self.window_size = window_size;
+        if let Some(delay) = self.mandel_texture.resize_window(window_size) {
+            // render again once the resize settled, so the texture can grow
+            self.redraw_after(delay);
+        }
+
+        self.update_fractal(self.frame_rect.center());
+
+        EventResult::Redraw
+    }
+
+    // while the window is split, the mouse pans and zooms the Julia set over its half and
+    // picks its parameter over the Mandelbrot half on the way to the usual handling; a gesture
+    // stays with the half it started in
+    fn split_view_event(
+        &mut self,
+        event: Event<UserEvent>,
+    ) -> ControlFlow<EventResult, Event<UserEvent>> {
+        if !self.julia.enabled {
+            return ControlFlow::Continue(event);
+        }
+
+        let idle = matches!(self.manipulate_state, ManipulateState::Idle) && !self.julia_drag;
+        let split = self.window_size.x;
+        let over_julia = move |position: UVec2| position.x >= split;
+
+        match event {
+            Event::MouseMove { position, delta } if self.julia_drag => {
+                self.move_julia(position, delta.as_dvec2(), 1.0);
+                ControlFlow::Break(EventResult::Continue)
+            }
+            Event::MouseMove { position, .. } if idle && !over_julia(position) => {
+                let anchor = window_anchor(position, self.window_size);
+                self.julia.c = frame_point(self.frame_rect, self.rotation, anchor);
+                self.update_julia();
+                ControlFlow::Continue(event)
+            }
+            Event::MouseButton(_, ElementState::Released, _) if self.julia_drag => {
+                self.julia_drag = false;
+                ControlFlow::Break(EventResult::Continue)
+            }
+            Event::MouseButton(button, ElementState::Pressed, position)
+                if idle && over_julia(position) =>
+            {
+                self.julia_drag = button == MouseButtons::Touch
+                    || self.mouse_bindings.gesture(button, self.modifiers) == Some(Gesture::Pan);
+                ControlFlow::Break(EventResult::Continue)
+            }
+            Event::MouseWheel(position, delta) if idle && over_julia(position) => {
+                let (_, zoom_speed) = modifier_speeds(self.modifiers);
+                let zoom = self
+                    .zoom_base
+                    .powf(3.0 * delta as f64 * zoom_speed / self.zoom_divisor);
+                self.move_julia(position, DVec2::ZERO, zoom);
+                ControlFlow::Break(EventResult::Continue)
+            }
+            // the other gestures belong to the Mandelbrot half
+            Event::MouseMove { position, .. }
+            | Event::PinchZoom { position, .. }
+            | Event::TouchpadScroll(position, ..)
+                if idle && over_julia(position) =>
+            {
+                ControlFlow::Break(EventResult::Continue)
+            }
+            event => ControlFlow::Continue(event),
+        }
+    }
+
+    // pans the Julia half by `delta` pixels and multiplies its frame size by `zoom`, the point
+    // under `position` in the window stays in place
+    fn move_julia(&mut self, position: UVec2, delta: DVec2, zoom: f64) {
+        let (_, julia) = split_halves(self.surface_size);
+        let half_size = julia.size.max(UVec2::ONE);
+        let anchor = window_anchor(position.saturating_sub(julia.pos), half_size);
+        let pan = delta / half_size.as_dvec2();
+        let pan = DVec2::new(pan.x, -pan.y);
+
+        let zoom = clamped_zoom(self.julia.frame_rect.size.y, zoom);
+        self.julia.frame_rect = zoomed_frame_rect(self.julia.frame_rect, 0.0, anchor, pan, zoom);
+        self.update_julia();
+    }
+
+    // the set of the current parameter, at most every `JULIA_UPDATE_INTERVAL` while it keeps
+    // changing; the image is drawn once it is there
+    fn update_julia(&mut self) {
+        if !self.julia.enabled {
+            return;
+        }
+
+        let (_, julia) = split_halves(self.surface_size);
+        let style = JuliaStyle {
+            power: self.mandel_texture.power(),
+            palette_mapping: self.mandel_texture.palette_mapping(),
+            interior_color: self.mandel_texture.interior_color(),
+        };
+        let event_loop_proxy = self.event_loop_proxy.clone();
+        let delay = self
+            .julia
+            .update(&self.runtime, julia.size, style, move || {
+                let _ = event_loop_proxy.lock().send_event(UserEvent::Redraw);
+            });
+        if let Some(delay) = delay {
+            self.redraw_after(delay);
+        }
+    }
+
     fn update_user_event(&mut self, event: UserEvent) -> EventResult {
         match event {
             UserEvent::Redraw => EventResult::Redraw,
             UserEvent::TileReady {
                 tile_index: _tile_index,
             } => EventResult::Redraw,
+            UserEvent::TileFailed { tile_index, reason } => {
+                tracing::error!(tile_index, reason, "Tile failed");
+                EventResult::Continue
+            }
+            UserEvent::ExportFinished { path, result } => {
+                match result {
+                    Ok(()) => tracing::info!("Exported raw escape counts to {}", path.display()),
+                    Err(error) => {
+                        tracing::error!("Raw export to {} failed: {}", path.display(), error)
+                    }
+                }
+                EventResult::Continue
+            }
+            UserEvent::TitleChanged(title) => EventResult::SetTitle(title),
+            UserEvent::CursorChanged(cursor) => EventResult::SetCursor(cursor),
+            UserEvent::ResetView => self.reset_view(),
+            UserEvent::BuddhabrotProgress {
+                generation,
+                samples,
+            } => {
+                if !self.buddhabrot.progress(generation) {
+                    return EventResult::Continue;
+                }
+                if samples == self.buddhabrot.samples {
+                    tracing::info!("Buddhabrot finished with {} samples", samples);
+                }
+                EventResult::Redraw
+            }
+            UserEvent::FileLoaded { path, result } => match result {
+                Ok(DroppedFile::View(view, palette)) => {
+                    tracing::info!("Flying to the view {}", path.display());
+                    if let Some(palette) = palette {
+                        self.julia.set_palette(palette.clone());
+                        self.mandel_texture.set_palette(palette);
+                        self.dropped_palette = view.palette.clone();
+                    }
+                    self.go_to_view(&view)
+                }
+                Ok(DroppedFile::Palette(palette)) => {
+                    tracing::info!("Using the palette {}", path.display());
+                    self.julia.set_palette(palette.clone());
+                    self.mandel_texture.set_palette(palette);
+                    self.dropped_palette = Some(path);
+                    EventResult::Redraw
+                }
+                Err(error) => {
+                    tracing::error!("Failed to load {}: {}", path.display(), error);
+                    EventResult::Continue
+                }
+            },
+        }
+    }
+
+    // a large file can't hold up the frames, it is read on the runtime and
+    // comes back as a user event
+    fn load_dropped_file(&self, path: PathBuf) {
+        if !DroppedFile::is_supported(&path) {
+            tracing::warn!(
+                "Ignoring {}, views (.toml, .json) and palettes (.json, .png) can be dropped",
+                path.display()
+            );
+            return;
         }
+
+        let event_loop_proxy = self.event_loop_proxy.clone();
+        self.runtime.spawn_blocking(move || {
+            let result = DroppedFile::load(&path).map_err(|error| format!("{:#}", error));
+            let event = UserEvent::FileLoaded { path, result };
+            let _ = event_loop_proxy.lock().send_event(event);
+        });
+    }
+
+    // the settings of the view right away, the frame by flight like a preset
+    fn go_to_view(&mut self, view: &ViewState) -> EventResult {
+        self.pending_iteration_factor = None;
+        self.morph = None;
+        if self.buddhabrot.enabled && view.rotation != 0.0 {
+            tracing::info!("Buddhabrot off, it shows upright views only");
+            self.buddhabrot.enabled = false;
+            self.buddhabrot.stop();
+        }
+        self.rotation = view.rotation;
+        apply_view_settings(&mut self.mandel_texture, view);
+        self.apply_paused();
+        self.animate_to(view.frame_rect(self.aspect))
+    }
+
+    // a moved frame or another iteration limit starts the accumulation over
+    fn start_buddhabrot(&mut self) {
+        let event_loop_proxy = self.event_loop_proxy.clone();
+        self.buddhabrot.start(
+            &self.runtime,
+            self.frame_rect,
+            self.window_size,
+            self.mandel_texture.max_iters(),
+            self.buddhabrot_workers,
+            move |generation, samples| {
+                let event = UserEvent::BuddhabrotProgress {
+                    generation,
+                    samples,
+                };
+                let _ = event_loop_proxy.lock().send_event(event);
+            },
+        );
     }
 
     fn update_fractal(&mut self, focus: DVec2) {
+        if self.buddhabrot.enabled {
+            self.start_buddhabrot();
+        }
+
         let event_loop_proxy = self.event_loop_proxy.clone();
 
         self.mandel_texture
-            .update(self.frame_rect, focus, move |index| {
-                event_loop_proxy
-                    .lock()
-                    .send_event(UserEvent::TileReady { tile_index: index })
-                    .unwrap();
+            .update(self.frame_rect, focus, move |index, result| {
+                let event = match result {
+                    Ok(()) => UserEvent::TileReady { tile_index: index },
+                    Err(reason) => UserEvent::TileFailed {
+                        tile_index: index,
+                        reason,
+                    },
+                };
+                event_loop_proxy.lock().send_event(event).unwrap();
             });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposure_steps_return_to_one() {
+        let mut exposure = 1.0;
+        for _ in 0..3 {
+            exposure = log2_step(exposure, EXPOSURE_STEP, 1.0, MAX_EXPOSURE_LOG2);
+        }
+        assert!((exposure - 0.75f32.exp2()).abs() < 1e-6);
+        for _ in 0..3 {
+            exposure = log2_step(exposure, EXPOSURE_STEP, -1.0, MAX_EXPOSURE_LOG2);
+        }
+        assert_eq!(exposure, 1.0);
+
+        // clamped at both ends, and an off-grid value snaps to the nearest step
+        assert_eq!(log2_step(16.0, EXPOSURE_STEP, 1.0, MAX_EXPOSURE_LOG2), 16.0);
+        assert_eq!(log2_step(0.25, GAMMA_STEP, -1.0, MAX_GAMMA_LOG2), 0.25);
+        assert_eq!(log2_step(1.01, GAMMA_STEP, 0.0, MAX_GAMMA_LOG2), 1.0);
+    }
+
+    #[test]
+    fn title_shows_zoom_and_center() {
+        let frame_rect = DRect::from_center_size(DEFAULT_CENTER, DVec2::new(4.0, 2.5));
+        assert_eq!(
+            window_title(
+                frame_rect,
+                0.0,
+                1000,
+                MANDELBROT_POWER,
+                wgpu::PresentMode::Fifo,
+                None
+            ),
+            "Mandelbrot explorer - zoom 1.0x at -0.740, 0.000, present Fifo"
+        );
+
+        let deep = DRect::from_center_size(DVec2::new(-0.75, 0.1), DVec2::splat(2.5e-9));
+        assert_eq!(
+            window_title(deep, 0.0, 1000, 3.5, wgpu::PresentMode::Mailbox, Some(6750)),
+            "Mandelbrot explorer - zoom 1.00e9x at -0.750000000000, 0.100000000000, power 3.50, \
+             iterations 6750, present Mailbox"
+        );
+    }
+
+    #[test]
+    fn zoom_keeps_anchor_in_place() {
+        let frame_rect = DRect::from_center_size(DEFAULT_CENTER, DVec2::new(4.0, 2.5));
+        let anchor = DVec2::new(0.3, -0.2);
+        let point = |rect: DRect| rect.center() + rect.size * anchor;
+
+        for zoom in [0.5, 1.0 / 1.07, 2.0] {
+            let zoomed = zoomed_frame_rect(frame_rect, 0.0, anchor, DVec2::ZERO, zoom);
+            assert_eq!(zoomed.size, frame_rect.size * zoom);
+            assert!((point(zoomed) - point(frame_rect)).length() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn zoom_steps_keep_cursor_point_fixed() {
+        let window_size = UVec2::new(1280, 720);
+        let cursor = UVec2::new(1001, 87);
+        let anchor = window_anchor(cursor, window_size);
+        // the pixel centers of the corners are half a pixel inside the frame
+        assert_eq!(
+            window_anchor(UVec2::new(0, window_size.y - 1), window_size),
+            DVec2::splat(0.5) / window_size.as_dvec2() - 0.5
+        );
+
+        let mut frame_rect =
+            DRect::from_center_size(DVec2::new(-0.7436, 0.1318), aspect(window_size) * 1e-3);
+        let cursor_point = frame_point(frame_rect, 0.0, anchor);
+        for _ in 0..200 {
+            frame_rect = zoomed_frame_rect(frame_rect, 0.0, anchor, DVec2::ZERO, 1.0 / 1.07);
+            let drift = frame_point(frame_rect, 0.0, anchor) - cursor_point;
+            assert!(
+                drift.abs().cmple(cursor_point.abs() * f64::EPSILON).all(),
+                "{:?}",
+                drift
+            );
+        }
+    }
+
+    #[test]
+    fn smoothed_scroll_eases_in_regardless_of_frame_rate() {
+        assert_eq!(eased_scroll_step(3.0, 0.0, 0.0), 3.0);
+        assert_eq!(eased_scroll_step(3.0, 0.0, ZOOM_SMOOTHING), 0.0);
+
+        // half the duration in frames of either rate
+        let remaining_after = |frames: u32| {
+            let mut pending = 3.0;
+            for _ in 0..frames {
+                let frame_time = ZOOM_SMOOTHING / 2.0 / frames as f64;
+                pending -= eased_scroll_step(pending, frame_time, ZOOM_SMOOTHING);
+            }
+            pending
+        };
+        let at_60_hz = remaining_after(3);
+        let at_240_hz = remaining_after(12);
+        assert!(
+            (at_60_hz - at_240_hz).abs() < 1e-9,
+            "{} {}",
+            at_60_hz,
+            at_240_hz
+        );
+        assert!(at_60_hz > 0.1 && at_60_hz < 1.0, "{}", at_60_hz);
+
+        // the tail is applied at once, shortly after the duration
+        let mut pending = 3.0;
+        let mut frames = 0;
+        while pending != 0.0 {
+            pending -= eased_scroll_step(pending, ZOOM_SMOOTHING / 6.0, ZOOM_SMOOTHING);
+            frames += 1;
+        }
+        assert_eq!(frames, 9);
+    }
+
+    #[test]
+    fn turned_view_maps_the_window_about_its_center() {
+        let frame_rect = DRect::from_center_size(DVec2::new(-0.5, 0.25), DVec2::new(4.0, 2.0));
+        let quarter = std::f64::consts::FRAC_PI_2;
+        // the right edge of the window shows what was above the center
+        let point = frame_point(frame_rect, quarter, DVec2::new(0.5, 0.0));
+        assert!(
+            (point - DVec2::new(-0.5, 2.25)).length() < 1e-12,
+            "{}",
+            point
+        );
+        assert_eq!(
+            frame_point(frame_rect, 0.0, DVec2::new(0.5, 0.0)),
+            DVec2::new(1.5, 0.25)
+        );
+
+        // zooming at the cursor and panning by a window fraction follow the turn
+        let anchor = DVec2::new(0.3, -0.2);
+        let zoomed = zoomed_frame_rect(frame_rect, 1.0, anchor, DVec2::ZERO, 0.5);
+        let drift = frame_point(zoomed, 1.0, anchor) - frame_point(frame_rect, 1.0, anchor);
+        assert!(drift.length() < 1e-12, "{}", drift);
+        let panned = zoomed_frame_rect(frame_rect, quarter, DVec2::ZERO, DVec2::X * 0.25, 1.0);
+        assert!((panned.center() - DVec2::new(-0.5, -0.75)).length() < 1e-12);
+
+        // the box is upright on screen, a tall box on the turned view is still a tall one
+        let window_size = UVec2::new(1000, 500);
+        let rect = box_frame_rect(
+            frame_rect,
+            quarter,
+            window_size,
+            UVec2::new(600, 50),
+            UVec2::new(700, 250),
+        );
+        assert!((rect.size - DVec2::new(1.6, 0.8)).abs().max_element() < 1e-12);
+        let middle = (window_anchor(UVec2::new(600, 50), window_size)
+            + window_anchor(UVec2::new(700, 250), window_size))
+            / 2.0;
+        let center = frame_point(frame_rect, quarter, middle);
+        assert!((rect.center() - center).length() < 1e-12);
+
+        assert_eq!(
+            window_title(
+                frame_rect,
+                -quarter,
+                1000,
+                MANDELBROT_POWER,
+                wgpu::PresentMode::Fifo,
+                None
+            ),
+            "Mandelbrot explorer - zoom 1.2x at -0.500, 0.250, turned -90°, present Fifo"
+        );
+    }
+
+    #[test]
+    fn box_zoom_fits_the_box() {
+        let window_size = UVec2::new(1000, 500);
+        let frame_rect = DRect::from_center_size(DVec2::ZERO, DVec2::new(4.0, 2.0));
+
+        // a tall box in the top right quarter, the frame widens to the window aspect
+        let rect = box_frame_rect(
+            frame_rect,
+            0.0,
+            window_size,
+            UVec2::new(600, 50),
+            UVec2::new(700, 250),
+        );
+        assert!(
+            (rect.center() - DVec2::new(0.602, 0.398))
+                .abs()
+                .max_element()
+                < 1e-12
+        );
+        assert!((rect.size - DVec2::new(1.6, 0.8)).abs().max_element() < 1e-12);
+
+        // dragged the other way, a wide box
+        let rect = box_frame_rect(
+            frame_rect,
+            0.0,
+            window_size,
+            UVec2::new(500, 250),
+            UVec2::new(0, 200),
+        );
+        assert!((rect.size - DVec2::new(2.0, 1.0)).abs().max_element() < 1e-12);
+    }
+
+    #[test]
+    fn gestures_pick_the_cursor() {
+        let box_zoom = ManipulateState::BoxZoom {
+            start: UVec2::ZERO,
+            offset: IVec2::ONE,
+        };
+        assert_eq!(cursor_kind(&ManipulateState::Drag, 0), CursorKind::Grabbing);
+        assert_eq!(cursor_kind(&box_zoom, 100), CursorKind::Crosshair);
+        assert_eq!(
+            cursor_kind(
+                &ManipulateState::ZoomAxis {
+                    anchor: UVec2::ZERO
+                },
+                0
+            ),
+            CursorKind::ResizeVertical
+        );
+        assert_eq!(
+            cursor_kind(&ManipulateState::Idle, BUSY_CURSOR_TILES),
+            CursorKind::Default
+        );
+        assert_eq!(
+            cursor_kind(&ManipulateState::Idle, BUSY_CURSOR_TILES + 1),
+            CursorKind::Progress
+        );
+    }
+
+    #[test]
+    fn flights_rise_between_distant_spots() {
+        let overview = (DEFAULT_CENTER, DEFAULT_FRAME_HEIGHT);
+        let seahorse = Preset::find("seahorse").unwrap();
+        let elephant = Preset::find("elephant").unwrap();
+        let seahorse = (seahorse.center, seahorse.height);
+        let elephant = (elephant.center, elephant.height);
+
+        for (from, to) in [
+            (overview, seahorse),
+            (seahorse, elephant),
+            (elephant, overview),
+        ] {
+            let waypoints = flight_waypoints(from, to);
+            let top = waypoints[1].1;
+            let duration = flight_duration(&waypoints);
+            assert!((MIN_FLIGHT_DURATION..=MAX_FLIGHT_DURATION).contains(&duration));
+
+            assert_eq!(flight_position(&waypoints, 0.0), from);
+            let (center, height) = flight_position(&waypoints, 1.0);
+            assert!(center.distance(to.0) < 1e-12 && (height - to.1).abs() < to.1 * 1e-12);
+
+            let path: Vec<_> = (0..=100)
+                .map(|step| flight_position(&waypoints, step as f64 / 100.0))
+                .collect();
+            assert!(path
+                .iter()
+                .all(|&(_, height)| height <= top * (1.0 + 1e-12)));
+            // the destination is in view once the frame narrows toward it
+            let (center, height) = path[90];
+            assert!(center.distance(to.0) < height);
+        }
+
+        // the two valleys don't fit in either frame, the flight zooms out over both
+        let waypoints = flight_waypoints(seahorse, elephant);
+        assert!(waypoints[1].1 > seahorse.0.distance(elephant.0));
+        // from the overview the flight only zooms in
+        assert_eq!(flight_waypoints(overview, seahorse)[1], overview);
+    }
+
+    #[test]
+    fn extreme_zooms_keep_frame_finite() {
+        let mut frame_rect = DRect::from_center_size(DEFAULT_CENTER, DVec2::new(4.0, 2.5));
+        let anchor = DVec2::new(0.5, -0.5);
+        let zoom = |frame_rect: DRect, zoom: f64| {
+            zoomed_frame_rect(
+                frame_rect,
+                0.0,
+                anchor,
+                DVec2::ZERO,
+                clamped_zoom(frame_rect.size.y, zoom),
+            )
+        };
+        let is_valid = |rect: DRect| {
+            rect.pos.is_finite() && rect.size.is_finite() && rect.size.cmpgt(DVec2::ZERO).all()
+        };
+
+        for _ in 0..10_000 {
+            frame_rect = zoom(frame_rect, 0.5);
+            assert!(is_valid(frame_rect), "{:?}", frame_rect);
+        }
+        let min_height = DEFAULT_FRAME_HEIGHT * PRECISION_LIMIT_SCALE;
+        assert!((frame_rect.size.y - min_height).abs() < min_height * 1e-9);
+
+        for bad_zoom in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(zoom(frame_rect, bad_zoom).size, frame_rect.size);
+        }
+
+        for _ in 0..10_000 {
+            frame_rect = zoom(frame_rect, 2.0);
+            assert!(is_valid(frame_rect), "{:?}", frame_rect);
+        }
+        assert!(frame_rect.size.y <= DEFAULT_FRAME_HEIGHT * MAX_FRAME_HEIGHT_SCALE * (1.0 + 1e-9));
+    }
+
+    #[test]
+    fn initial_frame_shows_the_whole_set() {
+        for (window_size, width) in [
+            (UVec2::new(1280, 720), 2.5 * 16.0 / 9.0),
+            (UVec2::new(800, 800), 2.5),
+            (UVec2::new(720, 1280), 2.5 * 9.0 / 16.0),
+            // minimized windows count as a single pixel
+            (UVec2::ZERO, 2.5),
+        ] {
+            let frame_rect = initial_frame_rect(window_size);
+            assert!(
+                frame_rect.center().abs_diff_eq(DEFAULT_CENTER, 1e-12),
+                "{:?}",
+                frame_rect
+            );
+            assert_eq!(frame_rect.size.y, DEFAULT_FRAME_HEIGHT);
+            assert!(
+                (frame_rect.size.x - width).abs() < 1e-12,
+                "{:?}",
+                frame_rect
+            );
+        }
+    }
+
+    #[test]
+    fn resize_keeps_vertical_extent() {
+        let frame_rect = DRect::from_center_size(DEFAULT_CENTER, DVec2::new(4.0, 2.5));
+
+        let wide = resized_frame_rect(frame_rect, UVec2::new(2000, 500));
+        assert_eq!(wide.center(), frame_rect.center());
+        assert_eq!(wide.size, DVec2::new(10.0, 2.5));
+
+        let tall = resized_frame_rect(wide, UVec2::new(500, 1000));
+        assert_eq!(tall.size, DVec2::new(1.25, 2.5));
+
+        // minimized and restored, nothing collapses
+        let minimized = resized_frame_rect(tall, UVec2::new(1, 1));
+        assert_eq!(minimized.size, DVec2::new(2.5, 2.5));
+        let zero = resized_frame_rect(tall, UVec2::ZERO);
+        assert_eq!(zero.size, DVec2::new(2.5, 2.5));
+        let restored = resized_frame_rect(minimized, UVec2::new(1600, 1000));
+        assert_eq!(restored.size, DVec2::new(4.0, 2.5));
+        assert_eq!(restored.center(), frame_rect.center());
+    }
+
+    #[test]
+    fn cursor_anchored_resize_keeps_the_point_under_the_cursor() {
+        let frame_rect = DRect::from_center_size(DEFAULT_CENTER, DVec2::new(4.0, 2.5));
+        let old_size = UVec2::new(1600, 1000);
+        let position = UVec2::new(1200, 300);
+        let point = frame_point(frame_rect, 0.0, window_anchor(position, old_size));
+
+        // dragging the left edge, the cursor stays at its pixel
+        let resized =
+            resized_frame_rect_at(frame_rect, 0.0, old_size, UVec2::new(1800, 1000), position);
+        assert_eq!(resized.size.y, frame_rect.size.y);
+        let moved = frame_point(
+            resized,
+            0.0,
+            window_anchor(position, UVec2::new(1800, 1000)),
+        );
+        assert!((moved - point).length() < 1e-12, "{} {}", moved, point);
+
+        // the window shrinks past the cursor, the point moves to the nearest pixel
+        let small = UVec2::new(800, 200);
+        let resized = resized_frame_rect_at(frame_rect, 0.0, old_size, small, position);
+        let moved = frame_point(resized, 0.0, window_anchor(UVec2::new(799, 199), small));
+        assert!((moved - point).length() < 1e-12, "{} {}", moved, point);
+
+        // the same size changes nothing
+        let same = resized_frame_rect_at(frame_rect, 0.0, old_size, old_size, position);
+        assert!((same.center() - frame_rect.center()).length() < 1e-12);
+        assert!((same.size - frame_rect.size).length() < 1e-12);
+    }
+
+    #[test]
+    fn split_halves_cover_the_window() {
+        let (left, right) = split_halves(UVec2::new(1921, 1080));
+        assert_eq!(
+            left,
+            URect::from_pos_size(UVec2::ZERO, UVec2::new(960, 1080))
+        );
+        assert_eq!(
+            right,
+            URect::from_pos_size(UVec2::new(960, 0), UVec2::new(961, 1080))
+        );
+
+        // a window too narrow to split keeps a pixel for the Mandelbrot set
+        let (left, right) = split_halves(UVec2::new(1, 10));
+        assert_eq!(left.size, UVec2::new(1, 10));
+        assert_eq!(right.size, UVec2::new(0, 10));
+    }
+
+    #[test]
+    fn fullscreen_shortcuts() {
+        use winit::keyboard::KeyCode;
+
+        assert!(is_fullscreen_shortcut(
+            KeyCode::F11,
+            ModifiersState::empty()
+        ));
+        assert!(is_fullscreen_shortcut(KeyCode::Enter, ModifiersState::ALT));
+        assert!(is_fullscreen_shortcut(
+            KeyCode::NumpadEnter,
+            ModifiersState::ALT
+        ));
+        assert!(!is_fullscreen_shortcut(
+            KeyCode::Enter,
+            ModifiersState::empty()
+        ));
+        assert!(!is_fullscreen_shortcut(
+            KeyCode::Enter,
+            ModifiersState::ALT | ModifiersState::CONTROL
+        ));
+        assert!(!is_fullscreen_shortcut(KeyCode::KeyF, ModifiersState::ALT));
+
+        // N alone is bound to an action
+        assert!(is_new_window_shortcut(
+            KeyCode::KeyN,
+            ModifiersState::CONTROL
+        ));
+        assert!(!is_new_window_shortcut(
+            KeyCode::KeyN,
+            ModifiersState::empty()
+        ));
+        assert!(!is_new_window_shortcut(
+            KeyCode::KeyN,
+            ModifiersState::CONTROL | ModifiersState::SHIFT
+        ));
+    }
+
+    #[test]
+    fn digits_store_and_recall_view_slots() {
+        use winit::keyboard::KeyCode;
+
+        assert_eq!(
+            view_slot_key(KeyCode::Digit3, ModifiersState::empty()),
+            Some((3, false))
+        );
+        assert_eq!(
+            view_slot_key(KeyCode::Digit0, ModifiersState::CONTROL),
+            Some((0, true))
+        );
+        assert_eq!(
+            view_slot_key(
+                KeyCode::Digit9,
+                ModifiersState::CONTROL | ModifiersState::SHIFT
+            ),
+            None
+        );
+        assert_eq!(view_slot_key(KeyCode::Digit1, ModifiersState::ALT), None);
+        assert_eq!(view_slot_key(KeyCode::KeyA, ModifiersState::CONTROL), None);
+    }
+
+    #[test]
+    fn key_list_follows_the_bindings() {
+        let list = shortcuts(&KeyBindings::default());
+        // every action once, with all of its keys, then the fixed shortcuts
+        let actions = KeyBindings::default().keys().len();
+        assert_eq!(list.len(), actions + FIXED_SHORTCUTS.len());
+        assert!(list.contains(&("R, Home".to_string(), Action::ResetView.description())));
+        assert!(list.contains(&("/, F1".to_string(), Action::ToggleHelp.description())));
+        assert_eq!(list[actions].0, "F11, Alt+Enter");
+
+        let settings: Settings = toml::from_str("[keys]\ntoggle_pause = \"F5\"").unwrap();
+        let list = shortcuts(&settings.keys);
+        assert!(list.contains(&("F5".to_string(), Action::TogglePause.description())));
+        assert!(!list.iter().any(|(keys, _)| keys == "P"));
+    }
+
+    #[test]
+    fn modifiers_scale_pan_and_zoom() {
+        assert_eq!(modifier_speeds(ModifiersState::empty()), (1.0, 1.0));
+        assert_eq!(modifier_speeds(ModifiersState::SHIFT), (1.0, 4.0));
+        assert_eq!(modifier_speeds(ModifiersState::CONTROL), (1.0, 0.25));
+        assert_eq!(modifier_speeds(ModifiersState::ALT), (0.1, 1.0));
+        assert_eq!(
+            modifier_speeds(ModifiersState::SHIFT | ModifiersState::CONTROL),
+            (1.0, 1.0)
+        );
+        // the logo key is left to the system
+        assert_eq!(modifier_speeds(ModifiersState::SUPER), (1.0, 1.0));
+    }
+
+    #[test]
+    fn morph_sweeps_between_powers() {
+        assert_eq!(morph_power(0.0), MANDELBROT_POWER);
+        assert!((morph_power(0.5) - MORPH_MAX_POWER).abs() < 1e-12);
+        assert!((morph_power(1.0) - MANDELBROT_POWER).abs() < 1e-12);
+        assert!((morph_power(0.25) - morph_power(0.75)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reset_returns_to_the_mandelbrot_set() {
+        use crate::mandel_texture::tests::{request_test_device, test_surface_config};
+
+        let Some((device, queue)) = request_test_device() else {
+            eprintln!("No adapter, skipping the view reset test");
+            return;
+        };
+        let window_size = UVec2::new(256, 128);
+        let settings = Settings::default();
+        let mut texture = MandelTexture::new(
+            &device,
+            &queue,
+            &test_surface_config(window_size),
+            window_size,
+            &settings,
+            &Palette::builtin(),
+        )
+        .unwrap();
+        texture.set_kind(FractalKind::Julia(DVec2::new(-0.4, 0.6)));
+        texture.set_power(3.0);
+        texture.set_coloring_mode(ColoringMode::Smooth);
+        texture.set_exposure(2.0);
+        texture.set_rotation(ROTATION_STEP);
+
+        reset_view_settings(&mut texture, 2.0, PaletteMapping::Log);
+        assert_eq!(texture.kind(), FractalKind::Mandelbrot);
+        assert_eq!(texture.power(), MANDELBROT_POWER);
+        assert_eq!(texture.coloring_mode(), ColoringMode::default());
+        assert_eq!(texture.exposure(), 1.0);
+        assert_eq!(texture.rotation(), 0.0);
+        assert_eq!(texture.iteration_factor(), 2.0);
+        assert_eq!(texture.palette_mapping(), PaletteMapping::Log);
+    }
+}
@@ -0,0 +1,152 @@
+//! Where the texture pages of the app lie in the plane and how they split into tiles, the part
+//! of the tiled rendering that runs without a GPU.
+use glam::{DVec2, IVec2, UVec2};
+
+use crate::kernel::{
+    compute_frame, refine_boundary, CancelSignal, FractalKernel, KernelOptions, PixelGrid,
+};
+use crate::mandelbrot_simd::{EscapeMetric, FractalKind, KernelPixel, Precision, SimdKernel};
+use crate::math::{DRect, URect};
+
+// the main cardioid and the period-2 bulb lie in this rect
+const MAIN_BULBS_BOUNDS: DRect = DRect {
+    pos: DVec2::new(-1.25, -0.65),
+    size: DVec2::new(1.625, 1.3),
+};
+// halvings of a rect per side before `is_interior_rect` gives up on it
+const INTERIOR_SPLITS: u32 = 4;
+// how far inside their edges the bounds of a rect have to stay, far more than the rounding of
+// the bounds, and enough that the kernel's own rounding can't make an orbit there escape
+const INTERIOR_MARGIN: f64 = 1e-9;
+
+/// Grid position of the page containing `point`, `fractal_rect` is the page at (0, 0).
+pub fn page_coord(fractal_rect: DRect, point: DVec2) -> IVec2 {
+    ((point - fractal_rect.pos) / fractal_rect.size)
+        .floor()
+        .as_ivec2()
+}
+
+/// The page at `coord` of the grid of `page_coord`. Neighbor pages share their edges exactly,
+/// so no pixel falls between them.
+pub fn page_rect(fractal_rect: DRect, coord: IVec2) -> DRect {
+    let pos = fractal_rect.pos + coord.as_dvec2() * fractal_rect.size;
+    let end = fractal_rect.pos + (coord + 1).as_dvec2() * fractal_rect.size;
+
+    DRect::from_pos_size(pos, end - pos)
+}
+
+/// The tiles of a page of `texture_size` texels, column by column from the lower left.
+pub fn tile_rects(texture_size: UVec2, tile_size: u32) -> impl Iterator<Item = URect> {
+    assert!(
+        tile_size.is_power_of_two(),
+        "Tile size must be a power of two"
+    );
+    assert!(
+        texture_size.x.is_multiple_of(tile_size) && texture_size.y.is_multiple_of(tile_size),
+        "Tile size must divide the texture size"
+    );
+
+    let tile_count = texture_size / tile_size;
+    (0..tile_count.x).flat_map(move |i| {
+        (0..tile_count.y).map(move |j| {
+            URect::from_pos_size(UVec2::new(i, j) * tile_size, UVec2::splat(tile_size))
+        })
+    })
+}
+
+/// The kernel of the tiles of the page `grid`, in the precision its pixel size needs.
+pub fn tile_kernel(grid: PixelGrid, kind: FractalKind, escape_metric: EscapeMetric) -> SimdKernel {
+    SimdKernel {
+        kind,
+        escape_metric,
+        precision: Precision::for_pixel_size(grid.pixel_size().max_element()),
+    }
+}
+
+/// Computes the `tile` texels of the page `grid` into `buffer`, the bottom row first.
+/// Anti-aliasing refines every pixel on the border of the tile, without it the values are
+/// the ones a frame of the whole page has.
+pub fn compute_tile<K: FractalKernel, P: KernelPixel, C: CancelSignal + Clone>(
+    kernel: &K,
+    grid: PixelGrid,
+    tile: URect,
+    options: KernelOptions,
+    aa_threshold: Option<u16>,
+    cancel_token: C,
+    buffer: &mut [P],
+) -> anyhow::Result<()> {
+    compute_frame(kernel, grid, tile, options, cancel_token.clone(), buffer)?;
+
+    if let Some(threshold) = aa_threshold {
+        refine_boundary(kernel, grid, tile, options, threshold, cancel_token, buffer)?;
+    }
+
+    Ok(())
+}
+
+/// True if every point of `rect` is inside the main cardioid or the period-2 bulb of the
+/// Mandelbrot set of power 2, whose orbits never escape, so its tiles are all interior
+/// without iterating them. Never true for a rect reaching out of both, it may be false for
+/// one barely inside.
+pub fn is_interior_rect(rect: DRect) -> bool {
+    MAIN_BULBS_BOUNDS.contains(&rect) && covered_by_main_bulbs(rect, INTERIOR_SPLITS)
+}
+
+// the bounds of the membership tests over the whole rect are loose for large rects, those
+// are split in four until each quarter passes a test or `splits` runs out
+fn covered_by_main_bulbs(rect: DRect, splits: u32) -> bool {
+    if in_bulb(rect) || in_cardioid(rect) {
+        return true;
+    }
+    if splits == 0 {
+        return false;
+    }
+
+    let half = rect.size / 2.0;
+    [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+        .into_iter()
+        .all(|(x, y)| {
+            let quarter = DRect::from_pos_size(rect.pos + DVec2::new(x, y) * half, half);
+            covered_by_main_bulbs(quarter, splits - 1)
+        })
+}
+
+// the disk of radius 1/4 around -1 is convex, the rect is inside with its farthest corner
+fn in_bulb(rect: DRect) -> bool {
+    let [x, y] = farthest_squares(rect, DVec2::new(-1.0, 0.0));
+
+    x + y < 1.0 / 16.0 - INTERIOR_MARGIN
+}
+
+// `q (q + x - 1/4) <= y^2 / 4` with `q = (x - 1/4)^2 + y^2` at every point, bounded from above
+// with interval arithmetic over the rect
+fn in_cardioid(rect: DRect) -> bool {
+    let x = [rect.pos.x - 0.25, rect.upper_right().x - 0.25];
+    let [nearest_x, nearest_y] = nearest_squares(rect, DVec2::new(0.25, 0.0));
+    let [farthest_x, farthest_y] = farthest_squares(rect, DVec2::new(0.25, 0.0));
+    let q = [nearest_x + nearest_y, farthest_x + farthest_y];
+
+    let max_qx = q
+        .into_iter()
+        .flat_map(|q| x.map(|x| q * x))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let max = q[1] * q[1] + max_qx - nearest_y / 4.0;
+
+    max < -INTERIOR_MARGIN
+}
+
+// the squared distances along each axis from `point` to the nearest point of `rect`
+fn nearest_squares(rect: DRect, point: DVec2) -> [f64; 2] {
+    let nearest = point.clamp(rect.pos, rect.upper_right());
+
+    ((nearest - point) * (nearest - point)).to_array()
+}
+
+// the squared distances along each axis from `point` to the farthest corner of `rect`
+fn farthest_squares(rect: DRect, point: DVec2) -> [f64; 2] {
+    let farthest = (rect.pos - point)
+        .abs()
+        .max((rect.upper_right() - point).abs());
+
+    (farthest * farthest).to_array()
+}
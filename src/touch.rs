@@ -0,0 +1,199 @@
+use glam::{DVec2, UVec2};
+use winit::event::TouchPhase;
+
+use crate::event::{ElementState, Event, MouseButtons};
+
+// a third finger is ignored until one of the first two lifts
+const MAX_TOUCHES: usize = 2;
+
+/// Turns touch screen input into the mouse-like events of the app:
+/// one finger drags the view, two fingers pinch zoom around their midpoint and drag it along.
+#[derive(Debug, Default)]
+pub struct TouchTracker {
+    // active touches by id with their last location, in the order they started
+    touches: Vec<(u64, DVec2)>,
+}
+
+impl TouchTracker {
+    pub fn update<UserEvent>(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        location: DVec2,
+    ) -> Event<UserEvent> {
+        let index = self
+            .touches
+            .iter()
+            .position(|&(touch_id, _)| touch_id == id);
+
+        match (phase, index) {
+            (TouchPhase::Started, None) if self.touches.len() < MAX_TOUCHES => {
+                self.touches.push((id, location));
+
+                // the second finger turns the drag into a pinch, which needs no button state
+                if self.touches.len() == 1 {
+                    Event::MouseButton(MouseButtons::Touch, ElementState::Pressed, pixel(location))
+                } else {
+                    Event::Unknown
+                }
+            }
+            (TouchPhase::Moved, Some(index)) => {
+                let old_distance = self.distance();
+                let old_midpoint = self.midpoint();
+                let old_location = std::mem::replace(&mut self.touches[index].1, location);
+
+                if self.touches.len() == 1 {
+                    return Event::MouseMove {
+                        position: pixel(location),
+                        delta: pixel(location).as_ivec2() - pixel(old_location).as_ivec2(),
+                    };
+                }
+
+                let distance = self.distance();
+                if old_distance <= 0.0 || distance <= 0.0 {
+                    return Event::Unknown;
+                }
+
+                // the picture scales around the old midpoint, which then follows the fingers
+                Event::PinchZoom {
+                    position: pixel(old_midpoint),
+                    delta: pixel(self.midpoint()).as_ivec2() - pixel(old_midpoint).as_ivec2(),
+                    scale_factor: distance / old_distance,
+                }
+            }
+            (TouchPhase::Ended | TouchPhase::Cancelled, Some(index)) => {
+                self.touches.remove(index);
+
+                // with one finger left the drag continues from its current location
+                if self.touches.is_empty() {
+                    Event::MouseButton(MouseButtons::Touch, ElementState::Released, pixel(location))
+                } else {
+                    Event::Unknown
+                }
+            }
+            _ => Event::Unknown,
+        }
+    }
+
+    fn midpoint(&self) -> DVec2 {
+        let sum: DVec2 = self.touches.iter().map(|&(_, location)| location).sum();
+        sum / self.touches.len().max(1) as f64
+    }
+
+    fn distance(&self) -> f64 {
+        match self.touches.as_slice() {
+            [(_, a), (_, b)] => a.distance(*b),
+            _ => 0.0,
+        }
+    }
+}
+
+// window coordinates are truncated to whole pixels, like cursor positions
+fn pixel(location: DVec2) -> UVec2 {
+    location.as_uvec2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(tracker: &mut TouchTracker, id: u64, phase: TouchPhase, x: f64, y: f64) -> Event<()> {
+        tracker.update(id, phase, DVec2::new(x, y))
+    }
+
+    #[test]
+    fn one_finger_drags() {
+        let mut tracker = TouchTracker::default();
+
+        assert_eq!(
+            touch(&mut tracker, 1, TouchPhase::Started, 10.0, 20.0),
+            Event::MouseButton(
+                MouseButtons::Touch,
+                ElementState::Pressed,
+                UVec2::new(10, 20)
+            )
+        );
+        assert_eq!(
+            touch(&mut tracker, 1, TouchPhase::Moved, 15.5, 18.0),
+            Event::MouseMove {
+                position: UVec2::new(15, 18),
+                delta: glam::IVec2::new(5, -2),
+            }
+        );
+        assert_eq!(
+            touch(&mut tracker, 1, TouchPhase::Ended, 15.5, 18.0),
+            Event::MouseButton(
+                MouseButtons::Touch,
+                ElementState::Released,
+                UVec2::new(15, 18)
+            )
+        );
+        // a lifted finger is forgotten
+        assert_eq!(
+            touch(&mut tracker, 1, TouchPhase::Moved, 30.0, 30.0),
+            Event::Unknown
+        );
+    }
+
+    #[test]
+    fn two_fingers_pinch_and_third_is_ignored() {
+        let mut tracker = TouchTracker::default();
+
+        touch(&mut tracker, 1, TouchPhase::Started, 100.0, 100.0);
+        assert_eq!(
+            touch(&mut tracker, 2, TouchPhase::Started, 200.0, 100.0),
+            Event::Unknown
+        );
+        assert_eq!(
+            touch(&mut tracker, 2, TouchPhase::Moved, 300.0, 100.0),
+            Event::PinchZoom {
+                position: UVec2::new(150, 100),
+                delta: glam::IVec2::new(50, 0),
+                scale_factor: 2.0,
+            }
+        );
+        // the midpoint moves along with the fingers
+        assert_eq!(
+            touch(&mut tracker, 1, TouchPhase::Moved, 110.0, 100.0),
+            Event::PinchZoom {
+                position: UVec2::new(200, 100),
+                delta: glam::IVec2::new(5, 0),
+                scale_factor: 0.95,
+            }
+        );
+
+        assert_eq!(
+            touch(&mut tracker, 3, TouchPhase::Started, 50.0, 50.0),
+            Event::Unknown
+        );
+        assert_eq!(
+            touch(&mut tracker, 3, TouchPhase::Moved, 60.0, 60.0),
+            Event::Unknown
+        );
+        assert_eq!(
+            touch(&mut tracker, 3, TouchPhase::Ended, 60.0, 60.0),
+            Event::Unknown
+        );
+
+        // lifting one finger leaves a drag with the other, without a jump
+        assert_eq!(
+            touch(&mut tracker, 2, TouchPhase::Ended, 300.0, 100.0),
+            Event::Unknown
+        );
+        assert_eq!(
+            touch(&mut tracker, 1, TouchPhase::Moved, 114.0, 100.0),
+            Event::MouseMove {
+                position: UVec2::new(114, 100),
+                delta: glam::IVec2::new(4, 0),
+            }
+        );
+        assert_eq!(
+            touch(&mut tracker, 1, TouchPhase::Cancelled, 114.0, 100.0),
+            Event::MouseButton(
+                MouseButtons::Touch,
+                ElementState::Released,
+                UVec2::new(114, 100)
+            )
+        );
+    }
+}
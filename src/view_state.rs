@@ -0,0 +1,456 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use glam::DVec2;
+use serde::{Deserialize, Serialize};
+
+use crate::mandel_texture::ColoringMode;
+use crate::mandelbrot_simd::{EscapeMetric, FractalKind, DEFAULT_JULIA_C, MANDELBROT_POWER};
+use crate::math::DRect;
+use crate::palette::PaletteMapping;
+use crate::settings;
+use crate::tiled_fractal_app::{
+    DEFAULT_FRAME_HEIGHT, MAX_FRAME_HEIGHT_SCALE, MAX_ITERATION_FACTOR, MIN_ITERATION_FACTOR,
+    MORPH_MAX_POWER, PRECISION_LIMIT_SCALE,
+};
+
+const FILE_NAME: &str = "last_view.toml";
+const SLOTS_FILE_NAME: &str = "view_slots.toml";
+/// Quick-save slots, one per digit key.
+pub const SLOT_COUNT: usize = 10;
+
+/// What the app showed when it was closed, it starts there the next time unless run with `--fresh`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ViewState {
+    pub center_x: f64,
+    pub center_y: f64,
+    /// Vertical extent of the frame, the horizontal one follows the window aspect.
+    pub height: f64,
+    pub iteration_factor: f64,
+    pub power: f64,
+    pub escape_metric: EscapeMetric,
+    pub coloring_mode: ColoringMode,
+    #[serde(default)]
+    pub palette_mapping: PaletteMapping,
+    /// The Mandelbrot set in views saved before the other fractals could be.
+    #[serde(default)]
+    pub kind: ViewKind,
+    /// The `c` of a Julia set as `[x, y]`, the default one of the kind key if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub julia_c: Option<[f64; 2]>,
+    /// Radians the view is turned by counterclockwise, upright in views saved before it could
+    /// turn.
+    #[serde(default)]
+    pub rotation: f64,
+    /// Used unless `--palette` picks another one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub palette: Option<PathBuf>,
+}
+
+/// `FractalKind` without the `c` of a Julia set, that one is a field of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewKind {
+    #[default]
+    Mandelbrot,
+    Julia,
+    BurningShip,
+    Newton,
+}
+
+impl ViewState {
+    /// Next to the config file, so portable installs keep their view too.
+    pub fn path() -> Option<PathBuf> {
+        Some(settings::config_path()?.with_file_name(FILE_NAME))
+    }
+
+    /// The view saved at `path`; a missing file is the first run, an invalid one is reported
+    /// and ignored, both start at the default view.
+    pub fn load(path: &Path) -> Option<Self> {
+        if !path.exists() {
+            return None;
+        }
+
+        match Self::from_file(path) {
+            Ok(view) => Some(view),
+            Err(error) => {
+                tracing::warn!("Ignoring the saved view {}: {:#}", path.display(), error);
+                None
+            }
+        }
+    }
+
+    /// Writes a temporary file and renames it over `path`, a crash halfway leaves the old view.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        write_atomically(path, || toml::to_string(self))
+            .with_context(|| format!("Failed to save the view to {}", path.display()))
+    }
+
+    /// Reads a view saved by the app, or the same fields as JSON from a `.json` file.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let is_json = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        if is_json {
+            Self::from_json(&text)
+        } else {
+            Self::from_toml(&text)
+        }
+    }
+
+    pub fn center(&self) -> DVec2 {
+        DVec2::new(self.center_x, self.center_y)
+    }
+
+    pub fn frame_rect(&self, aspect: DVec2) -> DRect {
+        DRect::from_center_size(self.center(), aspect * self.height)
+    }
+
+    pub fn fractal_kind(&self) -> FractalKind {
+        match self.kind {
+            ViewKind::Mandelbrot => FractalKind::Mandelbrot,
+            ViewKind::Julia => {
+                FractalKind::Julia(self.julia_c.map_or(DEFAULT_JULIA_C, DVec2::from_array))
+            }
+            ViewKind::BurningShip => FractalKind::BurningShip,
+            ViewKind::Newton => FractalKind::Newton,
+        }
+    }
+
+    /// The `kind` and `julia_c` fields of a view showing `kind`.
+    pub fn kind_fields(kind: FractalKind) -> (ViewKind, Option<[f64; 2]>) {
+        match kind {
+            FractalKind::Mandelbrot => (ViewKind::Mandelbrot, None),
+            FractalKind::Julia(c) => (ViewKind::Julia, Some(c.to_array())),
+            FractalKind::BurningShip => (ViewKind::BurningShip, None),
+            FractalKind::Newton => (ViewKind::Newton, None),
+        }
+    }
+
+    fn from_toml(text: &str) -> anyhow::Result<Self> {
+        let view: Self = toml::from_str(text)?;
+        view.validate()?;
+
+        Ok(view)
+    }
+
+    fn from_json(text: &str) -> anyhow::Result<Self> {
+        let view: Self = serde_json::from_str(text)?;
+        view.validate()?;
+
+        Ok(view)
+    }
+
+    // the app clamps what it can reach itself, anything outside that was edited or damaged
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.center().is_finite() {
+            bail!("the center is not a finite point");
+        }
+        let min_height = DEFAULT_FRAME_HEIGHT * PRECISION_LIMIT_SCALE;
+        let max_height = DEFAULT_FRAME_HEIGHT * MAX_FRAME_HEIGHT_SCALE;
+        if !(min_height..=max_height).contains(&self.height) {
+            bail!(
+                "height {} is outside {}..={}",
+                self.height,
+                min_height,
+                max_height
+            );
+        }
+        if !(MIN_ITERATION_FACTOR..=MAX_ITERATION_FACTOR).contains(&self.iteration_factor) {
+            bail!("iteration factor {} is out of range", self.iteration_factor);
+        }
+        if !(MANDELBROT_POWER..=MORPH_MAX_POWER).contains(&self.power) {
+            bail!("power {} is out of range", self.power);
+        }
+        if self
+            .julia_c
+            .is_some_and(|c| !DVec2::from_array(c).is_finite())
+        {
+            bail!("the Julia parameter is not a finite point");
+        }
+        if !self.rotation.is_finite() {
+            bail!("rotation {} is not finite", self.rotation);
+        }
+
+        Ok(())
+    }
+}
+
+/// Views stored with Ctrl and a digit and recalled with the digit, kept across sessions in a
+/// file next to the last view. The file has a table per stored slot, named by its digit.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ViewSlots {
+    slots: BTreeMap<String, ViewState>,
+}
+
+impl ViewSlots {
+    pub fn path() -> Option<PathBuf> {
+        Some(settings::config_path()?.with_file_name(SLOTS_FILE_NAME))
+    }
+
+    /// The slots saved at `path`, none if the file is missing; an invalid file is reported
+    /// and ignored as a whole.
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+
+        let result = std::fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Self::from_toml(&text));
+        result.unwrap_or_else(|error| {
+            tracing::warn!("Ignoring the view slots {}: {:#}", path.display(), error);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        write_atomically(path, || toml::to_string(self))
+            .with_context(|| format!("Failed to save the view slots to {}", path.display()))
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&ViewState> {
+        self.slots.get(&slot.to_string())
+    }
+
+    pub fn set(&mut self, slot: usize, view: ViewState) {
+        assert!(slot < SLOT_COUNT, "there is no view slot {}", slot);
+        self.slots.insert(slot.to_string(), view);
+    }
+
+    fn from_toml(text: &str) -> anyhow::Result<Self> {
+        let slots: Self = toml::from_str(text)?;
+        for (name, view) in &slots.slots {
+            if !name.parse().is_ok_and(|slot: usize| slot < SLOT_COUNT) {
+                bail!("{:?} is not a slot from 0 to {}", name, SLOT_COUNT - 1);
+            }
+            view.validate()
+                .with_context(|| format!("Invalid view in slot {}", name))?;
+        }
+
+        Ok(slots)
+    }
+}
+
+// a temporary file renamed over `path`
+fn write_atomically(
+    path: &Path,
+    text: impl FnOnce() -> Result<String, toml::ser::Error>,
+) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp_path = path.with_extension("toml.tmp");
+    std::fs::write(&temp_path, text()?)?;
+    std::fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view() -> ViewState {
+        ViewState {
+            center_x: -0.743643887037151,
+            center_y: 0.131825904205330,
+            height: 2.5e-11,
+            iteration_factor: 2.0,
+            power: MANDELBROT_POWER,
+            escape_metric: EscapeMetric::Chebyshev,
+            coloring_mode: ColoringMode::Smooth,
+            palette_mapping: PaletteMapping::Log,
+            kind: ViewKind::Julia,
+            julia_c: Some([-0.4, 0.6]),
+            rotation: std::f64::consts::FRAC_PI_4,
+            palette: Some(PathBuf::from("palettes/fire.json")),
+        }
+    }
+
+    #[test]
+    fn saved_view_round_trips() {
+        let dir = std::env::temp_dir().join(format!("fractal_view_state_{}", std::process::id()));
+        let path = dir.join(FILE_NAME);
+        view().save(&path).unwrap();
+        assert_eq!(ViewState::load(&path), Some(view()));
+        // saving again replaces the file
+        let without_palette = ViewState {
+            palette: None,
+            ..view()
+        };
+        without_palette.save(&path).unwrap();
+        assert_eq!(ViewState::load(&path), Some(without_palette));
+        assert!(!path.with_extension("toml.tmp").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(ViewState::load(&dir.join(FILE_NAME)), None);
+    }
+
+    #[test]
+    fn corrupt_views_are_rejected() {
+        let text = toml::to_string(&view()).unwrap();
+        assert_eq!(ViewState::from_toml(&text).unwrap(), view());
+
+        let invalid = [
+            ViewState {
+                height: 0.0,
+                ..view()
+            },
+            ViewState {
+                height: -1.0,
+                ..view()
+            },
+            ViewState {
+                height: f64::INFINITY,
+                ..view()
+            },
+            ViewState {
+                center_x: f64::NAN,
+                ..view()
+            },
+            ViewState {
+                iteration_factor: 100.0,
+                ..view()
+            },
+            ViewState {
+                power: 0.5,
+                ..view()
+            },
+            ViewState {
+                julia_c: Some([f64::NAN, 0.0]),
+                ..view()
+            },
+            ViewState {
+                rotation: f64::INFINITY,
+                ..view()
+            },
+        ];
+        for view in invalid {
+            let text = toml::to_string(&view).unwrap();
+            assert!(ViewState::from_toml(&text).is_err(), "{}", text);
+        }
+
+        assert!(ViewState::from_toml("").is_err());
+        assert!(ViewState::from_toml(&text[..text.len() / 2]).is_err());
+        assert!(ViewState::from_toml(&text.replace("chebyshev", "taxicab")).is_err());
+        assert!(ViewState::from_toml(&format!("{}zoom = 3.0\n", text)).is_err());
+        assert!(ViewState::from_toml(&text.replace("\"julia\"", "\"mandelbar\"")).is_err());
+    }
+
+    #[test]
+    fn views_keep_their_fractal() {
+        assert_eq!(
+            view().fractal_kind(),
+            FractalKind::Julia(DVec2::new(-0.4, 0.6))
+        );
+        for kind in [
+            FractalKind::Mandelbrot,
+            FractalKind::Julia(DVec2::new(0.285, 0.01)),
+            FractalKind::BurningShip,
+            FractalKind::Newton,
+        ] {
+            let (kind_name, julia_c) = ViewState::kind_fields(kind);
+            let view = ViewState {
+                kind: kind_name,
+                julia_c,
+                ..view()
+            };
+            let text = toml::to_string(&view).unwrap();
+            assert_eq!(ViewState::from_toml(&text).unwrap().fractal_kind(), kind);
+        }
+
+        // views saved before the kind was show the Mandelbrot set
+        let text = toml::to_string(&view()).unwrap();
+        let old: String = text
+            .lines()
+            .filter(|line| !line.starts_with("kind") && !line.starts_with("julia_c"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let old = ViewState::from_toml(&old).unwrap();
+        assert_eq!(old.fractal_kind(), FractalKind::Mandelbrot);
+        assert_eq!(old.rotation, view().rotation);
+        // a Julia set without its `c` gets the default one
+        let without_c = ViewState {
+            julia_c: None,
+            ..view()
+        };
+        assert_eq!(
+            without_c.fractal_kind(),
+            FractalKind::Julia(DEFAULT_JULIA_C)
+        );
+    }
+
+    #[test]
+    fn views_from_before_rotation_are_upright() {
+        let text = toml::to_string(&view()).unwrap();
+        let old: String = text
+            .lines()
+            .filter(|line| !line.starts_with("rotation"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let old = ViewState::from_toml(&old).unwrap();
+        assert_eq!(old.rotation, 0.0);
+        assert_eq!(
+            old,
+            ViewState {
+                rotation: 0.0,
+                ..view()
+            }
+        );
+    }
+
+    #[test]
+    fn view_slots_round_trip() {
+        let dir = std::env::temp_dir().join(format!("fractal_view_slots_{}", std::process::id()));
+        let path = dir.join(SLOTS_FILE_NAME);
+        assert_eq!(ViewSlots::load(&path), ViewSlots::default());
+
+        let mut slots = ViewSlots::default();
+        slots.set(3, view());
+        slots.set(
+            0,
+            ViewState {
+                palette: None,
+                ..view()
+            },
+        );
+        slots.save(&path).unwrap();
+        let loaded = ViewSlots::load(&path);
+        assert_eq!(loaded, slots);
+        assert_eq!(loaded.get(3), Some(&view()));
+        assert_eq!(loaded.get(1), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let text = toml::to_string(&slots).unwrap();
+        assert!(ViewSlots::from_toml(&text.replace("[3]", "[12]")).is_err());
+        assert!(ViewSlots::from_toml(&text.replace("[3]", "[three]")).is_err());
+        slots.set(
+            5,
+            ViewState {
+                height: 0.0,
+                ..view()
+            },
+        );
+        assert!(ViewSlots::from_toml(&toml::to_string(&slots).unwrap()).is_err());
+    }
+
+    #[test]
+    fn views_load_from_json() {
+        let text = serde_json::to_string(&view()).unwrap();
+        assert_eq!(ViewState::from_json(&text).unwrap(), view());
+
+        let invalid = serde_json::to_string(&ViewState {
+            height: 0.0,
+            ..view()
+        })
+        .unwrap();
+        assert!(ViewState::from_json(&invalid).is_err());
+        // palettes are JSON too, they don't pass for a view
+        assert!(ViewState::from_json(r#"{"stops": []}"#).is_err());
+    }
+}
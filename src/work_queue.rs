@@ -0,0 +1,156 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+/// Jobs waiting for a fixed set of workers, the most urgent first. Every `supersede` starts a
+/// generation and drains the jobs of the one before, so work that never started costs nothing
+/// to cancel. A job a worker took just before is stale as well, the worker checks
+/// `is_current` before starting it.
+pub struct WorkQueue<T> {
+    inner: Mutex<Inner<T>>,
+    available: Notify,
+}
+
+struct Inner<T> {
+    generation: u64,
+    jobs: BinaryHeap<Queued<T>>,
+}
+
+// ordered by priority alone, the lowest value comes out of the heap first
+struct Queued<T> {
+    priority: usize,
+    generation: u64,
+    job: T,
+}
+
+impl<T> WorkQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                generation: 0,
+                jobs: BinaryHeap::new(),
+            }),
+            available: Notify::new(),
+        }
+    }
+
+    /// Starts a new generation and returns it with the jobs of the last one that no worker
+    /// took yet, in no particular order.
+    pub fn supersede(&self) -> (u64, Vec<T>) {
+        let mut inner = self.inner.lock();
+        inner.generation += 1;
+        let stale = inner.jobs.drain().map(|queued| queued.job).collect();
+
+        (inner.generation, stale)
+    }
+
+    /// Queues `job` for `generation` unless a later one started, lower priorities go first.
+    pub fn push(&self, generation: u64, priority: usize, job: T) {
+        let mut inner = self.inner.lock();
+        if generation != inner.generation {
+            return;
+        }
+        inner.jobs.push(Queued {
+            priority,
+            generation,
+            job,
+        });
+        drop(inner);
+
+        self.available.notify_one();
+    }
+
+    /// Waits for the most urgent job, returned with the generation it was queued for.
+    pub async fn pop(&self) -> (u64, T) {
+        loop {
+            // registered before looking, a push in between still wakes this worker
+            let notified = self.available.notified();
+            if let Some(queued) = self.inner.lock().jobs.pop() {
+                return (queued.generation, queued.job);
+            }
+            notified.await;
+        }
+    }
+
+    /// False once a later generation started, the job is stale then.
+    pub fn is_current(&self, generation: u64) -> bool {
+        self.inner.lock().generation == generation
+    }
+}
+
+impl<T> fmt::Debug for WorkQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock();
+        f.debug_struct("WorkQueue")
+            .field("generation", &inner.generation)
+            .field("jobs", &inner.jobs.len())
+            .finish()
+    }
+}
+
+impl<T> PartialEq for Queued<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl<T> Eq for Queued<T> {}
+
+impl<T> PartialOrd for Queued<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Queued<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn superseding_drains_stale_jobs() {
+        let queue = WorkQueue::new();
+        let (first, stale) = queue.supersede();
+        assert!(stale.is_empty());
+        for (priority, job) in [(2, "c"), (0, "a"), (1, "b")] {
+            queue.push(first, priority, job);
+        }
+        assert_eq!(queue.pop().await, (first, "a"));
+
+        let (second, mut stale) = queue.supersede();
+        stale.sort_unstable();
+        assert_eq!(stale, ["b", "c"]);
+        assert!(!queue.is_current(first));
+        assert!(queue.is_current(second));
+
+        // pushes of the superseded generation are dropped, the new jobs come out by priority
+        queue.push(first, 0, "late");
+        queue.push(second, 5, "y");
+        queue.push(second, 3, "x");
+        assert_eq!(queue.pop().await, (second, "x"));
+        assert_eq!(queue.pop().await, (second, "y"));
+        assert!(queue.supersede().1.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_waiting_worker_wakes_for_a_push() {
+        let queue = std::sync::Arc::new(WorkQueue::new());
+        let (generation, _) = queue.supersede();
+        let worker = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.pop().await }
+        });
+        tokio::task::yield_now().await;
+
+        queue.push(generation, 0, 7);
+        assert_eq!(worker.await.unwrap(), (generation, 7));
+    }
+}
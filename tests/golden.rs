@@ -0,0 +1,242 @@
+//! Small fixed views rendered by the kernels and compared against the reference buffers in
+//! `tests/golden`. The references come from `ScalarKernel`; after a change that is meant to
+//! alter the output, `FRACTAL_BLESS=1 cargo test --test golden` writes them anew.
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use glam::{DVec2, UVec2};
+
+use fractal::kernel::{
+    compute_frame, FractalKernel, KernelOptions, NeverCancelled, PixelGrid, ScalarKernel,
+};
+use fractal::mandelbrot_simd::{KernelPixel, Pixel, SimdKernel, MANDELBROT_POWER};
+use fractal::math::DRect;
+
+const SIZE: UVec2 = UVec2::new(64, 64);
+// mismatches listed by value in the failure message, the map shows all of them
+const LISTED_MISMATCHES: usize = 10;
+
+struct View {
+    name: &'static str,
+    frame: DRect,
+    max_iter: u32,
+}
+
+fn views() -> [View; 3] {
+    [
+        // the whole set as the app starts
+        View {
+            name: "default_view",
+            frame: DRect::from_center_size(DVec2::new(-0.74, 0.0), DVec2::splat(2.5)),
+            max_iter: 1000,
+        },
+        // seahorse valley at 1e8 zoom
+        View {
+            name: "seahorse_1e8",
+            frame: DRect::from_center_size(
+                DVec2::new(-0.743_643_887_037_151, 0.131_825_904_205_330),
+                DVec2::splat(2.5e-8),
+            ),
+            max_iter: 3000,
+        },
+        // across the upper edge of the main cardioid, where interior points converge slowly
+        View {
+            name: "cardioid_edge",
+            frame: DRect::from_center_size(DVec2::new(0.25, 0.5), DVec2::splat(0.05)),
+            max_iter: 2000,
+        },
+    ]
+}
+
+/// How far a kernel may stray from the reference.
+#[derive(Debug, Clone, Copy)]
+struct Tolerance {
+    /// Absolute difference in escape counts, `0.0` asks for bit-exact values.
+    iterations: f64,
+    /// Pixels allowed beyond `iterations`, for orbits that cross the escape radius on
+    /// the other side of a rounding step.
+    outliers: usize,
+}
+
+// the reference kernel itself
+const EXACT: Tolerance = Tolerance {
+    iterations: 0.0,
+    outliers: 0,
+};
+// lanes evaluate the same operations, but vectorized math and fused multiply-adds may round
+// differently on other targets
+const SIMD_BANDED: Tolerance = Tolerance {
+    iterations: 1.0,
+    outliers: 0,
+};
+// about four f32 steps at the deep zoom limits
+const SIMD_SMOOTH: Tolerance = Tolerance {
+    iterations: 1e-3,
+    outliers: 0,
+};
+
+fn render<K: FractalKernel, P: KernelPixel>(kernel: &K, view: &View) -> Vec<f32> {
+    let grid = PixelGrid::new(view.frame, SIZE);
+    let options = KernelOptions {
+        max_iter: view.max_iter,
+        power: MANDELBROT_POWER,
+    };
+    let mut buffer = vec![P::default(); SIZE.element_product() as usize];
+    compute_frame(
+        kernel,
+        grid,
+        grid.all(),
+        options,
+        NeverCancelled,
+        &mut buffer,
+    )
+    .unwrap();
+
+    // banded counts fit an f32 exactly, both kinds share the file format
+    buffer.iter().map(|pixel| pixel.value() as f32).collect()
+}
+
+fn reference_path(view: &View, pixel: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}_{}.bin", view.name, pixel))
+}
+
+fn read_values(path: &PathBuf) -> Vec<f32> {
+    let bytes = std::fs::read(path).unwrap_or_else(|error| {
+        panic!(
+            "{}: {}, FRACTAL_BLESS=1 writes the references",
+            path.display(),
+            error
+        )
+    });
+    bytes
+        .chunks_exact(size_of::<f32>())
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+fn write_values(path: &PathBuf, values: &[f32]) {
+    let bytes: Vec<u8> = values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, bytes).unwrap();
+}
+
+fn check<K: FractalKernel, P: KernelPixel>(
+    kernel_name: &str,
+    kernel: &K,
+    pixel: &str,
+    tolerance: Tolerance,
+) {
+    let bless = std::env::var_os("FRACTAL_BLESS").is_some();
+    let mut failures = String::new();
+
+    for view in views() {
+        let actual = render::<K, P>(kernel, &view);
+        let path = reference_path(&view, pixel);
+        if bless && kernel_name == "scalar" {
+            write_values(&path, &actual);
+            continue;
+        }
+
+        let expected = read_values(&path);
+        assert_eq!(expected.len(), actual.len(), "{}", path.display());
+        if let Some(report) = compare(&expected, &actual, tolerance) {
+            let actual_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+                .join(format!("{}_{}_{}.bin", view.name, pixel, kernel_name));
+            write_values(&actual_path, &actual);
+            writeln!(
+                failures,
+                "{} {} {} differs from {}, written to {}\n{}",
+                kernel_name,
+                pixel,
+                view.name,
+                path.display(),
+                actual_path.display(),
+                report
+            )
+            .unwrap();
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures);
+}
+
+// `None` if `actual` is within `tolerance`, otherwise the mismatches and a map of them with
+// the first row at the bottom like the frame: `+` above the reference, `-` below, `o` where
+// only one of them is inside the set
+fn compare(expected: &[f32], actual: &[f32], tolerance: Tolerance) -> Option<String> {
+    let mismatches: Vec<usize> = (0..expected.len())
+        .filter(|&index| {
+            let (expected, actual) = (expected[index], actual[index]);
+            expected.to_bits() != actual.to_bits()
+                && (expected as f64 - actual as f64).abs() > tolerance.iterations
+        })
+        .collect();
+    if mismatches.len() <= tolerance.outliers {
+        return None;
+    }
+
+    let max_difference = mismatches
+        .iter()
+        .map(|&index| (expected[index] - actual[index]).abs())
+        .fold(0.0, f32::max);
+    let mut report = format!(
+        "{} pixels beyond {} iterations, {} allowed, largest difference {}\n",
+        mismatches.len(),
+        tolerance.iterations,
+        tolerance.outliers,
+        max_difference
+    );
+    for &index in mismatches.iter().take(LISTED_MISMATCHES) {
+        writeln!(
+            report,
+            "  ({}, {}): expected {}, got {}",
+            index as u32 % SIZE.x,
+            index as u32 / SIZE.x,
+            expected[index],
+            actual[index]
+        )
+        .unwrap();
+    }
+
+    let mut map = vec![b'.'; expected.len()];
+    for &index in &mismatches {
+        map[index] = if (expected[index] == 0.0) != (actual[index] == 0.0) {
+            b'o'
+        } else if actual[index] > expected[index] {
+            b'+'
+        } else {
+            b'-'
+        };
+    }
+    for row in map.chunks_exact(SIZE.x as usize).rev() {
+        report.push_str(std::str::from_utf8(row).unwrap());
+        report.push('\n');
+    }
+
+    Some(report)
+}
+
+#[test]
+fn scalar_banded_matches_reference() {
+    check::<_, Pixel>("scalar", &ScalarKernel, "banded", EXACT);
+}
+
+#[test]
+fn scalar_smooth_matches_reference() {
+    check::<_, f32>("scalar", &ScalarKernel, "smooth", EXACT);
+}
+
+#[test]
+fn simd_banded_matches_reference() {
+    check::<_, Pixel>("simd", &SimdKernel::default(), "banded", SIMD_BANDED);
+}
+
+#[test]
+fn simd_smooth_matches_reference() {
+    check::<_, f32>("simd", &SimdKernel::default(), "smooth", SIMD_SMOOTH);
+}
@@ -0,0 +1,272 @@
+//! The grid of pages around a view computed tile by tile the way the app does it, checked
+//! against frames computed whole by `raw_export::compute`. Pins down where pages and tiles
+//! sample the plane: a tile or page off by a pixel, or by a fraction of one, shows up as a seam.
+use glam::{DVec2, IVec2, UVec2};
+
+use fractal::kernel::{KernelOptions, NeverCancelled, PixelGrid};
+use fractal::mandelbrot_simd::{EscapeMetric, FractalKind, KernelPixel, Pixel, MANDELBROT_POWER};
+use fractal::math::{DRect, URect};
+use fractal::raw_export;
+use fractal::tiling::{
+    compute_tile, is_interior_rect, page_coord, page_rect, tile_kernel, tile_rects,
+};
+
+// small pages with several tiles in both directions
+const TEXTURE_SIZE: UVec2 = UVec2::new(96, 64);
+const TILE_SIZE: u32 = 32;
+// pages per side, as in the app
+const GRID_SIZE: i32 = 3;
+// the page offset is added to a point before the pixel offset, the whole grid adds them at
+// once, so a coordinate may round differently by an ulp or so of the largest coordinate in
+// the grid; a sampling error of a pixel is many orders of magnitude more
+const POINT_TOLERANCE_ULPS: f64 = 4.0;
+
+struct View {
+    name: &'static str,
+    // the page at (0, 0) of the grid, the size the texture covers
+    page: DRect,
+    center: DVec2,
+    max_iter: u32,
+}
+
+fn views() -> [View; 3] {
+    [
+        // the whole set as the app starts, the pages are centered on it
+        View {
+            name: "default_view",
+            page: DRect::from_center_size(DVec2::new(-0.74, 0.0), DVec2::new(1.5, 1.0)),
+            center: DVec2::new(-0.74, 0.0),
+            max_iter: 1000,
+        },
+        // seahorse valley at 1e8 zoom, the pages are millions of page sizes from the origin
+        View {
+            name: "seahorse_1e8",
+            page: DRect::from_pos_size(DVec2::ZERO, DVec2::new(3e-8, 2e-8)),
+            center: DVec2::new(-0.743_643_887_037_151, 0.131_825_904_205_330),
+            max_iter: 3000,
+        },
+        View {
+            name: "cardioid_edge",
+            page: DRect::from_pos_size(DVec2::new(0.1, 0.2), DVec2::new(0.03, 0.02)),
+            center: DVec2::new(0.25, 0.5),
+            max_iter: 2000,
+        },
+    ]
+}
+
+// the pages of the grid around the view with their offsets in the grid, in texels
+fn pages(view: &View) -> Vec<(PixelGrid, UVec2)> {
+    let radius = GRID_SIZE / 2;
+    let center = page_coord(view.page, view.center);
+
+    (-radius..=radius)
+        .flat_map(|y| (-radius..=radius).map(move |x| IVec2::new(x, y)))
+        .map(|offset| {
+            let grid = PixelGrid::new(page_rect(view.page, center + offset), TEXTURE_SIZE);
+            (grid, (offset + radius).as_uvec2() * TEXTURE_SIZE)
+        })
+        .collect()
+}
+
+fn options(view: &View) -> KernelOptions {
+    KernelOptions {
+        max_iter: view.max_iter,
+        power: MANDELBROT_POWER,
+    }
+}
+
+// the tiles of a page assembled into one image with the top row first, like an export
+fn render_tiled<P: KernelPixel>(view: &View, grid: PixelGrid) -> Vec<P> {
+    let kernel = tile_kernel(grid, FractalKind::default(), EscapeMetric::default());
+    let mut image = vec![P::default(); TEXTURE_SIZE.element_product() as usize];
+
+    for tile in tile_rects(TEXTURE_SIZE, TILE_SIZE) {
+        let mut buffer = vec![P::default(); tile.size.element_product() as usize];
+        compute_tile(
+            &kernel,
+            grid,
+            tile,
+            options(view),
+            None,
+            NeverCancelled,
+            &mut buffer,
+        )
+        .unwrap();
+
+        // tile buffers have the bottom row first
+        for (row, values) in buffer.chunks_exact(tile.size.x as usize).enumerate() {
+            let y = TEXTURE_SIZE.y - 1 - tile.pos.y - row as u32;
+            let start = (y * TEXTURE_SIZE.x + tile.pos.x) as usize;
+            image[start..start + values.len()].copy_from_slice(values);
+        }
+    }
+
+    image
+}
+
+// the values of the points of `rect` on a grid of `size`, as a tile of a page computes them
+fn compute_rect(rect: DRect, size: UVec2, max_iter: u32) -> Vec<Pixel> {
+    let grid = PixelGrid::new(rect, size);
+    let kernel = tile_kernel(grid, FractalKind::default(), EscapeMetric::default());
+    let mut buffer = vec![Pixel::default(); size.element_product() as usize];
+    compute_tile(
+        &kernel,
+        grid,
+        URect::from_pos_size(UVec2::ZERO, size),
+        KernelOptions {
+            max_iter,
+            power: MANDELBROT_POWER,
+        },
+        None,
+        NeverCancelled,
+        &mut buffer,
+    )
+    .unwrap();
+
+    buffer
+}
+
+// every page assembled from its tiles is the frame of the page computed at once; without
+// anti-aliasing, which refines the borders of tiles
+fn check_pages<P: KernelPixel + Send>(pixel: &str) {
+    let mut failures = Vec::new();
+    for view in views() {
+        for (grid, offset) in pages(&view) {
+            let kernel = tile_kernel(grid, FractalKind::default(), EscapeMetric::default());
+            let whole: Vec<P> = raw_export::compute(&kernel, grid, options(&view), None).unwrap();
+            let tiled = render_tiled::<P>(&view, grid);
+
+            let mismatches: Vec<(UVec2, f64, f64)> = (0..whole.len())
+                .filter(|&index| tiled[index].value().to_bits() != whole[index].value().to_bits())
+                .map(|index| {
+                    let texel =
+                        UVec2::new(index as u32 % TEXTURE_SIZE.x, index as u32 / TEXTURE_SIZE.x);
+                    (texel, tiled[index].value(), whole[index].value())
+                })
+                .collect();
+            if !mismatches.is_empty() {
+                failures.push(format!(
+                    "{} {} page at {}: {} texels differ, (texel, tiled, whole) {:?}",
+                    view.name,
+                    pixel,
+                    offset,
+                    mismatches.len(),
+                    &mismatches[..mismatches.len().min(10)]
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn banded_tiles_match_their_page() {
+    check_pages::<Pixel>("banded");
+}
+
+#[test]
+fn smooth_tiles_match_their_page() {
+    check_pages::<f32>("smooth");
+}
+
+// the pages side by side sample the points of one grid over all of them, so there is neither
+// a gap nor an overlap at their edges
+#[test]
+fn pages_sample_the_whole_grid() {
+    for view in views() {
+        let pages = pages(&view);
+        let first = pages.first().unwrap().0.frame;
+        let last = pages.last().unwrap().0.frame;
+        let whole = PixelGrid::new(
+            DRect::from_pos_size(first.pos, last.pos + last.size - first.pos),
+            TEXTURE_SIZE * GRID_SIZE as u32,
+        );
+        let scale = first.pos.abs().max((last.pos + last.size).abs());
+        let tolerance = scale * f64::EPSILON * POINT_TOLERANCE_ULPS;
+
+        for (grid, offset) in pages {
+            for y in 0..TEXTURE_SIZE.y {
+                for x in 0..TEXTURE_SIZE.x {
+                    let texel = UVec2::new(x, y);
+                    let page_point = grid.point(texel);
+                    let grid_point = whole.point(offset + texel);
+                    assert!(
+                        (page_point - grid_point).abs().cmple(tolerance).all(),
+                        "{} texel {} of the page at {}: {:?} instead of {:?}, {} pixels apart",
+                        view.name,
+                        texel,
+                        offset,
+                        page_point,
+                        grid_point,
+                        (page_point - grid_point) / grid.pixel_size()
+                    );
+                }
+            }
+        }
+    }
+}
+
+// the tiles skipped as inside the set are the ones the kernel finds all inside
+#[test]
+fn interior_tiles_never_escape() {
+    // the whole set on a page of tiles a few tenths across
+    let page_size = UVec2::new(480, 320);
+    let grid = PixelGrid::new(
+        DRect::from_center_size(DVec2::new(-0.74, 0.0), DVec2::new(3.0, 2.0)),
+        page_size,
+    );
+    let pixel_size = grid.pixel_size();
+
+    let mut interior = 0;
+    for tile in tile_rects(page_size, TILE_SIZE) {
+        let rect = DRect::from_pos_size(
+            grid.frame.pos + tile.pos.as_dvec2() * pixel_size,
+            tile.size.as_dvec2() * pixel_size,
+        );
+        if !is_interior_rect(rect) {
+            continue;
+        }
+
+        interior += 1;
+        let values = compute_rect(rect, tile.size, 1000);
+        assert!(
+            values.iter().all(|value| value.value() == 0.0),
+            "{:?}",
+            rect
+        );
+    }
+    // the cardioid alone covers a dozen or so
+    assert!(interior >= 10, "{}", interior);
+}
+
+#[test]
+fn tiles_straddling_the_edges_are_not_interior() {
+    let rect = |x0: f64, y0: f64, x1: f64, y1: f64| {
+        DRect::from_pos_size(DVec2::new(x0, y0), DVec2::new(x1 - x0, y1 - y0))
+    };
+    // well inside the cardioid and the bulb, and a tiny one next to the edge of the cardioid
+    assert!(is_interior_rect(rect(-0.5, -0.3, -0.1, 0.3)));
+    assert!(is_interior_rect(rect(-1.1, -0.1, -0.9, 0.1)));
+    assert!(is_interior_rect(rect(0.25, 0.49, 0.2501, 0.4901)));
+
+    for straddling in [
+        // across the right end of the cardioid
+        rect(0.3, -0.05, 0.45, 0.05),
+        // over the cusp, its corners and its center are all inside, the thin sliver outside
+        // runs right of the cusp along the axis
+        rect(0.24, -0.04, 0.3, 0.06),
+        // the neck between the cardioid and the bulb, both sides are inside
+        rect(-0.8, 0.002, -0.7, 0.012),
+        // across the left end of the bulb
+        rect(-1.3, -0.05, -1.2, 0.05),
+    ] {
+        assert!(!is_interior_rect(straddling), "{:?}", straddling);
+        let values = compute_rect(straddling, UVec2::splat(64), 10_000);
+        assert!(
+            values.iter().any(|&value| value.value() != 0.0),
+            "{:?} has no escaping point",
+            straddling
+        );
+    }
+}